@@ -0,0 +1,120 @@
+# English. Keys are looked up by the game, values are what's shown. A line starting with whitespace
+# continues the value above it on a new line, and `{ $name }` is filled in by the game.
+
+## Title and menus
+game-title = Broom Shakalaka
+menu-play = Play
+menu-settings = Settings
+menu-credits = Credits
+menu-back = Back
+loading = Loading...
+
+pause-title = Game paused
+pause-continue = Continue
+pause-restart = Restart level
+pause-restart-campaign = Restart from the first level
+pause-quit = Quit to title
+pause-quit-prompt = Quit? Progress in this level will be lost
+pause-quit-confirm = Confirm
+pause-quit-cancel = Cancel
+
+settings-title = Settings
+settings-master = Master
+settings-music = Music
+settings-sfx = Sfx
+settings-auto-sweep = Auto-sweep
+settings-landing-marker = Landing marker
+settings-mirror-sweep = Mirror sweep
+settings-trails = Stone trails
+settings-shadows = Shadows
+settings-reduce-motion = Reduce motion
+settings-screen-shake = Screen shake
+settings-palette = Palette
+settings-theme = Theme
+settings-stone-skin = Stone
+settings-language = Language
+settings-play-music = Play Music
+settings-play-sfx = Play Sfx
+settings-toggle = Toggle
+settings-change = Change
+settings-on = On
+settings-off = Off
+palette-normal = Normal
+palette-deuteranopia = Deuteranopia
+palette-high-contrast = High Contrast
+theme-day = Day
+theme-night = Night
+theme-dusk = Dusk
+skin-classic = Classic
+skin-granite = Granite
+skin-team-red = Team red
+skin-team-yellow = Team yellow
+
+credits-code = Code
+credits-music = Music
+credits-sfx = Sound effects
+credits-fonts = Fonts
+
+end-thanks = Thanks for playing!
+end-under-budget = Levels finished under budget: { $count }
+end-finish = End game
+
+## Gameplay
+controls-title = Controls
+controls-restart = { $key }: Restart Level
+controls-brooms = 1-3, Tab or Scroll: Switch Brooms
+controls-erase = Right Drag: Erase Sweeping
+controls-precision = Shift + Drag: Precision Sweeping
+controls-undo = { $key }: Undo Last Stroke
+controls-legend = { $key }: Tile Legend
+controls-keyboard = Arrows + Enter/Space: Keyboard Sweeping
+controls-rewind = Hold { $key }: Rewind
+controls-follow = { $key }: Follow Stone
+controls-camera = Middle Drag / Ctrl+Scroll / Home: Camera
+
+broom-straight = Broom: Straight
+broom-counterclockwise = Broom: Counterclockwise
+broom-clockwise = Broom: Clockwise
+broom-slow-down = Broom: SlowDown
+broom-goal = Broom: Goal
+broom-wall = Broom: Wall
+broom-speed-up = Broom: SpeedUp
+broom-speed-gate = Broom: SpeedGate
+precision = Precision
+swept = Swept: { $amount }
+aim-hint = Drag from the stone to aim, release or press Space to throw
+hint = Hint
+
+legend-button = ?
+legend-straight = Keeps the stone going straight at its speed
+legend-counterclockwise = Curves the stone to the left
+legend-clockwise = Curves the stone to the right
+legend-slow-down = Slows the stone down, sweep it to smooth it out
+legend-speed-up = Speeds the stone up the way the arrow points
+legend-wall = Bounces the stone off
+legend-speed-gate = Bounces stones slower than its number, lets faster ones through
+legend-goal = Stop a stone here to win
+
+tip-level-4 = Let's get BOOSTING!
+tip-level-5 = Good luck with this one ;)
+
+tutorial-sweep-tile =
+    Let's practice that sweeping technique!
+    Click and drag on the tile to make it smooth
+tutorial-stone-untouchable = You can't affect the stone directly
+tutorial-sweep-speed = Sweep tiles to control the speed
+tutorial-number-keys =
+    Number keys change broom types
+    #2 sure might be handy
+tutorial-third-broom = I wonder what the #3 does...
+tutorial-restart = Remember that you can hit R at any time to restart
+tutorial-nice = Nice!
+
+win-title = Level complete!
+win-time = Time: { $time }
+win-next = Next level
+win-finish = Finish
+fail-title = Too bad!
+fail-hint = Press R to retry
+fail-hint-rewind = Hold Backspace to rewind, or press R to retry
+retry = Retry
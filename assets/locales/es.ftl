@@ -0,0 +1,119 @@
+# Spanish. See en.ftl for the format.
+
+## Title and menus
+game-title = Broom Shakalaka
+menu-play = Jugar
+menu-settings = Ajustes
+menu-credits = Créditos
+menu-back = Volver
+loading = Cargando...
+
+pause-title = Juego en pausa
+pause-continue = Continuar
+pause-restart = Reiniciar nivel
+pause-restart-campaign = Empezar desde el primer nivel
+pause-quit = Salir al título
+pause-quit-prompt = ¿Salir? Se perderá el progreso de este nivel
+pause-quit-confirm = Confirmar
+pause-quit-cancel = Cancelar
+
+settings-title = Ajustes
+settings-master = General
+settings-music = Música
+settings-sfx = Efectos
+settings-auto-sweep = Barrido automático
+settings-landing-marker = Marcador de llegada
+settings-mirror-sweep = Barrido en espejo
+settings-trails = Estelas de piedra
+settings-shadows = Sombras
+settings-reduce-motion = Reducir movimiento
+settings-screen-shake = Vibración de pantalla
+settings-palette = Paleta
+settings-theme = Tema
+settings-stone-skin = Piedra
+settings-language = Idioma
+settings-play-music = Probar música
+settings-play-sfx = Probar efectos
+settings-toggle = Cambiar
+settings-change = Cambiar
+settings-on = Sí
+settings-off = No
+palette-normal = Normal
+palette-deuteranopia = Deuteranopía
+palette-high-contrast = Alto contraste
+theme-day = Día
+theme-night = Noche
+theme-dusk = Atardecer
+skin-classic = Clásica
+skin-granite = Granito
+skin-team-red = Equipo rojo
+skin-team-yellow = Equipo amarillo
+
+credits-code = Código
+credits-music = Música
+credits-sfx = Efectos de sonido
+credits-fonts = Fuentes
+
+end-thanks = ¡Gracias por jugar!
+end-under-budget = Niveles terminados dentro del presupuesto: { $count }
+end-finish = Terminar
+
+## Gameplay
+controls-title = Controles
+controls-restart = { $key }: Reiniciar nivel
+controls-brooms = 1-3, Tab o rueda: Cambiar escoba
+controls-erase = Arrastrar con clic derecho: Borrar barrido
+controls-precision = Mayús + arrastrar: Barrido preciso
+controls-undo = { $key }: Deshacer último trazo
+controls-legend = { $key }: Leyenda de casillas
+controls-keyboard = Flechas + Intro/Espacio: Barrer con el teclado
+controls-rewind = Mantener { $key }: Rebobinar
+controls-follow = { $key }: Seguir la piedra
+controls-camera = Arrastrar con clic central / Ctrl+rueda / Inicio: Cámara
+
+broom-straight = Escoba: Recta
+broom-counterclockwise = Escoba: Antihoraria
+broom-clockwise = Escoba: Horaria
+broom-slow-down = Escoba: Frenar
+broom-goal = Escoba: Meta
+broom-wall = Escoba: Muro
+broom-speed-up = Escoba: Acelerar
+broom-speed-gate = Escoba: Barrera de velocidad
+precision = Precisión
+swept = Barrido: { $amount }
+aim-hint = Arrastra desde la piedra para apuntar, suelta o pulsa Espacio para lanzar
+hint = Pista
+
+legend-button = ?
+legend-straight = Mantiene la piedra recta y a su velocidad
+legend-counterclockwise = Curva la piedra hacia la izquierda
+legend-clockwise = Curva la piedra hacia la derecha
+legend-slow-down = Frena la piedra, bárrela para alisarla
+legend-speed-up = Acelera la piedra hacia donde apunta la flecha
+legend-wall = Hace rebotar la piedra
+legend-speed-gate = Hace rebotar las piedras más lentas que su número y deja pasar las más rápidas
+legend-goal = Detén una piedra aquí para ganar
+
+tip-level-4 = ¡A ACELERAR!
+tip-level-5 = Suerte con este ;)
+
+tutorial-sweep-tile =
+    ¡Practiquemos esa técnica de barrido!
+    Haz clic y arrastra sobre la casilla para alisarla
+tutorial-stone-untouchable = No puedes tocar la piedra directamente
+tutorial-sweep-speed = Barre casillas para controlar la velocidad
+tutorial-number-keys =
+    Las teclas numéricas cambian de escoba
+    La #2 podría venir bien
+tutorial-third-broom = Me pregunto qué hará la #3...
+tutorial-restart = Recuerda que puedes pulsar R cuando quieras para reiniciar
+tutorial-nice = ¡Bien!
+
+win-title = ¡Nivel completado!
+win-time = Tiempo: { $time }
+win-next = Siguiente nivel
+win-finish = Terminar
+fail-title = ¡Qué pena!
+fail-hint = Pulsa R para reintentar
+fail-hint-rewind = Mantén Retroceso para rebobinar, o pulsa R para reintentar
+retry = Reintentar
@@ -93,8 +93,9 @@ fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
     // Let's retry the loop approach but purely screen-space based on the previous working shader concept 
     // but optimized and using screen coords.
     
-    // Number of particles - higher density 
-    let num_particles = 150;
+    // Number of particles - higher density, scaled down for smaller celebrations
+    let intensity = clamp(material.params.w, 0.0, 1.0);
+    let num_particles = i32(round(150.0 * intensity));
     
     // Loop through particles
     for (var i = 0; i < num_particles; i++) {
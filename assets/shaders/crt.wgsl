@@ -15,6 +15,9 @@ struct CrtSettings {
     brightness: f32,
     noise_intensity: f32,
     time: f32,
+    power_line: f32,
+    power_line_intensity: f32,
+    _webgl2_padding: vec2<f32>,
 }
 @group(0) @binding(2) var<uniform> settings: CrtSettings;
 
@@ -126,6 +129,12 @@ fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
     // Apply brightness adjustment
     color = color * settings.brightness;
     
+    // Power-on line, a thin white band swept down the screen
+    if (settings.power_line >= 0.0) {
+        let line = 1.0 - smoothstep(0.0, 0.01, abs(curved_uv.y - settings.power_line));
+        color = color + vec3<f32>(line * settings.power_line_intensity);
+    }
+    
     // Slight phosphor glow (bloom simulation) - brighten bright areas slightly
     let luminance = dot(color, vec3<f32>(0.299, 0.587, 0.114));
     let glow = smoothstep(0.5, 1.0, luminance) * 0.1;
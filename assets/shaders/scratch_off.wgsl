@@ -7,6 +7,7 @@ struct ScratchOffMaterial {
     top_color: vec4<f32>,
     reveal_color: vec4<f32>,
     progress: f32,
+    seed: f32,
 };
 
 @group(2) @binding(0)
@@ -51,7 +52,7 @@ fn fbm(p: vec2<f32>) -> f32 {
 }
 
 // Scratch line pattern - creates directional scratches
-fn scratch_pattern(uv: vec2<f32>, progress: f32) -> f32 {
+fn scratch_pattern(uv: vec2<f32>, progress: f32, seed: f32) -> f32 {
     var scratch = 0.0;
     
     // Multiple layers of scratches at different angles and scales
@@ -74,7 +75,7 @@ fn scratch_pattern(uv: vec2<f32>, progress: f32) -> f32 {
         let scratch_uv = vec2<f32>(rotated.x * scale, rotated.y * scale * 0.3);
         
         // Use noise to create scratch pattern
-        let n = noise(scratch_uv + vec2<f32>(f32(i) * 17.3, f32(i) * 23.7));
+        let n = noise(scratch_uv + vec2<f32>(f32(i) * 17.3 + seed, f32(i) * 23.7 + seed * 1.7));
         
         // Threshold based on progress - more scratches as progress increases
         // Different thresholds for each layer create staggered reveal
@@ -91,8 +92,8 @@ fn scratch_pattern(uv: vec2<f32>, progress: f32) -> f32 {
 }
 
 // Creates rough, organic edges for scratches
-fn scratch_edge_roughness(uv: vec2<f32>, progress: f32) -> f32 {
-    let edge_noise = fbm(uv * 20.0);
+fn scratch_edge_roughness(uv: vec2<f32>, progress: f32, seed: f32) -> f32 {
+    let edge_noise = fbm(uv * 20.0 + vec2<f32>(seed, seed * 0.6));
     return edge_noise * 0.15 * progress;
 }
 
@@ -101,10 +102,10 @@ fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
     let uv = mesh.uv;
     
     // Get the scratch pattern
-    let scratch = scratch_pattern(uv, material.progress);
+    let scratch = scratch_pattern(uv, material.progress, material.seed);
     
     // Add some edge roughness to the scratches
-    let roughness = scratch_edge_roughness(uv, material.progress);
+    let roughness = scratch_edge_roughness(uv, material.progress, material.seed);
     
     // Combine scratch pattern with roughness
     let reveal_amount = clamp(scratch + roughness, 0.0, 1.0);
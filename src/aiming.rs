@@ -0,0 +1,238 @@
+//! Aiming phase for levels without a fixed throw. The player drags from a stone to pick the
+//! direction and power of its throw, and letting go starts the countdown.
+
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+
+use crate::{
+    PausableSystems,
+    camera::MainCamera,
+    gameplay::{GameState, create_tapered_line_mesh},
+    input_buffer::InputBuffer,
+    layers,
+    level::OnLevel,
+    level_transition::transitioning,
+    localization::Localized,
+    replay::ReplayPlayback,
+    screens::Screen,
+    stone::{Stone, StoneIndex, Velocity},
+    tile::TileAssets,
+    tile_cursor::TileCursor,
+    ui::MainUI,
+};
+
+/// Throw speed gained per world unit dragged away from the stone
+const AIM_SPEED_PER_UNIT: f32 = 2.0;
+
+/// Drags shorter than this keep the current aim, so a click on the stone doesn't reset it
+const AIM_DEAD_ZONE: f32 = 5.0;
+
+/// A stone can be grabbed from this many stone radii away from its center
+const AIM_GRAB_RADII: f32 = 2.0;
+
+/// How far the aim arrow reaches per unit of throw speed
+const AIM_ARROW_SECONDS: f32 = 0.4;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameState::Aiming), start_aiming)
+        .add_systems(
+            Update,
+            (aim_stone.run_if(not(transitioning)), draw_aim_arrows)
+                .chain()
+                .run_if(in_state(Screen::Gameplay).and(in_state(GameState::Aiming)))
+                .in_set(PausableSystems),
+        );
+}
+
+#[derive(Component)]
+struct AimArrow;
+
+#[derive(Component)]
+struct AimHintUI;
+
+/// Returns the throw for dragging from `stone_position` to `cursor`, pointing towards the cursor
+/// with a speed proportional to the drag length, or `None` if the drag is too short to count.
+fn aimed_velocity(
+    stone_position: Vec2,
+    cursor: Vec2,
+    min_speed: f32,
+    max_speed: f32,
+) -> Option<Vec2> {
+    let drag = cursor - stone_position;
+    let length = drag.length();
+    if length < AIM_DEAD_ZONE {
+        return None;
+    }
+    let speed = (length * AIM_SPEED_PER_UNIT).clamp(min_speed, max_speed);
+    Some(drag / length * speed)
+}
+
+fn aim_hint_ui() -> impl Bundle {
+    (
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            top: Val::Px(16.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        Pickable::IGNORE,
+        AimHintUI,
+        MainUI,
+        children![(
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            Localized::new("aim-hint"),
+            TextFont {
+                font_size: 28.0,
+                ..default()
+            },
+            TextColor(Color::srgba(1.0, 0.9, 0.2, 0.9)),
+            Pickable::IGNORE,
+        )],
+    )
+}
+
+fn start_aiming(
+    mut commands: Commands,
+    main_ui: Query<Entity, With<MainUI>>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    // Replays carry their own throws, which were applied when the level restarted
+    if replay_playback.is_some() {
+        next_game_state.set(GameState::Countdown);
+        return;
+    }
+
+    for entity in &main_ui {
+        commands.entity(entity).despawn();
+    }
+    commands.spawn((
+        DespawnOnExit(Screen::Gameplay),
+        DespawnOnExit(GameState::Aiming),
+        aim_hint_ui(),
+    ));
+}
+
+fn aim_stone(
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    egui_wants_input: Option<Res<EguiWantsInput>>,
+    window: Single<&Window>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut stones: Query<(Entity, &Stone, &StoneIndex, &Transform, &mut Velocity)>,
+    mut grabbed: Local<Option<Entity>>,
+    mut on_level: ResMut<OnLevel>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut input_buffer: ResMut<InputBuffer>,
+    tile_cursor: Res<TileCursor>,
+) {
+    let (camera, camera_transform) = *camera;
+    let cursor = window
+        .cursor_position()
+        .and_then(|position| camera.viewport_to_world_2d(camera_transform, position).ok());
+
+    if mouse_buttons.just_released(MouseButton::Left) && grabbed.take().is_some() {
+        next_game_state.set(GameState::Countdown);
+        return;
+    }
+    // Throws with the aim as it is, unless a stone is being dragged or Space is sweeping
+    if grabbed.is_none() && !tile_cursor.is_visible() && input_buffer.consume(KeyCode::Space) {
+        next_game_state.set(GameState::Countdown);
+        return;
+    }
+    let Some(cursor) = cursor else {
+        return;
+    };
+
+    if mouse_buttons.just_pressed(MouseButton::Left) {
+        let egui_has_pointer = egui_wants_input.is_some_and(|egui| egui.wants_any_pointer_input());
+        if egui_has_pointer {
+            return;
+        }
+        *grabbed = stones
+            .iter()
+            .filter(|(_, stone, _, transform, _)| {
+                transform.translation.truncate().distance(cursor) <= stone.radius * AIM_GRAB_RADII
+            })
+            .min_by(|(_, _, _, a, _), (_, _, _, b, _)| {
+                a.translation
+                    .truncate()
+                    .distance(cursor)
+                    .total_cmp(&b.translation.truncate().distance(cursor))
+            })
+            .map(|(entity, ..)| entity);
+    }
+
+    let Some(entity) = *grabbed else {
+        return;
+    };
+    let Ok((_, _, stone_index, transform, mut velocity)) = stones.get_mut(entity) else {
+        *grabbed = None;
+        return;
+    };
+    let level = &mut on_level.0;
+    if let Some(aimed) = aimed_velocity(
+        transform.translation.truncate(),
+        cursor,
+        level.min_throw_speed,
+        level.max_throw_speed,
+    ) {
+        velocity.0 = aimed;
+        // Stored on the level so restarting keeps the aim and the replay can record it
+        if let Some(stone_config) = level.stone_configs.get_mut(stone_index.0) {
+            stone_config.aimed_velocity = Some(aimed);
+        }
+    }
+}
+
+fn draw_aim_arrows(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    tile_assets: Res<TileAssets>,
+    stones: Query<(&Transform, &Velocity), With<Stone>>,
+    arrows: Query<Entity, With<AimArrow>>,
+) {
+    for arrow in &arrows {
+        commands.entity(arrow).despawn();
+    }
+
+    for (transform, velocity) in &stones {
+        let start = transform.translation.truncate();
+        let end = start + velocity.0 * AIM_ARROW_SECONDS;
+        if let Some(mesh) = create_tapered_line_mesh(&[start, end], 10.0, 2.0) {
+            commands.spawn((
+                DespawnOnExit(Screen::Gameplay),
+                DespawnOnExit(GameState::Aiming),
+                AimArrow,
+                Mesh2d(meshes.add(mesh)),
+                MeshMaterial2d(tile_assets.arrow_material.clone()),
+                Transform::from_xyz(0., 0., layers::FX),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aimed_velocity_points_at_cursor() {
+        // 50 units straight up is 100 speed, inside the 50..300 range
+        let velocity = aimed_velocity(Vec2::ZERO, Vec2::new(0.0, 50.0), 50.0, 300.0).unwrap();
+        assert_eq!(velocity, Vec2::new(0.0, 100.0));
+    }
+
+    #[test]
+    fn test_aimed_velocity_clamps_speed() {
+        let stone = Vec2::new(10.0, 10.0);
+        // Short drags still throw at the minimum speed
+        let slow = aimed_velocity(stone, stone + Vec2::new(10.0, 0.0), 100.0, 300.0).unwrap();
+        assert_eq!(slow, Vec2::new(100.0, 0.0));
+        // Long drags are capped at the maximum speed
+        let fast = aimed_velocity(stone, stone + Vec2::new(-1000.0, 0.0), 100.0, 300.0).unwrap();
+        assert_eq!(fast, Vec2::new(-300.0, 0.0));
+        // Drags inside the dead zone keep whatever aim the stone already had
+        assert_eq!(aimed_velocity(stone, stone, 100.0, 300.0), None);
+    }
+}
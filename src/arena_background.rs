@@ -0,0 +1,242 @@
+//! The arena around the level: a tiled ice sheet under the grid, and rows of crowd and banners
+//! past its edges that move a little with the camera so they seem further away than the ice.
+//!
+//! It's spawned with each level's grid and sized to it, so restarting or changing levels builds
+//! it again. The ice takes its color from the tile palette, and the banners theirs from the tile
+//! theme.
+
+use bevy::{camera::visibility::RenderLayers, prelude::*, transform::TransformSystems};
+
+use crate::{
+    asset_tracking::LoadResource,
+    camera::{MainCamera, level_bounds},
+    hex_grid::HexGrid,
+    layers::{BACKGROUND, BOARD, child_z},
+    screen_shake::apply_screen_shake,
+    screens::Screen,
+    tile::{TilePalette, TileTheme, TileType},
+};
+
+/// How far the ice reaches past the level on every side, as a fraction of the level's size
+const ICE_MARGIN: f32 = 1.5;
+/// Height of a row of crowd, and the gap between it and the level
+const CROWD_HEIGHT: f32 = 64.0;
+const CROWD_GAP: f32 = 60.0;
+/// Size of a banner, and the space between banners
+const BANNER_SIZE: Vec2 = Vec2::new(90.0, 26.0);
+const BANNER_SPACING: f32 = 220.0;
+/// Fraction of the camera's movement each layer moves along with. The more it moves with the
+/// camera, the further away it looks.
+const CROWD_FOLLOW: f32 = 0.45;
+const BANNER_FOLLOW: f32 = 0.25;
+/// Where each layer's drawn, back to front
+const ICE_Z: f32 = BACKGROUND;
+const CROWD_Z: f32 = BACKGROUND + 1.0;
+const BANNER_Z: f32 = BACKGROUND + 1.5;
+/// Tiles whose colors the banners cycle through
+const BANNER_TILES: [TileType; 4] = [
+    TileType::SlowDown,
+    TileType::TurnClockwise,
+    TileType::TurnCounterclockwise,
+    TileType::Goal,
+];
+
+pub(super) fn plugin(app: &mut App) {
+    app.load_resource::<BackgroundAssets>()
+        .add_systems(
+            Update,
+            (add_arena_background, apply_background_palette)
+                .chain()
+                .run_if(in_state(Screen::Gameplay).and(resource_exists::<BackgroundAssets>)),
+        )
+        .add_systems(
+            PostUpdate,
+            move_parallax_layers
+                .before(apply_screen_shake)
+                .before(TransformSystems::Propagate)
+                .run_if(in_state(Screen::Gameplay)),
+        );
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct BackgroundAssets {
+    #[dependency]
+    ice: Handle<Image>,
+    #[dependency]
+    crowd: Handle<Image>,
+}
+
+impl FromWorld for BackgroundAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            ice: assets.load("images/ice.png"),
+            crowd: assets.load("images/crowd.png"),
+        }
+    }
+}
+
+#[derive(Component)]
+enum BackgroundLayer {
+    Ice,
+    Crowd,
+    /// Which of `BANNER_TILES` it's colored like
+    Banner(usize),
+}
+
+/// Moves a layer by `follow` of how far the camera is from `anchor`, starting from `origin`
+#[derive(Component, Debug)]
+struct Parallax {
+    origin: Vec2,
+    anchor: Vec2,
+    follow: f32,
+}
+
+/// Where a parallax layer sits with the camera at `camera`. With the camera on its anchor the
+/// layer's at its origin, and it only moves `follow` as far as the camera does.
+fn parallax_position(origin: Vec2, anchor: Vec2, camera: Vec2, follow: f32) -> Vec2 {
+    origin + (camera - anchor) * follow
+}
+
+fn ice_tint(palette: TilePalette) -> Color {
+    match palette {
+        TilePalette::Normal => Color::srgb(0.85, 0.93, 1.0),
+        TilePalette::Deuteranopia => Color::srgb(0.9, 0.9, 0.95),
+        TilePalette::HighContrast => Color::srgb(0.35, 0.35, 0.4),
+    }
+}
+
+fn add_arena_background(
+    mut commands: Commands,
+    background_assets: Res<BackgroundAssets>,
+    grids: Query<(Entity, &HexGrid), Added<HexGrid>>,
+) {
+    for (entity, grid) in &grids {
+        let Some(bounds) = level_bounds(grid) else {
+            continue;
+        };
+        let center = bounds.center();
+        // Children of the grid, so placed relative to the board
+        let z = |z: f32| child_z(z, BOARD, 1.0);
+        let width = bounds.width() * (1.0 + 2.0 * ICE_MARGIN);
+        let mut layers = vec![(
+            BackgroundLayer::Ice,
+            Sprite {
+                image: background_assets.ice.clone(),
+                custom_size: Some(bounds.size() * (1.0 + 2.0 * ICE_MARGIN)),
+                image_mode: SpriteImageMode::Tiled {
+                    tile_x: true,
+                    tile_y: true,
+                    stretch_value: 1.0,
+                },
+                ..default()
+            },
+            Transform::from_translation(center.extend(z(ICE_Z))),
+        )];
+        // A row of crowd along the top and bottom of the level, the bottom one turned to face it
+        for side in [1.0, -1.0] {
+            let y = center.y + side * (bounds.half_size().y + CROWD_GAP + CROWD_HEIGHT / 2.0);
+            layers.push((
+                BackgroundLayer::Crowd,
+                Sprite {
+                    image: background_assets.crowd.clone(),
+                    custom_size: Some(Vec2::new(width, CROWD_HEIGHT)),
+                    image_mode: SpriteImageMode::Tiled {
+                        tile_x: true,
+                        tile_y: false,
+                        stretch_value: 1.0,
+                    },
+                    color: Color::srgb(0.12, 0.14, 0.2),
+                    flip_y: side < 0.0,
+                    ..default()
+                },
+                Transform::from_xyz(center.x, y, z(CROWD_Z)),
+            ));
+        }
+        let banner_y = center.y + bounds.half_size().y + CROWD_GAP / 2.0;
+        let banner_count = (width / BANNER_SPACING) as usize;
+        for index in 0..banner_count {
+            let x = center.x + (index as f32 - (banner_count - 1) as f32 / 2.0) * BANNER_SPACING;
+            layers.push((
+                BackgroundLayer::Banner(index % BANNER_TILES.len()),
+                Sprite::from_color(Color::WHITE, BANNER_SIZE),
+                Transform::from_xyz(x, banner_y, z(BANNER_Z)),
+            ));
+        }
+
+        commands.entity(entity).with_children(|parent| {
+            for (layer, sprite, transform) in layers {
+                let follow = match layer {
+                    BackgroundLayer::Ice => None,
+                    BackgroundLayer::Crowd => Some(CROWD_FOLLOW),
+                    BackgroundLayer::Banner(_) => Some(BANNER_FOLLOW),
+                };
+                let mut background = parent.spawn((
+                    Name::new("Arena Background"),
+                    layer,
+                    sprite,
+                    transform,
+                    // Kept off the minimap
+                    RenderLayers::layer(0),
+                ));
+                if let Some(follow) = follow {
+                    background.insert(Parallax {
+                        origin: transform.translation.truncate(),
+                        anchor: center,
+                        follow,
+                    });
+                }
+            }
+        });
+    }
+}
+
+fn apply_background_palette(
+    palette: Res<TilePalette>,
+    theme: Res<TileTheme>,
+    mut layers: Query<(Ref<BackgroundLayer>, &mut Sprite)>,
+) {
+    for (layer, mut sprite) in &mut layers {
+        if !palette.is_changed() && !theme.is_changed() && !layer.is_added() {
+            continue;
+        }
+        match *layer {
+            BackgroundLayer::Ice => sprite.color = ice_tint(*palette),
+            BackgroundLayer::Crowd => {}
+            BackgroundLayer::Banner(index) => {
+                sprite.color = theme.colors().tile_color(*palette, &BANNER_TILES[index]);
+            }
+        }
+    }
+}
+
+/// Runs before the screen shake so the layers follow the steady camera rather than the shake
+fn move_parallax_layers(
+    camera: Single<&Transform, With<MainCamera>>,
+    mut layers: Query<(&Parallax, &mut Transform), Without<MainCamera>>,
+) {
+    let camera = camera.translation.truncate();
+    for (parallax, mut transform) in &mut layers {
+        let position = parallax_position(parallax.origin, parallax.anchor, camera, parallax.follow);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parallax_layers_lag_the_camera() {
+        let origin = Vec2::new(100.0, 400.0);
+        let anchor = Vec2::new(50.0, 50.0);
+        // Framed on the level, the layer's where it was put
+        assert_eq!(parallax_position(origin, anchor, anchor, 0.45), origin);
+        // Panning moves it along with the camera, but less, so it drifts the other way on screen
+        let camera = anchor + Vec2::new(200.0, -80.0);
+        let moved = parallax_position(origin, anchor, camera, 0.45) - origin;
+        assert!((moved - Vec2::new(90.0, -36.0)).length() < 1e-4);
+    }
+}
@@ -52,6 +52,12 @@ impl ResourceHandles {
     pub fn is_all_done(&self) -> bool {
         self.waiting.is_empty()
     }
+
+    /// How many of the requested resources have loaded, out of how many were requested
+    pub fn progress(&self) -> (usize, usize) {
+        let loaded = self.finished.len();
+        (loaded, loaded + self.waiting.len())
+    }
 }
 
 fn load_resource_assets(world: &mut World) {
@@ -0,0 +1,254 @@
+//! Stats for each attempt at a level, dumped as a line of JSON once the attempt is over: appended
+//! to `attempt_stats.jsonl` on native, logged on the web.
+
+use bevy::prelude::*;
+
+use crate::{
+    PausableSystems,
+    gameplay::{GameState, LevelComplete, StoneStopped},
+    level::{CurrentLevel, OnLevel},
+    level_timer::LevelTimer,
+    replay::ReplayPlayback,
+    screens::Screen,
+    stone::{ReachedGoal, Stone, StoneIsStopped, Velocity},
+    tile::{IsGoal, TileDragging},
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+const ATTEMPT_STATS_PATH: &str = "attempt_stats.jsonl";
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<AttemptStats>()
+        .add_systems(OnEnter(GameState::Countdown), reset_attempt_stats)
+        .add_systems(
+            Update,
+            track_peak_speed
+                .run_if(in_state(Screen::Gameplay).and(in_state(GameState::Playing)))
+                .in_set(PausableSystems),
+        )
+        .add_observer(export_on_level_complete)
+        .add_observer(export_on_stone_stopped);
+}
+
+/// What's tracked over the attempt itself, everything else is read off the level once it's over
+#[derive(Resource, Default, Debug)]
+pub struct AttemptStats {
+    /// Fastest any stone went this attempt
+    pub peak_speed: f32,
+    /// Set once the attempt has been written out, so a win and its stone stopping only export once
+    pub exported: bool,
+}
+
+/// Everything exported about a finished attempt
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttemptSummary {
+    pub level: CurrentLevel,
+    pub reached_goal: bool,
+    /// Closest any stone ended up to the goal, `None` on levels without stones
+    pub final_distance_to_goal: Option<f32>,
+    /// How far the tiles were swept away from their original types, over every tile
+    pub total_sweep_distance: f32,
+    pub elapsed: f32,
+    pub peak_speed: f32,
+    /// Fastest any stone was still going at the end
+    pub final_speed: f32,
+}
+
+impl AttemptSummary {
+    pub fn to_json(&self) -> String {
+        let final_distance_to_goal = match self.final_distance_to_goal {
+            Some(distance) => distance.to_string(),
+            None => "null".to_string(),
+        };
+        format!(
+            "{{\"level\":\"{:?}\",\"reached_goal\":{},\"final_distance_to_goal\":{},\"total_sweep_distance\":{},\"elapsed_secs\":{},\"peak_speed\":{},\"final_speed\":{}}}",
+            self.level,
+            self.reached_goal,
+            final_distance_to_goal,
+            self.total_sweep_distance,
+            self.elapsed,
+            self.peak_speed,
+            self.final_speed,
+        )
+    }
+}
+
+fn reset_attempt_stats(mut attempt_stats: ResMut<AttemptStats>) {
+    *attempt_stats = AttemptStats::default();
+}
+
+fn track_peak_speed(
+    stones: Query<&Velocity, With<Stone>>,
+    mut attempt_stats: ResMut<AttemptStats>,
+) {
+    for velocity in &stones {
+        let speed = velocity.0.length();
+        if speed > attempt_stats.peak_speed {
+            attempt_stats.peak_speed = speed;
+        }
+    }
+}
+
+/// The part of the tile's sweeping that's no longer its original type
+fn swept_away(tile_dragging: &TileDragging, min_sweep_distance: f32) -> f32 {
+    let left = tile_dragging
+        .distance_dragged
+        .get(&tile_dragging.base_tile_type)
+        .copied()
+        .unwrap_or(0.0);
+    (min_sweep_distance - left).max(0.0)
+}
+
+type StoneStats<'a> = (
+    &'a Velocity,
+    &'a Transform,
+    Has<ReachedGoal>,
+    Has<StoneIsStopped>,
+);
+
+fn export_on_level_complete(
+    _event: On<LevelComplete>,
+    on_level: Res<OnLevel>,
+    level_timer: Res<LevelTimer>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    stones: Query<StoneStats, With<Stone>>,
+    goal: Query<&Transform, With<IsGoal>>,
+    tiles: Query<&TileDragging>,
+    mut attempt_stats: ResMut<AttemptStats>,
+) {
+    if replay_playback.is_some() {
+        return;
+    }
+    export(
+        &on_level,
+        &level_timer,
+        &stones,
+        &goal,
+        &tiles,
+        &mut attempt_stats,
+    );
+}
+
+/// Only exports once the last stone has stopped, wins having already been exported
+fn export_on_stone_stopped(
+    _event: On<StoneStopped>,
+    on_level: Res<OnLevel>,
+    level_timer: Res<LevelTimer>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    stones: Query<StoneStats, With<Stone>>,
+    goal: Query<&Transform, With<IsGoal>>,
+    tiles: Query<&TileDragging>,
+    mut attempt_stats: ResMut<AttemptStats>,
+) {
+    if replay_playback.is_some() || !stones.iter().all(|(_, _, _, stopped)| stopped) {
+        return;
+    }
+    export(
+        &on_level,
+        &level_timer,
+        &stones,
+        &goal,
+        &tiles,
+        &mut attempt_stats,
+    );
+}
+
+fn export(
+    on_level: &OnLevel,
+    level_timer: &LevelTimer,
+    stones: &Query<StoneStats, With<Stone>>,
+    goal: &Query<&Transform, With<IsGoal>>,
+    tiles: &Query<&TileDragging>,
+    attempt_stats: &mut AttemptStats,
+) {
+    if attempt_stats.exported {
+        return;
+    }
+    attempt_stats.exported = true;
+
+    let level = &on_level.0;
+    let goal_position = goal.iter().next().map(|goal| goal.translation.truncate());
+    let final_distance_to_goal = goal_position.and_then(|goal_position| {
+        stones
+            .iter()
+            .map(|(_, transform, _, _)| transform.translation.truncate().distance(goal_position))
+            .min_by(f32::total_cmp)
+    });
+    let summary = AttemptSummary {
+        level: level.current_level,
+        reached_goal: level.current_level == CurrentLevel::Level0
+            || stones.iter().any(|(_, _, reached_goal, _)| reached_goal),
+        final_distance_to_goal,
+        total_sweep_distance: tiles
+            .iter()
+            .map(|tile_dragging| swept_away(tile_dragging, level.min_sweep_distance))
+            .sum(),
+        elapsed: level_timer.elapsed,
+        peak_speed: attempt_stats.peak_speed,
+        final_speed: stones
+            .iter()
+            .map(|(velocity, _, _, _)| velocity.0.length())
+            .fold(0.0, f32::max),
+    };
+    write_attempt(&summary.to_json());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_attempt(json: &str) {
+    use std::io::Write;
+
+    let written = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(ATTEMPT_STATS_PATH)
+        .and_then(|mut file| writeln!(file, "{json}"));
+    if let Err(error) = written {
+        warn!("Couldn't write attempt stats to {ATTEMPT_STATS_PATH}: {error}");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_attempt(json: &str) {
+    info!("Attempt stats: {json}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tile::{TileType, sweep};
+
+    #[test]
+    fn test_attempt_summary_to_json() {
+        let summary = AttemptSummary {
+            level: CurrentLevel::Level2,
+            reached_goal: false,
+            final_distance_to_goal: Some(42.5),
+            total_sweep_distance: 300.0,
+            elapsed: 12.25,
+            peak_speed: 400.0,
+            final_speed: 0.0,
+        };
+        assert_eq!(
+            summary.to_json(),
+            "{\"level\":\"Level2\",\"reached_goal\":false,\"final_distance_to_goal\":42.5,\"total_sweep_distance\":300,\"elapsed_secs\":12.25,\"peak_speed\":400,\"final_speed\":0}"
+        );
+        // No stones means no distance, which JSON spells null
+        let no_stones = AttemptSummary {
+            final_distance_to_goal: None,
+            ..summary
+        };
+        assert!(
+            no_stones
+                .to_json()
+                .contains("\"final_distance_to_goal\":null")
+        );
+    }
+
+    #[test]
+    fn test_swept_away_counts_only_converted_sweeping() {
+        let mut tile = TileDragging::new(&TileType::SlowDown, 100.0);
+        assert_eq!(swept_away(&tile, 100.0), 0.0);
+        sweep(&mut tile, &TileType::MaintainSpeed, 30.0);
+        assert_eq!(swept_away(&tile, 100.0), 30.0);
+    }
+}
@@ -0,0 +1,135 @@
+//! Auto-sweep assist. With it turned on in the settings, clicking a tile sweeps the `SlowDown` tiles
+//! on the straight line from the nearest moving stone to the click into `MaintainSpeed`.
+
+use bevy::prelude::*;
+
+use crate::{
+    camera::MainCamera,
+    gameplay::GameState,
+    hex_grid::{HexCoordinate, HexGrid, world_to_hex},
+    level::OnLevel,
+    replay::{ReplayClock, ReplayPlayback, ReplayRecording},
+    stone::{Stone, StoneIsStopped},
+    sweep_budget::SweepBudget,
+    tile::{CanBeDragged, Stroke, TileDragging, TileType, sweeping_allowed},
+};
+
+/// Presses held longer than this are drags that ended where they started, not clicks
+const AUTO_SWEEP_MAX_PRESS_SECS: f32 = 0.3;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<AutoSweep>();
+}
+
+/// Settings toggle for the auto-sweep assist
+#[derive(Resource, Default)]
+pub struct AutoSweep(pub bool);
+
+/// Tiles crossed by the straight line from `from` to `to`, in the order the line reaches them.
+/// Coordinates outside the level are left out.
+pub fn tiles_along_line(hex_grid: &HexGrid, from: Vec2, to: Vec2) -> Vec<HexCoordinate> {
+    // A quarter radius step can't jump over the corner of a hexagon by much
+    let step = hex_grid.hex_radius / 4.0;
+    let steps = (from.distance(to) / step).ceil() as usize;
+    let mut tiles: Vec<HexCoordinate> = Vec::new();
+    for i in 0..=steps {
+        let t = if steps == 0 {
+            0.0
+        } else {
+            i as f32 / steps as f32
+        };
+        let coordinate = world_to_hex(from.lerp(to, t), hex_grid);
+        if hex_grid.level.grid.contains_key(&coordinate) && !tiles.contains(&coordinate) {
+            tiles.push(coordinate);
+        }
+    }
+    tiles
+}
+
+pub fn on_tile_auto_sweep_click(
+    click: On<Pointer<Click>>,
+    auto_sweep: Res<AutoSweep>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    hex_grid: Single<&HexGrid>,
+    stones: Query<&Transform, (With<Stone>, Without<StoneIsStopped>)>,
+    mut tiles: Query<(&HexCoordinate, &mut TileDragging), With<CanBeDragged>>,
+    on_level: Res<OnLevel>,
+    game_state: Res<State<GameState>>,
+    replay_clock: Res<ReplayClock>,
+    mut replay_recording: ResMut<ReplayRecording>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    mut sweep_budget: ResMut<SweepBudget>,
+) {
+    let level = &on_level.0;
+    // Same rules as sweeping by hand
+    let blocked = replay_playback.is_some() || !sweeping_allowed(game_state.get(), level);
+    if !auto_sweep.0
+        || blocked
+        || click.button != PointerButton::Primary
+        || click.duration.as_secs_f32() > AUTO_SWEEP_MAX_PRESS_SECS
+    {
+        return;
+    }
+    let (camera, camera_transform) = *camera;
+    let Ok(target) = camera.viewport_to_world_2d(camera_transform, click.pointer_location.position)
+    else {
+        return;
+    };
+    let Some(from) = stones
+        .iter()
+        .map(|transform| transform.translation.truncate())
+        .min_by(|a, b| a.distance(target).total_cmp(&b.distance(target)))
+    else {
+        return;
+    };
+
+    // Swept from the stone outwards, so a running out budget goes to the nearest tiles
+    let stroke = Stroke::Sweep(TileType::MaintainSpeed);
+    for coordinate in tiles_along_line(&hex_grid, from, target) {
+        let Some((_, mut tile)) = tiles.iter_mut().find(|(tile_coordinate, tile)| {
+            **tile_coordinate == coordinate && tile.base_tile_type == TileType::SlowDown
+        }) else {
+            continue;
+        };
+        let distance = sweep_budget.spend(
+            level,
+            tile.distance_to_complete(&TileType::MaintainSpeed, level.min_sweep_distance),
+        );
+        if distance <= 0.0 {
+            continue;
+        }
+        stroke.apply(&mut tile, distance);
+        replay_recording.record(replay_clock.0, &coordinate, &stroke, distance);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hex_grid::hex_to_world,
+        level::{CurrentLevel, get_level},
+    };
+
+    #[test]
+    fn test_tiles_along_line_follows_level_1_corridor() {
+        // Level1 is a straight diagonal corridor from the stone to the goal, so the line between
+        // their centers crosses every tile in between, in order, and nothing else
+        let level = get_level(CurrentLevel::Level1);
+        let hex_grid = HexGrid::new(&level);
+        let start = level.stone_configs[0].start_coordinate.clone();
+        let goal = level.goal_coordinate.clone();
+        let tiles = tiles_along_line(
+            &hex_grid,
+            hex_to_world(&start, &hex_grid),
+            hex_to_world(&goal, &hex_grid),
+        );
+
+        assert_eq!(tiles.first(), Some(&start));
+        assert_eq!(tiles.last(), Some(&goal));
+        assert_eq!(tiles.len() as i32, start.distance(&goal) + 1);
+        for pair in tiles.windows(2) {
+            assert_eq!(pair[0].distance(&pair[1]), 1);
+        }
+    }
+}
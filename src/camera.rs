@@ -0,0 +1,496 @@
+//! Gameplay camera controls: middle mouse drag to pan, Ctrl + scroll to zoom, and Home to go back
+//! to the framing that fits the whole level on screen. Resizing the window goes back to it too.
+//!
+//! Levels can also have the camera follow the stones while they're moving, toggled with F. It zooms
+//! in and trails them without leaving the level, then eases back to the framing once they stop.
+//! Following their centroid zooms out as far as it takes to keep every moving stone in view. The
+//! camera holds still mid-stroke, so the tiles don't slide out from under the sweep.
+
+use bevy::{
+    input::{
+        common_conditions::input_just_pressed,
+        mouse::{AccumulatedMouseMotion, MouseScrollUnit, MouseWheel},
+    },
+    prelude::*,
+    window::WindowResized,
+};
+use bevy_egui::input::EguiWantsInput;
+
+use crate::{
+    PausableSystems,
+    gameplay::{GameState, setup},
+    hex_grid::{HexGrid, hex_to_world},
+    level::{CurrentLevel, OnLevel},
+    screens::Screen,
+    stone::{ReachedGoal, Stone, StoneIsStopped, Velocity},
+    undo::StrokeUndo,
+};
+
+/// The camera the game is played through, as opposed to the minimap's
+#[derive(Component)]
+pub struct MainCamera;
+
+/// Smallest projection scale, i.e. the most zoomed in the camera can get
+const MIN_ZOOM: f32 = 0.5;
+
+/// Largest projection scale, i.e. the most zoomed out the camera can get
+const MAX_ZOOM: f32 = 3.0;
+
+/// How much one line of scrolling changes the zoom by
+const ZOOM_STEP: f32 = 0.1;
+
+/// Scroll distance in pixels that counts as one line on pixel-precise devices like trackpads
+const PIXELS_PER_LINE: f32 = 50.0;
+
+/// Leaves some room around the level when fitting it on screen
+const FIT_MARGIN: f32 = 1.1;
+
+/// Projection scale while following, a little closer than the level's natural size
+const FOLLOW_ZOOM: f32 = 0.75;
+
+/// Fraction of the view around its center the followed stone can move in without the camera moving
+const FOLLOW_DEADZONE: f32 = 0.3;
+
+/// Toggles following the moving stones
+pub const FOLLOW_KEY: KeyCode = KeyCode::KeyF;
+
+/// How quickly the camera catches up while following or easing back, per second, by default
+const FOLLOW_RATE: f32 = 4.0;
+
+/// Which point the camera follows when more than one stone is moving
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FollowTarget {
+    #[default]
+    Fastest,
+    Centroid,
+}
+
+#[derive(Resource, Debug)]
+pub struct CameraFollow {
+    /// Flipped with F, going against the level's `camera_follow` until the level changes
+    pub toggled: bool,
+    pub target: FollowTarget,
+    /// How quickly the camera catches up while following or easing back, per second
+    pub strength: f32,
+    /// Set while the camera's been moved off the level's framing by following
+    away_from_framing: bool,
+}
+
+impl Default for CameraFollow {
+    fn default() -> Self {
+        Self {
+            toggled: false,
+            target: FollowTarget::default(),
+            strength: FOLLOW_RATE,
+            away_from_framing: false,
+        }
+    }
+}
+
+impl CameraFollow {
+    pub fn enabled(&self, on_level: &OnLevel) -> bool {
+        on_level.0.camera_follow != self.toggled
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<CameraFollow>();
+    app.add_systems(
+        Update,
+        (
+            fit_camera_on_level_change.run_if(resource_exists::<OnLevel>),
+            toggle_camera_follow.run_if(input_just_pressed(FOLLOW_KEY)),
+            (pan_camera, zoom_camera),
+            follow_stones.run_if(resource_exists::<OnLevel>),
+            reset_camera.run_if(resource_exists::<OnLevel>.and(input_just_pressed(KeyCode::Home))),
+        )
+            .chain()
+            .run_if(in_state(Screen::Gameplay))
+            .in_set(PausableSystems),
+    )
+    // Not paused, so resizing the window behind the pause menu still reframes the level
+    .add_systems(
+        Update,
+        fit_camera_on_window_resize
+            .run_if(in_state(Screen::Gameplay).and(resource_exists::<OnLevel>)),
+    )
+    .add_systems(OnEnter(Screen::Gameplay), reset_camera.after(setup))
+    .add_systems(OnExit(Screen::Gameplay), reset_camera_to_origin);
+}
+
+/// Returns true while either Ctrl key is held, which turns scrolling into zooming.
+pub fn zoom_modifier_pressed(input: &ButtonInput<KeyCode>) -> bool {
+    input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+}
+
+/// Returns the camera translation and projection scale that fit the level inside `viewport_size`.
+/// Levels that already fit are never magnified, so small levels look the same as before zooming.
+fn fit_framing(hex_grid: &HexGrid, viewport_size: Vec2) -> (Vec2, f32) {
+    let Some(bounds) = level_bounds(hex_grid) else {
+        return (Vec2::ZERO, 1.0);
+    };
+    let size = bounds.size() * FIT_MARGIN;
+    let scale = (size / viewport_size).max_element().clamp(1.0, MAX_ZOOM);
+    (bounds.center(), scale)
+}
+
+/// World space rectangle covering every tile of the level, or `None` for a level without tiles.
+pub fn level_bounds(hex_grid: &HexGrid) -> Option<Rect> {
+    let mut min = Vec2::splat(f32::MAX);
+    let mut max = Vec2::splat(f32::MIN);
+    for coordinate in hex_grid.level.grid.keys() {
+        let position = hex_to_world(coordinate, hex_grid);
+        min = min.min(position - hex_grid.hex_radius);
+        max = max.max(position + hex_grid.hex_radius);
+    }
+    (min.x <= max.x).then(|| Rect::from_corners(min, max))
+}
+
+fn apply_fit_framing(
+    on_level: &OnLevel,
+    viewport_size: Option<Vec2>,
+    transform: &mut Transform,
+    projection: &mut Projection,
+) {
+    let Some(viewport_size) = viewport_size else {
+        return;
+    };
+    let (center, scale) = fit_framing(&HexGrid::new(&on_level.0), viewport_size);
+    transform.translation.x = center.x;
+    transform.translation.y = center.y;
+    if let Projection::Orthographic(orthographic) = projection {
+        orthographic.scale = scale;
+    }
+}
+
+fn fit_camera_on_level_change(
+    mut last_level: Local<Option<CurrentLevel>>,
+    on_level: Res<OnLevel>,
+    mut camera_follow: ResMut<CameraFollow>,
+    camera: Single<(&Camera, &mut Transform, &mut Projection), With<MainCamera>>,
+) {
+    if *last_level == Some(on_level.0.current_level) {
+        return;
+    }
+    *last_level = Some(on_level.0.current_level);
+    camera_follow.toggled = false;
+    camera_follow.away_from_framing = false;
+
+    let (camera, mut transform, mut projection) = camera.into_inner();
+    apply_fit_framing(
+        &on_level,
+        camera.logical_viewport_size(),
+        &mut transform,
+        &mut projection,
+    );
+}
+
+/// The camera only catches up with the window's new size later in the frame, so this fits to the
+/// size in the resize itself
+fn fit_camera_on_window_resize(
+    mut resizes: MessageReader<WindowResized>,
+    on_level: Res<OnLevel>,
+    camera: Single<(&mut Transform, &mut Projection), With<MainCamera>>,
+) {
+    let Some(resize) = resizes.read().last() else {
+        return;
+    };
+    let (mut transform, mut projection) = camera.into_inner();
+    apply_fit_framing(
+        &on_level,
+        Some(Vec2::new(resize.width, resize.height)),
+        &mut transform,
+        &mut projection,
+    );
+}
+
+fn reset_camera(
+    on_level: Res<OnLevel>,
+    camera: Single<(&Camera, &mut Transform, &mut Projection), With<MainCamera>>,
+) {
+    let (camera, mut transform, mut projection) = camera.into_inner();
+    apply_fit_framing(
+        &on_level,
+        camera.logical_viewport_size(),
+        &mut transform,
+        &mut projection,
+    );
+}
+
+fn pan_camera(
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    camera: Single<(&mut Transform, &Projection), With<MainCamera>>,
+) {
+    if !mouse_buttons.pressed(MouseButton::Middle) || mouse_motion.delta == Vec2::ZERO {
+        return;
+    }
+    let (mut transform, projection) = camera.into_inner();
+    let Projection::Orthographic(orthographic) = projection else {
+        return;
+    };
+    // Screen y points down while world y points up
+    let delta = Vec2::new(-mouse_motion.delta.x, mouse_motion.delta.y) * orthographic.scale;
+    transform.translation += delta.extend(0.0);
+}
+
+fn zoom_camera(
+    input: Res<ButtonInput<KeyCode>>,
+    mut mouse_wheel: MessageReader<MouseWheel>,
+    egui_wants_input: Option<Res<EguiWantsInput>>,
+    window: Single<&Window>,
+    camera: Single<(&Camera, &mut Transform, &mut Projection), With<MainCamera>>,
+) {
+    let lines: f32 = mouse_wheel
+        .read()
+        .map(|wheel| match wheel.unit {
+            MouseScrollUnit::Line => wheel.y,
+            MouseScrollUnit::Pixel => wheel.y / PIXELS_PER_LINE,
+        })
+        .sum();
+    // Leave scrolling to the debug UI when the pointer is over it
+    let egui_has_pointer = egui_wants_input.is_some_and(|egui| egui.wants_any_pointer_input());
+    if lines == 0.0 || !zoom_modifier_pressed(&input) || egui_has_pointer {
+        return;
+    }
+
+    let (camera, mut transform, mut projection) = camera.into_inner();
+    let Projection::Orthographic(orthographic) = &mut *projection else {
+        return;
+    };
+    let old_scale = orthographic.scale;
+    let new_scale = (old_scale * (1.0 - ZOOM_STEP).powf(lines)).clamp(MIN_ZOOM, MAX_ZOOM);
+    orthographic.scale = new_scale;
+
+    // Keep the world point under the cursor fixed while zooming
+    if let (Some(cursor), Some(viewport_size)) =
+        (window.cursor_position(), camera.logical_viewport_size())
+    {
+        let offset = (cursor - viewport_size / 2.0) * Vec2::new(1.0, -1.0);
+        transform.translation += (offset * (old_scale - new_scale)).extend(0.0);
+    }
+}
+
+fn toggle_camera_follow(mut camera_follow: ResMut<CameraFollow>) {
+    camera_follow.toggled = !camera_follow.toggled;
+}
+
+/// Where the camera has to move to for `target` to be inside the deadzone around its center,
+/// moving as little as it can
+pub fn deadzone_follow(center: Vec2, target: Vec2, half_deadzone: Vec2) -> Vec2 {
+    let offset = target - center;
+    center + offset - offset.clamp(-half_deadzone, half_deadzone)
+}
+
+/// Keeps a view reaching `half_view` either side of `center` inside `bounds`. Along an axis where
+/// the view is wider than the bounds, it's centered on them instead.
+pub fn clamp_to_bounds(center: Vec2, half_view: Vec2, bounds: Rect) -> Vec2 {
+    let min = bounds.min + half_view;
+    let max = bounds.max - half_view;
+    let axis = |value: f32, min: f32, max: f32, middle: f32| {
+        if min > max {
+            middle
+        } else {
+            value.clamp(min, max)
+        }
+    };
+    let middle = bounds.center();
+    Vec2::new(
+        axis(center.x, min.x, max.x, middle.x),
+        axis(center.y, min.y, max.y, middle.y),
+    )
+}
+
+/// Centered on the centroid of `positions`, zoomed out from `FOLLOW_ZOOM` as far as it takes to
+/// fit every stone of `radius` at them, but never further than the level's own `fit_scale`
+pub fn centroid_framing(
+    positions: &[Vec2],
+    radius: f32,
+    viewport_size: Vec2,
+    fit_scale: f32,
+) -> Option<(Vec2, f32)> {
+    if positions.is_empty() {
+        return None;
+    }
+    let centroid = positions.iter().sum::<Vec2>() / positions.len() as f32;
+    // Each way from the centroid, so the stones all fit with it in the middle
+    let reach = positions.iter().fold(Vec2::ZERO, |reach, position| {
+        reach.max((*position - centroid).abs())
+    }) + Vec2::splat(radius);
+    let needed = (reach * 2.0 * FIT_MARGIN / viewport_size).max_element();
+    let scale = needed.clamp(FOLLOW_ZOOM, fit_scale.max(FOLLOW_ZOOM));
+    Some((centroid, scale.clamp(MIN_ZOOM, MAX_ZOOM)))
+}
+
+/// Follows the moving stones while the level's playing, and eases back to the level's framing once
+/// they've all stopped or reached the goal
+fn follow_stones(
+    time: Res<Time>,
+    on_level: Res<OnLevel>,
+    game_state: Res<State<GameState>>,
+    mut camera_follow: ResMut<CameraFollow>,
+    stroke_undo: Res<StrokeUndo>,
+    hex_grid: Single<&HexGrid>,
+    stones: Query<
+        (&Stone, &Transform, &Velocity),
+        (
+            With<Stone>,
+            Without<StoneIsStopped>,
+            Without<ReachedGoal>,
+            Without<MainCamera>,
+        ),
+    >,
+    camera: Single<(&Camera, &mut Transform, &mut Projection), With<MainCamera>>,
+) {
+    let (camera, mut transform, mut projection) = camera.into_inner();
+    let (Projection::Orthographic(orthographic), Some(viewport_size)) =
+        (&mut *projection, camera.logical_viewport_size())
+    else {
+        return;
+    };
+    // Moving the board mid-stroke would sweep whatever tiles it slid under the pointer
+    if stroke_undo.in_progress() {
+        return;
+    }
+    let following = camera_follow.enabled(&on_level) && *game_state.get() == GameState::Playing;
+    let center = transform.translation.truncate();
+    let catch_up = 1.0 - (-camera_follow.strength * time.delta_secs()).exp();
+    let (fit_center, fit_scale) = fit_framing(&hex_grid, viewport_size);
+
+    let framing = match camera_follow.target {
+        FollowTarget::Fastest => stones
+            .iter()
+            .max_by(|(_, _, a), (_, _, b)| a.0.length().total_cmp(&b.0.length()))
+            .map(|(_, transform, _)| {
+                let scale = FOLLOW_ZOOM.clamp(MIN_ZOOM, MAX_ZOOM);
+                let half_deadzone = viewport_size * scale / 2.0 * FOLLOW_DEADZONE;
+                let target = transform.translation.truncate();
+                (deadzone_follow(center, target, half_deadzone), scale)
+            }),
+        FollowTarget::Centroid => {
+            let positions: Vec<Vec2> = stones
+                .iter()
+                .map(|(_, transform, _)| transform.translation.truncate())
+                .collect();
+            let radius = stones
+                .iter()
+                .map(|(stone, _, _)| stone.radius)
+                .fold(0.0, f32::max);
+            centroid_framing(&positions, radius, viewport_size, fit_scale)
+        }
+    };
+
+    let followed = framing.filter(|_| following);
+    let (goal_center, goal_scale) = match followed {
+        Some((wanted, scale)) => {
+            camera_follow.away_from_framing = true;
+            let half_view = viewport_size * scale / 2.0;
+            let wanted = clamp_to_bounds(
+                wanted,
+                half_view,
+                level_bounds(&hex_grid).unwrap_or(Rect::from_center_size(wanted, Vec2::ZERO)),
+            );
+            (wanted, scale)
+        }
+        None if camera_follow.away_from_framing => (fit_center, fit_scale),
+        None => return,
+    };
+
+    let new_center = center.lerp(goal_center, catch_up);
+    let new_scale = orthographic.scale + (goal_scale - orthographic.scale) * catch_up;
+    // Close enough to the framing to call it back
+    if followed.is_none()
+        && new_center.distance(goal_center) < 0.5
+        && (new_scale - goal_scale).abs() < 0.001
+    {
+        transform.translation.x = goal_center.x;
+        transform.translation.y = goal_center.y;
+        orthographic.scale = goal_scale;
+        camera_follow.away_from_framing = false;
+        return;
+    }
+    transform.translation.x = new_center.x;
+    transform.translation.y = new_center.y;
+    orthographic.scale = new_scale;
+}
+
+fn reset_camera_to_origin(mut camera: Single<(&mut Transform, &mut Projection), With<MainCamera>>) {
+    let (transform, projection) = &mut *camera;
+    transform.translation = Vec3::ZERO;
+    if let Projection::Orthographic(orthographic) = &mut **projection {
+        orthographic.scale = 1.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::get_level;
+
+    #[test]
+    fn test_fit_framing_does_not_magnify_small_levels() {
+        // Level1 fits in the 1024x768 window, so it stays at its natural size
+        let hex_grid = HexGrid::new(&get_level(CurrentLevel::Level1));
+        let (_, scale) = fit_framing(&hex_grid, Vec2::new(1024.0, 768.0));
+        assert_eq!(scale, 1.0);
+    }
+
+    #[test]
+    fn test_follow_stays_in_the_deadzone_and_the_level() {
+        let half_deadzone = Vec2::new(50.0, 40.0);
+        // Inside the deadzone the camera stays put
+        assert_eq!(
+            deadzone_follow(Vec2::ZERO, Vec2::new(30.0, -20.0), half_deadzone),
+            Vec2::ZERO
+        );
+        // Outside it only moves far enough to bring the target back to its edge
+        assert_eq!(
+            deadzone_follow(Vec2::ZERO, Vec2::new(80.0, -100.0), half_deadzone),
+            Vec2::new(30.0, -60.0)
+        );
+
+        let bounds = Rect::new(0.0, 0.0, 1000.0, 300.0);
+        let half_view = Vec2::new(200.0, 200.0);
+        // The view can't go past the level's left edge, and is taller than the level so it stays
+        // centered vertically
+        assert_eq!(
+            clamp_to_bounds(Vec2::new(50.0, 290.0), half_view, bounds),
+            Vec2::new(200.0, 150.0)
+        );
+        assert_eq!(
+            clamp_to_bounds(Vec2::new(500.0, 0.0), half_view, bounds),
+            Vec2::new(500.0, 150.0)
+        );
+    }
+
+    #[test]
+    fn test_fit_framing_zooms_out_for_small_viewports() {
+        // A quarter-size viewport has to zoom out to fit the same level
+        let hex_grid = HexGrid::new(&get_level(CurrentLevel::Level1));
+        let (full_center, _) = fit_framing(&hex_grid, Vec2::new(1024.0, 768.0));
+        let (half_center, half_scale) = fit_framing(&hex_grid, Vec2::new(256.0, 192.0));
+        assert!(half_scale > 1.0);
+        // Framing is always centered on the level regardless of zoom
+        assert_eq!(full_center, half_center);
+    }
+
+    #[test]
+    fn test_centroid_framing_zooms_out_to_keep_every_stone_in_view() {
+        let viewport_size = Vec2::new(1000.0, 500.0);
+        assert_eq!(centroid_framing(&[], 10.0, viewport_size, 2.0), None);
+
+        // Stones close together get the usual follow zoom, centered between them
+        let close = [Vec2::new(0.0, 0.0), Vec2::new(40.0, 20.0)];
+        let (center, scale) = centroid_framing(&close, 10.0, viewport_size, 2.0).unwrap();
+        assert_eq!(center, Vec2::new(20.0, 10.0));
+        assert_eq!(scale, FOLLOW_ZOOM);
+
+        // Spread out, it zooms out far enough for the tallest gap to fit with a margin
+        let apart = [Vec2::new(0.0, -300.0), Vec2::new(0.0, 300.0)];
+        let (_, scale) = centroid_framing(&apart, 10.0, viewport_size, 2.0).unwrap();
+        assert!((scale - 620.0 * FIT_MARGIN / 500.0).abs() < 1e-5, "{scale}");
+        // But never past where the whole level already fits
+        let (_, scale) = centroid_framing(&apart, 10.0, viewport_size, 1.0).unwrap();
+        assert_eq!(scale, 1.0);
+    }
+}
@@ -3,15 +3,60 @@ use bevy::render::render_resource::AsBindGroup;
 use bevy::shader::ShaderRef;
 use bevy::sprite_render::Material2d;
 
+/// Share of a full celebration's confetti that a win sweeping every tile still gets
+const MIN_CONFETTI_INTENSITY: f32 = 0.33;
+
+/// How big a celebration a win earns, from `MIN_CONFETTI_INTENSITY` up to 1.0. The fewer of the
+/// level's sweepable tiles it took, the more confetti.
+pub fn celebration_intensity(tiles_swept: usize, sweepable_tiles: usize) -> f32 {
+    if sweepable_tiles == 0 {
+        return 1.0;
+    }
+    let untouched = 1.0 - (tiles_swept as f32 / sweepable_tiles as f32).min(1.0);
+    MIN_CONFETTI_INTENSITY + (1.0 - MIN_CONFETTI_INTENSITY) * untouched
+}
+
 #[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
 pub struct ConfettiMaterial {
-    /// x = time, y = framebuffer_width, z = framebuffer_height, w = unused
+    /// x = time, y = framebuffer_width, z = framebuffer_height, w = intensity from 0 to 1
     #[uniform(0)]
     pub params: Vec4,
 }
 
+impl ConfettiMaterial {
+    /// Framebuffer size to draw the confetti at, in physical pixels so HiDPI screens aren't
+    /// stretched
+    pub fn resolution(window: &Window) -> Vec2 {
+        window.physical_size().as_vec2()
+    }
+
+    /// Points the shader at a new framebuffer size, for when the window is resized
+    pub fn set_resolution(&mut self, resolution: Vec2) {
+        self.params.y = resolution.x;
+        self.params.z = resolution.y;
+    }
+}
+
 impl Material2d for ConfettiMaterial {
     fn fragment_shader() -> ShaderRef {
         "shaders/confetti.wgsl".into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_celebration_intensity() {
+        // Winning without sweeping is the biggest celebration
+        assert_eq!(celebration_intensity(0, 10), 1.0);
+        // Sweeping everything still gets some confetti
+        assert_eq!(celebration_intensity(10, 10), MIN_CONFETTI_INTENSITY);
+        // Halfway in between
+        let half = (1.0 + MIN_CONFETTI_INTENSITY) / 2.0;
+        assert!((celebration_intensity(5, 10) - half).abs() < 1e-6);
+        // Levels with nothing to sweep always get the full celebration
+        assert_eq!(celebration_intensity(0, 0), 1.0);
+    }
+}
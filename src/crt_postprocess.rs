@@ -1,8 +1,14 @@
 //! CRT Post-Processing Effect
 //!
 //! Adds a retro CRT monitor effect with scanlines, curvature, chromatic aberration, and vignette.
+//! Each level starts with the screen powering on, a short flash and bulge with a white line swept
+//! down it, unless motion is reduced in the settings.
+//!
+//! If the shader's missing or doesn't compile the pass is skipped, which leaves the picture as it
+//! was, and a warning says why the effect is off.
 
 use bevy::{
+    asset::LoadState,
     core_pipeline::{
         FullscreenShader,
         core_2d::graph::{Core2d, Node2d},
@@ -10,7 +16,7 @@ use bevy::{
     ecs::query::QueryItem,
     prelude::*,
     render::{
-        RenderApp, RenderStartup,
+        Render, RenderApp, RenderStartup, RenderSystems,
         extract_component::{
             ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
             UniformComponentPlugin,
@@ -27,8 +33,20 @@ use bevy::{
     },
 };
 
+use crate::{
+    camera::MainCamera,
+    gameplay::{LevelStart, ReduceMotion},
+};
+
 const SHADER_ASSET_PATH: &str = "shaders/crt.wgsl";
 
+/// How long the power-on animation takes
+const POWER_ON_SECS: f32 = 0.6;
+/// Curvature added on top of the steady value at the start of the power-on
+const POWER_ON_CURVATURE: f32 = 0.4;
+/// Brightness multiplier at the start of the power-on
+const POWER_ON_BRIGHTNESS: f32 = 2.5;
+
 /// Plugin that adds CRT post-processing effect to 2D cameras
 pub struct CrtPostProcessPlugin;
 
@@ -37,13 +55,20 @@ impl Plugin for CrtPostProcessPlugin {
         app.add_plugins((
             ExtractComponentPlugin::<CrtSettings>::default(),
             UniformComponentPlugin::<CrtSettings>::default(),
-        ));
+        ))
+        .add_systems(Update, animate_crt_power_on)
+        .add_observer(start_crt_power_on);
 
         let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
             return;
         };
 
-        render_app.add_systems(RenderStartup, init_crt_pipeline);
+        render_app
+            .add_systems(RenderStartup, init_crt_pipeline)
+            .add_systems(
+                Render,
+                warn_if_crt_unavailable.in_set(RenderSystems::Cleanup),
+            );
 
         render_app
             .add_render_graph_node::<ViewNodeRunner<CrtPostProcessNode>>(
@@ -81,9 +106,13 @@ impl ViewNode for CrtPostProcessNode {
         (view_target, _crt_settings, settings_index): QueryItem<Self::ViewQuery>,
         world: &World,
     ) -> Result<(), NodeRunError> {
-        let crt_pipeline = world.resource::<CrtPipeline>();
+        let Some(crt_pipeline) = world.get_resource::<CrtPipeline>() else {
+            return Ok(());
+        };
         let pipeline_cache = world.resource::<PipelineCache>();
 
+        // Until the pipeline's ready, or for good if it never will be, nothing's written to the
+        // post process target and the frame goes on unchanged
         let Some(pipeline) = pipeline_cache.get_render_pipeline(crt_pipeline.pipeline_id) else {
             return Ok(());
         };
@@ -130,6 +159,7 @@ impl ViewNode for CrtPostProcessNode {
 struct CrtPipeline {
     layout: BindGroupLayoutDescriptor,
     sampler: Sampler,
+    shader: Handle<Shader>,
     pipeline_id: CachedRenderPipelineId,
 }
 
@@ -161,7 +191,7 @@ fn init_crt_pipeline(
         layout: vec![layout.clone()],
         vertex: vertex_state,
         fragment: Some(FragmentState {
-            shader,
+            shader: shader.clone(),
             targets: vec![Some(ColorTargetState {
                 format: TextureFormat::bevy_default(),
                 blend: None,
@@ -175,13 +205,39 @@ fn init_crt_pipeline(
     commands.insert_resource(CrtPipeline {
         layout,
         sampler,
+        shader,
         pipeline_id,
     });
 }
 
+/// A shader that can't be loaded leaves the pipeline queued forever rather than failed, so that's
+/// checked on the asset as well
+fn warn_if_crt_unavailable(
+    crt_pipeline: Option<Res<CrtPipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    asset_server: Res<AssetServer>,
+    mut warned: Local<bool>,
+) {
+    let Some(crt_pipeline) = crt_pipeline.filter(|_| !*warned) else {
+        return;
+    };
+    let problem =
+        if let Some(LoadState::Failed(error)) = asset_server.get_load_state(&crt_pipeline.shader) {
+            format!("couldn't load {SHADER_ASSET_PATH}: {error}")
+        } else if let CachedPipelineState::Err(error) =
+            pipeline_cache.get_render_pipeline_state(crt_pipeline.pipeline_id)
+        {
+            format!("its pipeline failed to compile: {error}")
+        } else {
+            return;
+        };
+    warn!("The CRT effect is off, {problem}");
+    *warned = true;
+}
+
 /// Settings for the CRT post-processing effect.
 /// Add this component to a Camera2d to enable the effect.
-#[derive(Component, Clone, Copy, ExtractComponent, ShaderType)]
+#[derive(Component, Clone, Copy, Debug, PartialEq, ExtractComponent, ShaderType)]
 pub struct CrtSettings {
     /// Scanline intensity (0.0 = no scanlines, 1.0 = full intensity)
     pub scanline_intensity: f32,
@@ -199,6 +255,12 @@ pub struct CrtSettings {
     pub noise_intensity: f32,
     /// Time for animated effects (scanline flicker, noise)
     pub time: f32,
+    /// Height of the power-on line as a fraction of the screen, negative while there isn't one
+    pub power_line: f32,
+    /// How bright the power-on line is
+    pub power_line_intensity: f32,
+    /// WebGL2 needs uniforms to be a multiple of 16 bytes
+    pub _webgl2_padding: Vec2,
 }
 
 impl Default for CrtSettings {
@@ -212,6 +274,86 @@ impl Default for CrtSettings {
             brightness: 1.,
             noise_intensity: 0.003,
             time: 0.0,
+            power_line: -1.0,
+            power_line_intensity: 0.0,
+            _webgl2_padding: Vec2::ZERO,
+        }
+    }
+}
+
+/// The CRT powering on, on the camera for the length of the animation. The settings the player
+/// chose are kept here meanwhile and put back once it's done.
+#[derive(Component, Clone, Debug)]
+pub struct CrtAnimation {
+    pub elapsed: f32,
+    pub steady: CrtSettings,
+}
+
+impl CrtAnimation {
+    /// The settings the player chose, rather than whatever the animation is showing
+    pub fn steady_settings<'a>(
+        settings: &'a CrtSettings,
+        animation: Option<&'a CrtAnimation>,
+    ) -> &'a CrtSettings {
+        animation.map_or(settings, |animation| &animation.steady)
+    }
+}
+
+/// `steady` part way through powering on, `progress` going from 0 to 1. The bulge and flash settle
+/// back as the line sweeps down, ending on `steady` exactly.
+pub fn power_on(steady: &CrtSettings, progress: f32) -> CrtSettings {
+    if progress >= 1.0 {
+        return *steady;
+    }
+    let left = 1.0 - progress.max(0.0);
+    CrtSettings {
+        curvature: steady.curvature + POWER_ON_CURVATURE * left * left,
+        brightness: steady.brightness * (1.0 + (POWER_ON_BRIGHTNESS - 1.0) * left * left),
+        power_line: progress.max(0.0),
+        power_line_intensity: left,
+        ..*steady
+    }
+}
+
+/// Restarting mid-animation starts over from the same steady settings
+fn start_crt_power_on(
+    _event: On<LevelStart>,
+    mut commands: Commands,
+    reduce_motion: Res<ReduceMotion>,
+    camera: Query<(Entity, &CrtSettings, Option<&CrtAnimation>), With<MainCamera>>,
+) {
+    let Ok((entity, settings, animation)) = camera.single() else {
+        return;
+    };
+    if reduce_motion.0 {
+        return;
+    }
+    commands.entity(entity).insert(CrtAnimation {
+        elapsed: 0.0,
+        steady: *CrtAnimation::steady_settings(settings, animation),
+    });
+}
+
+/// Turning on reduce motion mid-animation skips to the end
+fn animate_crt_power_on(
+    mut commands: Commands,
+    time: Res<Time>,
+    reduce_motion: Res<ReduceMotion>,
+    mut camera: Query<(Entity, &mut CrtSettings, &mut CrtAnimation)>,
+) {
+    for (entity, mut settings, mut animation) in &mut camera {
+        animation.elapsed += time.delta_secs();
+        let progress = if reduce_motion.0 {
+            1.0
+        } else {
+            animation.elapsed / POWER_ON_SECS
+        };
+        *settings = CrtSettings {
+            time: settings.time,
+            ..power_on(&animation.steady, progress)
+        };
+        if progress >= 1.0 {
+            commands.entity(entity).remove::<CrtAnimation>();
         }
     }
 }
@@ -222,3 +364,31 @@ pub fn update_crt_time(time: Res<Time>, mut settings: Query<&mut CrtSettings>) {
         setting.time = time.elapsed_secs();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_power_on_settles_on_the_steady_settings() {
+        let steady = CrtSettings {
+            curvature: 0.1,
+            brightness: 0.8,
+            ..default()
+        };
+        let start = power_on(&steady, 0.0);
+        assert_eq!(start.curvature, 0.1 + POWER_ON_CURVATURE);
+        assert_eq!(start.brightness, 0.8 * POWER_ON_BRIGHTNESS);
+        assert_eq!(start.power_line, 0.0);
+        // Halfway the line is halfway down and the bulge and flash have mostly settled
+        let halfway = power_on(&steady, 0.5);
+        assert_eq!(halfway.power_line, 0.5);
+        assert!(halfway.curvature > steady.curvature && halfway.curvature < start.curvature);
+        assert!(halfway.brightness > steady.brightness && halfway.brightness < start.brightness);
+        // The rest is untouched throughout, and it ends on what the player chose with no line
+        assert_eq!(halfway.scanline_count, steady.scanline_count);
+        assert_eq!(power_on(&steady, 1.0), steady);
+        assert_eq!(power_on(&steady, 1.5), steady);
+        assert!(steady.power_line < 0.0);
+    }
+}
@@ -1,18 +1,55 @@
+//! Egui debug window for tuning level parameters live. Only enabled in dev builds.
+
 use bevy::prelude::*;
-use bevy_egui::{EguiContexts, egui};
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
 
 use crate::{
+    camera::{CameraFollow, FollowTarget, MainCamera},
+    crt_postprocess::{CrtAnimation, CrtSettings},
+    fire_trail::ClassicFireTrail,
     gameplay::restart_game,
-    hex_grid::HexGrid,
-    level::{CurrentLevel, Facing, OnLevel, get_level},
-    stone::Stone,
-    tile::{CurrentDragTileType, ScratchOffMaterial},
+    hex_grid::{HexCoordinate, HexGrid},
+    level::{CurrentLevel, Facing, Level, OnLevel, get_level},
+    minimap::MINIMAP_BOTTOM,
+    replay::{PlayReplay, Replay, ReplayClock, ReplayRecording},
+    screens::Screen,
+    sim::PhysicsParams,
+    solver::solve,
+    speed_readout::ShowStoneSpeed,
+    stone::{Stone, StoneSkin, StoneSkins},
+    tile::{
+        CurrentDragTileType, ScratchOffMaterial, ShowWallFlash, Stroke, TileAssets, TileDragging,
+        TileType,
+    },
 };
 
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            sync_debug_ui_state,
+            (step_level_on_key, on_debug_ui_level_change)
+                .chain()
+                .run_if(resource_exists::<DebugUIState>),
+        )
+            .chain()
+            .run_if(in_state(Screen::Gameplay).and(resource_exists::<OnLevel>)),
+    )
+    .add_systems(
+        EguiPrimaryContextPass,
+        debug_ui.run_if(in_state(Screen::Gameplay).and(resource_exists::<DebugUIState>)),
+    );
+}
+
 #[derive(Clone, Debug)]
 pub struct StoneUIConfig {
     pub velocity_magnitude: f32,
     pub facing: Facing,
+    /// sRGB, the format egui's color picker edits
+    pub color: [f32; 3],
+    /// `None` uses the skin picked in the settings
+    pub skin: Option<StoneSkin>,
+    pub start_offset: Vec2,
 }
 
 #[derive(Resource, Clone, Debug)]
@@ -26,20 +63,189 @@ pub struct DebugUIState {
     pub rotation_factor: f32,
     pub snap_distance: f32,
     pub snap_velocity: f32,
+    pub stop_velocity: f32,
+    pub overlap_samples: u32,
     pub current_level: CurrentLevel,
     pub speed_up_factor: f32,
+    pub restitution: f32,
+    pub wall_friction: f32,
+    pub erase_rate: f32,
+    pub live_sweeping: bool,
+    pub rewind: bool,
+    pub fixed_throw: bool,
+    pub camera_follow: bool,
+    pub min_throw_speed: f32,
+    pub max_throw_speed: f32,
+    pub double_click_sweep: bool,
+    pub double_click_window: f32,
+    pub keyboard_sweep_rate: f32,
+    pub sweep_slow_speed: f32,
+    pub sweep_fast_speed: f32,
+    pub sweep_slow_multiplier: f32,
+    pub sweep_fast_multiplier: f32,
+    pub precision_sweep_scale: f32,
+    pub pick_tolerance: f32,
+    pub sweep_budget_enabled: bool,
+    pub sweep_budget: f32,
+    pub par: usize,
+    pub replay_code: String,
+    pub replay_error: Option<String>,
+    pub solver_message: Option<String>,
+}
+
+impl DebugUIState {
+    pub fn from_level(level: &Level) -> Self {
+        Self {
+            drag_coefficient: level.drag_coefficient,
+            stone_configs: level
+                .stone_configs
+                .iter()
+                .map(|stone_config| StoneUIConfig {
+                    velocity_magnitude: stone_config.velocity_magnitude,
+                    facing: stone_config.facing.clone(),
+                    color: {
+                        let color = stone_config.color.to_srgba();
+                        [color.red, color.green, color.blue]
+                    },
+                    skin: stone_config.skin,
+                    start_offset: stone_config.start_offset,
+                })
+                .collect(),
+            min_sweep_distance: level.min_sweep_distance,
+            hex_radius: level.hex_radius,
+            stone_radius: level.stone_radius,
+            slow_down_factor: level.slow_down_factor,
+            rotation_factor: level.rotation_factor,
+            snap_distance: level.snap_distance,
+            snap_velocity: level.snap_velocity,
+            stop_velocity: level.stop_velocity,
+            overlap_samples: level.overlap_samples,
+            current_level: level.current_level,
+            speed_up_factor: level.speed_up_factor,
+            restitution: level.restitution,
+            wall_friction: level.wall_friction,
+            erase_rate: level.erase_rate,
+            live_sweeping: level.live_sweeping,
+            rewind: level.rewind,
+            fixed_throw: level.fixed_throw,
+            camera_follow: level.camera_follow,
+            min_throw_speed: level.min_throw_speed,
+            max_throw_speed: level.max_throw_speed,
+            double_click_sweep: level.double_click_sweep,
+            double_click_window: level.double_click_window,
+            keyboard_sweep_rate: level.keyboard_sweep_rate,
+            sweep_slow_speed: level.sweep_slow_speed,
+            sweep_fast_speed: level.sweep_fast_speed,
+            sweep_slow_multiplier: level.sweep_slow_multiplier,
+            sweep_fast_multiplier: level.sweep_fast_multiplier,
+            precision_sweep_scale: level.precision_sweep_scale,
+            pick_tolerance: level.pick_tolerance,
+            sweep_budget_enabled: level.sweep_budget.is_some(),
+            sweep_budget: level.sweep_budget.unwrap_or(2000.0),
+            par: level.par,
+            replay_code: String::new(),
+            replay_error: None,
+            solver_message: None,
+        }
+    }
+}
+
+/// Resets the debug UI to the active level's values whenever the level changes,
+/// so stale sliders from the previous level don't get written back into it.
+fn sync_debug_ui_state(
+    mut commands: Commands,
+    mut last_level: Local<Option<CurrentLevel>>,
+    on_level: Res<OnLevel>,
+    debug_ui_state: Option<Res<DebugUIState>>,
+) {
+    if *last_level != Some(on_level.0.current_level) {
+        *last_level = Some(on_level.0.current_level);
+        let mut new_state = DebugUIState::from_level(&on_level.0);
+        // Keep a pasted replay code around, playing it switches levels
+        if let Some(debug_ui_state) = debug_ui_state {
+            new_state.replay_code = debug_ui_state.replay_code.clone();
+        }
+        commands.insert_resource(new_state);
+    }
 }
 
 pub fn debug_ui(
+    mut commands: Commands,
     mut contexts: EguiContexts,
     mut debug_ui_state: ResMut<DebugUIState>,
     mut on_level: ResMut<OnLevel>,
+    replay_clock: Res<ReplayClock>,
+    mut replay_recording: ResMut<ReplayRecording>,
+    mut tiles: Query<(&HexCoordinate, &mut TileDragging)>,
+    mut show_stone_speed: ResMut<ShowStoneSpeed>,
+    mut show_wall_flash: ResMut<ShowWallFlash>,
+    mut classic_fire_trail: ResMut<ClassicFireTrail>,
+    ui_scale: Res<UiScale>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    mut crt: Query<(&mut CrtSettings, Option<&mut CrtAnimation>), With<MainCamera>>,
+    mut camera_follow: ResMut<CameraFollow>,
 ) -> Result {
+    // Show the player's aim in place of the configured throw
+    if debug_ui_state.current_level == on_level.0.current_level {
+        for (ui_config, stone_config) in debug_ui_state
+            .stone_configs
+            .iter_mut()
+            .zip(&on_level.0.stone_configs)
+        {
+            if let Some(aimed) = stone_config.aimed_velocity {
+                ui_config.velocity_magnitude = aimed.length();
+                ui_config.facing = Facing::nearest(aimed);
+            }
+        }
+    }
+
+    let ctx = contexts.ctx_mut()?;
+    // Starts out under the minimap, clear of the level's UI in the other corners
+    let below_minimap = egui::pos2(
+        ctx.content_rect().right() - 16.0,
+        MINIMAP_BOTTOM * ui_scale.0 + 16.0,
+    );
     egui::Window::new("Debug")
         .default_open(false)
-        .show(contexts.ctx_mut()?, |debug_ui| {
+        .pivot(egui::Align2::RIGHT_TOP)
+        .default_pos(below_minimap)
+        .show(ctx, |debug_ui| {
             debug_ui.add(egui::Label::new("R to restart"));
             debug_ui.add(egui::Label::new("Space to pause/resume"));
+            debug_ui.checkbox(&mut show_stone_speed.0, "Show Speed");
+            debug_ui.checkbox(&mut show_wall_flash.0, "Flash Near-Miss Walls");
+            debug_ui.checkbox(&mut classic_fire_trail.0, "Classic Fire Trail");
+            // Not part of the level, so it stays put across level changes
+            let mut physics_hz = 1.0 / fixed_time.timestep().as_secs_f64();
+            if debug_ui
+                .add(egui::Slider::new(&mut physics_hz, 30.0..=240.0).text("Physics Rate (Hz)"))
+                .changed()
+            {
+                fixed_time.set_timestep_hz(physics_hz);
+            }
+            if let Ok((mut crt_settings, crt_animation)) = crt.single_mut() {
+                // Mid power-on, the sliders change what it settles back to
+                let steady = match crt_animation {
+                    Some(crt_animation) => &mut crt_animation.into_inner().steady,
+                    None => crt_settings.as_mut(),
+                };
+                debug_ui.collapsing("CRT", |debug_ui| crt_sliders(debug_ui, steady));
+            }
+            egui::ComboBox::from_label("Camera Follows")
+                .selected_text(format!("{:?}", camera_follow.target))
+                .show_ui(debug_ui, |debug_ui| {
+                    for target in [FollowTarget::Fastest, FollowTarget::Centroid] {
+                        debug_ui.selectable_value(
+                            &mut camera_follow.target,
+                            target,
+                            format!("{target:?}"),
+                        );
+                    }
+                });
+            debug_ui.add(
+                egui::Slider::new(&mut camera_follow.strength, 0.5..=12.0)
+                    .text("Camera Follow Strength"),
+            );
 
             debug_ui.separator();
             debug_ui.add(egui::Label::new("Level Selection"));
@@ -85,14 +291,89 @@ pub fn debug_ui(
                 egui::Slider::new(&mut debug_ui_state.snap_velocity, 5.0..=100.0)
                     .text("Snap Velocity"),
             );
+            debug_ui.add(
+                egui::Slider::new(&mut debug_ui_state.stop_velocity, 0.1..=20.0)
+                    .text("Stop Velocity"),
+            );
+            debug_ui.add(
+                egui::Slider::new(&mut debug_ui_state.overlap_samples, 6..=120)
+                    .text("Overlap Samples"),
+            );
             debug_ui.add(
                 egui::Slider::new(&mut debug_ui_state.speed_up_factor, 0.0..=500.0)
                     .text("Speed Up Factor"),
             );
+            debug_ui.add(
+                egui::Slider::new(&mut debug_ui_state.restitution, 0.0..=1.0)
+                    .text("Stone Restitution"),
+            );
+            debug_ui.add(
+                egui::Slider::new(&mut debug_ui_state.wall_friction, 0.0..=0.5)
+                    .text("Wall Friction"),
+            );
+            debug_ui.add(
+                egui::Slider::new(&mut debug_ui_state.erase_rate, 0.0..=5.0).text("Erase Rate"),
+            );
+            debug_ui.checkbox(&mut debug_ui_state.live_sweeping, "Sweep While Moving");
+            debug_ui.checkbox(&mut debug_ui_state.rewind, "Rewind (Backspace)");
+            debug_ui.checkbox(&mut debug_ui_state.fixed_throw, "Fixed Throw");
+            debug_ui.checkbox(&mut debug_ui_state.camera_follow, "Camera Follow (F)");
+            debug_ui.add(
+                egui::Slider::new(&mut debug_ui_state.min_throw_speed, 0.0..=500.0)
+                    .text("Min Throw Speed"),
+            );
+            debug_ui.add(
+                egui::Slider::new(&mut debug_ui_state.max_throw_speed, 0.0..=500.0)
+                    .text("Max Throw Speed"),
+            );
+            debug_ui.checkbox(
+                &mut debug_ui_state.double_click_sweep,
+                "Double-Click Completes Sweep",
+            );
+            debug_ui.add(
+                egui::Slider::new(&mut debug_ui_state.double_click_window, 0.1..=1.0)
+                    .text("Double-Click Window"),
+            );
+            debug_ui.add(
+                egui::Slider::new(&mut debug_ui_state.keyboard_sweep_rate, 50.0..=2000.0)
+                    .text("Keyboard Sweep Rate"),
+            );
+            debug_ui.add(
+                egui::Slider::new(&mut debug_ui_state.sweep_slow_speed, 0.0..=2000.0)
+                    .text("Slow Sweep Speed"),
+            );
+            debug_ui.add(
+                egui::Slider::new(&mut debug_ui_state.sweep_fast_speed, 0.0..=2000.0)
+                    .text("Fast Sweep Speed"),
+            );
+            debug_ui.add(
+                egui::Slider::new(&mut debug_ui_state.sweep_slow_multiplier, 0.0..=2.0)
+                    .text("Slow Sweep Multiplier"),
+            );
+            debug_ui.add(
+                egui::Slider::new(&mut debug_ui_state.sweep_fast_multiplier, 0.0..=2.0)
+                    .text("Fast Sweep Multiplier"),
+            );
+            debug_ui.add(
+                egui::Slider::new(&mut debug_ui_state.precision_sweep_scale, 0.05..=1.0)
+                    .text("Precision Sweep Scale (Shift)"),
+            );
+            debug_ui.add(
+                egui::Slider::new(&mut debug_ui_state.pick_tolerance, 0.0..=0.5)
+                    .text("Pick Tolerance"),
+            );
+            debug_ui.checkbox(&mut debug_ui_state.sweep_budget_enabled, "Sweep Budget");
+            debug_ui.add_enabled(
+                debug_ui_state.sweep_budget_enabled,
+                egui::Slider::new(&mut debug_ui_state.sweep_budget, 0.0..=10000.0)
+                    .text("Sweep Budget"),
+            );
+            debug_ui.add(egui::Slider::new(&mut debug_ui_state.par, 0..=30).text("Par (Tiles)"));
 
             debug_ui.separator();
             debug_ui.add(egui::Label::new("Stone Configurations"));
 
+            let hex_radius = debug_ui_state.hex_radius;
             for (i, stone_config) in debug_ui_state.stone_configs.iter_mut().enumerate() {
                 debug_ui.collapsing(format!("Stone {}", i + 1), |debug_ui| {
                     debug_ui.add(
@@ -110,10 +391,101 @@ pub fn debug_ui(
                                 );
                             }
                         });
+                    debug_ui.horizontal(|debug_ui| {
+                        debug_ui.label("Color (applies on restart)");
+                        egui::color_picker::color_edit_button_rgb(
+                            debug_ui,
+                            &mut stone_config.color,
+                        );
+                    });
+                    egui::ComboBox::from_id_salt(format!("stone_skin_{}", i))
+                        .selected_text(match stone_config.skin {
+                            Some(skin) => format!("{:?}", skin),
+                            None => "Settings".to_string(),
+                        })
+                        .show_ui(debug_ui, |debug_ui| {
+                            debug_ui.selectable_value(&mut stone_config.skin, None, "Settings");
+                            for skin in StoneSkin::iterator() {
+                                debug_ui.selectable_value(
+                                    &mut stone_config.skin,
+                                    Some(*skin),
+                                    format!("{:?}", skin),
+                                );
+                            }
+                        });
+                    debug_ui.add(
+                        egui::Slider::new(
+                            &mut stone_config.start_offset.x,
+                            -hex_radius..=hex_radius,
+                        )
+                        .text("Start Offset X (applies on restart)"),
+                    );
+                    debug_ui.add(
+                        egui::Slider::new(
+                            &mut stone_config.start_offset.y,
+                            -hex_radius..=hex_radius,
+                        )
+                        .text("Start Offset Y (applies on restart)"),
+                    );
                 });
             }
+
+            debug_ui.separator();
+            debug_ui.add(egui::Label::new("Replay"));
+            if debug_ui.button("Copy solution code").clicked() {
+                debug_ui.ctx().copy_text(replay_recording.0.to_code());
+            }
+            debug_ui.add(
+                egui::TextEdit::singleline(&mut debug_ui_state.replay_code)
+                    .hint_text("Paste a solution code"),
+            );
+            if debug_ui.button("Play replay").clicked() {
+                match Replay::from_code(&debug_ui_state.replay_code) {
+                    Ok(replay) => {
+                        debug_ui_state.replay_error = None;
+                        commands.trigger(PlayReplay(replay));
+                    }
+                    Err(error) => debug_ui_state.replay_error = Some(error.to_string()),
+                }
+            }
+            if let Some(error) = &debug_ui_state.replay_error {
+                debug_ui.colored_label(egui::Color32::RED, error);
+            }
+
+            debug_ui.separator();
+            debug_ui.add(egui::Label::new("Solver"));
+            if debug_ui.button("Solve and sweep").clicked() {
+                let level = &on_level.0;
+                match solve(level, &PhysicsParams::from_level(level)) {
+                    Some(solution) => {
+                        let stroke = Stroke::Sweep(TileType::MaintainSpeed);
+                        for (coordinate, mut tile) in &mut tiles {
+                            if solution.contains(coordinate) {
+                                stroke.apply(&mut tile, level.min_sweep_distance);
+                                replay_recording.record(
+                                    replay_clock.0,
+                                    coordinate,
+                                    &stroke,
+                                    level.min_sweep_distance,
+                                );
+                            }
+                        }
+                        debug_ui_state.solver_message =
+                            Some(format!("Swept {} tiles", solution.len()));
+                    }
+                    None => debug_ui_state.solver_message = Some("No solution found".to_string()),
+                }
+            }
+            if let Some(message) = &debug_ui_state.solver_message {
+                debug_ui.label(message);
+            }
         });
 
+    // The level changed underneath us, wait for the state to be rebuilt before syncing
+    if debug_ui_state.current_level != on_level.0.current_level {
+        return Ok(());
+    }
+
     // Sync debug UI state to the active level
     on_level.0.hex_radius = debug_ui_state.hex_radius;
     on_level.0.stone_radius = debug_ui_state.stone_radius;
@@ -123,13 +495,77 @@ pub fn debug_ui(
     on_level.0.rotation_factor = debug_ui_state.rotation_factor;
     on_level.0.snap_distance = debug_ui_state.snap_distance;
     on_level.0.snap_velocity = debug_ui_state.snap_velocity;
+    on_level.0.stop_velocity = debug_ui_state.stop_velocity;
+    on_level.0.overlap_samples = debug_ui_state.overlap_samples;
     on_level.0.speed_up_factor = debug_ui_state.speed_up_factor;
+    on_level.0.restitution = debug_ui_state.restitution;
+    on_level.0.wall_friction = debug_ui_state.wall_friction;
+    on_level.0.erase_rate = debug_ui_state.erase_rate;
+    on_level.0.live_sweeping = debug_ui_state.live_sweeping;
+    on_level.0.rewind = debug_ui_state.rewind;
+    on_level.0.fixed_throw = debug_ui_state.fixed_throw;
+    on_level.0.camera_follow = debug_ui_state.camera_follow;
+    on_level.0.min_throw_speed = debug_ui_state.min_throw_speed;
+    on_level.0.max_throw_speed = debug_ui_state.max_throw_speed;
+    on_level.0.double_click_sweep = debug_ui_state.double_click_sweep;
+    on_level.0.double_click_window = debug_ui_state.double_click_window;
+    on_level.0.keyboard_sweep_rate = debug_ui_state.keyboard_sweep_rate;
+    on_level.0.sweep_slow_speed = debug_ui_state.sweep_slow_speed;
+    on_level.0.sweep_fast_speed = debug_ui_state.sweep_fast_speed;
+    on_level.0.sweep_slow_multiplier = debug_ui_state.sweep_slow_multiplier;
+    on_level.0.sweep_fast_multiplier = debug_ui_state.sweep_fast_multiplier;
+    on_level.0.precision_sweep_scale = debug_ui_state.precision_sweep_scale;
+    on_level.0.pick_tolerance = debug_ui_state.pick_tolerance;
+    on_level.0.sweep_budget = debug_ui_state
+        .sweep_budget_enabled
+        .then_some(debug_ui_state.sweep_budget);
+    on_level.0.par = debug_ui_state.par;
+    for (stone_config, ui_config) in on_level
+        .0
+        .stone_configs
+        .iter_mut()
+        .zip(&debug_ui_state.stone_configs)
+    {
+        let [red, green, blue] = ui_config.color;
+        stone_config.color = Color::srgb(red, green, blue);
+        stone_config.skin = ui_config.skin;
+        stone_config.start_offset = ui_config.start_offset;
+    }
 
     Ok(())
 }
 
+fn crt_sliders(debug_ui: &mut egui::Ui, crt: &mut CrtSettings) {
+    debug_ui
+        .add(egui::Slider::new(&mut crt.scanline_intensity, 0.0..=1.0).text("Scanline Intensity"));
+    debug_ui.add(egui::Slider::new(&mut crt.scanline_count, 50.0..=1000.0).text("Scanline Count"));
+    debug_ui.add(egui::Slider::new(&mut crt.curvature, 0.0..=0.5).text("Curvature"));
+    debug_ui
+        .add(egui::Slider::new(&mut crt.vignette_intensity, 0.0..=1.0).text("Vignette Intensity"));
+    debug_ui.add(
+        egui::Slider::new(&mut crt.chromatic_aberration, 0.0..=0.2).text("Chromatic Aberration"),
+    );
+    debug_ui.add(egui::Slider::new(&mut crt.brightness, 0.0..=2.0).text("Brightness"));
+    debug_ui.add(egui::Slider::new(&mut crt.noise_intensity, 0.0..=0.1).text("Noise Intensity"));
+}
+
+/// Page Down and Page Up step through the levels like the level combo box, stopping at the first
+/// and last
+fn step_level_on_key(input: Res<ButtonInput<KeyCode>>, mut debug_ui_state: ResMut<DebugUIState>) {
+    let current_level = debug_ui_state.current_level;
+    let stepped = if input.just_pressed(KeyCode::PageDown) {
+        current_level.next()
+    } else if input.just_pressed(KeyCode::PageUp) {
+        current_level.previous()
+    } else {
+        None
+    };
+    if let Some(level) = stepped {
+        debug_ui_state.current_level = level;
+    }
+}
+
 pub fn on_debug_ui_level_change(
-    mut maybe_old_level: Local<Option<CurrentLevel>>,
     debug_ui_state: Res<DebugUIState>,
     mut commands: Commands,
     grid: Query<Entity, With<HexGrid>>,
@@ -137,11 +573,12 @@ pub fn on_debug_ui_level_change(
     meshes: ResMut<Assets<Mesh>>,
     materials: ResMut<Assets<ColorMaterial>>,
     scratch_materials: ResMut<Assets<ScratchOffMaterial>>,
+    tile_assets: ResMut<TileAssets>,
+    stone_skins: StoneSkins,
     current_drag_tile_type: ResMut<CurrentDragTileType>,
     mut on_level: ResMut<OnLevel>,
 ) {
-    let old_level = maybe_old_level.replace(debug_ui_state.current_level);
-    if old_level == Some(debug_ui_state.current_level) || old_level.is_none() {
+    if debug_ui_state.current_level == on_level.0.current_level {
         return;
     }
     let level = get_level(debug_ui_state.current_level);
@@ -153,6 +590,8 @@ pub fn on_debug_ui_level_change(
         meshes,
         materials,
         scratch_materials,
+        tile_assets,
+        stone_skins,
         current_drag_tile_type,
         &level,
     );
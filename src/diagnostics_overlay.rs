@@ -0,0 +1,147 @@
+//! An overlay for performance work, toggled with F3 in any build. It shows the frame rate, how many
+//! trail dots and mesh and material assets are alive, and how long the last physics and trajectory
+//! runs took.
+
+use std::time::Duration;
+
+use bevy::{
+    diagnostic::{DiagnosticPath, DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    input::common_conditions::input_just_pressed,
+    prelude::*,
+};
+
+use crate::fire_trail::TrailDot;
+
+const TOGGLE_KEY: KeyCode = KeyCode::F3;
+
+/// The text is only rewritten this often, so it can be read and doesn't cost much itself
+const REFRESH_SECS: f32 = 0.25;
+
+pub(super) fn plugin(app: &mut App) {
+    if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+        app.add_plugins(FrameTimeDiagnosticsPlugin::default());
+    }
+    app.init_resource::<PhysicsTimings>()
+        .add_systems(Startup, spawn_diagnostics_overlay)
+        .add_systems(
+            Update,
+            (
+                toggle_diagnostics_overlay.run_if(input_just_pressed(TOGGLE_KEY)),
+                update_diagnostics_overlay,
+            )
+                .chain(),
+        );
+}
+
+/// How long the last runs of the physics that's worth watching took
+#[derive(Resource, Default, Debug)]
+pub struct PhysicsTimings {
+    /// The last `apply_tile_velocity_effects`
+    pub tile_effects: Duration,
+    /// The last trajectory prediction for the move line
+    pub trajectories: Duration,
+}
+
+#[derive(Component)]
+struct DiagnosticsOverlay {
+    refresh: Timer,
+}
+
+fn spawn_diagnostics_overlay(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Diagnostics Overlay"),
+        DiagnosticsOverlay {
+            refresh: Timer::from_seconds(REFRESH_SECS, TimerMode::Repeating),
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            right: Val::Px(8.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        GlobalZIndex(3),
+        Visibility::Hidden,
+        Pickable::IGNORE,
+        children![(
+            Text::new(""),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            Pickable::IGNORE,
+        )],
+    ));
+}
+
+fn toggle_diagnostics_overlay(overlay: Single<(&mut DiagnosticsOverlay, &mut Visibility)>) {
+    let (mut overlay, mut visibility) = overlay.into_inner();
+    *visibility = match *visibility {
+        Visibility::Hidden => Visibility::Inherited,
+        _ => Visibility::Hidden,
+    };
+    // Fill the text in on the next frame rather than a refresh later
+    let duration = overlay.refresh.duration();
+    overlay.refresh.set_elapsed(duration);
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn update_diagnostics_overlay(
+    time: Res<Time>,
+    diagnostics: Res<DiagnosticsStore>,
+    physics_timings: Res<PhysicsTimings>,
+    trail_dots: Query<&Visibility, With<TrailDot>>,
+    meshes: Res<Assets<Mesh>>,
+    color_materials: Res<Assets<ColorMaterial>>,
+    overlay: Single<(&mut DiagnosticsOverlay, &Visibility, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    let (mut overlay, visibility, children) = overlay.into_inner();
+    if *visibility == Visibility::Hidden {
+        return;
+    }
+    if !overlay.refresh.tick(time.delta()).just_finished() {
+        return;
+    }
+    let smoothed = |path: &DiagnosticPath| {
+        diagnostics
+            .get(path)
+            .and_then(|diagnostic| diagnostic.smoothed())
+            .unwrap_or(0.0)
+    };
+    let lines = [
+        format!("FPS: {:.0}", smoothed(&FrameTimeDiagnosticsPlugin::FPS)),
+        format!(
+            "Frame time: {:.2} ms",
+            smoothed(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        ),
+        // Faded dots are kept hidden for reuse
+        format!(
+            "Trail dots: {}",
+            trail_dots
+                .iter()
+                .filter(|visibility| **visibility != Visibility::Hidden)
+                .count()
+        ),
+        format!("Meshes: {}", meshes.len()),
+        format!("Color materials: {}", color_materials.len()),
+        format!(
+            "Tile effects: {:.3} ms",
+            millis(physics_timings.tile_effects)
+        ),
+        format!(
+            "Trajectories: {:.3} ms",
+            millis(physics_timings.trajectories)
+        ),
+    ];
+    for child in children {
+        if let Ok(mut text) = texts.get_mut(*child) {
+            text.0 = lines.join("\n");
+        }
+    }
+}
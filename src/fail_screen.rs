@@ -0,0 +1,175 @@
+//! The overlay shown once every stone has stopped without any reaching the goal. It shows how each
+//! stone did and offers to retry, which R still does too, or to quit.
+
+use bevy::{ecs::spawn::SpawnIter, prelude::*};
+
+use crate::{
+    gameplay::{GameState, StoneStopped},
+    level::OnLevel,
+    level_transition::{StartLevelTransition, TransitionTo},
+    localization::Localized,
+    menus::btn,
+    screens::Screen,
+    stone::{ReachedGoal, Stone, StoneIndex, StoneIsStopped},
+};
+
+const STONE_ICON_SIZE: f32 = 28.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_observer(on_stone_stopped);
+}
+
+/// Whether the level is lost, given whether each stone `(stopped, reached_goal)`. Stones still
+/// moving might yet make it, and any stone in the goal wins instead.
+pub fn all_stones_fell_short(stones: impl IntoIterator<Item = (bool, bool)>) -> bool {
+    let mut any_stones = false;
+    for (stopped, reached_goal) in stones {
+        if reached_goal || !stopped {
+            return false;
+        }
+        any_stones = true;
+    }
+    any_stones
+}
+
+fn on_stone_stopped(
+    _event: On<StoneStopped>,
+    mut commands: Commands,
+    stones: Query<(&Stone, &StoneIndex, Has<StoneIsStopped>, Has<ReachedGoal>)>,
+    game_state: Res<State<GameState>>,
+    on_level: Res<OnLevel>,
+) {
+    if *game_state.get() != GameState::Playing
+        || !all_stones_fell_short(
+            stones
+                .iter()
+                .map(|(_, _, stopped, reached_goal)| (stopped, reached_goal)),
+        )
+    {
+        return;
+    }
+    commands.set_state(GameState::Lost);
+
+    let level = &on_level.0;
+    let mut stone_results: Vec<_> = stones
+        .iter()
+        .map(|(stone, index, _, reached_goal)| (index.0, stone.skin, reached_goal))
+        .collect();
+    stone_results.sort_by_key(|(index, _, _)| *index);
+    let stone_icons: Vec<_> = stone_results
+        .into_iter()
+        .filter_map(|(index, skin, reached_goal)| {
+            let color = skin.color(level.stone_configs.get(index)?.color);
+            Some(stone_icon(color, reached_goal))
+        })
+        .collect();
+    let hint = if level.rewind {
+        "fail-hint-rewind"
+    } else {
+        "fail-hint"
+    };
+
+    commands.spawn((
+        Name::new("Fail Overlay"),
+        DespawnOnExit(Screen::Gameplay),
+        DespawnOnExit(GameState::Lost),
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        // Dims the board and keeps the pointer off the tiles underneath
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+        children![(
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(20.0),
+                padding: UiRect::all(Val::Px(30.0)),
+                border: UiRect::all(Val::Px(2.0)),
+                border_radius: BorderRadius::all(Val::Px(25.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.9)),
+            BorderColor::all(Color::srgb(0.9, 0.9, 0.9)),
+            children![
+                (
+                    Localized::new("fail-title"),
+                    TextFont {
+                        font_size: 36.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgba(1.0, 0.9, 0.2, 0.9)),
+                ),
+                (
+                    Node {
+                        column_gap: Val::Px(12.0),
+                        ..default()
+                    },
+                    Children::spawn(SpawnIter(stone_icons.into_iter())),
+                ),
+                (
+                    Localized::new(hint),
+                    TextFont {
+                        font_size: 18.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ),
+                (
+                    Node {
+                        column_gap: Val::Px(30.0),
+                        ..default()
+                    },
+                    children![btn("retry", retry), btn("pause-quit", quit_to_title)],
+                ),
+            ],
+        )],
+    ));
+}
+
+/// A dot in the stone's color, dimmed unless it made it into the goal
+fn stone_icon(color: Color, reached_goal: bool) -> impl Bundle {
+    let (fill, border) = if reached_goal {
+        (color, Color::srgb(1.0, 0.84, 0.0))
+    } else {
+        (color.with_alpha(0.3), Color::srgba(1.0, 1.0, 1.0, 0.4))
+    };
+    (
+        Node {
+            width: Val::Px(STONE_ICON_SIZE),
+            height: Val::Px(STONE_ICON_SIZE),
+            border: UiRect::all(Val::Px(2.0)),
+            border_radius: BorderRadius::MAX,
+            ..default()
+        },
+        BackgroundColor(fill),
+        BorderColor::all(border),
+    )
+}
+
+fn retry(_: On<Pointer<Click>>, mut commands: Commands) {
+    commands.trigger(StartLevelTransition(TransitionTo::RestartLevel));
+}
+
+fn quit_to_title(_: On<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Title);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_stones_fell_short() {
+        // One stone stopped short, another still sliding
+        assert!(!all_stones_fell_short([(true, false), (false, false)]));
+        // Every stone stopped and none made it
+        assert!(all_stones_fell_short([(true, false), (true, false)]));
+        // A stone in the goal is a win, even with the rest stopped
+        assert!(!all_stones_fell_short([(true, false), (false, true)]));
+        assert!(!all_stones_fell_short([]));
+    }
+}
@@ -1,42 +1,297 @@
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
 
 use crate::{
-    gameplay::GameState,
+    hex_grid::{HexCoordinate, HexGrid, world_to_hex_tolerant},
+    layers,
+    level::OnLevel,
     screens::Screen,
-    stone::{Stone, Velocity},
+    stone::{Stone, StoneSkin, Velocity, has_stopped},
+    tile::{SweptTileType, Theme, TilePalette, TileTheme, TileType},
 };
 
 const EMBER_SEED: u32 = 12345;
 
+/// Most trail dots showing at once. Past this new ones are skipped until older ones fade.
+const MAX_TRAIL_DOTS: usize = 512;
+
 #[derive(Component)]
 pub struct TrailDot {
     pub ttl: f32,
     pub ttl0: f32,
+    /// Flames go out as soon as play stops, shavings fade out on their own
+    pub flame: bool,
+}
+
+/// Trail dots are hidden and reused once they fade rather than despawned, each keeping its own
+/// material to fade, and all drawn with one unit circle scaled to size. That keeps the number of
+/// meshes and materials flat however long the stones slide for.
+#[derive(Resource)]
+pub struct TrailPool {
+    circle: Handle<Mesh>,
+    /// Faded out dots, hidden until they're needed again
+    free: Vec<(Entity, Handle<ColorMaterial>)>,
+    live: usize,
+}
+
+impl FromWorld for TrailPool {
+    fn from_world(world: &mut World) -> Self {
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+        Self {
+            circle: meshes.add(Circle::new(1.0)),
+            free: Vec::new(),
+            live: 0,
+        }
+    }
+}
+
+impl TrailPool {
+    fn recycle(&mut self, entity: Entity, material: &Handle<ColorMaterial>) {
+        self.free.push((entity, material.clone()));
+        self.live = self.live.saturating_sub(1);
+    }
+}
+
+/// Leaving gameplay despawns every dot, so none of the pooled ones can be reused
+pub fn reset_trail_pool(mut pool: ResMut<TrailPool>) {
+    pool.free.clear();
+    pool.live = 0;
+}
+
+/// Shows trail dots, reusing faded ones before adding more
+#[derive(SystemParam)]
+pub struct TrailDots<'w, 's> {
+    commands: Commands<'w, 's>,
+    materials: ResMut<'w, Assets<ColorMaterial>>,
+    pool: ResMut<'w, TrailPool>,
+}
+
+impl TrailDots<'_, '_> {
+    /// Shows `dot` as a circle of `radius` in `color`, with `transform`'s scale stretching it
+    fn spawn(&mut self, dot: TrailDot, radius: f32, color: Color, mut transform: Transform) {
+        if self.pool.live >= MAX_TRAIL_DOTS {
+            return;
+        }
+        self.pool.live += 1;
+        transform.scale *= Vec3::new(radius, radius, 1.0);
+        if let Some((entity, material)) = self.pool.free.pop() {
+            if let Some(material) = self.materials.get_mut(&material) {
+                material.color = color;
+            }
+            self.commands
+                .entity(entity)
+                .insert((dot, transform, Visibility::Inherited));
+            return;
+        }
+        self.commands.spawn((
+            DespawnOnExit(Screen::Gameplay),
+            dot,
+            Mesh2d(self.pool.circle.clone()),
+            MeshMaterial2d(self.materials.add(color)),
+            transform,
+            Pickable::IGNORE,
+        ));
+    }
+}
+
+/// Where a stone starting on `coordinate` starts its trail's random numbers, so stones thrown
+/// side by side don't flicker in step. Never zero, which xorshift would be stuck on.
+pub fn ember_seed(coordinate: &HexCoordinate) -> u32 {
+    let hash = (coordinate.q as u32).wrapping_mul(73_856_093)
+        ^ (coordinate.r as u32).wrapping_mul(19_349_663);
+    (EMBER_SEED ^ hash).max(1)
+}
+
+/// Xorshift pseudo-random number in 0..1 for trail effects, stepping `seed` along
+pub fn rand01(seed: &mut u32) -> f32 {
+    let mut x = *seed;
+    x ^= x << 13;
+    x ^= x >> 17;
+    x ^= x << 5;
+    *seed = x;
+    (x >> 8) as f32 / (1 << 24) as f32
+}
+
+/// Swept distance between ice shavings, in the same units as the tiles' sweep distance
+const SHAVING_SPACING: f32 = 12.0;
+const SHAVING_RADIUS: f32 = 2.5;
+const SHAVING_TTL: f32 = 0.45;
+const SHAVING_COLOR: Color = Color::srgba(0.92, 0.97, 1.0, 0.85);
+/// How much of the broom's color shows through the shavings' icy white
+const SHAVING_TINT: f32 = 0.5;
+/// How much denser and wider shavings fly at the level's `sweep_fast_speed` than at a standstill
+const MAX_SHAVING_SPREAD: f32 = 2.5;
+
+/// Most trail particles a stone spawns in one frame, so a long hitch doesn't flood the screen
+const MAX_TRAIL_EMISSIONS_PER_FRAME: usize = 64;
+
+/// Adds `dt` to `accum` and takes out one `interval` for each particle due this frame. Returns
+/// where in the frame each particle was due, from 0.0 at the start to 1.0 at the end, earliest
+/// first. Whatever is left of `accum` carries over so emission stays even across frames.
+fn trail_emission_fractions(accum: &mut f32, dt: f32, interval: f32) -> Vec<f32> {
+    *accum += dt;
+    let mut fractions = Vec::new();
+    while *accum >= interval && fractions.len() < MAX_TRAIL_EMISSIONS_PER_FRAME {
+        *accum -= interval;
+        // What's left is how long ago this particle was due
+        let fraction = if dt > 0.0 { 1.0 - *accum / dt } else { 1.0 };
+        fractions.push(fraction.clamp(0.0, 1.0));
+    }
+    if fractions.len() == MAX_TRAIL_EMISSIONS_PER_FRAME {
+        *accum = 0.0;
+    }
+    fractions
+}
+
+/// Settings toggle for the stones' trails. Turning it off stops new dots, the ones already
+/// showing fade out as usual.
+#[derive(Resource)]
+pub struct EnableTrails(pub bool);
+
+impl Default for EnableTrails {
+    fn default() -> Self {
+        Self(true)
+    }
 }
 
-/// Simple pseudo-random number generator for trail effects
-fn rand01() -> f32 {
-    let seed = EMBER_SEED;
-    let seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
-    ((seed >> 16) & 0x7fff) as f32 / 32767.0
+/// Debug toggle that gives every stone the classic fire trail, whatever it's sliding over
+#[derive(Resource, Default)]
+pub struct ClassicFireTrail(pub bool);
+
+/// How a stone's trail looks, picked by the tile it's sliding over
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrailStyle {
+    Fire,
+    /// Cool blue puffs over swept ice
+    Mist,
+    /// Small white flecks scraped off rough ice
+    Chips,
+    /// Thin streaks twisted the way the tile turns the stone, in the tile's color
+    Streak {
+        clockwise: bool,
+    },
+    Sparks,
 }
 
-/// System that spawns fire trail particles behind moving stones.
+impl TrailStyle {
+    pub fn for_tile(tile_type: &TileType) -> Self {
+        match tile_type {
+            TileType::SlowDown => TrailStyle::Chips,
+            TileType::TurnClockwise => TrailStyle::Streak { clockwise: true },
+            TileType::TurnCounterclockwise => TrailStyle::Streak { clockwise: false },
+            TileType::SpeedUp(_) => TrailStyle::Sparks,
+            _ => TrailStyle::Mist,
+        }
+    }
+
+    fn look(self, theme: &Theme, palette: TilePalette) -> TrailLook {
+        let srgb = |r, g, b| Srgba::new(r, g, b, 1.0);
+        match self {
+            TrailStyle::Fire => TrailLook {
+                glow_slow: srgb(1.0, 0.20, 0.05),
+                glow_fast: srgb(1.0, 0.75, 0.05),
+                core: srgb(1.0, 0.95, 0.65),
+                speck: srgb(1.0, 0.10, 0.05),
+                glow_shape: Vec3::new(2.2, 3.2, 0.28),
+                core_shape: Vec3::new(1.6, 2.2, 0.22),
+                speck_chance: 0.22,
+                swirl: 0.0,
+            },
+            TrailStyle::Mist => TrailLook {
+                glow_slow: srgb(0.55, 0.75, 1.0),
+                glow_fast: srgb(0.75, 0.9, 1.0),
+                core: srgb(0.9, 0.97, 1.0),
+                speck: srgb(0.7, 0.85, 1.0),
+                glow_shape: Vec3::new(1.1, 0.9, 0.8),
+                core_shape: Vec3::new(0.8, 0.6, 0.6),
+                speck_chance: 0.1,
+                swirl: 0.0,
+            },
+            TrailStyle::Chips => TrailLook {
+                glow_slow: srgb(0.85, 0.9, 0.95),
+                glow_fast: srgb(1.0, 1.0, 1.0),
+                core: srgb(1.0, 1.0, 1.0),
+                speck: srgb(0.95, 0.97, 1.0),
+                glow_shape: Vec3::new(0.5, 0.4, 0.45),
+                core_shape: Vec3::new(0.4, 0.3, 0.35),
+                speck_chance: 0.6,
+                swirl: 0.0,
+            },
+            TrailStyle::Streak { clockwise } => {
+                let tile_type = if clockwise {
+                    TileType::TurnClockwise
+                } else {
+                    TileType::TurnCounterclockwise
+                };
+                let tint = Srgba::from(theme.tile_color(palette, &tile_type)).with_alpha(1.0);
+                TrailLook {
+                    glow_slow: tint,
+                    glow_fast: tint.mix(&Srgba::WHITE, 0.4),
+                    core: tint.mix(&Srgba::WHITE, 0.7),
+                    speck: tint,
+                    glow_shape: Vec3::new(3.0, 3.5, 0.16),
+                    core_shape: Vec3::new(2.0, 2.5, 0.12),
+                    speck_chance: 0.15,
+                    swirl: if clockwise { -0.5 } else { 0.5 },
+                }
+            }
+            TrailStyle::Sparks => TrailLook {
+                glow_slow: srgb(1.0, 0.85, 0.3),
+                glow_fast: srgb(1.0, 1.0, 0.7),
+                core: srgb(1.0, 1.0, 0.9),
+                speck: srgb(1.0, 0.9, 0.4),
+                glow_shape: Vec3::new(1.4, 2.4, 0.12),
+                core_shape: Vec3::new(1.0, 1.6, 0.1),
+                speck_chance: 0.7,
+                swirl: 0.0,
+            },
+        }
+    }
+}
+
+/// Colors and shapes of a trail's puffs, see `spawn_trail_particles`
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct TrailLook {
+    /// The main puff's color at a crawl and at full speed, their alpha's ignored
+    glow_slow: Srgba,
+    glow_fast: Srgba,
+    core: Srgba,
+    speck: Srgba,
+    /// Length along the motion at a crawl, extra length at full speed, and width, as multiples of
+    /// the puff's radius
+    glow_shape: Vec3,
+    core_shape: Vec3,
+    /// Chance of a speck with each puff at a crawl, going up a little with speed
+    speck_chance: f32,
+    /// Radians the puffs are turned off the direction of motion, counterclockwise
+    swirl: f32,
+}
+
+/// System that spawns trail particles behind moving stones, styled by the tile under each one.
+/// Particles are spread along the stone's movement this frame, so the trail stays continuous
+/// through frame spikes.
 pub fn spawn_fire_trail(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut trail_dots: TrailDots,
     mut stone_query: Query<(&mut Stone, &Velocity, &Transform)>,
+    tiles: Query<(&HexCoordinate, &SweptTileType)>,
+    grid: Single<&HexGrid>,
     time: Res<Time>,
+    on_level: Res<OnLevel>,
+    palette: Res<TilePalette>,
+    theme: Res<TileTheme>,
+    classic_fire_trail: Res<ClassicFireTrail>,
+    enable_trails: Res<EnableTrails>,
 ) {
+    if !enable_trails.0 {
+        return;
+    }
     let dt = time.delta_secs();
+    let theme = theme.colors();
 
     for (mut stone, velocity, transform) in &mut stone_query {
-        let speed = velocity.0.length();
-
-        if speed <= 5.0 {
+        if has_stopped(velocity, on_level.0.stop_velocity) {
             continue;
         }
+        let speed = velocity.0.length();
 
         // 0..1 based on speed (tweak these 2 numbers freely)
         let t = ((speed - 20.0) / 450.0).clamp(0.0, 1.0);
@@ -45,119 +300,213 @@ pub fn spawn_fire_trail(
         // slow ~0.04s, fast ~0.01s
         let interval = 0.04 - 0.03 * t;
 
-        stone.trail_accum += dt;
-        if stone.trail_accum < interval {
-            continue;
+        let end = transform.translation.truncate();
+        let start = end - velocity.0 * dt;
+        let style = if classic_fire_trail.0 {
+            TrailStyle::Fire
+        } else {
+            world_to_hex_tolerant(end, &grid, 0.0)
+                .and_then(|coordinate| tiles.iter().find(|(tile, _)| **tile == coordinate))
+                .map_or(TrailStyle::Mist, |(_, swept)| {
+                    TrailStyle::for_tile(&swept.0)
+                })
+        };
+        let look = style.look(&theme, *palette);
+        let stone = &mut *stone;
+        for fraction in trail_emission_fractions(&mut stone.trail_accum, dt, interval) {
+            spawn_trail_particles(
+                &mut trail_dots,
+                &mut stone.ember_seed,
+                stone.radius,
+                stone.skin,
+                start.lerp(end, fraction),
+                velocity.0,
+                t,
+                &look,
+            );
         }
-        stone.trail_accum = 0.0;
+    }
+}
 
-        let dir = velocity.0.normalize_or_zero();
-        let angle = dir.y.atan2(dir.x);
+/// Spawns one puff of trail behind a stone at `position` moving with `velocity`, `t` being how
+/// fast it's going from 0 to 1.
+fn spawn_trail_particles(
+    trail_dots: &mut TrailDots,
+    seed: &mut u32,
+    radius: f32,
+    skin: StoneSkin,
+    position: Vec2,
+    velocity: Vec2,
+    t: f32,
+    look: &TrailLook,
+) {
+    let dir = velocity.normalize_or_zero();
+    let angle = dir.y.atan2(dir.x) + look.swirl;
 
-        // Put the flame further behind the stone so it reads like a tail
-        let behind = if dir == Vec2::ZERO {
-            Vec2::ZERO
-        } else {
-            -dir * (stone.radius * (0.9 + 0.9 * t))
-        };
+    // Put the puff further behind the stone so it reads like a tail
+    let behind = if dir == Vec2::ZERO {
+        Vec2::ZERO
+    } else {
+        -dir * skin.trail_offset(radius, t)
+    };
 
-        // Tiny jitter so it licks around like flame
-        let j = stone.radius * (0.40 + 0.50 * t);
-        let jx = (rand01() - 0.5) * j;
-        let jy = (rand01() - 0.5) * j;
+    // Tiny jitter so it licks around like flame
+    let j = radius * (0.40 + 0.50 * t);
+    let jx = (rand01(seed) - 0.5) * j;
+    let jy = (rand01(seed) - 0.5) * j;
 
-        let base_x = transform.translation.x + behind.x + jx;
-        let base_y = transform.translation.y + behind.y + jy;
+    let base_x = position.x + behind.x + jx;
+    let base_y = position.y + behind.y + jy;
 
-        // --- Main flame streak (orange/red) ---
-        let glow_r = stone.radius * (0.55 + 0.55 * t);
-        let glow_ttl = 0.22 + 0.22 * t;
-        let glow_alpha = 0.14 + 0.45 * t;
+    // --- Main streak ---
+    let glow_r = radius * (0.55 + 0.55 * t);
+    let glow_ttl = 0.22 + 0.22 * t;
+    let glow_alpha = 0.14 + 0.45 * t;
 
-        // Fire gradient: slow = red/orange, fast = more yellow
-        let glow_color = Color::srgba(1.0, 0.20 + 0.55 * t, 0.05, glow_alpha);
+    // Slow is the style's deep color, fast its bright one
+    let glow_color = look
+        .glow_slow
+        .mix(&look.glow_fast, t)
+        .with_alpha(glow_alpha);
 
-        commands.spawn((
-            DespawnOnExit(Screen::Gameplay),
-            DespawnOnExit(GameState::Playing),
+    trail_dots.spawn(
+        TrailDot {
+            ttl: glow_ttl,
+            ttl0: glow_ttl,
+            flame: true,
+        },
+        glow_r,
+        glow_color.into(),
+        Transform {
+            translation: Vec3::new(base_x, base_y, layers::TRAIL),
+            rotation: Quat::from_rotation_z(angle),
+            // Stretch along motion to look flamey (not circular)
+            scale: Vec3::new(
+                look.glow_shape.x + look.glow_shape.y * t,
+                look.glow_shape.z,
+                1.0,
+            ),
+        },
+    );
+
+    // --- Bright core streak, often ---
+    if rand01(seed) < (0.55 + 0.25 * t) {
+        let core_r = radius * (0.22 + 0.18 * t);
+        let core_ttl = 0.12 + 0.10 * t;
+        let core_alpha = 0.18 + 0.45 * t;
+
+        trail_dots.spawn(
             TrailDot {
-                ttl: glow_ttl,
-                ttl0: glow_ttl,
+                ttl: core_ttl,
+                ttl0: core_ttl,
+                flame: true,
             },
-            Mesh2d(meshes.add(Circle::new(glow_r))),
-            MeshMaterial2d(materials.add(glow_color)),
+            core_r,
+            look.core.with_alpha(core_alpha).into(),
             Transform {
-                translation: Vec3::new(base_x, base_y, 2.0),
+                translation: Vec3::new(base_x, base_y, layers::TRAIL + 0.05),
                 rotation: Quat::from_rotation_z(angle),
-                // Stretch along motion to look flamey (not circular)
-                scale: Vec3::new(2.2 + 3.2 * t, 0.28, 1.0),
+                scale: Vec3::new(
+                    look.core_shape.x + look.core_shape.y * t,
+                    look.core_shape.z,
+                    1.0,
+                ),
             },
-        ));
-
-        // --- Hot core streak (yellow/white), often ---
-        if rand01() < (0.55 + 0.25 * t) {
-            let core_r = stone.radius * (0.22 + 0.18 * t);
-            let core_ttl = 0.12 + 0.10 * t;
-            let core_alpha = 0.18 + 0.45 * t;
+        );
+    }
 
-            let core_color = Color::srgba(1.0, 0.95, 0.65, core_alpha);
+    // --- Occasional speck ---
+    if rand01(seed) < (look.speck_chance + 0.18 * t) {
+        let ember_r = radius * 0.10;
+        let ember_ttl = 0.28 + 0.15 * t;
+        let ember_alpha = 0.10 + 0.20 * t;
 
-            commands.spawn((
-                DespawnOnExit(Screen::Gameplay),
-                DespawnOnExit(GameState::Playing),
-                TrailDot {
-                    ttl: core_ttl,
-                    ttl0: core_ttl,
-                },
-                Mesh2d(meshes.add(Circle::new(core_r))),
-                MeshMaterial2d(materials.add(core_color)),
-                Transform {
-                    translation: Vec3::new(base_x, base_y, 2.05),
-                    rotation: Quat::from_rotation_z(angle),
-                    scale: Vec3::new(1.6 + 2.2 * t, 0.22, 1.0),
-                },
-            ));
-        }
+        let sx = (rand01(seed) - 0.5) * (radius * 1.2);
+        let sy = (rand01(seed) - 0.5) * (radius * 1.2);
 
-        // --- Occasional ember speck (small red dot) ---
-        if rand01() < (0.22 + 0.18 * t) {
-            let ember_r = stone.radius * 0.10;
-            let ember_ttl = 0.28 + 0.15 * t;
-            let ember_alpha = 0.10 + 0.20 * t;
+        trail_dots.spawn(
+            TrailDot {
+                ttl: ember_ttl,
+                ttl0: ember_ttl,
+                flame: true,
+            },
+            ember_r,
+            look.speck.with_alpha(ember_alpha).into(),
+            Transform::from_xyz(base_x + sx, base_y + sy, layers::TRAIL + 0.02),
+        );
+    }
+}
 
-            let ember_color = Color::srgba(1.0, 0.10, 0.05, ember_alpha);
+/// How many times denser and wider shavings fly for a sweep at `speed`, up to
+/// `MAX_SHAVING_SPREAD` at `fast_speed`
+pub fn shaving_spread(speed: f32, fast_speed: f32) -> f32 {
+    let fast = (speed / fast_speed.max(1.0)).clamp(0.0, 1.0);
+    1.0 + (MAX_SHAVING_SPREAD - 1.0) * fast
+}
 
-            let sx = (rand01() - 0.5) * (stone.radius * 1.2);
-            let sy = (rand01() - 0.5) * (stone.radius * 1.2);
+/// Spawns the ice shavings that fly off tiles as they're swept
+#[derive(SystemParam)]
+pub struct SweepShavings<'w, 's> {
+    trail_dots: TrailDots<'w, 's>,
+    on_level: Res<'w, OnLevel>,
+    palette: Res<'w, TilePalette>,
+    theme: Res<'w, TileTheme>,
+    /// Swept distance left over from earlier strokes, so slow sweeps still shed some
+    accum: Local<'s, f32>,
+}
 
-            commands.spawn((
-                DespawnOnExit(Screen::Gameplay),
-                DespawnOnExit(GameState::Playing),
+impl SweepShavings<'_, '_> {
+    /// Spreads shavings along a stroke from `from` to `to`, one per `SHAVING_SPACING` of
+    /// `distance` swept, tinted by `broom`. Faster sweeps at `speed` shed more, further out.
+    pub fn spawn(&mut self, from: Vec2, to: Vec2, distance: f32, broom: &TileType, speed: f32) {
+        let spread = shaving_spread(speed, self.on_level.0.sweep_fast_speed);
+        let tint = self.theme.colors().tile_color(*self.palette, broom);
+        let color = SHAVING_COLOR.mix(&tint, SHAVING_TINT);
+        // Kicked off to either side of the stroke, like the broom is flicking them away
+        let side = (to - from).perp().normalize_or_zero();
+        for (i, fraction) in
+            trail_emission_fractions(&mut self.accum, distance, SHAVING_SPACING / spread)
+                .into_iter()
+                .enumerate()
+        {
+            let offset = if i % 2 == 0 { 1.0 } else { -1.0 } * SHAVING_RADIUS * 2.0 * spread;
+            let position = from.lerp(to, fraction) + side * offset;
+            self.trail_dots.spawn(
                 TrailDot {
-                    ttl: ember_ttl,
-                    ttl0: ember_ttl,
+                    ttl: SHAVING_TTL,
+                    ttl0: SHAVING_TTL,
+                    flame: false,
                 },
-                Mesh2d(meshes.add(Circle::new(ember_r))),
-                MeshMaterial2d(materials.add(ember_color)),
-                Transform::from_xyz(base_x + sx, base_y + sy, 2.02),
-            ));
+                SHAVING_RADIUS,
+                color,
+                Transform::from_xyz(position.x, position.y, layers::TRAIL + 0.1),
+            );
         }
     }
 }
 
-/// System that fades and despawns trail dots over time.
+/// System that fades trail dots over time, hiding them for reuse once they're gone.
 pub fn update_fire_trail(
-    mut commands: Commands,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    mut trail_dots: Query<(Entity, &mut TrailDot, &MeshMaterial2d<ColorMaterial>)>,
+    mut pool: ResMut<TrailPool>,
+    mut trail_dots: Query<(
+        Entity,
+        &mut TrailDot,
+        &mut Visibility,
+        &MeshMaterial2d<ColorMaterial>,
+    )>,
     time: Res<Time>,
 ) {
     let dt = time.delta_secs();
 
-    for (entity, mut dot, mat_handle) in &mut trail_dots {
+    for (entity, mut dot, mut visibility, mat_handle) in &mut trail_dots {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
         dot.ttl -= dt;
         if dot.ttl <= 0.0 {
-            commands.entity(entity).despawn();
+            *visibility = Visibility::Hidden;
+            pool.recycle(entity, &mat_handle.0);
             continue;
         }
 
@@ -170,3 +519,213 @@ pub fn update_fire_trail(
     }
 }
 
+/// Puts the stones' flames out once play stops
+pub fn put_out_flames(
+    mut pool: ResMut<TrailPool>,
+    mut trail_dots: Query<(
+        Entity,
+        &TrailDot,
+        &mut Visibility,
+        &MeshMaterial2d<ColorMaterial>,
+    )>,
+) {
+    for (entity, dot, mut visibility, material) in &mut trail_dots {
+        if dot.flame && *visibility != Visibility::Hidden {
+            *visibility = Visibility::Hidden;
+            pool.recycle(entity, &material.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::{CurrentLevel, get_level};
+
+    #[test]
+    fn test_large_dt_emits_proportionally_more() {
+        // Powers of two keep the accumulator exact
+        let interval = 1.0 / 16.0;
+
+        let mut accum = 0.0;
+        assert_eq!(
+            trail_emission_fractions(&mut accum, interval, interval).len(),
+            1
+        );
+
+        // A frame eight intervals long emits eight particles, evenly spread along the frame
+        let mut accum = 0.0;
+        let fractions = trail_emission_fractions(&mut accum, 8.0 * interval, interval);
+        assert_eq!(fractions.len(), 8);
+        for (i, fraction) in fractions.iter().enumerate() {
+            assert_eq!(*fraction, (i + 1) as f32 / 8.0);
+        }
+        assert_eq!(accum, 0.0);
+    }
+
+    #[test]
+    fn test_sweep_shavings_scale_with_distance() {
+        // The same fractions are used to place shavings along a stroke by distance swept
+        let mut accum = 0.0;
+        assert_eq!(
+            trail_emission_fractions(&mut accum, 4.0 * SHAVING_SPACING, SHAVING_SPACING).len(),
+            4
+        );
+        assert_eq!(
+            trail_emission_fractions(&mut accum, SHAVING_SPACING / 2.0, SHAVING_SPACING).len(),
+            0
+        );
+        // Faster sweeps shed more shavings, up to a limit
+        assert_eq!(shaving_spread(0.0, 400.0), 1.0);
+        assert!(shaving_spread(200.0, 400.0) > 1.0);
+        assert_eq!(shaving_spread(400.0, 400.0), MAX_SHAVING_SPREAD);
+        assert_eq!(shaving_spread(4000.0, 400.0), MAX_SHAVING_SPREAD);
+    }
+
+    /// A world with one stone sliding right at `speed` and the trail systems ready to run on it
+    fn sliding_stone_world(skin: StoneSkin, speed: f32) -> (World, Schedule) {
+        let mut world = World::new();
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<Assets<ColorMaterial>>();
+        world.init_resource::<TrailPool>();
+        world.init_resource::<Time>();
+        world.insert_resource(OnLevel(get_level(CurrentLevel::Level1)));
+        world.init_resource::<TilePalette>();
+        world.init_resource::<TileTheme>();
+        world.init_resource::<ClassicFireTrail>();
+        world.init_resource::<EnableTrails>();
+        world.spawn(HexGrid::new(&get_level(CurrentLevel::Level1)));
+        world.spawn((
+            Stone {
+                radius: 20.0,
+                skin,
+                trail_accum: 0.0,
+                ember_seed: EMBER_SEED,
+            },
+            Velocity(Vec2::new(speed, 0.0)),
+            Transform::default(),
+        ));
+        let mut schedule = Schedule::default();
+        schedule.add_systems((spawn_fire_trail, update_fire_trail).chain());
+        (world, schedule)
+    }
+
+    #[test]
+    fn test_trail_assets_stay_flat() {
+        // Fast enough to shed the most flame there is
+        let (mut world, mut schedule) = sliding_stone_world(StoneSkin::TeamRed, 500.0);
+        world.insert_resource(ClassicFireTrail(true));
+
+        let frame = std::time::Duration::from_secs_f32(1.0 / 60.0);
+        let mut counts = Vec::new();
+        for _ in 0..10_000 {
+            world.resource_mut::<Time>().advance_by(frame);
+            schedule.run(&mut world);
+            counts.push((
+                world.resource::<Assets<Mesh>>().len(),
+                world.resource::<Assets<ColorMaterial>>().len(),
+            ));
+        }
+        // Every dot shares the one circle, and once the first dots have faded their materials
+        // are reused rather than new ones added
+        assert!(counts.iter().all(|(meshes, _)| *meshes == 1));
+        let settled = counts[1000].1;
+        assert!(settled <= MAX_TRAIL_DOTS);
+        assert!(
+            counts[1000..]
+                .iter()
+                .all(|(_, materials)| *materials == settled)
+        );
+    }
+
+    #[test]
+    fn test_disabled_trails_fade_out_and_stop() {
+        let (mut world, mut schedule) = sliding_stone_world(StoneSkin::Classic, 300.0);
+        // Added by the render plugins in the game, and what fading dots are hidden with
+        world.register_required_components::<Mesh2d, Visibility>();
+        let mut run_for = |world: &mut World, seconds: f32| {
+            for _ in 0..(seconds * 60.0) as usize {
+                world
+                    .resource_mut::<Time>()
+                    .advance_by(std::time::Duration::from_secs_f32(1.0 / 60.0));
+                schedule.run(world);
+            }
+        };
+        let showing = |world: &mut World| {
+            world
+                .query::<(&TrailDot, &Visibility)>()
+                .iter(world)
+                .filter(|(_, visibility)| **visibility != Visibility::Hidden)
+                .count()
+        };
+
+        run_for(&mut world, 0.5);
+        assert!(showing(&mut world) > 0);
+        // Turned off mid-slide, nothing new shows and what was there fades away
+        world.resource_mut::<EnableTrails>().0 = false;
+        run_for(&mut world, 5.0);
+        assert_eq!(showing(&mut world), 0);
+        assert_eq!(world.resource::<TrailPool>().live, 0);
+    }
+
+    #[test]
+    fn test_trail_styles_follow_the_tile() {
+        let palette = TilePalette::Normal;
+        let theme = Theme::default();
+        let styles = [
+            TrailStyle::for_tile(&TileType::MaintainSpeed),
+            TrailStyle::for_tile(&TileType::SlowDown),
+            TrailStyle::for_tile(&TileType::TurnClockwise),
+            TrailStyle::for_tile(&TileType::TurnCounterclockwise),
+            TrailStyle::Sparks,
+        ];
+        // Every tile's trail looks different, and none of them is the classic fire
+        for (i, style) in styles.iter().enumerate() {
+            assert_ne!(
+                style.look(&theme, palette),
+                TrailStyle::Fire.look(&theme, palette)
+            );
+            for other in &styles[i + 1..] {
+                assert_ne!(
+                    style.look(&theme, palette),
+                    other.look(&theme, palette),
+                    "{style:?} {other:?}"
+                );
+            }
+        }
+        // The classic fire still goes from red to yellow as the stone speeds up
+        let fire = TrailStyle::Fire.look(&theme, palette);
+        assert_eq!(fire.glow_slow.mix(&fire.glow_fast, 1.0).green, 0.75);
+        // Streaks take their tint from the theme's turn tiles
+        let streak = TrailStyle::Streak { clockwise: true };
+        assert_ne!(
+            streak.look(&TileTheme::Night.colors(), palette),
+            streak.look(&theme, palette)
+        );
+    }
+
+    #[test]
+    fn test_rand01_varies_and_repeats_from_a_seed() {
+        let sequence = |mut seed: u32| (0..100).map(|_| rand01(&mut seed)).collect::<Vec<_>>();
+        let numbers = sequence(EMBER_SEED);
+        assert!(numbers.iter().all(|n| (0.0..1.0).contains(n)));
+        assert!(numbers.windows(2).any(|pair| pair[0] != pair[1]));
+        // Both sides of the flame's 50/50-ish branches come up
+        assert!(numbers.iter().any(|n| *n < 0.5) && numbers.iter().any(|n| *n >= 0.5));
+        // The same seed always gives the same trail
+        assert_eq!(sequence(EMBER_SEED), numbers);
+        assert_ne!(sequence(ember_seed(&HexCoordinate { q: 1, r: 2 })), numbers);
+    }
+
+    #[test]
+    fn test_leftover_time_carries_over() {
+        let interval = 1.0 / 16.0;
+        let mut accum = 0.0;
+        // Half an interval isn't enough on its own, but the next half is
+        assert!(trail_emission_fractions(&mut accum, interval / 2.0, interval).is_empty());
+        assert_eq!(
+            trail_emission_fractions(&mut accum, interval / 2.0, interval),
+            vec![1.0]
+        );
+    }
+}
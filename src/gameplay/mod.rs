@@ -1,8 +1,16 @@
+use std::collections::HashSet;
+
 use bevy::{
+    camera::visibility::RenderLayers,
+    input::mouse::{MouseScrollUnit, MouseWheel},
     mesh::{Indices, PrimitiveTopology},
+    picking::{PickingSystems, hover::HoverMap, pointer::PointerId},
+    platform::time::Instant,
     prelude::*,
     sprite_render::Material2dPlugin,
+    window::PrimaryWindow,
 };
+use bevy_egui::input::EguiWantsInput;
 use bevy_seedling::{
     prelude::{RepeatMode, Volume, VolumeNode},
     sample::{AudioSample, SamplePlayer},
@@ -10,22 +18,40 @@ use bevy_seedling::{
 };
 
 use crate::{
-    asset_tracking::LoadResource, confetti::ConfettiMaterial, stone::StoneHitWall, tile::IsGoal,
+    asset_tracking::LoadResource,
+    confetti::{ConfettiMaterial, celebration_intensity},
+    tile::IsGoal,
 };
 
 use crate::{
     PausableSystems,
-    fire_trail::{spawn_fire_trail, update_fire_trail},
-    hex_grid::{HexGrid, spawn_hex_grid},
-    level::{CurrentLevel, Level, OnLevel, get_initial_stone_velocity, get_level},
+    camera::{MainCamera, zoom_modifier_pressed},
+    diagnostics_overlay::PhysicsTimings,
+    fire_trail::{
+        ClassicFireTrail, EnableTrails, TrailPool, put_out_flames, reset_trail_pool,
+        spawn_fire_trail, update_fire_trail,
+    },
+    goal_vortex::SWALLOW_SECONDS,
+    hex_grid::{HexGrid, hex_to_world, spawn_hex_grid},
+    layers,
+    level::{CurrentLevel, Level, OnLevel, get_level},
+    level_transition::{StartLevelTransition, TransitionTo, transitioning},
+    menus::Menu,
+    replay::{Replay, ReplayRecording},
+    rewind::{StoneHistory, rewinding},
     screens::Screen,
+    sim::{PHYSICS_HZ, PhysicsParams, SimStone, step_stones},
     stone::{
-        Stone, Velocity, apply_stone_collision, apply_tile_velocity_effects, resolve_collision,
-        stone, update_stone_position,
+        Stone, StoneIndex, StoneSkin, StoneSkinAssets, StoneSkins, Velocity, apply_stone_collision,
+        apply_tile_velocity_effects, has_stopped, stone, turn_stone_handles, update_stone_position,
+        warn_escaped_stones,
     },
     tile::{
-        CurrentDragTileType, ScratchOffMaterial, TileAssets, TileDragging, TileType,
-        compute_tile_effects, toggle_tile_coordinates, update_tile_material,
+        CurrentDragTileType, MirrorSweep, ScratchOffMaterial, ShowWallFlash, SweptTileType,
+        TileAssets, TileChanged, TileDragging, TilePalette, TileTheme, TileType, apply_tile_theme,
+        can_sweep, cycle_broom_type, detect_tile_changes, pick_tiles_near_pointer,
+        tile_can_be_dragged, toggle_tile_coordinates, update_hover_outline,
+        update_not_allowed_overlay, update_tile_glyphs, update_tile_material, update_wall_flash,
     },
     ui,
 };
@@ -33,12 +59,43 @@ use crate::{
 #[derive(Component)]
 struct StoneMoveLine;
 
+/// Settings toggle for the marker on where each stone is predicted to stop
+#[derive(Resource)]
+pub struct ShowLandingMarker(pub bool);
+
+impl Default for ShowLandingMarker {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// Settings toggle that skips purely decorative animation
+#[derive(Resource, Default)]
+pub struct ReduceMotion(pub bool);
+
+/// A level's been set up from scratch, whether it's the first time or a restart
+#[derive(Event)]
+pub struct LevelStart;
+
 #[derive(Event)]
 pub struct LevelComplete;
 
 #[derive(Event)]
 pub struct StoneStopped;
 
+/// Restarts the current level from scratch, what the R key and the pause menu's restart do
+#[derive(Event)]
+pub struct RestartLevel;
+
+/// Moves on from a won level to the next one, or to the end screen after the last level
+#[derive(Event)]
+pub struct NextLevel;
+
+/// Starts the whole run over from the first level, what the pause menu's restart from the
+/// beginning does
+#[derive(Event)]
+pub struct RestartCampaign;
+
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct MainUpdateSystems;
 
@@ -46,8 +103,15 @@ pub struct MainUpdateSystems;
 pub enum GameState {
     #[default]
     Initial,
+    /// The player is dragging out the throw, only entered on levels without a fixed throw
+    Aiming,
     Countdown,
     Playing,
+    /// The level was won and the win screen is up, with physics and sweeping stopped
+    Won,
+    /// Every stone stopped short of the goal and the fail screen is up, physics and sweeping are
+    /// stopped here too
+    Lost,
 }
 
 #[derive(Component)]
@@ -58,39 +122,81 @@ pub(super) fn plugin(app: &mut App) {
         .add_plugins(Material2dPlugin::<ConfettiMaterial>::default())
         .add_plugins(ui::plugin);
 
+    app.insert_resource(Time::<Fixed>::from_hz(PHYSICS_HZ));
     app.init_state::<GameState>();
+    app.init_resource::<ShowLandingMarker>();
+    app.init_resource::<ReduceMotion>();
+    app.init_resource::<TilePalette>();
+    app.init_resource::<TileTheme>();
+    app.init_resource::<StoneSkin>();
+    app.init_resource::<StoneSkinAssets>();
+    app.init_resource::<ShowWallFlash>();
+    app.init_resource::<MirrorSweep>();
+    app.init_resource::<TrailPool>();
+    app.init_resource::<ClassicFireTrail>();
+    app.init_resource::<EnableTrails>();
     app.load_resource::<GameplayAssets>();
     app.add_systems(Startup, setup);
     app.add_systems(OnEnter(GameState::Playing), start_stone_noise);
-    app.add_systems(OnEnter(Screen::Gameplay), start_tile_noise);
+    app.add_systems(OnExit(GameState::Playing), put_out_flames);
+    app.add_systems(
+        OnEnter(Screen::Gameplay),
+        (start_tile_noise, spawn_broom_cursor),
+    );
+    app.add_systems(
+        OnExit(Screen::Gameplay),
+        (reset_game_state, reset_trail_pool),
+    );
+    app.add_systems(
+        PreUpdate,
+        pick_tiles_near_pointer
+            .in_set(PickingSystems::Backend)
+            .run_if(in_state(Screen::Gameplay)),
+    );
     app.add_systems(
         FixedUpdate,
         (
             apply_stone_collision,
             update_stone_position,
             apply_tile_velocity_effects,
+            warn_escaped_stones,
         )
             .chain()
             .run_if(in_state(Screen::Gameplay))
             .run_if(in_state(GameState::Playing))
+            .run_if(not(rewinding))
             .in_set(PausableSystems),
     )
     .add_systems(
         Update,
-        (spawn_fire_trail, update_fire_trail)
+        spawn_fire_trail
             .run_if(in_state(Screen::Gameplay))
             .run_if(in_state(GameState::Playing))
             .in_set(PausableSystems),
     )
+    // Sweep shavings fade out before the stone is thrown too
+    .add_systems(
+        Update,
+        update_fire_trail
+            .run_if(in_state(Screen::Gameplay))
+            .in_set(PausableSystems),
+    )
     .add_systems(
         Update,
         (
             draw_move_line,
             toggle_tile_coordinates,
             update_tile_material,
-            switch_broom,
-            level_0_complete_check,
+            apply_tile_theme,
+            update_tile_glyphs,
+            update_not_allowed_overlay,
+            update_hover_outline,
+            update_wall_flash,
+            turn_stone_handles,
+            switch_broom.run_if(not(transitioning).and(can_sweep)),
+            detect_tile_changes,
             celebrate,
+            update_confetti_resolution,
             play_get_in_there,
         )
             .in_set(MainUpdateSystems)
@@ -99,9 +205,14 @@ pub(super) fn plugin(app: &mut App) {
     )
     .add_systems(
         Update,
-        (restart_game_on_r_key_pressed) //, on_debug_ui_level_change)
+        // A level that's still being set up can't be restarted again
+        restart_game_on_r_key_pressed
             .after(MainUpdateSystems)
-            .run_if(in_state(Screen::Gameplay))
+            .run_if(
+                in_state(Screen::Gameplay)
+                    .and(not(transitioning))
+                    .and(not(in_state(GameState::Initial))),
+            )
             .in_set(PausableSystems),
     )
     .add_systems(
@@ -112,8 +223,16 @@ pub(super) fn plugin(app: &mut App) {
             .run_if(in_state(GameState::Playing)),
     )
     .add_systems(Update, update_tile_noise)
+    // Menus pause the game, and the cursor has to hide under them
+    .add_systems(
+        Update,
+        update_broom_cursor.run_if(in_state(Screen::Gameplay)),
+    )
     .add_observer(on_level_complete)
-    .add_observer(on_stone_hit_wall);
+    .add_observer(on_restart_level)
+    .add_observer(on_next_level)
+    .add_observer(on_restart_campaign)
+    .add_observer(check_level_0_complete);
 }
 
 #[derive(Resource, Asset, Clone, Reflect)]
@@ -129,6 +248,10 @@ pub struct GameplayAssets {
     pub ding: Handle<AudioSample>,
     #[dependency]
     pub noise: Handle<AudioSample>,
+    #[dependency]
+    pub clack: Handle<AudioSample>,
+    #[dependency]
+    pub thud: Handle<AudioSample>,
 }
 
 impl FromWorld for GameplayAssets {
@@ -140,6 +263,8 @@ impl FromWorld for GameplayAssets {
             wind: assets.load("audio/sfx/wind.ogg"),
             ding: assets.load("audio/sfx/ding.ogg"),
             noise: assets.load("audio/sfx/noise.ogg"),
+            clack: assets.load("audio/sfx/clack.wav"),
+            thud: assets.load("audio/sfx/thud.wav"),
         }
     }
 }
@@ -231,6 +356,12 @@ pub fn update_tile_noise(
     }
 }
 
+/// Leaving gameplay mid-level would otherwise keep the state around, and with it anything that
+/// only despawns when the state changes
+fn reset_game_state(mut next_game_state: ResMut<NextState<GameState>>) {
+    next_game_state.set(GameState::Initial);
+}
+
 pub fn spawn_game(
     mut commands: Commands,
     grid: Query<Entity, With<HexGrid>>,
@@ -238,6 +369,8 @@ pub fn spawn_game(
     meshes: ResMut<Assets<Mesh>>,
     materials: ResMut<Assets<ColorMaterial>>,
     scratch_materials: ResMut<Assets<ScratchOffMaterial>>,
+    tile_assets: ResMut<TileAssets>,
+    stone_skins: StoneSkins,
     current_drag_tile_type: ResMut<CurrentDragTileType>,
     on_level: Res<OnLevel>,
 ) {
@@ -248,62 +381,102 @@ pub fn spawn_game(
         meshes,
         materials,
         scratch_materials,
+        tile_assets,
+        stone_skins,
         current_drag_tile_type,
         &on_level.0,
     );
 }
 
-#[derive(Resource)]
-pub struct CelebrationTimer(Timer);
-
-impl Default for CelebrationTimer {
-    fn default() -> Self {
-        Self(Timer::from_seconds(3.0, TimerMode::Once))
+/// The confetti keeps going until the player picks what to do next on the win screen
+fn celebrate(
+    time: Res<Time>,
+    celebration_query: Query<&MeshMaterial2d<ConfettiMaterial>, With<Celebration>>,
+    mut confetti_materials: ResMut<Assets<ConfettiMaterial>>,
+) {
+    for material_handle in &celebration_query {
+        if let Some(material) = confetti_materials.get_mut(&material_handle.0) {
+            material.params.x += time.delta_secs();
+        }
     }
 }
 
-fn celebrate(
+fn on_next_level(
+    _event: On<NextLevel>,
     mut commands: Commands,
-    time: Res<Time>,
-    celebration_query: Query<(Entity, &MeshMaterial2d<ConfettiMaterial>), With<Celebration>>,
     mut on_level: ResMut<OnLevel>,
     grid: Query<Entity, With<HexGrid>>,
     stone_query: Query<Entity, With<Stone>>,
     meshes: ResMut<Assets<Mesh>>,
     materials: ResMut<Assets<ColorMaterial>>,
     scratch_materials: ResMut<Assets<ScratchOffMaterial>>,
-    mut confetti_materials: ResMut<Assets<ConfettiMaterial>>,
+    tile_assets: ResMut<TileAssets>,
+    stone_skins: StoneSkins,
     current_drag_tile_type: ResMut<CurrentDragTileType>,
-    mut celebration_timer: Local<CelebrationTimer>,
     mut next_screen: ResMut<NextState<Screen>>,
 ) {
-    if let Some((celebration_entity, material_handle)) = celebration_query.iter().next() {
-        if let Some(material) = confetti_materials.get_mut(&material_handle.0) {
-            material.params.x += time.delta_secs();
-        }
-        celebration_timer.0.tick(time.delta());
-        if celebration_timer.0.is_finished() {
-            if let Some(next_level) = CurrentLevel::iterator()
-                .skip_while(|&level| level != &on_level.0.current_level)
-                .nth(1)
-            {
-                on_level.0 = get_level(*next_level).clone();
-
-                restart_game(
-                    &mut commands,
-                    grid,
-                    stone_query,
-                    meshes,
-                    materials,
-                    scratch_materials,
-                    current_drag_tile_type,
-                    &get_level(*next_level),
-                );
-                commands.entity(celebration_entity).despawn();
-                celebration_timer.0.reset();
-            } else {
-                next_screen.set(Screen::End);
-            }
+    let Some(next_level) = on_level.0.current_level.next() else {
+        next_screen.set(Screen::End);
+        return;
+    };
+    on_level.0 = get_level(next_level);
+    restart_game(
+        &mut commands,
+        grid,
+        stone_query,
+        meshes,
+        materials,
+        scratch_materials,
+        tile_assets,
+        stone_skins,
+        current_drag_tile_type,
+        &on_level.0,
+    );
+}
+
+fn on_restart_campaign(
+    _event: On<RestartCampaign>,
+    mut commands: Commands,
+    mut on_level: ResMut<OnLevel>,
+    grid: Query<Entity, With<HexGrid>>,
+    stone_query: Query<Entity, With<Stone>>,
+    meshes: ResMut<Assets<Mesh>>,
+    materials: ResMut<Assets<ColorMaterial>>,
+    scratch_materials: ResMut<Assets<ScratchOffMaterial>>,
+    tile_assets: ResMut<TileAssets>,
+    stone_skins: StoneSkins,
+    current_drag_tile_type: ResMut<CurrentDragTileType>,
+) {
+    on_level.0 = get_level(CurrentLevel::Level0);
+    restart_game(
+        &mut commands,
+        grid,
+        stone_query,
+        meshes,
+        materials,
+        scratch_materials,
+        tile_assets,
+        stone_skins,
+        current_drag_tile_type,
+        &on_level.0,
+    );
+}
+
+/// Keeps the confetti's framebuffer size in step with the window, so it stays aspect correct if the
+/// window is resized mid-celebration
+fn update_confetti_resolution(
+    window: Single<&Window, With<PrimaryWindow>>,
+    celebration_query: Query<&MeshMaterial2d<ConfettiMaterial>, With<Celebration>>,
+    mut confetti_materials: ResMut<Assets<ConfettiMaterial>>,
+) {
+    let resolution = ConfettiMaterial::resolution(&window);
+    for material_handle in &celebration_query {
+        // Only touch the material when the size changed so it isn't re-uploaded every frame
+        let outdated = confetti_materials
+            .get(&material_handle.0)
+            .is_some_and(|material| material.params.yz() != resolution);
+        if outdated && let Some(material) = confetti_materials.get_mut(&material_handle.0) {
+            material.set_resolution(resolution);
         }
     }
 }
@@ -315,35 +488,89 @@ fn on_level_complete(
     gameplay_assets: Res<GameplayAssets>,
     mut confetti_materials: ResMut<Assets<ConfettiMaterial>>,
     windows: Query<&Window>,
+    on_level: Res<OnLevel>,
+    replay_recording: Res<ReplayRecording>,
 ) {
-    // Get actual framebuffer resolution (physical pixels, accounting for HiDPI scaling)
-    let (fb_width, fb_height) = if let Ok(window) = windows.single() {
-        let scale = window.scale_factor();
-        (
-            window.resolution.width() * scale,
-            window.resolution.height() * scale,
-        )
-    } else {
-        (1024.0, 768.0)
-    };
+    let resolution = windows
+        .single()
+        .map(ConfettiMaterial::resolution)
+        .unwrap_or(Vec2::new(1024.0, 768.0));
+
+    let (tiles_swept, sweepable_tiles) = swept_tile_counts(&replay_recording.0, &on_level.0);
+    let intensity = celebration_intensity(tiles_swept, sweepable_tiles);
 
     commands.spawn((
         Celebration,
         DespawnOnExit(Screen::Gameplay),
-        DespawnOnExit(GameState::Playing),
+        DespawnOnExit(GameState::Won),
         Mesh2d(meshes.add(Rectangle::new(5000.0, 5000.0))),
+        // Held back until the goal's swallowed the stone
         MeshMaterial2d(confetti_materials.add(ConfettiMaterial {
-            params: Vec4::new(0.0, fb_width, fb_height, 0.0),
+            params: Vec4::new(-SWALLOW_SECONDS, resolution.x, resolution.y, intensity),
         })),
-        Transform::from_xyz(0.0, 0.0, 100.0), // High Z-index
+        Transform::from_xyz(0.0, 0.0, layers::CELEBRATION),
     ));
     commands.spawn(SamplePlayer::new(gameplay_assets.crowd.clone()));
+    commands.set_state(GameState::Won);
+}
+
+/// How many distinct tiles `replay` swept, and how many tiles in `level` can be swept at all
+pub fn swept_tile_counts(replay: &Replay, level: &Level) -> (usize, usize) {
+    let tiles_swept = replay
+        .actions
+        .iter()
+        .map(|action| &action.coordinate)
+        .collect::<HashSet<_>>()
+        .len();
+    let sweepable_tiles = level
+        .grid
+        .values()
+        .filter(|tile_type| tile_can_be_dragged(tile_type))
+        .count();
+    (tiles_swept, sweepable_tiles)
 }
 
+/// Scroll distance in pixels that counts as one broom step on pixel-precise devices like trackpads
+const PIXELS_PER_BROOM_STEP: f32 = 50.0;
+
 pub fn switch_broom(
     input: Res<ButtonInput<KeyCode>>,
+    mut mouse_wheel: MessageReader<MouseWheel>,
+    mut pixel_scroll: Local<f32>,
+    egui_wants_input: Option<Res<EguiWantsInput>>,
     mut current_drag_tile_type: ResMut<CurrentDragTileType>,
 ) {
+    // Don't cycle while the debug UI is being scrolled or typed into, or while Ctrl + scroll zooms
+    let egui_has_focus = egui_wants_input.is_some_and(|egui| egui.wants_any_input());
+    let scroll_zooms = zoom_modifier_pressed(&input);
+    let mut step = 0;
+    for wheel in mouse_wheel.read() {
+        if egui_has_focus || scroll_zooms {
+            continue;
+        }
+        match wheel.unit {
+            MouseScrollUnit::Line => step -= wheel.y.signum() as i32,
+            MouseScrollUnit::Pixel => {
+                *pixel_scroll += wheel.y;
+                while pixel_scroll.abs() >= PIXELS_PER_BROOM_STEP {
+                    step -= pixel_scroll.signum() as i32;
+                    *pixel_scroll -= pixel_scroll.signum() * PIXELS_PER_BROOM_STEP;
+                }
+            }
+        }
+    }
+    if !egui_has_focus && input.just_pressed(KeyCode::Tab) {
+        if input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            step -= 1;
+        } else {
+            step += 1;
+        }
+    }
+    if step != 0 {
+        *current_drag_tile_type =
+            CurrentDragTileType(cycle_broom_type(&current_drag_tile_type.0, step));
+    }
+
     if input.just_pressed(KeyCode::Digit1) {
         *current_drag_tile_type = CurrentDragTileType(TileType::MaintainSpeed);
     }
@@ -355,29 +582,135 @@ pub fn switch_broom(
     }
 }
 
-fn restart_game_on_r_key_pressed(
-    input: Res<ButtonInput<KeyCode>>,
+/// A little broom that follows the mouse over the level, colored like the tiles the current broom
+/// sweeps to and leaning the way it turns
+#[derive(Component)]
+struct BroomCursor {
+    head_material: Handle<ColorMaterial>,
+}
+
+/// Sits above the tiles and the keyboard cursor's ring
+const BROOM_CURSOR_Z: f32 = layers::FX + 0.2;
+/// The head sits over the handle
+const BROOM_HEAD_Z: f32 = BROOM_CURSOR_Z + 0.1;
+/// How far the turning brooms lean, in radians
+const BROOM_CURSOR_LEAN: f32 = 0.5;
+
+fn spawn_broom_cursor(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let head_material = materials.add(Color::WHITE);
+    commands.spawn((
+        Name::new("Broom Cursor"),
+        DespawnOnExit(Screen::Gameplay),
+        BroomCursor {
+            head_material: head_material.clone(),
+        },
+        Transform::from_xyz(0., 0., BROOM_CURSOR_Z),
+        Visibility::Hidden,
+        children![
+            (
+                Mesh2d(meshes.add(Rectangle::new(3.0, 22.0))),
+                MeshMaterial2d(materials.add(Color::srgb(0.45, 0.3, 0.15))),
+                Transform::from_xyz(0., 11.0, 0.),
+                RenderLayers::layer(0),
+                Pickable::IGNORE,
+            ),
+            (
+                Mesh2d(meshes.add(Rectangle::new(14.0, 7.0))),
+                MeshMaterial2d(head_material),
+                Transform::from_xyz(0., 0., layers::child_z(BROOM_HEAD_Z, BROOM_CURSOR_Z, 1.0)),
+                RenderLayers::layer(0),
+                Pickable::IGNORE,
+            ),
+        ],
+    ));
+}
+
+fn broom_cursor_lean(broom: &TileType) -> f32 {
+    match broom {
+        TileType::TurnCounterclockwise => BROOM_CURSOR_LEAN,
+        TileType::TurnClockwise => -BROOM_CURSOR_LEAN,
+        _ => 0.0,
+    }
+}
+
+/// Hidden while the mouse is over the UI, or off the window, and while a menu is open
+fn update_broom_cursor(
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    hover_map: Res<HoverMap>,
+    ui_nodes: Query<(), With<ComputedNode>>,
+    menu: Res<State<Menu>>,
+    current_drag_tile_type: Res<CurrentDragTileType>,
+    palette: Res<TilePalette>,
+    theme: Res<TileTheme>,
+    cursor: Single<(Ref<BroomCursor>, &mut Transform, &mut Visibility)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let (broom_cursor, mut transform, mut visibility) = cursor.into_inner();
+    let over_ui = hover_map
+        .get(&PointerId::Mouse)
+        .is_some_and(|hovered| hovered.keys().any(|entity| ui_nodes.contains(*entity)));
+    let (camera, camera_transform) = *camera;
+    let position = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok());
+    let Some(position) = position.filter(|_| !over_ui && *menu.get() == Menu::None) else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+    visibility.set_if_neq(Visibility::Inherited);
+    transform.translation = position.extend(BROOM_CURSOR_Z);
+
+    let broom = &current_drag_tile_type.0;
+    if broom_cursor.is_added()
+        || current_drag_tile_type.is_changed()
+        || palette.is_changed()
+        || theme.is_changed()
+    {
+        transform.rotation = Quat::from_rotation_z(broom_cursor_lean(broom));
+        if let Some(material) = materials.get_mut(&broom_cursor.head_material) {
+            material.color = theme.colors().tile_color(*palette, broom);
+        }
+    }
+}
+
+pub const RESTART_KEY: KeyCode = KeyCode::KeyR;
+
+fn restart_game_on_r_key_pressed(input: Res<ButtonInput<KeyCode>>, mut commands: Commands) {
+    if input.just_pressed(RESTART_KEY) {
+        commands.trigger(StartLevelTransition(TransitionTo::RestartLevel));
+    }
+}
+
+fn on_restart_level(
+    _event: On<RestartLevel>,
     mut commands: Commands,
     grid: Query<Entity, With<HexGrid>>,
     stone_query: Query<Entity, With<Stone>>,
     meshes: ResMut<Assets<Mesh>>,
     materials: ResMut<Assets<ColorMaterial>>,
     scratch_materials: ResMut<Assets<ScratchOffMaterial>>,
+    tile_assets: ResMut<TileAssets>,
+    stone_skins: StoneSkins,
     current_drag_tile_type: ResMut<CurrentDragTileType>,
     on_level: Res<OnLevel>,
 ) {
-    if input.just_pressed(KeyCode::KeyR) {
-        restart_game(
-            &mut commands,
-            grid,
-            stone_query,
-            meshes,
-            materials,
-            scratch_materials,
-            current_drag_tile_type,
-            &on_level.0,
-        );
-    }
+    restart_game(
+        &mut commands,
+        grid,
+        stone_query,
+        meshes,
+        materials,
+        scratch_materials,
+        tile_assets,
+        stone_skins,
+        current_drag_tile_type,
+        &on_level.0,
+    );
 }
 pub fn restart_game(
     commands: &mut Commands,
@@ -386,6 +719,8 @@ pub fn restart_game(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     mut scratch_materials: ResMut<Assets<ScratchOffMaterial>>,
+    mut tile_assets: ResMut<TileAssets>,
+    mut stone_skins: StoneSkins,
     mut current_drag_tile_type: ResMut<CurrentDragTileType>,
     level: &Level,
 ) {
@@ -396,7 +731,7 @@ pub fn restart_game(
     }
 
     let grid = HexGrid::new(level);
-    let tile_assets = TileAssets::new(
+    tile_assets.rebuild_if_changed(
         &mut meshes,
         &mut materials,
         &grid,
@@ -406,20 +741,37 @@ pub fn restart_game(
     for stone_entity in stone_query {
         commands.entity(stone_entity).despawn();
     }
-    for stone_config in level.stone_configs.iter() {
+    for (index, stone_config) in level.stone_configs.iter().enumerate() {
+        if !stone_config.start_offset_fits(level) {
+            warn!(
+                "Stone {index}'s start offset {} would put it over its tile's edge, starting it on \
+                 the tile's center",
+                stone_config.start_offset
+            );
+        }
         commands.spawn((
             DespawnOnExit(Screen::Gameplay),
+            StoneIndex(index),
+            StoneHistory::default(),
             stone(
                 &mut meshes,
                 &mut materials,
-                &grid,
+                &mut stone_skins.assets,
+                stone_config.skin.unwrap_or(*stone_skins.chosen),
+                stone_config.color,
                 &stone_config.start_coordinate,
-                get_initial_stone_velocity(&stone_config.facing, &stone_config.velocity_magnitude),
+                stone_config.start_position(level, &grid),
+                stone_config.initial_velocity(),
                 &level.stone_radius,
             ),
         ));
     }
-    commands.set_state(GameState::Countdown);
+    if level.fixed_throw {
+        commands.set_state(GameState::Countdown);
+    } else {
+        commands.set_state(GameState::Aiming);
+    }
+    commands.trigger(LevelStart);
 }
 
 fn draw_move_line(
@@ -432,6 +784,8 @@ fn draw_move_line(
     tiles: Query<(&Transform, &TileDragging), Without<Stone>>,
     lines: Query<Entity, With<StoneMoveLine>>,
     fixed_time: Res<Time<Fixed>>,
+    show_landing_marker: Res<ShowLandingMarker>,
+    mut physics_timings: ResMut<PhysicsTimings>,
 ) {
     for l in &lines {
         commands.entity(l).despawn();
@@ -442,50 +796,86 @@ fn draw_move_line(
         .iter()
         .map(|(transform, tile_dragging)| {
             let position = transform.translation.truncate();
-            (position, tile_dragging)
+            (position, tile_dragging, transform.scale.x)
         })
         .collect();
 
     // Collect all stone data for multi-stone simulation
     let stone_data: Vec<_> = stones
         .iter()
-        .map(|(stone, velocity, transform)| {
-            (
-                transform.translation.truncate(),
-                velocity.clone(),
-                stone.radius,
-            )
+        .map(|(stone, velocity, transform)| SimStone {
+            position: transform.translation.truncate(),
+            velocity: velocity.clone(),
+            radius: stone.radius,
+            reached_goal: false,
         })
         .collect();
 
     // Simulate physics forward to predict trajectory for all stones together
+    let started = Instant::now();
     let trajectories = simulate_trajectories(
-        &stone_data,
+        stone_data,
         &tile_data,
         *grid,
-        on_level.0.drag_coefficient,
-        fixed_time.delta_secs(),
-        on_level.0.slow_down_factor,
-        on_level.0.rotation_factor,
-        on_level.0.speed_up_factor,
-        on_level.0.speed_up_arrow_radius,
+        &PhysicsParams::from_level(&on_level.0),
+        // The step length rather than the last step's delta, which is zero until the first step
+        fixed_time.timestep().as_secs_f32(),
     );
+    physics_timings.trajectories = started.elapsed();
 
+    let goal = hex_to_world(&on_level.0.goal_coordinate, &grid);
     for trajectory in trajectories {
-        if let Some(mesh) = create_tapered_line_mesh(&trajectory, 6.0, 1.0) {
+        if let Some(mesh) = create_tapered_line_mesh(&trajectory.points, 6.0, 1.0) {
             commands.spawn((
                 DespawnOnExit(Screen::Gameplay),
                 StoneMoveLine,
                 Mesh2d(meshes.add(mesh)),
                 MeshMaterial2d(tile_assets.line_material.clone()),
-                Transform::from_xyz(0., 0., 2.0),
+                Transform::from_xyz(0., 0., layers::PREDICTION),
+            ));
+        }
+        if show_landing_marker.0 {
+            let material =
+                if lands_in_goal(trajectory.rest_position, goal, on_level.0.snap_distance) {
+                    tile_assets.reach_goal_material.clone()
+                } else {
+                    tile_assets.fall_short_material.clone()
+                };
+            commands.spawn((
+                DespawnOnExit(Screen::Gameplay),
+                StoneMoveLine,
+                landing_marker(&tile_assets, material, trajectory.rest_position),
             ));
         }
     }
 }
 
+/// A crosshair over where a stone is predicted to stop
+fn landing_marker(
+    tile_assets: &TileAssets,
+    material: Handle<ColorMaterial>,
+    position: Vec2,
+) -> impl Bundle {
+    (
+        Mesh2d(tile_assets.landing_marker_mesh.clone()),
+        MeshMaterial2d(material.clone()),
+        Transform::from_xyz(position.x, position.y, layers::PREDICTION + 0.1),
+        children![
+            (
+                Mesh2d(tile_assets.landing_marker_bar_mesh.clone()),
+                MeshMaterial2d(material.clone()),
+            ),
+            (
+                Mesh2d(tile_assets.landing_marker_bar_mesh.clone()),
+                MeshMaterial2d(material),
+                Transform::from_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2)),
+            ),
+        ],
+    )
+}
+
 /// Creates a tapered line mesh that starts thick and thins out along the trajectory.
-fn create_tapered_line_mesh(points: &[Vec2], start_width: f32, end_width: f32) -> Option<Mesh> {
+pub fn create_tapered_line_mesh(points: &[Vec2], start_width: f32, end_width: f32) -> Option<Mesh> {
     if points.len() < 2 {
         return None;
     }
@@ -545,121 +935,81 @@ fn create_tapered_line_mesh(points: &[Vec2], start_width: f32, end_width: f32) -
 
 /// Simulates all stones' trajectories by forward-integrating physics.
 ///
-/// **Important**: The order of operations must match the FixedUpdate system chain:
-/// 1. apply_stone_collision (handle collisions)
-/// 2. update_stone_position (move)
-/// 3. apply_tile_velocity_effects (update velocity)
-fn simulate_trajectories(
-    stone_data: &[(Vec2, Velocity, f32)], // (position, velocity, radius)
-    tile_data: &[(Vec2, &TileDragging)],
+/// Each step goes through `step_stones`, which mirrors the FixedUpdate system chain.
+/// Unlike gameplay, stones don't snap into the goal so the line shows where they'd coast to.
+pub fn simulate_trajectories(
+    mut stones: Vec<SimStone>,
+    tile_data: &[(Vec2, &TileDragging, f32)],
     hex_grid: &HexGrid,
-    drag_coefficient: f32,
+    params: &PhysicsParams,
     fixed_dt: f32,
-    slow_down_factor: f32,
-    rotation_factor: f32,
-    speed_up_factor: f32,
-    speed_up_arrow_radius: f32,
-) -> Vec<Vec<Vec2>> {
-    const MIN_VELOCITY: f32 = 1.0; // Stop when velocity is very low
+) -> Vec<Trajectory> {
     const LINE_SEGMENT_SAMPLES: usize = 3;
 
-    // Initialize simulation state for each stone
-    let mut stones: Vec<_> = stone_data
-        .iter()
-        .map(|(pos, vel, radius)| (*pos, vel.clone(), *radius))
-        .collect();
-
-    let mut trajectories: Vec<Vec<Vec2>> = stones.iter().map(|(pos, _, _)| vec![*pos]).collect();
+    let mut trajectories: Vec<Vec<Vec2>> =
+        stones.iter().map(|stone| vec![stone.position]).collect();
 
     let steps = 10000;
     for i in 0..steps {
         // Check if all stones have stopped
         let all_stopped = stones
             .iter()
-            .all(|(_, vel, _)| vel.0.length_squared() < MIN_VELOCITY * MIN_VELOCITY);
+            .all(|stone| has_stopped(&stone.velocity, params.stop_velocity));
         if all_stopped {
             break;
         }
 
-        // Step 1: Apply stone collisions (matches apply_stone_collision)
-        for j in 0..stones.len() {
-            for k in (j + 1)..stones.len() {
-                let (pos1, vel1, radius1) = &stones[j];
-                let (pos2, vel2, radius2) = &stones[k];
-
-                if let Some((new_vel1, new_vel2)) =
-                    resolve_collision(*pos1, vel1, *radius1, *pos2, vel2, *radius2)
-                {
-                    stones[j].1 = new_vel1;
-                    stones[k].1 = new_vel2;
-                }
-            }
-        }
-
-        // Step 2: Move positions (matches update_stone_position)
-        for (pos, vel, _) in &mut stones {
-            *pos += vel.0 * fixed_dt;
-        }
+        step_stones(&mut stones, tile_data, hex_grid, params, None, fixed_dt);
 
         // Record trajectory points
         if i % LINE_SEGMENT_SAMPLES == 0 {
-            for (idx, (pos, _, _)) in stones.iter().enumerate() {
-                trajectories[idx].push(*pos);
+            for (idx, stone) in stones.iter().enumerate() {
+                trajectories[idx].push(stone.position);
             }
         }
-
-        // Step 3: Update velocities based on new positions (matches apply_tile_velocity_effects)
-        for (pos, vel, radius) in &mut stones {
-            *vel = compute_tile_effects(
-                *pos,
-                vel,
-                tile_data,
-                hex_grid,
-                drag_coefficient,
-                *radius,
-                slow_down_factor,
-                rotation_factor,
-                speed_up_factor,
-                speed_up_arrow_radius,
-            )
-            .velocity;
-        }
     }
 
     // Always include the final positions
-    for (idx, (pos, _, _)) in stones.iter().enumerate() {
-        if trajectories[idx].last() != Some(pos) {
-            trajectories[idx].push(*pos);
+    for (idx, stone) in stones.iter().enumerate() {
+        if trajectories[idx].last() != Some(&stone.position) {
+            trajectories[idx].push(stone.position);
         }
     }
 
     trajectories
+        .into_iter()
+        .zip(&stones)
+        .map(|(points, stone)| Trajectory {
+            points,
+            rest_position: stone.position,
+        })
+        .collect()
 }
 
-#[derive(Component)]
-pub struct Level0Completed;
+/// A stone's predicted path and where it comes to rest
+pub struct Trajectory {
+    pub points: Vec<Vec2>,
+    pub rest_position: Vec2,
+}
+
+/// Whether a stone coming to rest at `rest_position` is close enough to `goal` to snap into it
+pub fn lands_in_goal(rest_position: Vec2, goal: Vec2, snap_distance: f32) -> bool {
+    rest_position.distance(goal) < snap_distance
+}
 
-fn level_0_complete_check(
+/// Level0 is won by sweeping every tile into `MaintainSpeed`
+fn check_level_0_complete(
+    tile_changed: On<TileChanged>,
     mut commands: Commands,
     on_level: Res<OnLevel>,
-    tile_query: Query<(Entity, &TileDragging, Option<&Level0Completed>)>,
+    tiles: Query<&SweptTileType>,
 ) {
-    if (on_level.0.current_level == CurrentLevel::Level0)
-        && tile_query
+    if on_level.0.current_level == CurrentLevel::Level0
+        && tile_changed.new == TileType::MaintainSpeed
+        && tiles
             .iter()
-            .all(|(_, tile_dragging, level0_completed)| {
-                *tile_dragging
-                    .distance_dragged
-                    .get(&TileType::MaintainSpeed)
-                    .unwrap_or(&0.0)
-                    + 2.0
-                    >= on_level.0.min_sweep_distance
-                    && level0_completed.is_none()
-            })
+            .all(|swept_tile_type| swept_tile_type.0 == TileType::MaintainSpeed)
     {
-        for (entity, _, _) in tile_query.iter() {
-            commands.entity(entity).insert(Level0Completed);
-        }
         commands.trigger(LevelComplete);
     }
 }
@@ -667,6 +1017,14 @@ fn level_0_complete_check(
 #[derive(Component)]
 pub struct PlayedGetInThere;
 
+/// Squared distance on the board from a stone to the goal
+pub fn goal_distance_squared(stone: &Transform, goal: &Transform) -> f32 {
+    stone
+        .translation
+        .truncate()
+        .distance_squared(goal.translation.truncate())
+}
+
 fn play_get_in_there(
     mut commands: Commands,
     gameplay_assets: Res<GameplayAssets>,
@@ -676,11 +1034,7 @@ fn play_get_in_there(
 ) {
     let min_dist_from_snap = 80.0;
     let min_velocity = 40.0;
-    let distance_from_goal_squared = stone_query
-        .1
-        .translation
-        .truncate()
-        .distance_squared(goal_query.translation.truncate());
+    let distance_from_goal_squared = goal_distance_squared(stone_query.1, &goal_query);
     let velocity_squared = stone_query.2.0.length_squared();
     let inside_goal_tile =
         distance_from_goal_squared <= on_level.0.hex_radius * on_level.0.hex_radius;
@@ -694,11 +1048,3 @@ fn play_get_in_there(
         commands.spawn(SamplePlayer::new(gameplay_assets.get_in_there.clone()));
     }
 }
-
-fn on_stone_hit_wall(
-    _event: On<StoneHitWall>,
-    mut commands: Commands,
-    gameplay_assets: Res<GameplayAssets>,
-) {
-    commands.spawn(SamplePlayer::new(gameplay_assets.ding.clone()));
-}
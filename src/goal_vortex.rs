@@ -0,0 +1,245 @@
+//! Turns the goal into a vortex: a dark disc that pulses, a ring that spins faster the closer the
+//! nearest stone gets, and a black core. A stone that reaches the goal is swallowed, shrinking
+//! into the hole before the confetti starts.
+//!
+//! Everything lives on the goal tile and the stones, so restarting a level clears it all away.
+
+use bevy::prelude::*;
+
+use crate::{
+    PausableSystems,
+    gameplay::{LevelComplete, goal_distance_squared},
+    layers,
+    level::OnLevel,
+    screens::Screen,
+    stone::{ReachedGoal, Stone, StoneBody},
+    stone_squash::ImpactAnim,
+    tile::{IsGoal, TILE_FILL_Z, TileTheme},
+};
+
+/// Radii of the vortex's parts, as fractions of the level's `hex_radius`
+pub const OUTER_RADIUS: f32 = 0.55;
+const RING_RADIUS: f32 = 0.42;
+const CORE_RADIUS: f32 = 0.28;
+/// The ring's width, as a fraction of its radius
+const RING_WIDTH: f32 = 0.2;
+/// Radians per second the ring spins with no stone nearby, and with one right on top of it
+const IDLE_SPIN: f32 = 0.8;
+const MAX_SPIN: f32 = 8.0;
+/// Stones further away than this don't speed the ring up
+const SPIN_UP_DISTANCE: f32 = 400.0;
+/// Pulses per second of the outer disc, and how much bigger and smaller it gets
+const PULSE_FREQUENCY: f32 = 0.75;
+const PULSE_AMOUNT: f32 = 0.08;
+/// Where the vortex's parts are drawn, over the goal's fill
+const OUTER_Z: f32 = TILE_FILL_Z + 0.1;
+const RING_Z: f32 = TILE_FILL_Z + 0.11;
+const CORE_Z: f32 = TILE_FILL_Z + 0.12;
+/// How long a stone takes to disappear into the goal, the confetti waits for it
+pub const SWALLOW_SECONDS: f32 = 0.5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<VortexAssets>()
+        .add_systems(
+            Update,
+            (add_goal_vortex, spin_goal_vortex, swallow_stones)
+                .run_if(in_state(Screen::Gameplay))
+                .in_set(PausableSystems),
+        )
+        .add_systems(Update, apply_vortex_theme)
+        .add_observer(start_swallow);
+}
+
+/// The goal vortex's meshes are unit sized and scaled to the level, so they're shared by every
+/// level
+#[derive(Resource)]
+struct VortexAssets {
+    disc_mesh: Handle<Mesh>,
+    ring_mesh: Handle<Mesh>,
+    outer_material: Handle<ColorMaterial>,
+    ring_material: Handle<ColorMaterial>,
+    core_material: Handle<ColorMaterial>,
+}
+
+impl FromWorld for VortexAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+        // Six sided, so it's plain to see the ring turning
+        let disc_mesh = meshes.add(Circle::new(1.0));
+        let ring_mesh = meshes.add(Annulus::new(1.0 - RING_WIDTH, 1.0).mesh().resolution(6));
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        Self {
+            disc_mesh,
+            ring_mesh,
+            outer_material: materials.add(ColorMaterial::default()),
+            ring_material: materials.add(ColorMaterial::default()),
+            core_material: materials.add(ColorMaterial::default()),
+        }
+    }
+}
+
+/// One of the vortex's parts, a child of the goal tile
+#[derive(Component, Debug)]
+pub enum GoalVortex {
+    /// Pulses around `radius`
+    Outer {
+        radius: f32,
+    },
+    Ring,
+    Core,
+}
+
+/// A stone sinking into the goal
+#[derive(Component, Debug)]
+pub struct Swallowed {
+    /// `Time::elapsed_secs` when it reached the goal
+    pub started: f32,
+}
+
+/// How fast the ring spins with the nearest stone `distance` away, if there's a stone at all
+pub fn vortex_spin(distance: Option<f32>) -> f32 {
+    let closeness = distance.map_or(0.0, |distance| {
+        1.0 - (distance / SPIN_UP_DISTANCE).clamp(0.0, 1.0)
+    });
+    IDLE_SPIN.lerp(MAX_SPIN, closeness)
+}
+
+/// The stone's scale `progress` of the way into the goal, slow to start then dropping away
+pub fn swallow_scale(progress: f32) -> f32 {
+    let progress = progress.clamp(0.0, 1.0);
+    1.0 - progress * progress
+}
+
+/// Not paused, so a theme picked from the pause menu shows behind it straight away
+fn apply_vortex_theme(
+    theme: Res<TileTheme>,
+    vortex_assets: Res<VortexAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if !theme.is_changed() {
+        return;
+    }
+    let handles = [
+        &vortex_assets.outer_material,
+        &vortex_assets.ring_material,
+        &vortex_assets.core_material,
+    ];
+    for (handle, color) in handles.into_iter().zip(theme.colors().goal_hole) {
+        if let Some(material) = materials.get_mut(handle) {
+            material.color = color;
+        }
+    }
+}
+
+fn add_goal_vortex(
+    mut commands: Commands,
+    vortex_assets: Res<VortexAssets>,
+    on_level: Res<OnLevel>,
+    goals: Query<(Entity, &Transform), Added<IsGoal>>,
+) {
+    let hex_radius = on_level.0.hex_radius;
+    for (goal, transform) in &goals {
+        let outer = hex_radius * OUTER_RADIUS;
+        let z = |z: f32| layers::child_z(z, layers::BOARD, transform.scale.z);
+        commands.entity(goal).with_children(|parent| {
+            parent.spawn((
+                GoalVortex::Outer { radius: outer },
+                Mesh2d(vortex_assets.disc_mesh.clone()),
+                MeshMaterial2d(vortex_assets.outer_material.clone()),
+                Transform::from_xyz(0., 0., z(OUTER_Z)).with_scale(Vec3::splat(outer)),
+                Pickable::IGNORE,
+            ));
+            parent.spawn((
+                GoalVortex::Ring,
+                Mesh2d(vortex_assets.ring_mesh.clone()),
+                MeshMaterial2d(vortex_assets.ring_material.clone()),
+                Transform::from_xyz(0., 0., z(RING_Z))
+                    .with_scale(Vec3::splat(hex_radius * RING_RADIUS)),
+                Pickable::IGNORE,
+            ));
+            parent.spawn((
+                GoalVortex::Core,
+                Mesh2d(vortex_assets.disc_mesh.clone()),
+                MeshMaterial2d(vortex_assets.core_material.clone()),
+                Transform::from_xyz(0., 0., z(CORE_Z))
+                    .with_scale(Vec3::splat(hex_radius * CORE_RADIUS)),
+                Pickable::IGNORE,
+            ));
+        });
+    }
+}
+
+fn spin_goal_vortex(
+    time: Res<Time>,
+    goal: Single<&Transform, (With<IsGoal>, Without<GoalVortex>)>,
+    stones: Query<&Transform, (With<Stone>, Without<GoalVortex>)>,
+    mut parts: Query<(&GoalVortex, &mut Transform)>,
+) {
+    let nearest = stones
+        .iter()
+        .map(|stone| goal_distance_squared(stone, &goal))
+        .min_by(f32::total_cmp)
+        .map(f32::sqrt);
+    let spin = vortex_spin(nearest);
+    let pulse =
+        1.0 + PULSE_AMOUNT * (time.elapsed_secs() * PULSE_FREQUENCY * std::f32::consts::TAU).sin();
+    for (part, mut transform) in &mut parts {
+        match part {
+            GoalVortex::Outer { radius } => {
+                transform.scale = Vec3::splat(radius * pulse);
+            }
+            GoalVortex::Ring => transform.rotate_z(spin * time.delta_secs()),
+            GoalVortex::Core => {}
+        }
+    }
+}
+
+/// The stone that reached the goal gets its `ReachedGoal` just before `LevelComplete` fires
+fn start_swallow(
+    _event: On<LevelComplete>,
+    mut commands: Commands,
+    time: Res<Time>,
+    stones: Query<Entity, With<ReachedGoal>>,
+) {
+    for stone in &stones {
+        commands
+            .entity(stone)
+            .remove::<ImpactAnim>()
+            .insert(Swallowed {
+                started: time.elapsed_secs(),
+            });
+    }
+}
+
+fn swallow_stones(
+    time: Res<Time>,
+    stones: Query<(&Swallowed, &Children)>,
+    mut bodies: Query<&mut Transform, With<StoneBody>>,
+) {
+    for (swallowed, children) in &stones {
+        let progress = (time.elapsed_secs() - swallowed.started) / SWALLOW_SECONDS;
+        let mut bodies = bodies.iter_many_mut(children);
+        while let Some(mut transform) = bodies.fetch_next() {
+            *transform = Transform::from_scale(Vec3::splat(swallow_scale(progress)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vortex_spins_up_and_swallows() {
+        assert_eq!(vortex_spin(None), IDLE_SPIN);
+        assert_eq!(vortex_spin(Some(SPIN_UP_DISTANCE * 2.0)), IDLE_SPIN);
+        assert!((vortex_spin(Some(0.0)) - MAX_SPIN).abs() < 1e-5);
+        // Spins faster the closer the stone gets
+        assert!(vortex_spin(Some(100.0)) > vortex_spin(Some(200.0)));
+        assert_eq!(swallow_scale(0.0), 1.0);
+        assert!(swallow_scale(0.5) > 0.5);
+        // Gone once it's over, and stays gone
+        assert_eq!(swallow_scale(1.0), 0.0);
+        assert_eq!(swallow_scale(2.0), 0.0);
+    }
+}
@@ -1,13 +1,16 @@
 use bevy::prelude::*;
 
 use crate::{
-    level::Level,
+    auto_sweep::on_tile_auto_sweep_click,
+    layers,
+    level::{Facing, Level},
     screens::Screen,
     tile::{
-        CanBeDragged, IsGoal, ScratchOffMaterial, TileAssets, TileType, on_pointer_out,
-        on_pointer_over, on_tile_drag_end, on_tile_drag_enter, on_tile_drag_leave,
-        on_tile_dragging, tile, tile_can_be_dragged,
+        CanBeDragged, IsGoal, ScratchOffMaterial, TileAssets, TileType, hover_outline,
+        on_pointer_out, on_pointer_over, on_tile_click, on_tile_drag_end, on_tile_drag_enter,
+        on_tile_drag_leave, on_tile_dragging, tile, tile_can_be_dragged,
     },
+    undo::{on_stroke_end, on_stroke_start},
 };
 
 /// Component for the hex grid entity.
@@ -57,14 +60,66 @@ impl HexGrid {
             level: level.clone(),
         }
     }
+
+    /// Radius of the tile at `coordinate`, after its scale override if it has one
+    pub fn tile_radius(&self, coordinate: &HexCoordinate) -> f32 {
+        self.hex_radius * self.level.tile_scale(coordinate)
+    }
 }
 
-#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+#[derive(Component, Debug, PartialEq, Clone, Eq, Hash)]
 pub struct HexCoordinate {
     pub q: i32,
     pub r: i32,
 }
 
+impl HexCoordinate {
+    /// Returns the adjacent coordinate in the given direction.
+    ///
+    /// Columns are offset (see `hex_to_world`): odd columns sit half a hex higher than even ones,
+    /// so which row a diagonal neighbor lands on depends on the column's parity.
+    pub fn neighbor(&self, dir: &Facing) -> HexCoordinate {
+        let odd_column = self.q.rem_euclid(2) == 1;
+        let (dq, dr) = match (dir, odd_column) {
+            (Facing::Up, _) => (0, -1),
+            (Facing::Down, _) => (0, 1),
+            (Facing::UpRight, false) => (1, 0),
+            (Facing::UpRight, true) => (1, -1),
+            (Facing::DownRight, false) => (1, 1),
+            (Facing::DownRight, true) => (1, 0),
+            (Facing::DownLeft, false) => (-1, 1),
+            (Facing::DownLeft, true) => (-1, 0),
+            (Facing::UpLeft, false) => (-1, 0),
+            (Facing::UpLeft, true) => (-1, -1),
+        };
+        HexCoordinate {
+            q: self.q + dq,
+            r: self.r + dr,
+        }
+    }
+
+    /// All six adjacent coordinates, in [`Facing::iterator`] order.
+    pub fn neighbors(&self) -> [HexCoordinate; 6] {
+        let mut facings = Facing::iterator();
+        std::array::from_fn(|_| self.neighbor(facings.next().unwrap()))
+    }
+
+    /// Number of steps between two coordinates, moving only between adjacent hexes.
+    pub fn distance(&self, other: &HexCoordinate) -> i32 {
+        let (q1, r1, s1) = self.to_cube();
+        let (q2, r2, s2) = other.to_cube();
+        ((q1 - q2).abs() + (r1 - r2).abs() + (s1 - s2).abs()) / 2
+    }
+
+    /// Converts from offset coordinates to cube coordinates, where q + r + s == 0.
+    fn to_cube(&self) -> (i32, i32, i32) {
+        let q = self.q;
+        // Odd columns are shifted up, so they count as belonging to the row above
+        let r = self.r - (self.q + (self.q & 1)) / 2;
+        (q, r, -q - r)
+    }
+}
+
 /// Converts hex grid coordinates to world position for flat-top hexagons
 pub fn hex_to_world(hex_coord: &HexCoordinate, hex_grid: &HexGrid) -> Vec2 {
     let x = hex_grid.offset_x + hex_coord.q as f32 * hex_grid.horiz_spacing;
@@ -81,6 +136,85 @@ pub fn hex_to_world(hex_coord: &HexCoordinate, hex_grid: &HexGrid) -> Vec2 {
     Vec2::new(x, y)
 }
 
+/// Distances closer than this fraction of `hex_radius` count as a tie, so a point on an edge between
+/// hexagons doesn't flip between them with float rounding
+const TIE_TOLERANCE: f32 = 1e-3;
+
+/// The candidate with the smallest distance. Ties go to the lowest `q`, then the lowest `r`, so a
+/// point on a boundary always picks the same hexagon, whatever order the candidates come in.
+fn closest_with_tie_break(
+    candidates: impl IntoIterator<Item = (HexCoordinate, f32)>,
+    hex_radius: f32,
+) -> Option<(HexCoordinate, f32)> {
+    let candidates: Vec<_> = candidates.into_iter().collect();
+    let closest = candidates
+        .iter()
+        .map(|(_, distance)| *distance)
+        .min_by(f32::total_cmp)?;
+    candidates
+        .into_iter()
+        .filter(|(_, distance)| *distance - closest <= TIE_TOLERANCE * hex_radius)
+        .min_by_key(|(coordinate, _)| (coordinate.q, coordinate.r))
+}
+
+/// Converts a world position to the hex grid coordinate whose hexagon contains it. The result may
+/// lie outside the level, so check the level's grid before using it.
+/// Spacing ignores tile scales, so a point in the gap around a scaled down tile still gets the
+/// closest tile. Points on an edge or corner go to the lowest `q`, then the lowest `r`, of the
+/// hexagons sharing it.
+pub fn world_to_hex(position: Vec2, hex_grid: &HexGrid) -> HexCoordinate {
+    let q = ((position.x - hex_grid.offset_x) / hex_grid.horiz_spacing).round() as i32;
+    let r = hex_grid.rows.1
+        - 1
+        - ((position.y - hex_grid.offset_y) / hex_grid.vert_spacing).round() as i32;
+
+    // The rounded guess can be one off near the slanted edges, and every point is closest to the
+    // center of the hexagon that contains it
+    let candidates = (q - 1..=q + 1)
+        .flat_map(|q| (r - 1..=r + 1).map(move |r| HexCoordinate { q, r }))
+        .map(|coordinate| {
+            let distance = hex_to_world(&coordinate, hex_grid).distance(position);
+            (coordinate, distance)
+        });
+    closest_with_tie_break(candidates, hex_grid.hex_radius)
+        .unwrap()
+        .0
+}
+
+/// The circumradius a flat-top hexagon centered on the origin needs to contain `offset`
+fn hex_reach(offset: Vec2) -> f32 {
+    // Distance to the furthest pair of edges, over the apothem's share of the circumradius
+    [30.0_f32, 90.0, 150.0]
+        .into_iter()
+        .map(|degrees| offset.dot(Vec2::from_angle(degrees.to_radians())).abs())
+        .fold(0.0, f32::max)
+        / (3.0_f32.sqrt() / 2.0)
+}
+
+/// Like `world_to_hex`, but only for tiles in the level and forgiving of points just outside them:
+/// a point within `tolerance * hex_radius` of a tile's edge maps to the closest such tile. Points
+/// inside a tile always map to it, and points further out than the tolerance map to `None`. Ties
+/// break the same way as `world_to_hex`.
+/// For hovering and dragging, physics sticks to the exact tile shapes.
+pub fn world_to_hex_tolerant(
+    position: Vec2,
+    hex_grid: &HexGrid,
+    tolerance: f32,
+) -> Option<HexCoordinate> {
+    let nearest = world_to_hex(position, hex_grid);
+    // A tolerance under a hex's width never reaches past the nearest coordinate's neighbors
+    let candidates = std::iter::once(nearest.clone())
+        .chain(nearest.neighbors())
+        .filter(|coordinate| hex_grid.level.grid.contains_key(coordinate))
+        .map(|coordinate| {
+            let offset = position - hex_to_world(&coordinate, hex_grid);
+            let outside_by = hex_reach(offset) - hex_grid.tile_radius(&coordinate);
+            (coordinate, outside_by)
+        })
+        .filter(|(_, outside_by)| *outside_by <= tolerance * hex_grid.hex_radius);
+    closest_with_tie_break(candidates, hex_grid.hex_radius).map(|(coordinate, _)| coordinate)
+}
+
 pub fn spawn_hex_grid(
     commands: &mut Commands,
     grid: &HexGrid,
@@ -98,8 +232,8 @@ pub fn spawn_hex_grid(
                     .spawn((tile(
                         tile_type,
                         world_pos,
-                        q,
-                        r,
+                        HexCoordinate { q, r },
+                        level.tile_scale(&HexCoordinate { q, r }),
                         level.min_sweep_distance,
                         tile_assets,
                         scratch_materials,
@@ -110,6 +244,10 @@ pub fn spawn_hex_grid(
                     .observe(on_tile_drag_enter)
                     .observe(on_tile_drag_end)
                     .observe(on_tile_drag_leave)
+                    .observe(on_tile_click)
+                    .observe(on_tile_auto_sweep_click)
+                    .observe(on_stroke_start)
+                    .observe(on_stroke_end)
                     .id();
                 if tile_can_be_dragged(tile_type) {
                     commands.entity(tile_id).insert(CanBeDragged);
@@ -126,9 +264,241 @@ pub fn spawn_hex_grid(
         .spawn((
             DespawnOnExit(Screen::Gameplay),
             Visibility::Visible,
-            Transform::from_xyz(0., 0., 0.),
+            Transform::from_xyz(0., 0., layers::BOARD),
             grid.clone(),
         ))
         .add_children(&tile_entities)
+        .with_child(hover_outline(tile_assets))
         .id()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opposite(facing: &Facing) -> Facing {
+        match facing {
+            Facing::Up => Facing::Down,
+            Facing::UpRight => Facing::DownLeft,
+            Facing::DownRight => Facing::UpLeft,
+            Facing::Down => Facing::Up,
+            Facing::DownLeft => Facing::UpRight,
+            Facing::UpLeft => Facing::DownRight,
+        }
+    }
+
+    #[test]
+    fn test_neighbor_round_trips_on_even_and_odd_columns() {
+        // Going one way then back must return to the start regardless of column parity,
+        // including negative columns where `%` would get the parity wrong
+        for q in -3..=3 {
+            for r in -2..=2 {
+                let start = HexCoordinate { q, r };
+                for facing in Facing::iterator() {
+                    let there = start.neighbor(facing);
+                    assert_eq!(
+                        there.neighbor(&opposite(facing)),
+                        start,
+                        "{start:?} {facing}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_neighbor_matches_world_direction() {
+        // Each neighbor should sit one hex away in the direction its facing points
+        let level = crate::level::get_level(crate::level::CurrentLevel::Level1);
+        let grid = HexGrid::new(&level);
+        for q in 0..=1 {
+            let start = HexCoordinate { q, r: 1 };
+            for facing in Facing::iterator() {
+                let offset =
+                    hex_to_world(&start.neighbor(facing), &grid) - hex_to_world(&start, &grid);
+                let direction = offset.normalize();
+                assert!(
+                    direction.distance(facing.to_vector()) < 1e-4,
+                    "{start:?} {facing}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_distance() {
+        let origin = HexCoordinate { q: 0, r: 0 };
+        assert_eq!(origin.distance(&origin), 0);
+        for neighbor in origin.neighbors() {
+            assert_eq!(origin.distance(&neighbor), 1);
+        }
+        // Level1's corridor: (1, 1) to the goal at (7, 4) is six diagonal steps
+        assert_eq!(
+            HexCoordinate { q: 1, r: 1 }.distance(&HexCoordinate { q: 7, r: 4 }),
+            6
+        );
+        // Straight down a column
+        assert_eq!(origin.distance(&HexCoordinate { q: 0, r: 5 }), 5);
+    }
+
+    #[test]
+    fn test_world_to_hex_round_trips() {
+        // Hex centers map back to their own coordinate, and so do points just inside the corners,
+        // where the nearest row and column guesses are off by one
+        let level = crate::level::get_level(crate::level::CurrentLevel::Level1);
+        let grid = HexGrid::new(&level);
+        for q in -2..=9 {
+            for r in -2..=6 {
+                let coordinate = HexCoordinate { q, r };
+                let center = hex_to_world(&coordinate, &grid);
+                assert_eq!(world_to_hex(center, &grid), coordinate);
+                for corner in 0..6 {
+                    let angle = corner as f32 * std::f32::consts::FRAC_PI_3;
+                    let inside = center + Vec2::from_angle(angle) * grid.hex_radius * 0.95;
+                    assert_eq!(world_to_hex(inside, &grid), coordinate, "{coordinate:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_world_to_hex_tolerant_offsets() {
+        // Level0 is a single tile at the origin coordinate
+        let level = crate::level::get_level(crate::level::CurrentLevel::Level0);
+        let grid = HexGrid::new(&level);
+        let origin = HexCoordinate { q: 0, r: 0 };
+        let center = hex_to_world(&origin, &grid);
+        let apothem = grid.hex_radius * 3.0_f32.sqrt() / 2.0;
+        let up = |distance: f32| center + Vec2::Y * distance;
+
+        // Inside always hits, even with no tolerance
+        assert_eq!(
+            world_to_hex_tolerant(center, &grid, 0.0),
+            Some(origin.clone())
+        );
+        assert_eq!(
+            world_to_hex_tolerant(up(apothem * 0.95), &grid, 0.0),
+            Some(origin.clone())
+        );
+        // Just past the top edge only hits with some tolerance. Past an edge by d the hexagon has
+        // to grow by d / (sqrt(3) / 2) to reach, about 0.115 of a radius here
+        let just_outside = up(apothem + grid.hex_radius * 0.1);
+        assert_eq!(world_to_hex_tolerant(just_outside, &grid, 0.0), None);
+        assert_eq!(
+            world_to_hex_tolerant(just_outside, &grid, 0.25),
+            Some(origin.clone())
+        );
+        // About 0.35 of a radius out is past 0.25
+        let further_out = up(apothem + grid.hex_radius * 0.3);
+        assert_eq!(world_to_hex_tolerant(further_out, &grid, 0.25), None);
+        assert_eq!(
+            world_to_hex_tolerant(further_out, &grid, 0.4),
+            Some(origin.clone())
+        );
+        // Just past a corner, where the hexagon is furthest out
+        let past_corner = center + Vec2::X * grid.hex_radius * 1.1;
+        assert_eq!(world_to_hex_tolerant(past_corner, &grid, 0.0), None);
+        assert_eq!(
+            world_to_hex_tolerant(past_corner, &grid, 0.25),
+            Some(origin.clone())
+        );
+
+        // A scaled down tile is measured from its own edge, not the spacing's
+        let mut smaller = level.clone();
+        smaller.tile_scales.insert(origin.clone(), 0.5);
+        let grid = HexGrid::new(&smaller);
+        let in_gap = up(apothem * 0.6);
+        assert_eq!(world_to_hex_tolerant(in_gap, &grid, 0.0), None);
+        assert_eq!(world_to_hex_tolerant(in_gap, &grid, 0.25), Some(origin));
+    }
+
+    #[test]
+    fn test_world_to_hex_tolerant_prefers_the_tile_underneath() {
+        // Points inside a tile map to it however forgiving the lookup, even right by a neighbor
+        let level = crate::level::get_level(crate::level::CurrentLevel::Level1);
+        let grid = HexGrid::new(&level);
+        for coordinate in level.grid.keys() {
+            let center = hex_to_world(coordinate, &grid);
+            for corner in 0..6 {
+                let angle = corner as f32 * std::f32::consts::FRAC_PI_3;
+                let inside = center + Vec2::from_angle(angle) * grid.hex_radius * 0.95;
+                assert_eq!(
+                    world_to_hex_tolerant(inside, &grid, 0.5).as_ref(),
+                    Some(coordinate),
+                    "{coordinate:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_world_to_hex_breaks_ties_by_lowest_q_then_r() {
+        let level = crate::level::get_level(crate::level::CurrentLevel::Level1);
+        let grid = HexGrid::new(&level);
+        let lowest = |coordinates: &[&HexCoordinate]| {
+            coordinates
+                .iter()
+                .map(|coordinate| (coordinate.q, coordinate.r))
+                .min()
+                .unwrap()
+        };
+        for q in -1..=2 {
+            for r in -1..=2 {
+                let coordinate = HexCoordinate { q, r };
+                let center = hex_to_world(&coordinate, &grid);
+                // Halfway between two centers is the middle of their shared edge
+                for neighbor in coordinate.neighbors() {
+                    let midpoint = center.midpoint(hex_to_world(&neighbor, &grid));
+                    let picked = world_to_hex(midpoint, &grid);
+                    assert_eq!(
+                        (picked.q, picked.r),
+                        lowest(&[&coordinate, &neighbor]),
+                        "{coordinate:?} {neighbor:?}"
+                    );
+                }
+                // Every corner is shared by the hexagon and the two neighbors it's as far from
+                for corner in 0..6 {
+                    let angle = corner as f32 * std::f32::consts::FRAC_PI_3;
+                    let point = center + Vec2::from_angle(angle) * grid.hex_radius;
+                    let neighbors = coordinate.neighbors();
+                    let sharing: Vec<_> = std::iter::once(&coordinate)
+                        .chain(neighbors.iter().filter(|neighbor| {
+                            let distance = hex_to_world(neighbor, &grid).distance(point);
+                            (distance - grid.hex_radius).abs() < 1e-3
+                        }))
+                        .collect();
+                    assert_eq!(sharing.len(), 3, "{coordinate:?} corner {corner}");
+                    let picked = world_to_hex(point, &grid);
+                    assert_eq!((picked.q, picked.r), lowest(&sharing), "{coordinate:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_mirrored_tiles_sit_mirrored_in_the_world() {
+        let mut level = crate::level::get_level(crate::level::CurrentLevel::Level1);
+        // Columns 0 to 4, so the middle is column 2
+        level.grid = (0..5)
+            .flat_map(|q| (0..3).map(move |r| (HexCoordinate { q, r }, TileType::MaintainSpeed)))
+            .collect();
+        let grid = HexGrid::new(&level);
+        for coordinate in level.grid.keys() {
+            let Some(mirrored) = level.mirrored(coordinate) else {
+                assert_eq!(coordinate.q, 2, "{coordinate:?}");
+                continue;
+            };
+            let position = hex_to_world(coordinate, &grid);
+            let mirrored_position = hex_to_world(&mirrored, &grid);
+            assert!((mirrored_position - Vec2::new(-position.x, position.y)).length() < 1e-3);
+            assert_eq!(level.mirrored(&mirrored).as_ref(), Some(coordinate));
+        }
+        // Missing its mirror tile, or with the middle between two columns, nothing mirrors
+        level.grid.remove(&HexCoordinate { q: 4, r: 1 });
+        assert_eq!(level.mirrored(&HexCoordinate { q: 0, r: 1 }), None);
+        level
+            .grid
+            .insert(HexCoordinate { q: 5, r: 0 }, TileType::MaintainSpeed);
+        assert_eq!(level.mirrored(&HexCoordinate { q: 0, r: 0 }), None);
+    }
+}
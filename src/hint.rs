@@ -0,0 +1,239 @@
+//! Hints for stuck players. A "Hint" button highlights the shortest route of tiles from each stone
+//! to the goal, roughly the tiles worth sweeping into `MaintainSpeed`.
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    gameplay::create_tapered_line_mesh,
+    hex_grid::{HexCoordinate, HexGrid, hex_to_world},
+    layers,
+    level::{Level, OnLevel},
+    menus::btn,
+    screens::Screen,
+    tile::{TilePalette, TileTheme, TileType},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<HintAssets>()
+        .add_systems(OnEnter(Screen::Gameplay), spawn_hint_button)
+        .add_systems(Update, apply_hint_theme);
+}
+
+/// Line drawn through the hinted tiles. Spawned as a child of the hex grid so it goes away when
+/// the level restarts, and only hidden when the hint is toggled off.
+#[derive(Component)]
+struct HintPath;
+
+/// Shared by every hint path, recolored in place when the theme or palette changes
+#[derive(Resource)]
+struct HintAssets {
+    material: Handle<ColorMaterial>,
+}
+
+impl FromWorld for HintAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        Self {
+            material: materials.add(ColorMaterial::default()),
+        }
+    }
+}
+
+/// Finds the shortest route from `start` to `goal` that only passes through non-wall tiles, using
+/// A* with the hex distance as the heuristic. The returned path includes both ends.
+pub fn hex_path(
+    level: &Level,
+    start: HexCoordinate,
+    goal: HexCoordinate,
+) -> Option<Vec<HexCoordinate>> {
+    let walkable = |coordinate: &HexCoordinate| {
+        level
+            .grid
+            .get(coordinate)
+            .is_some_and(|tile_type| tile_type != &TileType::Wall)
+    };
+    if !walkable(&start) || !walkable(&goal) {
+        return None;
+    }
+
+    let mut came_from: HashMap<HexCoordinate, HexCoordinate> = HashMap::new();
+    let mut cost_so_far: HashMap<HexCoordinate, i32> = HashMap::from([(start.clone(), 0)]);
+    // Ties are broken on the coordinate so the same level always gives the same hint
+    let mut open = BinaryHeap::from([Reverse((start.distance(&goal), start.q, start.r))]);
+
+    while let Some(Reverse((_, q, r))) = open.pop() {
+        let current = HexCoordinate { q, r };
+        if current == goal {
+            let mut path = vec![current];
+            while let Some(previous) = came_from.get(path.last().unwrap()) {
+                path.push(previous.clone());
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        let cost = cost_so_far[&current] + 1;
+        for neighbor in current.neighbors() {
+            if !walkable(&neighbor) || cost_so_far.get(&neighbor).is_some_and(|&c| c <= cost) {
+                continue;
+            }
+            open.push(Reverse((
+                cost + neighbor.distance(&goal),
+                neighbor.q,
+                neighbor.r,
+            )));
+            cost_so_far.insert(neighbor.clone(), cost);
+            came_from.insert(neighbor, current.clone());
+        }
+    }
+
+    None
+}
+
+fn spawn_hint_button(mut commands: Commands) {
+    commands.spawn((
+        DespawnOnExit(Screen::Gameplay),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(16.0),
+            bottom: Val::Px(16.0),
+            ..default()
+        },
+        children![btn("hint", toggle_hint)],
+    ));
+}
+
+/// Not paused, so a theme picked from the pause menu shows on the hint straight away
+fn apply_hint_theme(
+    theme: Res<TileTheme>,
+    palette: Res<TilePalette>,
+    hint_assets: Res<HintAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if !theme.is_changed() && !palette.is_changed() && !hint_assets.is_changed() {
+        return;
+    }
+    if let Some(material) = materials.get_mut(&hint_assets.material) {
+        material.color = theme
+            .colors()
+            .tile_color(*palette, &TileType::MaintainSpeed)
+            .with_alpha(0.8);
+    }
+}
+
+/// The paths are only built the first time the hint is shown on each level, after that toggling
+/// just shows and hides them
+fn toggle_hint(
+    _: On<Pointer<Click>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    hint_assets: Res<HintAssets>,
+    on_level: Res<OnLevel>,
+    grid: Single<(Entity, &HexGrid)>,
+    mut hint_paths: Query<&mut Visibility, With<HintPath>>,
+) {
+    if !hint_paths.is_empty() {
+        for mut visibility in &mut hint_paths {
+            visibility.toggle_inherited_hidden();
+        }
+        return;
+    }
+
+    let level = &on_level.0;
+    let (grid_entity, grid) = *grid;
+    for stone_config in &level.stone_configs {
+        let Some(path) = hex_path(
+            level,
+            stone_config.start_coordinate.clone(),
+            level.goal_coordinate.clone(),
+        ) else {
+            continue;
+        };
+        let points: Vec<_> = path
+            .iter()
+            .map(|coordinate| hex_to_world(coordinate, grid))
+            .collect();
+        if let Some(mesh) = create_tapered_line_mesh(&points, 12.0, 12.0) {
+            let hint_path = commands
+                .spawn((
+                    HintPath,
+                    Mesh2d(meshes.add(mesh)),
+                    MeshMaterial2d(hint_assets.material.clone()),
+                    // On the grid, so drawn relative to the board
+                    Transform::from_xyz(
+                        0.,
+                        0.,
+                        layers::child_z(layers::TILE_OVERLAY, layers::BOARD, 1.0),
+                    ),
+                ))
+                .id();
+            commands.entity(grid_entity).add_child(hint_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::{CurrentLevel, get_level};
+
+    #[test]
+    fn test_hex_path_follows_level_1_corridor() {
+        // Level1 is a single diagonal corridor, so the shortest path is exactly as long as the
+        // hex distance and every step moves to an adjacent tile
+        let level = get_level(CurrentLevel::Level1);
+        let start = level.stone_configs[0].start_coordinate.clone();
+        let path = hex_path(&level, start.clone(), level.goal_coordinate.clone()).unwrap();
+
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&level.goal_coordinate));
+        assert_eq!(
+            path.len() as i32 - 1,
+            start.distance(&level.goal_coordinate)
+        );
+        for step in path.windows(2) {
+            assert_eq!(step[0].distance(&step[1]), 1);
+        }
+    }
+
+    #[test]
+    fn test_hex_path_routes_around_walls() {
+        // A wall between the two ends forces a detour around it
+        let start = HexCoordinate { q: 0, r: 0 };
+        let wall = HexCoordinate { q: 0, r: 1 };
+        let goal = HexCoordinate { q: 0, r: 2 };
+        let mut level = get_level(CurrentLevel::Level1);
+        level.grid = [start.clone(), wall.clone(), goal.clone()]
+            .into_iter()
+            .flat_map(|coordinate| coordinate.neighbors().into_iter().chain([coordinate]))
+            .map(|coordinate| (coordinate, TileType::SlowDown))
+            .collect();
+        level.grid.insert(wall.clone(), TileType::Wall);
+
+        let path = hex_path(&level, start, goal).unwrap();
+        assert!(!path.contains(&wall));
+        assert_eq!(path.len(), 4);
+    }
+
+    #[test]
+    fn test_hex_path_without_route() {
+        // The goal is surrounded by walls
+        let start = HexCoordinate { q: 0, r: 0 };
+        let goal = HexCoordinate { q: 2, r: 2 };
+        let mut level = get_level(CurrentLevel::Level1);
+        level.grid = goal
+            .neighbors()
+            .into_iter()
+            .map(|coordinate| (coordinate, TileType::Wall))
+            .collect();
+        level.grid.insert(start.clone(), TileType::SlowDown);
+        level.grid.insert(goal.clone(), TileType::Goal);
+
+        assert_eq!(hex_path(&level, start, goal), None);
+    }
+}
@@ -0,0 +1,231 @@
+//! A glint that now and then slides across swept ice, so it reads as glossy. Each straight tile
+//! gets a thin band as a child, added and removed as tiles are swept to and from straight, and
+//! every band passes over its tile on its own schedule so they don't all flash at once.
+//!
+//! Bands only move while play isn't paused, are hidden with reduce motion on, and are skipped
+//! while their tile is off screen.
+
+use bevy::prelude::*;
+
+use crate::{
+    PausableSystems,
+    camera::MainCamera,
+    fire_trail::{ember_seed, rand01},
+    gameplay::ReduceMotion,
+    hex_grid::HexCoordinate,
+    layers,
+    level::OnLevel,
+    screens::Screen,
+    tile::{SweptTileType, TILE_FILL_Z, TileType},
+};
+
+/// Seconds between a band's passes, and how long it takes to cross its tile
+const SHIMMER_PERIOD: f32 = 7.0;
+const SHIMMER_SWEEP: f32 = 1.4;
+/// The band's width as a fraction of the tile's radius
+const SHIMMER_WIDTH: f32 = 0.14;
+/// Which way the band lies across the tile, in degrees, leaning a little off the tile's edges
+const SHIMMER_ANGLE: f32 = 110.0;
+/// Over the tile's fill, under its overlays
+const SHIMMER_Z: f32 = TILE_FILL_Z + 0.2;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ShimmerAssets>().add_systems(
+        Update,
+        (add_ice_shimmer, animate_ice_shimmer.in_set(PausableSystems))
+            .chain()
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Every band is the same unit square, stretched to fit its tile
+#[derive(Resource)]
+struct ShimmerAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<ColorMaterial>,
+}
+
+impl FromWorld for ShimmerAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Rectangle::new(1.0, 1.0));
+        let material = world
+            .resource_mut::<Assets<ColorMaterial>>()
+            .add(Color::WHITE.with_alpha(0.22));
+        Self { mesh, material }
+    }
+}
+
+/// The glint on a straight tile, `phase` being how far through the period its passes start
+#[derive(Component, Debug)]
+pub struct IceShimmer {
+    phase: f32,
+}
+
+/// Where the band is `time` into its period, across an upright hex of circumradius `radius`: its
+/// center in the tile and its length, or `None` between passes
+pub fn shimmer_band(time: f32, radius: f32) -> Option<(Vec2, f32)> {
+    let progress = time.rem_euclid(SHIMMER_PERIOD) / SHIMMER_SWEEP;
+    if progress >= 1.0 {
+        return None;
+    }
+    let along = Vec2::from_angle(SHIMMER_ANGLE.to_radians());
+    let across = along.perp();
+    let half_width = radius * SHIMMER_WIDTH / 2.0;
+    // From the corner furthest back to the one furthest ahead, keeping the whole band on the tile
+    let reach = (0..6)
+        .map(|corner| {
+            let angle = (90.0 + 60.0 * corner as f32).to_radians();
+            (Vec2::from_angle(angle) * radius).dot(across)
+        })
+        .fold(0.0, f32::max)
+        - half_width;
+    let offset = across * (reach * (2.0 * progress - 1.0));
+
+    // Clip both of the band's sides to the hex's edges, which face every 60 degrees from 0. The
+    // hex is convex, so a band that ends where both sides are inside it is inside it too.
+    let apothem = radius * 3.0_f32.sqrt() / 2.0;
+    let (mut start, mut end) = (f32::NEG_INFINITY, f32::INFINITY);
+    for side in [offset - across * half_width, offset + across * half_width] {
+        for edge in 0..6 {
+            let normal = Vec2::from_angle((60.0 * edge as f32).to_radians());
+            let facing = along.dot(normal);
+            let room = apothem - side.dot(normal);
+            if facing > 1e-4 {
+                end = end.min(room / facing);
+            } else if facing < -1e-4 {
+                start = start.max(room / facing);
+            }
+        }
+    }
+    let length = (end - start).max(0.0);
+    Some((offset + along * (start + end) / 2.0, length))
+}
+
+/// Gives tiles swept to straight a band, and takes it off tiles swept to anything else
+fn add_ice_shimmer(
+    mut commands: Commands,
+    shimmer_assets: Res<ShimmerAssets>,
+    tiles: Query<(
+        Entity,
+        &HexCoordinate,
+        &Transform,
+        Ref<SweptTileType>,
+        Option<&Children>,
+    )>,
+    shimmers: Query<(), With<IceShimmer>>,
+) {
+    for (tile, coordinate, transform, swept_tile_type, children) in &tiles {
+        if !swept_tile_type.is_changed() {
+            continue;
+        }
+        let existing =
+            children.and_then(|children| children.iter().find(|child| shimmers.contains(*child)));
+        match (swept_tile_type.0 == TileType::MaintainSpeed, existing) {
+            (true, None) => {
+                let mut seed = ember_seed(coordinate);
+                commands.entity(tile).with_child((
+                    Name::new("Ice Shimmer"),
+                    IceShimmer {
+                        phase: rand01(&mut seed),
+                    },
+                    Mesh2d(shimmer_assets.mesh.clone()),
+                    MeshMaterial2d(shimmer_assets.material.clone()),
+                    Transform::from_xyz(
+                        0.0,
+                        0.0,
+                        layers::child_z(SHIMMER_Z, layers::BOARD, transform.scale.z),
+                    )
+                    .with_rotation(Quat::from_rotation_z(SHIMMER_ANGLE.to_radians())),
+                    Visibility::Hidden,
+                    Pickable::IGNORE,
+                ));
+            }
+            (false, Some(shimmer)) => commands.entity(shimmer).despawn(),
+            _ => {}
+        }
+    }
+}
+
+/// Keeps its own clock so the bands pick up where they were after a pause
+fn animate_ice_shimmer(
+    time: Res<Time>,
+    reduce_motion: Res<ReduceMotion>,
+    on_level: Res<OnLevel>,
+    camera: Single<(&GlobalTransform, &Projection), With<MainCamera>>,
+    mut shimmers: Query<(
+        &IceShimmer,
+        &GlobalTransform,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+    mut clock: Local<f32>,
+) {
+    *clock += time.delta_secs();
+    let hex_radius = on_level.0.hex_radius;
+    let (camera_transform, projection) = *camera;
+    let on_screen = match projection {
+        Projection::Orthographic(orthographic) => {
+            let center = camera_transform.translation().truncate();
+            Rect::from_corners(
+                orthographic.area.min + center,
+                orthographic.area.max + center,
+            )
+            .inflate(hex_radius)
+        }
+        _ => Rect::from_center_size(Vec2::ZERO, Vec2::INFINITY),
+    };
+    for (shimmer, global_transform, mut transform, mut visibility) in &mut shimmers {
+        let band = shimmer_band(*clock + shimmer.phase * SHIMMER_PERIOD, hex_radius)
+            .filter(|_| !reduce_motion.0)
+            .filter(|_| on_screen.contains(global_transform.translation().truncate()));
+        let Some((center, length)) = band else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        visibility.set_if_neq(Visibility::Inherited);
+        transform.translation = center.extend(transform.translation.z);
+        transform.scale = Vec3::new(length, hex_radius * SHIMMER_WIDTH, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shimmer_band_crosses_the_tile_inside_it() {
+        let radius = 40.0;
+        let apothem = radius * 3.0_f32.sqrt() / 2.0;
+        let along = Vec2::from_angle(SHIMMER_ANGLE.to_radians());
+        let half_width = along.perp() * radius * SHIMMER_WIDTH / 2.0;
+        let mut previous = None;
+        for step in 0..=100 {
+            let time = SHIMMER_SWEEP * step as f32 / 101.0;
+            let (center, length) = shimmer_band(time, radius).unwrap();
+            // Every corner of the band is within the hex
+            for end in [-0.5, 0.5] {
+                for side in [-1.0, 1.0] {
+                    let corner = center + along * length * end + half_width * side;
+                    for edge in 0..6 {
+                        let normal = Vec2::from_angle((60.0 * edge as f32).to_radians());
+                        assert!(corner.dot(normal) <= apothem + 1e-3, "{time}: {corner}");
+                    }
+                }
+            }
+            // It moves steadily one way across the tile
+            let position = center.dot(along.perp());
+            if let Some(previous) = previous {
+                assert!(position > previous);
+            }
+            previous = Some(position);
+        }
+        // Then it's gone until the next pass
+        assert_eq!(shimmer_band(SHIMMER_SWEEP + 0.1, radius), None);
+        assert!(shimmer_band(SHIMMER_PERIOD + 0.1, radius).is_some());
+        // The middle of the pass reaches right across the tile
+        let (_, middle) = shimmer_band(SHIMMER_SWEEP / 2.0, radius).unwrap();
+        assert!(middle > apothem);
+    }
+}
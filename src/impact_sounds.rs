@@ -0,0 +1,202 @@
+//! Knocks for stones hitting each other and the walls: a clack between stones and a thud off the
+//! boards, louder the harder the hit and pitched a little differently each time so repeats don't
+//! sound canned. Only the speed along the hit's normal counts, so glancing blows stay quiet.
+//!
+//! A stone grinding along a wall hits it every physics step, so each pair that's just made a
+//! sound has to wait a moment before it can make another. The wall's ding takes the same turn as
+//! its thud.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_seedling::{
+    prelude::PerceptualVolume,
+    sample::{AudioSample, PlaybackSettings, SamplePlayer},
+};
+
+use crate::{
+    fire_trail::rand01,
+    gameplay::{GameplayAssets, LevelStart},
+    screens::Screen,
+    stone::{StoneHitWall, StonesCollided},
+};
+
+/// Hits slower than this along their normal make no sound
+const MIN_IMPACT_SPEED: f32 = 15.0;
+/// Hits at this speed or faster play at full volume
+const FULL_IMPACT_SPEED: f32 = 400.0;
+/// Seconds before the same pair can make another sound
+const IMPACT_COOLDOWN: f32 = 0.12;
+/// Furthest the playback rate strays from normal, either way
+const PITCH_VARIATION: f32 = 0.08;
+
+const CONVERTER: PerceptualVolume = PerceptualVolume::new();
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ImpactSounds>()
+        .add_systems(OnExit(Screen::Gameplay), clear_impact_sounds)
+        .add_observer(clear_impact_sounds_on_level_start)
+        .add_observer(play_wall_hit)
+        .add_observer(play_stone_collision);
+}
+
+/// What hit what, with a collision's stones in a set order so it doesn't matter which came first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ImpactPair {
+    Wall(Entity),
+    Stones(Entity, Entity),
+}
+
+impl ImpactPair {
+    pub fn stones(a: Entity, b: Entity) -> Self {
+        Self::Stones(a.min(b), a.max(b))
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct ImpactSounds {
+    /// When each pair last made a sound
+    last_played: HashMap<ImpactPair, f32>,
+    /// Steps along for each sound's pitch, never zero
+    seed: u32,
+}
+
+impl Default for ImpactSounds {
+    fn default() -> Self {
+        Self {
+            last_played: HashMap::new(),
+            seed: 0x2545_f491,
+        }
+    }
+}
+
+impl ImpactSounds {
+    /// Whether `pair` can make a sound at `now`, taking its turn if it can
+    pub fn try_play(&mut self, pair: ImpactPair, now: f32) -> bool {
+        if let Some(last) = self.last_played.get(&pair)
+            && now - last < IMPACT_COOLDOWN
+        {
+            return false;
+        }
+        self.last_played.insert(pair, now);
+        true
+    }
+
+    fn next_pitch(&mut self) -> f64 {
+        let spread = rand01(&mut self.seed) * 2.0 - 1.0;
+        (1.0 + spread * PITCH_VARIATION) as f64
+    }
+}
+
+/// How loud a hit at `normal_speed` is on the perceptual 0 to 1 scale, or `None` if it's too soft
+/// to hear
+pub fn impact_loudness(normal_speed: f32) -> Option<f32> {
+    (normal_speed >= MIN_IMPACT_SPEED).then(|| {
+        ((normal_speed - MIN_IMPACT_SPEED) / (FULL_IMPACT_SPEED - MIN_IMPACT_SPEED)).clamp(0.0, 1.0)
+    })
+}
+
+fn play_impact(
+    commands: &mut Commands,
+    impact_sounds: &mut ImpactSounds,
+    sample: &Handle<AudioSample>,
+    pair: ImpactPair,
+    normal_speed: f32,
+    now: f32,
+) -> bool {
+    let Some(loudness) = impact_loudness(normal_speed) else {
+        return false;
+    };
+    if !impact_sounds.try_play(pair, now) {
+        return false;
+    }
+    commands.spawn((
+        SamplePlayer::new(sample.clone()).with_volume(CONVERTER.perceptual_to_volume(loudness)),
+        PlaybackSettings::default().with_speed(impact_sounds.next_pitch()),
+    ));
+    true
+}
+
+fn play_wall_hit(
+    event: On<StoneHitWall>,
+    mut commands: Commands,
+    time: Res<Time>,
+    gameplay_assets: Res<GameplayAssets>,
+    mut impact_sounds: ResMut<ImpactSounds>,
+) {
+    let played = play_impact(
+        &mut commands,
+        &mut impact_sounds,
+        &gameplay_assets.thud,
+        ImpactPair::Wall(event.stone),
+        event.normal_speed,
+        time.elapsed_secs(),
+    );
+    if played {
+        commands.spawn(SamplePlayer::new(gameplay_assets.ding.clone()));
+    }
+}
+
+fn play_stone_collision(
+    event: On<StonesCollided>,
+    mut commands: Commands,
+    time: Res<Time>,
+    gameplay_assets: Res<GameplayAssets>,
+    mut impact_sounds: ResMut<ImpactSounds>,
+) {
+    let [a, b] = event.stones;
+    play_impact(
+        &mut commands,
+        &mut impact_sounds,
+        &gameplay_assets.clack,
+        ImpactPair::stones(a, b),
+        event.normal_speed,
+        time.elapsed_secs(),
+    );
+}
+
+fn clear_impact_sounds(mut impact_sounds: ResMut<ImpactSounds>) {
+    impact_sounds.last_played.clear();
+}
+
+fn clear_impact_sounds_on_level_start(_event: On<LevelStart>, impact_sounds: ResMut<ImpactSounds>) {
+    clear_impact_sounds(impact_sounds);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_impacts_scale_with_speed_and_wait_their_turn() {
+        // Too soft to hear, then louder the harder the hit, topping out at full volume
+        assert_eq!(impact_loudness(5.0), None);
+        let soft = impact_loudness(60.0).unwrap();
+        let hard = impact_loudness(300.0).unwrap();
+        assert!(0.0 < soft && soft < hard && hard < 1.0, "{soft} {hard}");
+        assert_eq!(impact_loudness(5000.0), Some(1.0));
+
+        // Grinding along a wall every physics step only sounds once per cooldown
+        let mut impact_sounds = ImpactSounds::default();
+        let mut world = World::new();
+        let stone = world.spawn_empty().id();
+        let other = world.spawn_empty().id();
+        let wall = ImpactPair::Wall(stone);
+        let played = (0..30)
+            .filter(|step| impact_sounds.try_play(wall, *step as f32 / 60.0))
+            .count();
+        assert_eq!(played, 4);
+        // Other pairs aren't held up by it, whichever way round their stones come
+        assert!(impact_sounds.try_play(ImpactPair::stones(other, stone), 0.4));
+        assert!(!impact_sounds.try_play(ImpactPair::stones(stone, other), 0.45));
+
+        // Pitches vary, but only a little
+        for _ in 0..100 {
+            let pitch = impact_sounds.next_pitch();
+            assert!(
+                (pitch - 1.0).abs() <= PITCH_VARIATION as f64 + 1e-6,
+                "{pitch}"
+            );
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Remembers key presses for a few frames, so a press that lands just before a state change (like
+//! Space right as aiming hands over to the countdown) still counts once the new state is listening.
+
+use bevy::prelude::*;
+
+/// Frames a press is kept around for before it's dropped
+const INPUT_BUFFER_FRAMES: u32 = 6;
+
+/// Keys worth buffering
+const BUFFERED_KEYS: [KeyCode; 1] = [KeyCode::Space];
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<InputBuffer>()
+        .add_systems(PreUpdate, buffer_key_presses);
+}
+
+/// Recent presses and how many more frames each is kept for
+#[derive(Resource, Default, Debug)]
+pub struct InputBuffer {
+    presses: Vec<(KeyCode, u32)>,
+}
+
+impl InputBuffer {
+    pub fn press(&mut self, key: KeyCode) {
+        self.presses.retain(|(pressed, _)| *pressed != key);
+        self.presses.push((key, INPUT_BUFFER_FRAMES));
+    }
+
+    /// Counts down a frame, dropping presses that have waited too long
+    pub fn tick(&mut self) {
+        self.presses.retain_mut(|(_, frames_left)| {
+            *frames_left = frames_left.saturating_sub(1);
+            *frames_left > 0
+        });
+    }
+
+    /// Takes the buffered press of `key`, so only one thing acts on it
+    pub fn consume(&mut self, key: KeyCode) -> bool {
+        let buffered = self.presses.iter().any(|(pressed, _)| *pressed == key);
+        self.presses.retain(|(pressed, _)| *pressed != key);
+        buffered
+    }
+}
+
+/// Runs before `Update`, where presses are consumed, so a fresh press is seen the frame it happens
+fn buffer_key_presses(input: Res<ButtonInput<KeyCode>>, mut input_buffer: ResMut<InputBuffer>) {
+    input_buffer.tick();
+    for key in BUFFERED_KEYS {
+        if input.just_pressed(key) {
+            input_buffer.press(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffered_press_expires() {
+        let mut input_buffer = InputBuffer::default();
+        input_buffer.press(KeyCode::Space);
+        for _ in 0..INPUT_BUFFER_FRAMES - 1 {
+            input_buffer.tick();
+        }
+        // Still there on the last frame it's kept for, and gone once consumed
+        assert!(input_buffer.consume(KeyCode::Space));
+        assert!(!input_buffer.consume(KeyCode::Space));
+
+        input_buffer.press(KeyCode::Space);
+        for _ in 0..INPUT_BUFFER_FRAMES {
+            input_buffer.tick();
+        }
+        assert!(!input_buffer.consume(KeyCode::Space));
+    }
+}
@@ -21,6 +21,25 @@ pub fn aabb_intersects(
     circle_aabb.intersects(&hex_aabb)
 }
 
+/// Whether a circle lies entirely inside a flat-top hexagon, i.e. inside its inscribed circle.
+pub fn circle_inside_hexagon(
+    circle_center: Vec2,
+    circle_radius: f32,
+    hex_center: Vec2,
+    hex_radius: f32,
+) -> bool {
+    let apothem = hex_radius * 3.0_f32.sqrt() / 2.0;
+    circle_center.distance(hex_center) + circle_radius < apothem
+}
+
+/// What `ratio_circle_area_inside_hexagon` returns for a circle entirely inside the hexagon, without
+/// clipping anything: the area of the `samples` sided polygon approximating the circle over the
+/// circle's area.
+pub fn ratio_circle_fully_inside(samples: u32) -> f32 {
+    let samples = samples as f32;
+    samples / std::f32::consts::TAU * (std::f32::consts::TAU / samples).sin()
+}
+
 pub fn ratio_circle_area_inside_hexagon(
     circle_center: Vec2,
     circle_radius: f32,
@@ -56,6 +75,35 @@ pub fn circle_area_inside_hexagon(
     polygon_area(&clipped_points)
 }
 
+/// `ratio_circle_area_inside_hexagon` for circles of the same size at each of `centers`, building
+/// the hexagon and the circle's outline once rather than for every circle.
+pub fn ratios_for_circles(
+    centers: &[Vec2],
+    radius: f32,
+    hex_center: Vec2,
+    hex_radius: f32,
+    samples: u32,
+) -> Vec<f32> {
+    let hex_points = hexagon_points(hex_radius, hex_center);
+    let outline = approximate_circle_points(radius, Vec2::ZERO, samples);
+    let circle_area = std::f32::consts::PI * radius * radius;
+    let mut circle_points = Vec::with_capacity(outline.len());
+    centers
+        .iter()
+        .map(|center| {
+            if !aabb_intersects(*center, radius, hex_center, hex_radius) {
+                return 0.0;
+            }
+            circle_points.clear();
+            circle_points.extend(outline.iter().map(|point| *point + *center));
+            polygon_area(&clip_polygon_sutherland_hodgman(
+                &circle_points,
+                &hex_points,
+            )) / circle_area
+        })
+        .collect()
+}
+
 /// Calculates the area of a polygon using the Shoelace formula.
 ///
 /// The polygon vertices should be in order (either clockwise or counter-clockwise).
@@ -377,4 +425,43 @@ mod tests {
             error_high
         );
     }
+
+    #[test]
+    fn test_circle_inside_hexagon_matches_clipped_ratio() {
+        let hex_radius = 58.0;
+        // Touching nothing but the inscribed circle's inside, the fast ratio matches clipping
+        let center = Vec2::new(10.0, -5.0);
+        assert!(circle_inside_hexagon(center, 15.0, Vec2::ZERO, hex_radius));
+        let clipped = ratio_circle_area_inside_hexagon(center, 15.0, Vec2::ZERO, hex_radius, 60);
+        assert!((clipped - ratio_circle_fully_inside(60)).abs() < 1e-4);
+        // Past the apothem the circle pokes out of a flat edge
+        assert!(!circle_inside_hexagon(
+            Vec2::new(0.0, 40.0),
+            15.0,
+            Vec2::ZERO,
+            hex_radius
+        ));
+    }
+
+    #[test]
+    fn test_batch_ratios_match_single_ratios() {
+        let hex_center = Vec2::new(10.0, -5.0);
+        let hex_radius = 35.0;
+        // Inside, across the edge, at a vertex, and well clear of the hexagon
+        let centers = [
+            Vec2::new(10.0, -5.0),
+            Vec2::new(40.0, 0.0),
+            Vec2::new(45.0, -5.0),
+            Vec2::new(10.0, 30.0),
+            Vec2::new(200.0, 200.0),
+        ];
+        let batch = ratios_for_circles(&centers, 12.0, hex_center, hex_radius, 60);
+        let single: Vec<_> = centers
+            .iter()
+            .map(|center| {
+                ratio_circle_area_inside_hexagon(*center, 12.0, hex_center, hex_radius, 60)
+            })
+            .collect();
+        assert_eq!(batch, single);
+    }
 }
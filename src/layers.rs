@@ -0,0 +1,57 @@
+//! Where everything in the world is drawn, back to front. Each layer is a z value, with what's on
+//! it stacked a little above it but always below the next layer:
+//!
+//! | Layer          | z    | What's on it                                                    |
+//! |----------------|------|-----------------------------------------------------------------|
+//! | `BACKGROUND`   | -5   | The arena's ice, crowd and banners around the level             |
+//! | `BOARD`        | 0    | Tiles and everything drawn on them: fills, glyphs, the vortex   |
+//! | `TILE_OVERLAY` | 1    | The hint path and the hovered tile's outline                    |
+//! | `PREDICTION`   | 2    | Move lines and landing markers                                  |
+//! | `TRAIL`        | 2.5  | Trail embers and sweep shavings                                 |
+//! | `STONE`        | 3    | Stones, their shadows just under them and handles on them       |
+//! | `FX`           | 5    | The aim arrow, the keyboard cursor's ring and the broom cursor  |
+//! | `CELEBRATION`  | 100  | Confetti, over all of gameplay but still under the UI and menus |
+//!
+//! Children are placed relative to their parent, and scaled by it, so anything parented to a
+//! tile or the grid should go through `child_z` to land on its layer.
+
+pub const BACKGROUND: f32 = -5.0;
+pub const BOARD: f32 = 0.0;
+pub const TILE_OVERLAY: f32 = 1.0;
+pub const PREDICTION: f32 = 2.0;
+pub const TRAIL: f32 = 2.5;
+pub const STONE: f32 = 3.0;
+pub const FX: f32 = 5.0;
+pub const CELEBRATION: f32 = 100.0;
+
+/// The z a child needs to be drawn at `z`, under a parent at `parent_z` scaled by `parent_scale`
+pub fn child_z(z: f32, parent_z: f32, parent_scale: f32) -> f32 {
+    (z - parent_z) / parent_scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layers_are_strictly_ordered() {
+        let layers = [
+            BACKGROUND,
+            BOARD,
+            TILE_OVERLAY,
+            PREDICTION,
+            TRAIL,
+            STONE,
+            FX,
+            CELEBRATION,
+        ];
+        for pair in layers.windows(2) {
+            assert!(pair[0] < pair[1], "{pair:?}");
+        }
+        // A child of a scaled tile still lands where it's asked to
+        let scale = 1.6;
+        let local = child_z(BOARD + 0.8, BOARD, scale);
+        assert!((BOARD + local * scale - (BOARD + 0.8)).abs() < 1e-6);
+        assert!(BOARD + local * scale < TILE_OVERLAY);
+    }
+}
@@ -2,12 +2,17 @@ use std::{collections::HashMap, fmt::Display, slice::Iter};
 
 use bevy::prelude::*;
 
-use crate::{hex_grid::HexCoordinate, tile::TileType};
+use crate::{
+    hex_grid::{HexCoordinate, HexGrid, hex_to_world},
+    intersection::circle_inside_hexagon,
+    stone::StoneSkin,
+    tile::TileType,
+};
 
 #[derive(Resource)]
 pub struct OnLevel(pub Level);
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
 pub enum CurrentLevel {
     #[default]
     Level0,
@@ -34,6 +39,27 @@ impl CurrentLevel {
         ];
         LEVELS.iter()
     }
+
+    /// Position of this level in [`CurrentLevel::iterator`], used as a stable id.
+    pub fn index(&self) -> usize {
+        CurrentLevel::iterator()
+            .position(|level| level == self)
+            .unwrap_or(0)
+    }
+
+    pub fn from_index(index: usize) -> Option<CurrentLevel> {
+        CurrentLevel::iterator().nth(index).copied()
+    }
+
+    /// The level that follows this one, `None` for the last level.
+    pub fn next(&self) -> Option<CurrentLevel> {
+        CurrentLevel::from_index(self.index() + 1)
+    }
+
+    /// The level before this one, `None` for the first level.
+    pub fn previous(&self) -> Option<CurrentLevel> {
+        CurrentLevel::from_index(self.index().checked_sub(1)?)
+    }
 }
 
 impl Display for CurrentLevel {
@@ -53,18 +79,15 @@ impl Display for CurrentLevel {
 
 #[derive(Clone, PartialEq, Debug, Eq, Hash)]
 pub enum Facing {
-    #[allow(dead_code)]
     Up,
     UpRight,
     DownRight,
     Down,
-    #[allow(dead_code)]
     DownLeft,
     UpLeft,
 }
 
 impl Facing {
-    #[allow(dead_code)]
     pub fn iterator() -> Iter<'static, Facing> {
         static DIRECTIONS: [Facing; 6] = [
             Facing::Up,
@@ -77,6 +100,17 @@ impl Facing {
         DIRECTIONS.iter()
     }
 
+    /// Position of this facing in [`Facing::iterator`], used as a stable id.
+    pub fn index(&self) -> usize {
+        Facing::iterator()
+            .position(|facing| facing == self)
+            .unwrap_or(0)
+    }
+
+    pub fn from_index(index: usize) -> Option<Facing> {
+        Facing::iterator().nth(index).cloned()
+    }
+
     pub fn to_vector(&self) -> Vec2 {
         Vec2::from_angle(self.to_angle())
     }
@@ -92,6 +126,18 @@ impl Facing {
             Facing::UpLeft => FRAC_PI_2 + FRAC_PI_3,    // 120° - up and left
         }
     }
+
+    /// The facing whose direction is closest to `direction`.
+    pub fn nearest(direction: Vec2) -> Facing {
+        Facing::iterator()
+            .max_by(|a, b| {
+                a.to_vector()
+                    .dot(direction)
+                    .total_cmp(&b.to_vector().dot(direction))
+            })
+            .cloned()
+            .unwrap_or(Facing::Up)
+    }
 }
 
 impl Display for Facing {
@@ -104,6 +150,10 @@ impl Display for Facing {
 pub struct Level {
     pub current_level: CurrentLevel,
     pub grid: HashMap<HexCoordinate, TileType>,
+    /// Per-tile size overrides as a scale on `hex_radius`, tiles not listed are full size. Tiles
+    /// stay on the same spacing, so a smaller tile leaves a gap to its neighbors that has no effect
+    /// on stones, and a bigger one overlaps them with both tiles' effects applying there.
+    pub tile_scales: HashMap<HexCoordinate, f32>,
     pub goal_coordinate: HexCoordinate,
     pub stone_configs: Vec<StoneConfig>,
     pub countdown: Option<u32>,
@@ -115,8 +165,121 @@ pub struct Level {
     pub rotation_factor: f32,
     pub snap_distance: f32,
     pub snap_velocity: f32,
+    /// Stones at or below this speed count as stopped, for the game and the trajectory preview
+    pub stop_velocity: f32,
+    /// Points around the stone's edge when working out how much of it each tile covers. 60 keeps
+    /// the area within 0.2%, fewer is faster but less accurate.
+    pub overlap_samples: u32,
     pub speed_up_factor: f32,
     pub speed_up_arrow_radius: f32,
+    /// Stone-on-stone bounciness, 1.0 is perfectly elastic and 0.0 perfectly inelastic
+    pub restitution: f32,
+    /// Drag on a stone sliding along a wall, scaled by how much of the stone overlaps the wall
+    pub wall_friction: f32,
+    /// Multiplier on drag distance when erasing with the right mouse button
+    pub erase_rate: f32,
+    /// Real-time levels only allow erasing before the stone is released
+    pub real_time: bool,
+    /// Whether tiles can still be swept once the stone is moving, sweeping during the countdown is
+    /// always allowed
+    pub live_sweeping: bool,
+    /// Forgiving mode, holding Backspace rewinds the stones a couple of seconds
+    pub rewind: bool,
+    /// Skips the aiming phase and throws every stone as configured
+    pub fixed_throw: bool,
+    /// The camera follows the stones while they're moving, unless toggled off with F
+    pub camera_follow: bool,
+    /// Speed range the player can aim a throw within
+    pub min_throw_speed: f32,
+    pub max_throw_speed: f32,
+    /// Whether double-clicking a tile completes its sweep instantly
+    pub double_click_sweep: bool,
+    /// Longest gap in seconds between two clicks that still counts as a double-click
+    pub double_click_window: f32,
+    /// Sweep distance per second while sweeping with the keyboard cursor
+    pub keyboard_sweep_rate: f32,
+    /// Drag speed in world units/second at and below which sweeps count `sweep_slow_multiplier`
+    pub sweep_slow_speed: f32,
+    /// Drag speed at and above which sweeps count `sweep_fast_multiplier`
+    pub sweep_fast_speed: f32,
+    pub sweep_slow_multiplier: f32,
+    pub sweep_fast_multiplier: f32,
+    /// How much sweeps count for while holding Shift, for fine adjustments near the threshold
+    pub precision_sweep_scale: f32,
+    /// How far outside a tile, as a fraction of `hex_radius`, the pointer still hovers and sweeps it
+    pub pick_tolerance: f32,
+    /// Total sweep distance the player gets for the level, unlimited when `None`
+    pub sweep_budget: Option<f32>,
+    /// Most tiles a win can sweep and still earn three stars
+    pub par: usize,
+}
+
+impl Level {
+    /// What every level shares, each one overrides the tunables it needs differently. The grid,
+    /// goal and stones are empty here, every level sets its own.
+    fn base() -> Self {
+        Self {
+            current_level: CurrentLevel::default(),
+            grid: HashMap::new(),
+            tile_scales: HashMap::new(),
+            goal_coordinate: HexCoordinate { q: 0, r: 0 },
+            stone_configs: Vec::new(),
+            countdown: Some(3),
+            hex_radius: 60.0,
+            drag_coefficient: 0.0036,
+            min_sweep_distance: 250.0,
+            stone_radius: 15.0,
+            slow_down_factor: 5.0,
+            rotation_factor: 0.025,
+            snap_distance: 40.0,
+            snap_velocity: 40.0,
+            stop_velocity: 2.0,
+            overlap_samples: 60,
+            speed_up_factor: 250.0,
+            speed_up_arrow_radius: 15.0,
+            restitution: 0.85,
+            wall_friction: 0.02,
+            erase_rate: 1.0,
+            real_time: false,
+            live_sweeping: true,
+            rewind: true,
+            fixed_throw: true,
+            camera_follow: false,
+            min_throw_speed: 100.0,
+            max_throw_speed: 300.0,
+            double_click_sweep: true,
+            double_click_window: 0.3,
+            keyboard_sweep_rate: 500.0,
+            sweep_slow_speed: 200.0,
+            sweep_fast_speed: 800.0,
+            sweep_slow_multiplier: 1.0,
+            sweep_fast_multiplier: 1.0,
+            precision_sweep_scale: 0.25,
+            pick_tolerance: 0.25,
+            sweep_budget: None,
+            par: 0,
+        }
+    }
+
+    /// `coordinate` mirrored left to right across the middle of the level, if a tile sits exactly
+    /// there. Odd columns sit higher than even ones, so when the middle falls between two columns
+    /// nothing mirrors exactly. A tile on the middle column is its own mirror, which doesn't count.
+    pub fn mirrored(&self, coordinate: &HexCoordinate) -> Option<HexCoordinate> {
+        let min = self.grid.keys().map(|coordinate| coordinate.q).min()?;
+        let max = self.grid.keys().map(|coordinate| coordinate.q).max()?;
+        if (min + max).rem_euclid(2) != 0 {
+            return None;
+        }
+        let mirrored = HexCoordinate {
+            q: min + max - coordinate.q,
+            r: coordinate.r,
+        };
+        (mirrored != *coordinate && self.grid.contains_key(&mirrored)).then_some(mirrored)
+    }
+
+    pub fn tile_scale(&self, coordinate: &HexCoordinate) -> f32 {
+        self.tile_scales.get(coordinate).copied().unwrap_or(1.0)
+    }
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -124,6 +287,41 @@ pub struct StoneConfig {
     pub velocity_magnitude: f32,
     pub start_coordinate: HexCoordinate,
     pub facing: Facing,
+    /// Throw set by the player during the aiming phase, replaces facing and velocity magnitude
+    pub aimed_velocity: Option<Vec2>,
+    pub color: Color,
+    /// Overrides the skin picked in the settings, so stones sharing a level can be told apart
+    pub skin: Option<StoneSkin>,
+    /// Where in its starting tile the stone sits, from the tile's center
+    pub start_offset: Vec2,
+}
+
+impl StoneConfig {
+    pub fn initial_velocity(&self) -> Vec2 {
+        self.aimed_velocity
+            .unwrap_or_else(|| get_initial_stone_velocity(&self.facing, &self.velocity_magnitude))
+    }
+
+    /// Whether `start_offset` keeps the whole stone inside its starting tile
+    pub fn start_offset_fits(&self, level: &Level) -> bool {
+        let tile_radius = level.hex_radius * level.tile_scale(&self.start_coordinate);
+        circle_inside_hexagon(
+            self.start_offset,
+            level.stone_radius,
+            Vec2::ZERO,
+            tile_radius,
+        )
+    }
+
+    /// Where the stone starts, back on its tile's center if `start_offset` doesn't fit
+    pub fn start_position(&self, level: &Level, grid: &HexGrid) -> Vec2 {
+        let offset = if self.start_offset_fits(level) {
+            self.start_offset
+        } else {
+            Vec2::ZERO
+        };
+        hex_to_world(&self.start_coordinate, grid) + offset
+    }
 }
 
 pub fn get_initial_stone_velocity(facing: &Facing, stone_velocity_magnitude: &f32) -> Vec2 {
@@ -150,18 +348,12 @@ fn get_level0() -> Level {
         hex_radius: 100.0,
         current_level: CurrentLevel::Level0,
         grid,
-        goal_coordinate: HexCoordinate { q: 0, r: 0 },
-        stone_configs: vec![],
         countdown: None,
-        drag_coefficient: 0.0036,
         min_sweep_distance: 1000.0,
-        stone_radius: 15.0,
-        slow_down_factor: 5.0,
-        rotation_factor: 0.025,
-        snap_distance: 40.0,
-        snap_velocity: 40.0,
-        speed_up_factor: 250.0,
-        speed_up_arrow_radius: 0.,
+        speed_up_arrow_radius: 0.0,
+        double_click_sweep: false,
+        par: 1,
+        ..Level::base()
     }
 }
 
@@ -201,7 +393,6 @@ fn get_level1() -> Level {
     ]);
 
     Level {
-        hex_radius: 60.0,
         current_level: CurrentLevel::Level1,
         grid,
         goal_coordinate,
@@ -209,17 +400,13 @@ fn get_level1() -> Level {
             start_coordinate,
             velocity_magnitude: 200.0,
             facing: Facing::DownRight,
+            aimed_velocity: None,
+            color: Color::BLACK,
+            skin: None,
+            start_offset: Vec2::ZERO,
         }],
-        countdown: Some(3),
-        drag_coefficient: 0.0036,
-        min_sweep_distance: 250.0,
-        stone_radius: 15.0,
-        slow_down_factor: 5.0,
-        rotation_factor: 0.025,
-        snap_distance: 40.0,
-        snap_velocity: 40.0,
-        speed_up_factor: 250.0,
-        speed_up_arrow_radius: 15.,
+        par: 5,
+        ..Level::base()
     }
 }
 
@@ -260,7 +447,6 @@ fn get_level2() -> Level {
     ]);
 
     Level {
-        hex_radius: 60.0,
         current_level: CurrentLevel::Level2,
         grid,
         goal_coordinate,
@@ -268,17 +454,13 @@ fn get_level2() -> Level {
             start_coordinate,
             velocity_magnitude: 190.0,
             facing: Facing::DownRight,
+            aimed_velocity: None,
+            color: Color::BLACK,
+            skin: None,
+            start_offset: Vec2::ZERO,
         }],
-        countdown: Some(3),
-        drag_coefficient: 0.0036,
-        min_sweep_distance: 250.0,
-        stone_radius: 15.0,
-        slow_down_factor: 5.0,
-        rotation_factor: 0.025,
-        snap_distance: 40.0,
-        snap_velocity: 40.0,
-        speed_up_factor: 250.0,
-        speed_up_arrow_radius: 15.,
+        par: 2,
+        ..Level::base()
     }
 }
 
@@ -313,7 +495,6 @@ fn get_level3() -> Level {
     ]);
 
     Level {
-        hex_radius: 60.0,
         current_level: CurrentLevel::Level3,
         grid,
         goal_coordinate,
@@ -321,17 +502,13 @@ fn get_level3() -> Level {
             start_coordinate,
             velocity_magnitude: 200.0,
             facing: Facing::DownRight,
+            aimed_velocity: None,
+            color: Color::BLACK,
+            skin: None,
+            start_offset: Vec2::ZERO,
         }],
-        countdown: Some(3),
-        drag_coefficient: 0.0036,
-        min_sweep_distance: 250.0,
-        stone_radius: 15.0,
-        slow_down_factor: 5.0,
-        rotation_factor: 0.025,
-        snap_distance: 40.0,
-        snap_velocity: 40.0,
-        speed_up_factor: 250.0,
-        speed_up_arrow_radius: 15.,
+        par: 2,
+        ..Level::base()
     }
 }
 
@@ -369,13 +546,16 @@ fn get_level4() -> Level {
             TileType::SpeedUp(Facing::UpRight),
         ),
         (HexCoordinate { q: 4, r: 1 }, TileType::MaintainSpeed),
-        (HexCoordinate { q: 5, r: 1 }, TileType::MaintainSpeed),
+        // Only a stone that kept its speed through the boost gets past
+        (
+            HexCoordinate { q: 5, r: 1 },
+            TileType::SpeedGate { threshold: 200.0 },
+        ),
         (HexCoordinate { q: 6, r: 0 }, TileType::MaintainSpeed),
         (goal_coordinate.clone(), TileType::Goal),
     ]);
 
     Level {
-        hex_radius: 60.0,
         current_level: CurrentLevel::Level4,
         grid,
         goal_coordinate,
@@ -383,17 +563,13 @@ fn get_level4() -> Level {
             start_coordinate,
             velocity_magnitude: 100.0,
             facing: Facing::DownRight,
+            aimed_velocity: None,
+            color: Color::BLACK,
+            skin: None,
+            start_offset: Vec2::ZERO,
         }],
-        countdown: Some(3),
-        drag_coefficient: 0.0036,
-        min_sweep_distance: 250.0,
-        stone_radius: 15.0,
-        slow_down_factor: 5.0,
-        rotation_factor: 0.025,
-        snap_distance: 40.0,
-        snap_velocity: 40.0,
-        speed_up_factor: 250.0,
-        speed_up_arrow_radius: 15.,
+        par: 1,
+        ..Level::base()
     }
 }
 
@@ -469,7 +645,6 @@ fn get_level5() -> Level {
     ]);
 
     Level {
-        hex_radius: 60.0,
         current_level: CurrentLevel::Level5,
         grid,
         goal_coordinate,
@@ -477,17 +652,15 @@ fn get_level5() -> Level {
             start_coordinate,
             velocity_magnitude: 200.0,
             facing: Facing::DownRight,
+            aimed_velocity: None,
+            color: Color::BLACK,
+            skin: None,
+            start_offset: Vec2::ZERO,
         }],
-        countdown: Some(3),
-        drag_coefficient: 0.0036,
-        min_sweep_distance: 250.0,
-        stone_radius: 15.0,
-        slow_down_factor: 5.0,
-        rotation_factor: 0.025,
-        snap_distance: 40.0,
-        snap_velocity: 40.0,
-        speed_up_factor: 250.0,
-        speed_up_arrow_radius: 15.,
+        // The player aims this one's throw
+        fixed_throw: false,
+        par: 6,
+        ..Level::base()
     }
 }
 
@@ -552,7 +725,6 @@ fn get_level6() -> Level {
     ]);
 
     Level {
-        hex_radius: 60.0,
         current_level: CurrentLevel::Level6,
         grid,
         goal_coordinate,
@@ -560,17 +732,14 @@ fn get_level6() -> Level {
             start_coordinate,
             velocity_magnitude: 250.0,
             facing: Facing::DownRight,
+            aimed_velocity: None,
+            color: Color::BLACK,
+            skin: None,
+            start_offset: Vec2::ZERO,
         }],
-        countdown: Some(3),
-        drag_coefficient: 0.0036,
-        min_sweep_distance: 250.0,
-        stone_radius: 15.0,
-        slow_down_factor: 5.0,
-        rotation_factor: 0.025,
-        snap_distance: 40.0,
-        snap_velocity: 40.0,
-        speed_up_factor: 250.0,
-        speed_up_arrow_radius: 15.,
+        rewind: false,
+        par: 5,
+        ..Level::base()
     }
 }
 
@@ -645,7 +814,6 @@ fn get_level7() -> Level {
     ]);
 
     Level {
-        hex_radius: 60.0,
         current_level: CurrentLevel::Level7,
         grid,
         goal_coordinate,
@@ -653,16 +821,112 @@ fn get_level7() -> Level {
             start_coordinate,
             velocity_magnitude: 200.0,
             facing: Facing::DownRight,
+            aimed_velocity: None,
+            color: Color::BLACK,
+            skin: None,
+            start_offset: Vec2::ZERO,
         }],
-        countdown: Some(3),
-        drag_coefficient: 0.0036,
-        min_sweep_distance: 250.0,
-        stone_radius: 15.0,
-        slow_down_factor: 5.0,
-        rotation_factor: 0.025,
-        snap_distance: 40.0,
-        snap_velocity: 40.0,
-        speed_up_factor: 250.0,
-        speed_up_arrow_radius: 47.,
+        speed_up_arrow_radius: 47.0,
+        rewind: false,
+        camera_follow: true,
+        // The finale: sweeps can't be taken back once the stone is on its way
+        real_time: true,
+        par: 4,
+        ..Level::base()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_stepping_through_levels_stops_at_the_ends() {
+        assert_eq!(CurrentLevel::Level0.previous(), None);
+        assert_eq!(CurrentLevel::Level0.next(), Some(CurrentLevel::Level1));
+        assert_eq!(CurrentLevel::Level7.next(), None);
+        assert_eq!(CurrentLevel::Level7.previous(), Some(CurrentLevel::Level6));
+        // Every level steps back to the one before it
+        for level in CurrentLevel::iterator() {
+            if let Some(next) = level.next() {
+                assert_eq!(next.previous(), Some(*level));
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_level_has_a_goal_its_stones_can_reach() {
+        for current_level in CurrentLevel::iterator() {
+            let level = get_level(*current_level);
+            // Level 0 is won by sweeping, with no stone to get anywhere
+            if level.stone_configs.is_empty() {
+                continue;
+            }
+            let goal = &level.goal_coordinate;
+            assert_eq!(
+                level.grid.get(goal),
+                Some(&TileType::Goal),
+                "{current_level}"
+            );
+            let goals = level
+                .grid
+                .values()
+                .filter(|t| **t == TileType::Goal)
+                .count();
+            assert_eq!(goals, 1, "{current_level}");
+
+            // Walls can't be swept away, so every start has to join up with the goal without them
+            for stone_config in &level.stone_configs {
+                let start = &stone_config.start_coordinate;
+                assert!(
+                    level.grid.get(start).is_some_and(|t| *t != TileType::Wall),
+                    "{current_level}: stone starts off the ice at {start:?}"
+                );
+                let mut seen = HashSet::from([start.clone()]);
+                let mut frontier = vec![start.clone()];
+                while let Some(coordinate) = frontier.pop() {
+                    for neighbor in coordinate.neighbors() {
+                        let open = level
+                            .grid
+                            .get(&neighbor)
+                            .is_some_and(|t| *t != TileType::Wall);
+                        if open && seen.insert(neighbor.clone()) {
+                            frontier.push(neighbor);
+                        }
+                    }
+                }
+                assert!(
+                    seen.contains(goal),
+                    "{current_level}: goal cut off from {start:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_start_offset_keeps_the_stone_on_its_tile() {
+        let mut level = get_level(CurrentLevel::Level1);
+        let grid = HexGrid::new(&level);
+        // Every shipped level starts its stones where they fit
+        for current_level in CurrentLevel::iterator() {
+            let shipped = get_level(*current_level);
+            for stone_config in &shipped.stone_configs {
+                assert!(stone_config.start_offset_fits(&shipped), "{current_level}");
+            }
+        }
+        let center = hex_to_world(&level.stone_configs[0].start_coordinate, &grid);
+        // Nudged towards an edge the stone starts there
+        let nudge = Vec2::new(level.hex_radius * 0.3, 0.0);
+        level.stone_configs[0].start_offset = nudge;
+        assert_eq!(
+            level.stone_configs[0].start_position(&level, &grid),
+            center + nudge
+        );
+        // Far enough to hang over the edge it's put back on the center instead
+        level.stone_configs[0].start_offset = Vec2::new(0.0, level.hex_radius * 0.8);
+        assert!(!level.stone_configs[0].start_offset_fits(&level));
+        assert_eq!(level.stone_configs[0].start_position(&level, &grid), center);
     }
 }
@@ -0,0 +1,240 @@
+//! A mm:ss.t timer in the top left that runs from the end of the countdown until the level is
+//! won. It counts real time, so slow motion doesn't stretch it, and turns gold when the finished
+//! time beats the best one for the level. Best times are kept between launches the same way as
+//! the settings.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    PausableSystems,
+    gameplay::{GameState, LevelComplete, LevelStart},
+    level::{CurrentLevel, OnLevel},
+    replay::ReplayPlayback,
+    saved_settings::{read_saved, write_saved},
+    screens::Screen,
+};
+
+/// Saved as `best_times.cfg`, see `read_saved`
+const BEST_TIMES_NAME: &str = "best_times";
+
+const TIMER_COLOR: Color = Color::WHITE;
+const BEST_TIME_COLOR: Color = Color::srgb(1.0, 0.84, 0.0);
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<LevelTimer>()
+        .init_resource::<BestTimes>()
+        .add_systems(Startup, load_best_times)
+        .add_systems(OnEnter(Screen::Gameplay), spawn_level_timer_ui)
+        .add_systems(
+            Update,
+            (
+                tick_level_timer.run_if(in_state(GameState::Playing)),
+                update_level_timer_text,
+            )
+                .chain()
+                .run_if(in_state(Screen::Gameplay))
+                .in_set(PausableSystems),
+        )
+        .add_observer(reset_level_timer)
+        .add_observer(stop_level_timer);
+}
+
+/// Time spent on the current attempt
+#[derive(Resource, Default, Debug)]
+pub struct LevelTimer {
+    pub elapsed: f32,
+    /// Set once the level is won
+    pub stopped: bool,
+    /// The finished time beat the level's previous best
+    pub new_best: bool,
+}
+
+/// Fastest finish of each level
+#[derive(Resource, Default, Clone, Debug, PartialEq)]
+pub struct BestTimes(pub HashMap<CurrentLevel, f32>);
+
+impl BestTimes {
+    /// Records `time` for `level` and returns whether it beat an earlier best. The first finish of a
+    /// level is recorded but has nothing to beat.
+    pub fn record(&mut self, level: CurrentLevel, time: f32) -> bool {
+        match self.0.get(&level) {
+            Some(&best) if best <= time => false,
+            best => {
+                let had_best = best.is_some();
+                self.0.insert(level, time);
+                had_best
+            }
+        }
+    }
+
+    /// One `level<index>=<seconds>` line per level that's been finished, in level order
+    pub fn to_file_string(&self) -> String {
+        CurrentLevel::iterator()
+            .filter_map(|level| {
+                let time = self.0.get(level)?;
+                Some(format!("level{}={}", level.index(), time))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Reads back `to_file_string`, any line that can't be read makes the whole file count as
+    /// corrupt
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut best_times = BestTimes::default();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (key, value) = line.split_once('=')?;
+            let level = CurrentLevel::from_index(key.strip_prefix("level")?.parse().ok()?)?;
+            let time = value
+                .parse()
+                .ok()
+                .filter(|time: &f32| time.is_finite() && *time >= 0.0)?;
+            best_times.0.insert(level, time);
+        }
+        Some(best_times)
+    }
+}
+
+/// Formats seconds as `mm:ss.t`
+pub fn format_level_time(secs: f32) -> String {
+    let tenths = (secs.max(0.0) * 10.0).floor() as u32;
+    format!(
+        "{:02}:{:02}.{}",
+        tenths / 600,
+        tenths / 10 % 60,
+        tenths % 10
+    )
+}
+
+/// The time last written to the text, so it's only rewritten when the shown tenth changes
+#[derive(Component, Default)]
+struct LevelTimerText {
+    shown: Option<(u32, bool)>,
+}
+
+fn spawn_level_timer_ui(mut commands: Commands) {
+    commands.spawn((
+        DespawnOnExit(Screen::Gameplay),
+        LevelTimerText::default(),
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(16.0),
+            top: Val::Px(16.0),
+            ..default()
+        },
+        Text::new(format_level_time(0.0)),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(TIMER_COLOR),
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+        Pickable::IGNORE,
+    ));
+}
+
+fn load_best_times(mut best_times: ResMut<BestTimes>) {
+    let Some(text) = read_saved(BEST_TIMES_NAME) else {
+        return;
+    };
+    match BestTimes::parse(&text) {
+        Some(saved) => *best_times = saved,
+        None => warn!("Couldn't read the saved best times, starting over"),
+    }
+}
+
+fn reset_level_timer(_event: On<LevelStart>, mut level_timer: ResMut<LevelTimer>) {
+    *level_timer = LevelTimer::default();
+}
+
+/// Paused with the rest of gameplay, but not slowed down by slow motion
+fn tick_level_timer(time: Res<Time<Real>>, mut level_timer: ResMut<LevelTimer>) {
+    if !level_timer.stopped {
+        level_timer.elapsed += time.delta_secs();
+    }
+}
+
+/// Replays finish in the time they were recorded in, so they don't count towards best times
+fn stop_level_timer(
+    _event: On<LevelComplete>,
+    on_level: Res<OnLevel>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    mut level_timer: ResMut<LevelTimer>,
+    mut best_times: ResMut<BestTimes>,
+) {
+    if level_timer.stopped {
+        return;
+    }
+    level_timer.stopped = true;
+    if replay_playback.is_some() {
+        return;
+    }
+    let level = on_level.0.current_level;
+    let previous = best_times.0.get(&level).copied();
+    level_timer.new_best = best_times.record(level, level_timer.elapsed);
+    // The first finish has nothing to beat but is still worth keeping
+    if best_times.0.get(&level).copied() != previous {
+        write_saved(BEST_TIMES_NAME, &best_times.to_file_string());
+    }
+}
+
+fn update_level_timer_text(
+    level_timer: Res<LevelTimer>,
+    text: Single<(&mut LevelTimerText, &mut Text, &mut TextColor)>,
+) {
+    let (mut timer_text, mut text, mut color) = text.into_inner();
+    let shown = (
+        (level_timer.elapsed * 10.0).floor() as u32,
+        level_timer.new_best,
+    );
+    if timer_text.shown == Some(shown) {
+        return;
+    }
+    timer_text.shown = Some(shown);
+    text.0 = format_level_time(level_timer.elapsed);
+    color.0 = if level_timer.new_best {
+        BEST_TIME_COLOR
+    } else {
+        TIMER_COLOR
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_level_time() {
+        assert_eq!(format_level_time(0.0), "00:00.0");
+        // Tenths are truncated, not rounded, so the display never runs ahead of the clock
+        assert_eq!(format_level_time(9.99), "00:09.9");
+        assert_eq!(format_level_time(75.25), "01:15.2");
+        assert_eq!(format_level_time(600.0), "10:00.0");
+    }
+
+    #[test]
+    fn test_best_times_only_beaten_by_faster_finish() {
+        let mut best_times = BestTimes::default();
+        // Nothing to beat on the first finish
+        assert!(!best_times.record(CurrentLevel::Level1, 20.0));
+        assert!(!best_times.record(CurrentLevel::Level1, 25.0));
+        assert!(best_times.record(CurrentLevel::Level1, 15.0));
+        assert_eq!(best_times.0.get(&CurrentLevel::Level1), Some(&15.0));
+    }
+
+    #[test]
+    fn test_best_times_survive_saving() {
+        let mut best_times = BestTimes::default();
+        best_times.record(CurrentLevel::Level1, 15.25);
+        best_times.record(CurrentLevel::Level6, 73.5);
+        assert_eq!(
+            BestTimes::parse(&best_times.to_file_string()),
+            Some(best_times)
+        );
+        assert_eq!(BestTimes::parse("level1=-3"), None);
+        assert_eq!(BestTimes::parse("level1=NaN"), None);
+        assert_eq!(BestTimes::parse("level99=10"), None);
+    }
+}
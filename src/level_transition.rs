@@ -0,0 +1,199 @@
+//! Fades the screen to white and back between levels, instead of cutting straight from one to the
+//! next. The level's only swapped once the screen's fully covered, and its countdown waits for the
+//! fade back in. Moving on, retrying and coming in from the menu all go through it.
+//!
+//! Input's held off while it runs, and reduce motion shortens it to a quick cut.
+
+use bevy::prelude::*;
+
+use crate::{
+    gameplay::{NextLevel, ReduceMotion, RestartCampaign, RestartLevel},
+    screens::Screen,
+};
+
+/// Seconds each way, fading out and back in
+const TRANSITION_SECONDS: f32 = 0.5;
+const QUICK_TRANSITION_SECONDS: f32 = 0.05;
+const TRANSITION_COLOR: Color = Color::WHITE;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<LevelTransition>()
+        .add_systems(OnEnter(Screen::Gameplay), spawn_transition_overlay)
+        .add_systems(OnExit(Screen::Gameplay), end_level_transition)
+        .add_systems(
+            Update,
+            update_level_transition.run_if(in_state(Screen::Gameplay)),
+        )
+        .add_observer(start_level_transition);
+}
+
+/// What happens to the level once the screen's covered
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransitionTo {
+    RestartLevel,
+    NextLevel,
+    RestartCampaign,
+}
+
+/// Covers the screen, then does what it's asked. Ignored while a transition's already running.
+#[derive(Event)]
+pub struct StartLevelTransition(pub TransitionTo);
+
+#[derive(Resource, Default, Debug, PartialEq)]
+pub enum LevelTransition {
+    #[default]
+    Idle,
+    /// Fading out, to do `then` once the screen's covered
+    Covering { elapsed: f32, then: TransitionTo },
+    /// Fading back in on the new level
+    Revealing { elapsed: f32 },
+}
+
+impl LevelTransition {
+    /// Moves on by `dt`, with each way taking `duration`, handing back what to do on the frame the
+    /// screen's first fully covered
+    pub fn advance(&mut self, dt: f32, duration: f32) -> Option<TransitionTo> {
+        match self {
+            Self::Idle => None,
+            Self::Covering { elapsed, then } => {
+                *elapsed += dt;
+                if *elapsed < duration {
+                    return None;
+                }
+                let then = *then;
+                *self = Self::Revealing { elapsed: 0.0 };
+                Some(then)
+            }
+            Self::Revealing { elapsed } => {
+                *elapsed += dt;
+                if *elapsed >= duration {
+                    *self = Self::Idle;
+                }
+                None
+            }
+        }
+    }
+
+    /// How opaque the overlay is, 1 being fully covered
+    pub fn alpha(&self, duration: f32) -> f32 {
+        match self {
+            Self::Idle => 0.0,
+            Self::Covering { elapsed, .. } => (elapsed / duration).clamp(0.0, 1.0),
+            Self::Revealing { elapsed } => 1.0 - (elapsed / duration).clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// Run condition for input that waits until the transition's done
+pub fn transitioning(transition: Res<LevelTransition>) -> bool {
+    *transition != LevelTransition::Idle
+}
+
+fn transition_seconds(reduce_motion: &ReduceMotion) -> f32 {
+    if reduce_motion.0 {
+        QUICK_TRANSITION_SECONDS
+    } else {
+        TRANSITION_SECONDS
+    }
+}
+
+#[derive(Component)]
+struct TransitionOverlay;
+
+/// Starts covered, so the level fades in from the menu
+fn spawn_transition_overlay(mut commands: Commands, mut transition: ResMut<LevelTransition>) {
+    *transition = LevelTransition::Revealing { elapsed: 0.0 };
+    commands.spawn((
+        Name::new("Level Transition"),
+        TransitionOverlay,
+        DespawnOnExit(Screen::Gameplay),
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        BackgroundColor(TRANSITION_COLOR),
+        // Over the rest of the UI, and blocking clicks while it's up
+        GlobalZIndex(2),
+    ));
+}
+
+fn start_level_transition(
+    event: On<StartLevelTransition>,
+    mut transition: ResMut<LevelTransition>,
+) {
+    if *transition == LevelTransition::Idle {
+        *transition = LevelTransition::Covering {
+            elapsed: 0.0,
+            then: event.0,
+        };
+    }
+}
+
+/// Runs on real time, so neither slow motion nor a pause mid-fade holds it up
+fn update_level_transition(
+    mut commands: Commands,
+    real_time: Res<Time<Real>>,
+    reduce_motion: Res<ReduceMotion>,
+    mut transition: ResMut<LevelTransition>,
+    overlay: Single<(&mut BackgroundColor, &mut Visibility), With<TransitionOverlay>>,
+) {
+    let duration = transition_seconds(&reduce_motion);
+    match transition.advance(real_time.delta_secs(), duration) {
+        Some(TransitionTo::RestartLevel) => commands.trigger(RestartLevel),
+        Some(TransitionTo::NextLevel) => commands.trigger(NextLevel),
+        Some(TransitionTo::RestartCampaign) => commands.trigger(RestartCampaign),
+        None => {}
+    }
+    // Fully covered on the frame the level's swapped, whatever the frame time
+    let (mut background, mut visibility) = overlay.into_inner();
+    background.0 = TRANSITION_COLOR.with_alpha(transition.alpha(duration));
+    visibility.set_if_neq(if *transition == LevelTransition::Idle {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    });
+}
+
+fn end_level_transition(mut transition: ResMut<LevelTransition>) {
+    *transition = LevelTransition::Idle;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transition_swaps_the_level_once_covered() {
+        let mut transition = LevelTransition::Covering {
+            elapsed: 0.0,
+            then: TransitionTo::NextLevel,
+        };
+        // Fading out, with the level left alone until the screen's covered
+        assert_eq!(transition.advance(0.2, 0.5), None);
+        assert!((transition.alpha(0.5) - 0.4).abs() < 1e-5);
+        assert_eq!(transition.advance(0.2, 0.5), None);
+        assert_eq!(transition.advance(0.2, 0.5), Some(TransitionTo::NextLevel));
+        // Then it fades back in from fully covered
+        assert_eq!(transition, LevelTransition::Revealing { elapsed: 0.0 });
+        assert_eq!(transition.alpha(0.5), 1.0);
+        assert_eq!(transition.advance(0.3, 0.5), None);
+        assert!((transition.alpha(0.5) - 0.4).abs() < 1e-5);
+        assert_eq!(transition.advance(0.3, 0.5), None);
+        assert_eq!(transition, LevelTransition::Idle);
+        assert_eq!(transition.alpha(0.5), 0.0);
+
+        // A long frame still only swaps the level once
+        let mut transition = LevelTransition::Covering {
+            elapsed: 0.0,
+            then: TransitionTo::RestartLevel,
+        };
+        assert_eq!(
+            transition.advance(2.0, 0.5),
+            Some(TransitionTo::RestartLevel)
+        );
+        assert_eq!(transition.advance(2.0, 0.5), None);
+        assert_eq!(transition, LevelTransition::Idle);
+    }
+}
@@ -0,0 +1,264 @@
+//! Translations for the UI's text. Each language has a Fluent-style file in `assets/locales` with
+//! one `key = value` per line, where indented lines continue the value on a new line and
+//! `{ $name }` is filled in by the game. Text is spawned with a `Localized` key rather than a
+//! literal, and is rewritten whenever the language changes.
+
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+    ui::UiSystems,
+};
+
+/// English ships inside the binary too, so the title screen has text before anything's loaded
+const ENGLISH: &str = include_str!("../assets/locales/en.ftl");
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<Translations>()
+        .init_asset_loader::<TranslationsLoader>()
+        .init_resource::<Language>()
+        .init_resource::<Strings>()
+        .add_systems(Startup, load_translations)
+        .add_systems(
+            Update,
+            switch_language.run_if(resource_exists::<TranslationHandles>),
+        )
+        .add_systems(PostUpdate, localize_text.before(UiSystems::Prepare));
+}
+
+/// The language picked in the settings menu
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Spanish];
+
+    pub fn next(self) -> Self {
+        match self {
+            Language::English => Language::Spanish,
+            Language::Spanish => Language::English,
+        }
+    }
+
+    /// The language's name in itself, so it can be found from any other
+    pub fn label(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Spanish => "Español",
+        }
+    }
+
+    fn path(self) -> &'static str {
+        match self {
+            Language::English => "locales/en.ftl",
+            Language::Spanish => "locales/es.ftl",
+        }
+    }
+}
+
+/// One language's text, by key
+#[derive(Asset, TypePath, Clone, Debug, Default)]
+pub struct Translations(HashMap<String, String>);
+
+impl Translations {
+    /// Lines that are neither a comment, a continuation, nor `key = value` are skipped
+    pub fn parse(text: &str) -> Self {
+        let mut translations: HashMap<String, String> = HashMap::new();
+        let mut last_key: Option<String> = None;
+        for line in text.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            if line.starts_with(char::is_whitespace) {
+                if let Some(value) = last_key.as_ref().and_then(|key| translations.get_mut(key)) {
+                    if !value.is_empty() {
+                        value.push('\n');
+                    }
+                    value.push_str(trimmed);
+                }
+                continue;
+            }
+            last_key = None;
+            if trimmed.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = trimmed.split_once('=') else {
+                continue;
+            };
+            let key = key.trim().to_string();
+            translations.insert(key.clone(), value.trim().to_string());
+            last_key = Some(key);
+        }
+        Self(translations)
+    }
+}
+
+#[derive(Default, TypePath)]
+struct TranslationsLoader;
+
+impl AssetLoader for TranslationsLoader {
+    type Asset = Translations;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Translations, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let text = String::from_utf8(bytes)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+        Ok(Translations::parse(&text))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ftl"]
+    }
+}
+
+/// The translations currently shown, which only switch over once the picked language has loaded
+#[derive(Resource, Debug)]
+pub struct Strings {
+    language: Language,
+    translations: Translations,
+}
+
+impl Default for Strings {
+    fn default() -> Self {
+        Self {
+            language: Language::English,
+            translations: Translations::parse(ENGLISH),
+        }
+    }
+}
+
+impl Strings {
+    /// The text for `key`, or the key itself if the language doesn't have it
+    pub fn tr(&self, key: &str) -> String {
+        self.translations
+            .0
+            .get(key)
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    /// Like `tr`, with each `{ $name }` filled in from `args`
+    pub fn tr_with(&self, key: &str, args: &[(&str, String)]) -> String {
+        let mut text = self.tr(key);
+        for (name, value) in args {
+            text = text
+                .replace(&format!("{{ ${name} }}"), value)
+                .replace(&format!("{{${name}}}"), value);
+        }
+        text
+    }
+}
+
+/// Keeps the entity's `Text` set to the current language's text for `key`
+#[derive(Component, Clone, Debug, PartialEq)]
+#[require(Text)]
+pub struct Localized {
+    pub key: &'static str,
+    pub args: Vec<(&'static str, String)>,
+}
+
+impl Localized {
+    pub fn new(key: &'static str) -> Self {
+        Self {
+            key,
+            args: Vec::new(),
+        }
+    }
+
+    pub fn with_arg(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.args.push((name, value.to_string()));
+        self
+    }
+}
+
+#[derive(Resource)]
+struct TranslationHandles(HashMap<Language, Handle<Translations>>);
+
+fn load_translations(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handles = Language::ALL
+        .into_iter()
+        .map(|language| (language, asset_server.load(language.path())))
+        .collect();
+    commands.insert_resource(TranslationHandles(handles));
+}
+
+/// Also picks up edits to the current language's file when hot reloading
+fn switch_language(
+    language: Res<Language>,
+    handles: Res<TranslationHandles>,
+    translations: Res<Assets<Translations>>,
+    mut asset_events: MessageReader<AssetEvent<Translations>>,
+    mut strings: ResMut<Strings>,
+) {
+    let Some(handle) = handles.0.get(&language) else {
+        return;
+    };
+    let modified = asset_events
+        .read()
+        .any(|event| event.is_modified(handle.id()));
+    if strings.language == *language && !modified {
+        return;
+    }
+    if let Some(loaded) = translations.get(handle) {
+        *strings = Strings {
+            language: *language,
+            translations: loaded.clone(),
+        };
+    }
+}
+
+fn localize_text(strings: Res<Strings>, mut texts: Query<(Ref<Localized>, &mut Text)>) {
+    for (localized, mut text) in &mut texts {
+        if strings.is_changed() || localized.is_changed() {
+            text.0 = strings.tr_with(localized.key, &localized.args);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translations_parse_and_fill_in() {
+        let strings = Strings {
+            language: Language::English,
+            translations: Translations::parse(
+                "# A comment\nplay = Play\n\nsteps =\n    First line\n    # Not a comment\nswept = Swept: { $amount }\nnot a translation",
+            ),
+        };
+        assert_eq!(strings.tr("play"), "Play");
+        // Indented lines continue the value, even if they look like comments
+        assert_eq!(strings.tr("steps"), "First line\n# Not a comment");
+        assert_eq!(
+            strings.tr_with("swept", &[("amount", "12".to_string())]),
+            "Swept: 12"
+        );
+        // Missing keys show up as themselves
+        assert_eq!(strings.tr("missing-key"), "missing-key");
+    }
+
+    #[test]
+    fn test_every_language_has_every_key() {
+        let english = Translations::parse(ENGLISH);
+        let spanish = Translations::parse(include_str!("../assets/locales/es.ftl"));
+        for key in english.0.keys() {
+            assert!(spanish.0.contains_key(key), "Spanish is missing {key}");
+        }
+        assert_eq!(english.0.len(), spanish.0.len());
+    }
+}
@@ -3,22 +3,60 @@
 // Disable console on Windows for non-dev builds.
 #![cfg_attr(not(feature = "dev"), windows_subsystem = "windows")]
 
+mod aiming;
+mod arena_background;
 mod asset_tracking;
+mod attempt_stats;
+mod auto_sweep;
+mod camera;
 mod confetti;
 mod crt_postprocess;
-//mod debug_ui;
+#[cfg(feature = "dev")]
+mod debug_ui;
 #[cfg(feature = "dev")]
 mod dev_tools;
+mod diagnostics_overlay;
+mod fail_screen;
 mod fire_trail;
 mod gameplay;
+mod goal_vortex;
 mod hex_grid;
+mod hint;
+mod ice_shimmer;
+mod impact_sounds;
+mod input_buffer;
 mod intersection;
+mod layers;
 mod level;
+mod level_timer;
+mod level_transition;
+mod localization;
 mod menus;
+mod minimap;
+mod progress;
+mod replay;
+mod rewind;
+mod saved_settings;
+mod screen_shake;
 mod screens;
+mod shadows;
+mod sim;
+mod slow_mo;
+#[cfg(feature = "dev")]
+mod solver;
+mod speed_readout;
 mod stone;
+mod stone_squash;
+mod sweep_budget;
 mod tile;
+mod tile_cursor;
+mod title_demo;
+mod touch_sweep;
+mod tutorial;
 mod ui;
+mod ui_scale;
+mod undo;
+mod win_screen;
 
 use bevy::prelude::*;
 use bevy::{asset::AssetMetaCheck, window::WindowResolution};
@@ -28,6 +66,7 @@ use bevy_rand::{
     prelude::{ChaCha8Rng, WyRand},
 };
 use bevy_seedling::SeedlingPlugin;
+use camera::MainCamera;
 use crt_postprocess::{CrtPostProcessPlugin, CrtSettings, update_crt_time};
 
 fn main() -> AppExit {
@@ -51,7 +90,7 @@ impl Plugin for AppPlugin {
                 .set(WindowPlugin {
                     primary_window: Some(Window {
                         resolution: WindowResolution::new(1024, 768),
-                        resizable: false,
+                        resizable: true,
                         title: "Hexagon Grid".into(),
                         ..default()
                     }),
@@ -69,12 +108,51 @@ impl Plugin for AppPlugin {
 
         // Add other plugins.
         app.add_plugins((
+            aiming::plugin,
             asset_tracking::plugin,
+            auto_sweep::plugin,
+            camera::plugin,
+            #[cfg(feature = "dev")]
+            debug_ui::plugin,
             #[cfg(feature = "dev")]
             dev_tools::plugin,
+            diagnostics_overlay::plugin,
             menus::plugin,
             screens::plugin,
             gameplay::plugin,
+            hint::plugin,
+            replay::plugin,
+            rewind::plugin,
+            (
+                arena_background::plugin,
+                attempt_stats::plugin,
+                fail_screen::plugin,
+                goal_vortex::plugin,
+                ice_shimmer::plugin,
+                impact_sounds::plugin,
+                input_buffer::plugin,
+                level_timer::plugin,
+                level_transition::plugin,
+                localization::plugin,
+                minimap::plugin,
+                progress::plugin,
+                saved_settings::plugin,
+                screen_shake::plugin,
+                shadows::plugin,
+            ),
+            (
+                slow_mo::plugin,
+                speed_readout::plugin,
+                stone_squash::plugin,
+                sweep_budget::plugin,
+                tile_cursor::plugin,
+                title_demo::plugin,
+                touch_sweep::plugin,
+                tutorial::plugin,
+                ui_scale::plugin,
+                undo::plugin,
+                win_screen::plugin,
+            ),
         ));
 
         // Set up the `Pause` state.
@@ -101,6 +179,9 @@ fn spawn_camera(mut commands: Commands, mut mesh_picking_settings: ResMut<MeshPi
     commands.spawn((
         Name::new("Camera"),
         Camera2d,
+        MainCamera,
+        // The minimap camera renders after this one, and would otherwise take over the UI
+        IsDefaultUiCamera,
         CrtSettings::default(),
         MeshPickingCamera,
     ));
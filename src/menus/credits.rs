@@ -1,17 +1,134 @@
-//! The credits menu.
+//! The credits menu, built from the sections listed in `assets/credits.ron`. Entries with a URL
+//! open it in the browser when clicked, and the list scrolls with the mouse wheel once it's taller
+//! than the screen.
 
-use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    input::{
+        common_conditions::input_just_pressed,
+        mouse::{MouseScrollUnit, MouseWheel},
+    },
+    prelude::*,
+};
+use serde::Deserialize;
 
-use crate::menus::{Menu, settings::btn};
+use crate::{
+    asset_tracking::LoadResource,
+    localization::Localized,
+    menus::{Menu, settings::btn},
+};
+
+/// How far one line of a mouse wheel scrolls the list
+const LINE_HEIGHT: f32 = 24.0;
 
 pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<Credits>()
+        .init_asset_loader::<CreditsLoader>()
+        .load_resource::<CreditsAssets>();
     app.add_systems(OnEnter(Menu::Credits), spawn_credits_menu);
     app.add_systems(
         Update,
-        go_back.run_if(in_state(Menu::Credits).and(input_just_pressed(KeyCode::Escape))),
+        (
+            go_back.run_if(input_just_pressed(KeyCode::Escape)),
+            fill_credits_list.run_if(resource_exists::<CreditsAssets>),
+            scroll_credits_list,
+        )
+            .run_if(in_state(Menu::Credits)),
     );
 }
 
+/// Who and what the game is built with
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct Credits {
+    pub sections: Vec<CreditsSection>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CreditsSection {
+    pub kind: CreditsKind,
+    pub entries: Vec<CreditsEntry>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CreditsKind {
+    Code,
+    Music,
+    Sfx,
+    Fonts,
+}
+
+impl CreditsKind {
+    fn heading_key(self) -> &'static str {
+        match self {
+            CreditsKind::Code => "credits-code",
+            CreditsKind::Music => "credits-music",
+            CreditsKind::Sfx => "credits-sfx",
+            CreditsKind::Fonts => "credits-fonts",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CreditsEntry {
+    pub text: String,
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+#[derive(Default, TypePath)]
+struct CreditsLoader;
+
+impl AssetLoader for CreditsLoader {
+    type Asset = Credits;
+    type Settings = ();
+    type Error = std::io::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Credits, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        ron::de::from_bytes(&bytes)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+#[derive(Resource, Asset, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct CreditsAssets {
+    #[dependency]
+    credits: Handle<Credits>,
+}
+
+impl FromWorld for CreditsAssets {
+    fn from_world(world: &mut World) -> Self {
+        let assets = world.resource::<AssetServer>();
+        Self {
+            credits: assets.load("credits.ron"),
+        }
+    }
+}
+
+/// The scrolling column the credits are listed in
+#[derive(Component)]
+struct CreditsList;
+
+/// Marks the list once it's been filled in, since the title screen can open the credits before
+/// the manifest has loaded
+#[derive(Component)]
+struct CreditsListFilled;
+
+/// The URL a credits entry opens when clicked
+#[derive(Component, Debug)]
+struct CreditLink(String);
+
 fn spawn_credits_menu(mut commands: Commands) {
     commands
         .spawn((
@@ -31,12 +148,18 @@ fn spawn_credits_menu(mut commands: Commands) {
         ))
         .with_children(|parent| {
             parent.spawn((
-                Text::new("SFX curtesy of the bbc.co.uk copyright 2026 BBC"),
-                TextFont {
-                    font_size: 20.0,
+                Name::new("Credits List"),
+                CreditsList,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(6.0),
+                    padding: UiRect::all(Val::Px(16.0)),
+                    max_height: Val::Percent(70.0),
+                    overflow: Overflow::scroll_y(),
                     ..default()
                 },
-                TextColor(Color::WHITE),
+                ScrollPosition::default(),
                 BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
             ));
             parent.spawn((
@@ -45,11 +168,106 @@ fn spawn_credits_menu(mut commands: Commands) {
                     width: Val::Percent(100.0),
                     ..Default::default()
                 },
-                children![btn("Back", go_back_on_click),],
+                children![btn("menu-back", go_back_on_click),],
             ));
         });
 }
 
+fn fill_credits_list(
+    mut commands: Commands,
+    list: Single<Entity, (With<CreditsList>, Without<CreditsListFilled>)>,
+    credits_assets: Res<CreditsAssets>,
+    credits: Res<Assets<Credits>>,
+) {
+    let Some(credits) = credits.get(&credits_assets.credits) else {
+        return;
+    };
+    commands
+        .entity(*list)
+        .insert(CreditsListFilled)
+        .with_children(|parent| {
+            for section in &credits.sections {
+                parent.spawn((
+                    Localized::new(section.kind.heading_key()),
+                    TextFont {
+                        font_size: 24.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                    Node {
+                        margin: UiRect::top(Val::Px(12.0)),
+                        ..default()
+                    },
+                ));
+                for entry in &section.entries {
+                    let mut text = parent.spawn((
+                        Text::new(entry.text.clone()),
+                        TextFont {
+                            font_size: 18.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(0.85, 0.85, 0.85)),
+                    ));
+                    if let Some(url) = &entry.url {
+                        text.insert((
+                            CreditLink(url.clone()),
+                            TextColor(Color::srgb(0.5, 0.75, 1.0)),
+                        ))
+                        .observe(open_credit_link);
+                    }
+                }
+            }
+        });
+}
+
+fn open_credit_link(click: On<Pointer<Click>>, links: Query<&CreditLink>) {
+    if let Ok(link) = links.get(click.entity) {
+        open_url(&link.0);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn open_url(url: &str) {
+    if let Err(error) = webbrowser::open(url) {
+        warn!("Couldn't open {url}: {error}");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn open_url(url: &str) {
+    let opened = web_sys::window().and_then(|window| {
+        window
+            .open_with_url_and_target(url, "_blank")
+            .ok()
+            .flatten()
+    });
+    if opened.is_none() {
+        warn!("Couldn't open {url}");
+    }
+}
+
+/// Node scrolling isn't built in, so the wheel moves the list, kept within its content
+fn scroll_credits_list(
+    mut mouse_wheel: MessageReader<MouseWheel>,
+    list: Single<(&mut ScrollPosition, &ComputedNode), With<CreditsList>>,
+) {
+    let (mut scroll_position, computed_node) = list.into_inner();
+    let mut dy = 0.0;
+    for wheel in mouse_wheel.read() {
+        dy -= match wheel.unit {
+            MouseScrollUnit::Line => wheel.y * LINE_HEIGHT,
+            MouseScrollUnit::Pixel => wheel.y,
+        };
+    }
+    if dy == 0.0 {
+        return;
+    }
+    let max_scroll = ((computed_node.content_size.y - computed_node.size.y)
+        * computed_node.inverse_scale_factor())
+    .max(0.0);
+    scroll_position.y = (scroll_position.y + dy).clamp(0.0, max_scroll);
+}
+
 fn go_back_on_click(_: On<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
     next_menu.set(Menu::Main);
 }
@@ -57,3 +275,38 @@ fn go_back_on_click(_: On<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>
 fn go_back(mut next_menu: ResMut<NextState<Menu>>) {
     next_menu.set(Menu::Main);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shipped_credits_parse() {
+        let credits: Credits = ron::de::from_str(include_str!("../../assets/credits.ron")).unwrap();
+        let kinds: Vec<_> = credits
+            .sections
+            .iter()
+            .map(|section| section.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                CreditsKind::Code,
+                CreditsKind::Music,
+                CreditsKind::Sfx,
+                CreditsKind::Fonts
+            ]
+        );
+        // Every section lists something, and every link is one a browser can open
+        for section in &credits.sections {
+            assert!(!section.entries.is_empty(), "{:?} is empty", section.kind);
+            for url in section
+                .entries
+                .iter()
+                .filter_map(|entry| entry.url.as_ref())
+            {
+                assert!(url.starts_with("https://"), "{url}");
+            }
+        }
+    }
+}
@@ -2,7 +2,11 @@
 
 use bevy::{input::common_conditions::input_just_pressed, prelude::*};
 
-use crate::menus::{Menu, settings::btn};
+use crate::{
+    localization::Localized,
+    menus::{Menu, settings::btn},
+    sweep_budget::RunStats,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Menu::End), spawn_end_menu);
@@ -12,7 +16,8 @@ pub(super) fn plugin(app: &mut App) {
     );
 }
 
-fn spawn_end_menu(mut commands: Commands) {
+fn spawn_end_menu(mut commands: Commands, run_stats: Res<RunStats>) {
+    let under_budget = run_stats.under_budget.len();
     commands
         .spawn((
             GlobalZIndex(2),
@@ -31,7 +36,7 @@ fn spawn_end_menu(mut commands: Commands) {
         ))
         .with_children(|parent| {
             parent.spawn((
-                Text::new("Thanks for playing!"),
+                Localized::new("end-thanks"),
                 TextFont {
                     font_size: 20.0,
                     ..default()
@@ -39,13 +44,24 @@ fn spawn_end_menu(mut commands: Commands) {
                 TextColor(Color::WHITE),
                 BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
             ));
+            if under_budget > 0 {
+                parent.spawn((
+                    Localized::new("end-under-budget").with_arg("count", under_budget),
+                    TextFont {
+                        font_size: 20.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                    BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+                ));
+            }
             parent.spawn((
                 Node {
                     justify_content: JustifyContent::SpaceAround,
                     width: Val::Percent(100.0),
                     ..Default::default()
                 },
-                children![btn("End game", go_back_on_click),],
+                children![btn("end-finish", go_back_on_click),],
             ));
         });
 }
@@ -4,6 +4,7 @@ use bevy::prelude::*;
 
 use crate::{
     asset_tracking::ResourceHandles,
+    localization::Localized,
     menus::{Menu, settings::btn},
     screens::Screen,
 };
@@ -31,7 +32,7 @@ fn spawn_main_menu(mut commands: Commands) {
         ))
         .with_children(|parent| {
             parent.spawn((
-                Text::new("Broom Shakalaka"),
+                Localized::new("game-title"),
                 TextFont {
                     font_size: 100.0,
                     ..default()
@@ -46,7 +47,7 @@ fn spawn_main_menu(mut commands: Commands) {
                     width: Val::Percent(100.0),
                     ..Default::default()
                 },
-                children![btn("Play", enter_loading_or_gameplay_screen),],
+                children![btn("menu-play", enter_loading_or_gameplay_screen),],
             ));
 
             parent.spawn((
@@ -55,7 +56,7 @@ fn spawn_main_menu(mut commands: Commands) {
                     width: Val::Percent(100.0),
                     ..Default::default()
                 },
-                children![btn("Settings", open_settings_menu),],
+                children![btn("menu-settings", open_settings_menu),],
             ));
             parent.spawn((
                 Node {
@@ -63,7 +64,7 @@ fn spawn_main_menu(mut commands: Commands) {
                     width: Val::Percent(100.0),
                     ..Default::default()
                 },
-                children![btn("Credits", open_credits_menu),],
+                children![btn("menu-credits", open_credits_menu),],
             ));
         });
 }
@@ -8,6 +8,8 @@ mod settings;
 
 use bevy::prelude::*;
 
+pub use settings::btn;
+
 pub(super) fn plugin(app: &mut App) {
     app.init_state::<Menu>();
 
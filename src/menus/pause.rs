@@ -3,6 +3,8 @@
 use bevy::{input::common_conditions::input_just_pressed, prelude::*};
 
 use crate::{
+    level_transition::{StartLevelTransition, TransitionTo},
+    localization::Localized,
     menus::{Menu, settings::btn},
     screens::Screen,
 };
@@ -15,9 +17,19 @@ pub(super) fn plugin(app: &mut App) {
     );
 }
 
+/// The pause menu's root, whose children are swapped out while confirming a quit
+#[derive(Component)]
+struct PauseMenu;
+
+/// On the pause menu's root while it's asking whether to really quit to the title
+#[derive(Component)]
+struct ConfirmingQuit;
+
 fn spawn_pause_menu(mut commands: Commands) {
     commands.spawn((
         (
+            Name::new("Pause Menu"),
+            PauseMenu,
             GlobalZIndex(2),
             DespawnOnExit(Menu::Pause),
             Node {
@@ -32,22 +44,40 @@ fn spawn_pause_menu(mut commands: Commands) {
             Visibility::default(),
             Pickable::IGNORE,
         ),
-        children![
-            (
-                Text::new("Game paused"),
-                TextFont {
-                    font_size: 30.0,
-                    ..default()
-                },
-                TextColor(Color::WHITE),
-            ),
-            btn("Continue", close_menu),
-            btn("Settings", open_settings_menu),
-            btn("Quit to title", quit_to_title),
-        ],
+        pause_menu_contents(),
     ));
 }
 
+fn heading(key: &'static str) -> impl Bundle {
+    (
+        Localized::new(key),
+        TextFont {
+            font_size: 30.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+    )
+}
+
+fn pause_menu_contents() -> impl Bundle {
+    children![
+        heading("pause-title"),
+        btn("pause-continue", close_menu),
+        btn("pause-restart", restart_level),
+        btn("pause-restart-campaign", restart_campaign),
+        btn("menu-settings", open_settings_menu),
+        btn("pause-quit", ask_to_quit),
+    ]
+}
+
+fn quit_confirmation_contents() -> impl Bundle {
+    children![
+        heading("pause-quit-prompt"),
+        btn("pause-quit-confirm", quit_to_title),
+        btn("pause-quit-cancel", cancel_quit),
+    ]
+}
+
 fn open_settings_menu(_: On<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
     next_menu.set(Menu::Settings);
 }
@@ -56,10 +86,66 @@ fn close_menu(_: On<Pointer<Click>>, mut next_menu: ResMut<NextState<Menu>>) {
     next_menu.set(Menu::None);
 }
 
+fn restart_level(
+    _: On<Pointer<Click>>,
+    mut commands: Commands,
+    mut next_menu: ResMut<NextState<Menu>>,
+) {
+    commands.trigger(StartLevelTransition(TransitionTo::RestartLevel));
+    next_menu.set(Menu::None);
+}
+
+fn restart_campaign(
+    _: On<Pointer<Click>>,
+    mut commands: Commands,
+    mut next_menu: ResMut<NextState<Menu>>,
+) {
+    commands.trigger(StartLevelTransition(TransitionTo::RestartCampaign));
+    next_menu.set(Menu::None);
+}
+
+fn ask_to_quit(
+    _: On<Pointer<Click>>,
+    mut commands: Commands,
+    pause_menu: Single<Entity, With<PauseMenu>>,
+) {
+    commands
+        .entity(*pause_menu)
+        .despawn_related::<Children>()
+        .insert((ConfirmingQuit, quit_confirmation_contents()));
+}
+
+fn cancel_quit(
+    _: On<Pointer<Click>>,
+    mut commands: Commands,
+    pause_menu: Single<Entity, With<PauseMenu>>,
+) {
+    back_to_pause_menu(&mut commands, *pause_menu);
+}
+
+fn back_to_pause_menu(commands: &mut Commands, pause_menu: Entity) {
+    commands
+        .entity(pause_menu)
+        .despawn_related::<Children>()
+        .remove::<ConfirmingQuit>()
+        .insert(pause_menu_contents());
+}
+
+/// Leaving the gameplay screen despawns the level and resets its state
 fn quit_to_title(_: On<Pointer<Click>>, mut next_screen: ResMut<NextState<Screen>>) {
     next_screen.set(Screen::Title);
 }
 
-fn go_back(mut next_menu: ResMut<NextState<Menu>>) {
-    next_menu.set(Menu::None);
+/// Backs out of the quit confirmation first, and only closes the menu from the pause menu itself
+fn go_back(
+    mut commands: Commands,
+    pause_menu: Single<(Entity, Has<ConfirmingQuit>), With<PauseMenu>>,
+    mut next_menu: ResMut<NextState<Menu>>,
+) {
+    let (pause_menu, confirming_quit) = *pause_menu;
+    if confirming_quit {
+        back_to_pause_menu(&mut commands, pause_menu);
+    } else {
+        next_menu.set(Menu::None);
+    }
 }
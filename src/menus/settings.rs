@@ -7,7 +7,19 @@ use bevy_seedling::{
     sample::{AudioSample, SamplePlayer},
 };
 
-use crate::{asset_tracking::LoadResource, menus::Menu, screens::Screen};
+use crate::{
+    asset_tracking::LoadResource,
+    auto_sweep::AutoSweep,
+    fire_trail::EnableTrails,
+    gameplay::{ReduceMotion, ShowLandingMarker},
+    localization::{Language, Localized},
+    menus::Menu,
+    screen_shake::ScreenShakeIntensity,
+    screens::Screen,
+    shadows::ShowShadows,
+    stone::StoneSkin,
+    tile::{MirrorSweep, TilePalette, TileTheme},
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.load_resource::<SettingsAssets>();
@@ -22,6 +34,17 @@ pub(super) fn plugin(app: &mut App) {
                 update_music_volume_label,
                 update_master_volume_label,
                 update_sfx_volume_label,
+                update_auto_sweep_label,
+                update_landing_marker_label,
+                update_mirror_sweep_label,
+                update_trails_label,
+                update_shadows_label,
+                update_reduce_motion_label,
+                update_screen_shake_label,
+                update_palette_label,
+                update_theme_label,
+                update_stone_skin_label,
+                update_language_label,
                 button_hover,
             )
                 .run_if(in_state(Menu::Settings)),
@@ -69,7 +92,7 @@ fn spawn_settings_menu(mut commands: Commands) {
         BorderColor::all(Color::srgb(0.9, 0.9, 0.9)),
         children![
             text((
-                Text::new("Sound Settings"),
+                Localized::new("settings-title"),
                 TextFont {
                     font_size: 32.0,
                     ..Default::default()
@@ -190,11 +213,146 @@ fn update_sfx_volume_label(
     label.0 = text;
 }
 
+// Assists
+fn toggle_auto_sweep(_: On<Pointer<Click>>, mut auto_sweep: ResMut<AutoSweep>) {
+    auto_sweep.0 = !auto_sweep.0;
+}
+
+fn update_auto_sweep_label(
+    mut label: Single<&mut Localized, With<AutoSweepLabel>>,
+    auto_sweep: Res<AutoSweep>,
+) {
+    label.set_if_neq(on_off(auto_sweep.0));
+}
+
+fn toggle_landing_marker(
+    _: On<Pointer<Click>>,
+    mut show_landing_marker: ResMut<ShowLandingMarker>,
+) {
+    show_landing_marker.0 = !show_landing_marker.0;
+}
+
+fn update_landing_marker_label(
+    mut label: Single<&mut Localized, With<LandingMarkerLabel>>,
+    show_landing_marker: Res<ShowLandingMarker>,
+) {
+    label.set_if_neq(on_off(show_landing_marker.0));
+}
+
+fn toggle_mirror_sweep(_: On<Pointer<Click>>, mut mirror_sweep: ResMut<MirrorSweep>) {
+    mirror_sweep.0 = !mirror_sweep.0;
+}
+
+fn update_mirror_sweep_label(
+    mut label: Single<&mut Localized, With<MirrorSweepLabel>>,
+    mirror_sweep: Res<MirrorSweep>,
+) {
+    label.set_if_neq(on_off(mirror_sweep.0));
+}
+
+fn toggle_trails(_: On<Pointer<Click>>, mut enable_trails: ResMut<EnableTrails>) {
+    enable_trails.0 = !enable_trails.0;
+}
+
+fn update_trails_label(
+    mut label: Single<&mut Localized, With<TrailsLabel>>,
+    enable_trails: Res<EnableTrails>,
+) {
+    label.set_if_neq(on_off(enable_trails.0));
+}
+
+fn toggle_shadows(_: On<Pointer<Click>>, mut show_shadows: ResMut<ShowShadows>) {
+    show_shadows.0 = !show_shadows.0;
+}
+
+fn update_shadows_label(
+    mut label: Single<&mut Localized, With<ShadowsLabel>>,
+    show_shadows: Res<ShowShadows>,
+) {
+    label.set_if_neq(on_off(show_shadows.0));
+}
+
+fn toggle_reduce_motion(_: On<Pointer<Click>>, mut reduce_motion: ResMut<ReduceMotion>) {
+    reduce_motion.0 = !reduce_motion.0;
+}
+
+fn update_reduce_motion_label(
+    mut label: Single<&mut Localized, With<ReduceMotionLabel>>,
+    reduce_motion: Res<ReduceMotion>,
+) {
+    label.set_if_neq(on_off(reduce_motion.0));
+}
+
+/// Screen shake goes in quarters, from off up to full
+const SCREEN_SHAKE_STEP: f32 = 0.25;
+
+fn lower_screen_shake(_: On<Pointer<Click>>, mut intensity: ResMut<ScreenShakeIntensity>) {
+    intensity.0 = (intensity.0 - SCREEN_SHAKE_STEP).max(0.0);
+}
+
+fn raise_screen_shake(_: On<Pointer<Click>>, mut intensity: ResMut<ScreenShakeIntensity>) {
+    intensity.0 = (intensity.0 + SCREEN_SHAKE_STEP).min(1.0);
+}
+
+fn update_screen_shake_label(
+    mut label: Single<&mut Text, With<ScreenShakeLabel>>,
+    intensity: Res<ScreenShakeIntensity>,
+) {
+    label.0 = format!("{}%", (intensity.0 * 100.0).round());
+}
+
+fn on_off(on: bool) -> Localized {
+    Localized::new(if on { "settings-on" } else { "settings-off" })
+}
+
+// Accessibility
+fn cycle_palette(_: On<Pointer<Click>>, mut palette: ResMut<TilePalette>) {
+    *palette = palette.next();
+}
+
+fn update_palette_label(
+    mut label: Single<&mut Localized, With<PaletteLabel>>,
+    palette: Res<TilePalette>,
+) {
+    label.set_if_neq(Localized::new(palette.label()));
+}
+
+fn cycle_theme(_: On<Pointer<Click>>, mut theme: ResMut<TileTheme>) {
+    *theme = theme.next();
+}
+
+fn update_theme_label(mut label: Single<&mut Localized, With<ThemeLabel>>, theme: Res<TileTheme>) {
+    label.set_if_neq(Localized::new(theme.label()));
+}
+
+fn cycle_stone_skin(_: On<Pointer<Click>>, mut stone_skin: ResMut<StoneSkin>) {
+    *stone_skin = stone_skin.next();
+}
+
+fn update_stone_skin_label(
+    mut label: Single<&mut Localized, With<StoneSkinLabel>>,
+    stone_skin: Res<StoneSkin>,
+) {
+    label.set_if_neq(Localized::new(stone_skin.label()));
+}
+
+fn cycle_language(_: On<Pointer<Click>>, mut language: ResMut<Language>) {
+    *language = language.next();
+}
+
+/// Always in the language itself, so it isn't localized
+fn update_language_label(
+    mut label: Single<&mut Text, With<LanguageLabel>>,
+    language: Res<Language>,
+) {
+    label.0 = language.label().to_string();
+}
+
 //  ============================ UI Code ============================ //
 
 fn core_grid() -> impl Bundle {
     (
-        Name::new("Sound Grid"),
+        Name::new("Settings Grid"),
         Node {
             row_gap: Val::Px(10.0),
             column_gap: Val::Px(30.0),
@@ -204,12 +362,34 @@ fn core_grid() -> impl Bundle {
             ..default()
         },
         children![
-            text(Text::new("Master")),
+            text(Localized::new("settings-master")),
             master_volume(),
-            text(Text::new("Music")),
+            text(Localized::new("settings-music")),
             music_volume(),
-            text(Text::new("Sfx")),
+            text(Localized::new("settings-sfx")),
             sfx_volume(),
+            text(Localized::new("settings-auto-sweep")),
+            auto_sweep_toggle(),
+            text(Localized::new("settings-landing-marker")),
+            landing_marker_toggle(),
+            text(Localized::new("settings-mirror-sweep")),
+            mirror_sweep_toggle(),
+            text(Localized::new("settings-trails")),
+            trails_toggle(),
+            text(Localized::new("settings-shadows")),
+            shadows_toggle(),
+            text(Localized::new("settings-palette")),
+            palette_toggle(),
+            text(Localized::new("settings-theme")),
+            theme_toggle(),
+            text(Localized::new("settings-stone-skin")),
+            stone_skin_toggle(),
+            text(Localized::new("settings-reduce-motion")),
+            reduce_motion_toggle(),
+            text(Localized::new("settings-screen-shake")),
+            screen_shake_intensity(),
+            text(Localized::new("settings-language")),
+            language_toggle(),
         ],
     )
 }
@@ -221,7 +401,10 @@ fn play_buttons() -> impl Bundle {
             width: Val::Percent(100.0),
             ..Default::default()
         },
-        children![btn("Play Music", play_music), btn("Play Sfx", play_sfx),],
+        children![
+            btn("settings-play-music", play_music),
+            btn("settings-play-sfx", play_sfx),
+        ],
     )
 }
 
@@ -243,7 +426,7 @@ fn back_button() -> impl Bundle {
             width: Val::Percent(100.0),
             ..Default::default()
         },
-        children![btn("Back", go_back_on_click),],
+        children![btn("menu-back", go_back_on_click),],
     )
 }
 
@@ -281,14 +464,170 @@ fn sfx_volume() -> impl Bundle {
 #[reflect(Component)]
 struct SfxVolumeLabel;
 
-pub fn btn<E, B, M, I>(t: impl Into<String>, action: I) -> impl Bundle
+fn auto_sweep_toggle() -> impl Bundle {
+    (
+        knobs_container(),
+        children![
+            knob_label((AutoSweepLabel, Localized::new("settings-off"))),
+            btn("settings-toggle", toggle_auto_sweep),
+        ],
+    )
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct AutoSweepLabel;
+
+fn landing_marker_toggle() -> impl Bundle {
+    (
+        knobs_container(),
+        children![
+            knob_label((LandingMarkerLabel, Localized::new("settings-off"))),
+            btn("settings-toggle", toggle_landing_marker),
+        ],
+    )
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct LandingMarkerLabel;
+
+fn mirror_sweep_toggle() -> impl Bundle {
+    (
+        knobs_container(),
+        children![
+            knob_label((MirrorSweepLabel, Localized::new("settings-off"))),
+            btn("settings-toggle", toggle_mirror_sweep),
+        ],
+    )
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct MirrorSweepLabel;
+
+fn trails_toggle() -> impl Bundle {
+    (
+        knobs_container(),
+        children![
+            knob_label((TrailsLabel, Localized::new("settings-on"))),
+            btn("settings-toggle", toggle_trails),
+        ],
+    )
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct TrailsLabel;
+
+fn shadows_toggle() -> impl Bundle {
+    (
+        knobs_container(),
+        children![
+            knob_label((ShadowsLabel, Localized::new("settings-on"))),
+            btn("settings-toggle", toggle_shadows),
+        ],
+    )
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct ShadowsLabel;
+
+fn reduce_motion_toggle() -> impl Bundle {
+    (
+        knobs_container(),
+        children![
+            knob_label((ReduceMotionLabel, Localized::new("settings-off"))),
+            btn("settings-toggle", toggle_reduce_motion),
+        ],
+    )
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct ReduceMotionLabel;
+
+fn screen_shake_intensity() -> impl Bundle {
+    (
+        knobs_container(),
+        children![
+            btn("-", lower_screen_shake),
+            knob_label(ScreenShakeLabel),
+            btn("+", raise_screen_shake),
+        ],
+    )
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct ScreenShakeLabel;
+
+fn palette_toggle() -> impl Bundle {
+    (
+        knobs_container(),
+        children![
+            knob_label((PaletteLabel, Localized::new("palette-normal"))),
+            btn("settings-change", cycle_palette),
+        ],
+    )
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct PaletteLabel;
+
+fn theme_toggle() -> impl Bundle {
+    (
+        knobs_container(),
+        children![
+            knob_label((ThemeLabel, Localized::new("theme-day"))),
+            btn("settings-change", cycle_theme),
+        ],
+    )
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct ThemeLabel;
+
+fn stone_skin_toggle() -> impl Bundle {
+    (
+        knobs_container(),
+        children![
+            knob_label((StoneSkinLabel, Localized::new("skin-classic"))),
+            btn("settings-change", cycle_stone_skin),
+        ],
+    )
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct StoneSkinLabel;
+
+fn language_toggle() -> impl Bundle {
+    (
+        knobs_container(),
+        children![
+            knob_label(LanguageLabel),
+            btn("settings-change", cycle_language),
+        ],
+    )
+}
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct LanguageLabel;
+
+/// A button labelled with the text for the localization key `key`. Labels that aren't keys, like
+/// "+", show as they are.
+pub fn btn<E, B, M, I>(key: &'static str, action: I) -> impl Bundle
 where
     E: EntityEvent,
     B: Bundle,
     I: IntoObserverSystem<E, B, M>,
 {
     let action = IntoObserverSystem::into_system(action);
-    let t: String = t.into();
 
     (
         Name::new("Button"),
@@ -298,7 +637,7 @@ where
                 .spawn((
                     Button,
                     BorderColor::all(Color::WHITE),
-                    children![Name::new("Button text"), text(Text(t))],
+                    children![Name::new("Button text"), text(Localized::new(key))],
                 ))
                 .observe(action);
         })),
@@ -328,7 +667,7 @@ fn knobs_container() -> impl Bundle {
     }
 }
 
-fn knob_label(label: impl Component) -> impl Bundle {
+fn knob_label(label: impl Bundle) -> impl Bundle {
     (
         Node {
             padding: UiRect::horizontal(Val::Px(10.0)),
@@ -0,0 +1,256 @@
+//! A minimap in the top right corner: a second camera with a small viewport that sees the tiles
+//! plus markers for the stones and the goal, but none of the lines, trails or confetti. Clicking
+//! it pans the main camera there.
+
+use bevy::{
+    camera::{ClearColorConfig, Viewport, visibility::RenderLayers},
+    prelude::*,
+};
+
+use crate::{
+    Pause,
+    camera::{MainCamera, level_bounds},
+    hex_grid::HexGrid,
+    layers,
+    level::OnLevel,
+    screens::Screen,
+    stone::{Stone, StoneBody},
+    tile::IsGoal,
+};
+
+/// Render layer only the minimap camera sees. Anything that should show on both the game and the
+/// minimap goes on layers 0 and `MINIMAP_LAYER`.
+pub const MINIMAP_LAYER: usize = 1;
+
+/// Logical size of the minimap, border included
+const MINIMAP_SIZE: Vec2 = Vec2::new(220.0, 165.0);
+const MINIMAP_BORDER: f32 = 2.0;
+const MINIMAP_RIGHT: f32 = 16.0;
+/// Leaves room for the sweep HUD above
+const MINIMAP_TOP: f32 = 64.0;
+/// Where the minimap's frame ends, for placing things below it
+pub const MINIMAP_BOTTOM: f32 = MINIMAP_TOP + MINIMAP_SIZE.y;
+/// Leaves some room around the level inside the minimap
+const MINIMAP_MARGIN: f32 = 1.1;
+
+const MINIMAP_BACKGROUND: Color = Color::srgb(0.08, 0.08, 0.1);
+const GOAL_MARKER_COLOR: Color = Color::srgb(1.0, 0.84, 0.0);
+/// Just over the stone it marks
+const STONE_MARKER_Z: f32 = layers::STONE + 0.5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Startup, setup_minimap_assets)
+        .add_systems(OnEnter(Screen::Gameplay), spawn_minimap)
+        .add_systems(
+            Update,
+            (
+                add_stone_markers,
+                add_goal_marker,
+                update_minimap_viewport,
+                fit_minimap_to_level,
+            )
+                .run_if(in_state(Screen::Gameplay)),
+        );
+}
+
+#[derive(Resource)]
+struct MinimapAssets {
+    stone_marker_mesh: Handle<Mesh>,
+    goal_marker_mesh: Handle<Mesh>,
+    goal_marker_material: Handle<ColorMaterial>,
+}
+
+#[derive(Component)]
+struct MinimapCamera;
+
+/// The minimap's border, and what catches clicks on it before they reach the tiles underneath
+#[derive(Component)]
+struct MinimapFrame;
+
+fn setup_minimap_assets(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(MinimapAssets {
+        stone_marker_mesh: meshes.add(Circle::new(30.0)),
+        goal_marker_mesh: meshes.add(Annulus::new(30.0, 45.0)),
+        goal_marker_material: materials.add(GOAL_MARKER_COLOR),
+    });
+}
+
+fn spawn_minimap(mut commands: Commands) {
+    commands.spawn((
+        DespawnOnExit(Screen::Gameplay),
+        MinimapCamera,
+        Camera2d,
+        Camera {
+            // After the main camera, so the minimap is drawn over the game
+            order: 1,
+            clear_color: ClearColorConfig::Custom(MINIMAP_BACKGROUND),
+            ..default()
+        },
+        RenderLayers::layer(MINIMAP_LAYER),
+    ));
+    commands
+        .spawn((
+            DespawnOnExit(Screen::Gameplay),
+            MinimapFrame,
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(MINIMAP_RIGHT),
+                top: Val::Px(MINIMAP_TOP),
+                width: Val::Px(MINIMAP_SIZE.x),
+                height: Val::Px(MINIMAP_SIZE.y),
+                border: UiRect::all(Val::Px(MINIMAP_BORDER)),
+                ..default()
+            },
+            BorderColor::all(Color::srgba(1.0, 1.0, 1.0, 0.7)),
+        ))
+        .observe(pan_to_minimap_click);
+}
+
+/// Stones keep their own color on the minimap, drawn big enough to see at its scale
+fn add_stone_markers(
+    mut commands: Commands,
+    minimap_assets: Res<MinimapAssets>,
+    stones: Query<(Entity, &Children), Added<Stone>>,
+    bodies: Query<&MeshMaterial2d<ColorMaterial>, With<StoneBody>>,
+) {
+    for (entity, children) in &stones {
+        let Some(material) = bodies.iter_many(children).next() else {
+            continue;
+        };
+        commands.entity(entity).with_child((
+            Mesh2d(minimap_assets.stone_marker_mesh.clone()),
+            MeshMaterial2d(material.0.clone()),
+            Transform::from_xyz(
+                0.0,
+                0.0,
+                layers::child_z(STONE_MARKER_Z, layers::STONE, 1.0),
+            ),
+            RenderLayers::layer(MINIMAP_LAYER),
+        ));
+    }
+}
+
+fn add_goal_marker(
+    mut commands: Commands,
+    minimap_assets: Res<MinimapAssets>,
+    goals: Query<(Entity, &Transform), Added<IsGoal>>,
+) {
+    for (entity, transform) in &goals {
+        commands.entity(entity).with_child((
+            Mesh2d(minimap_assets.goal_marker_mesh.clone()),
+            MeshMaterial2d(minimap_assets.goal_marker_material.clone()),
+            // Over the stones' markers
+            Transform::from_xyz(
+                0.0,
+                0.0,
+                layers::child_z(layers::FX, layers::BOARD, transform.scale.z),
+            ),
+            RenderLayers::layer(MINIMAP_LAYER),
+        ));
+    }
+}
+
+/// Physical position and size of the minimap's viewport, just inside the frame's border. The frame
+/// is sized in UI units, so `ui_scale` converts it to the window's.
+fn minimap_viewport(window: &Window, ui_scale: f32) -> Viewport {
+    let scale_factor = window.scale_factor();
+    let inner_size = (MINIMAP_SIZE - 2.0 * MINIMAP_BORDER) * ui_scale;
+    let position = Vec2::new(
+        window.width() - (MINIMAP_RIGHT + MINIMAP_SIZE.x - MINIMAP_BORDER) * ui_scale,
+        (MINIMAP_TOP + MINIMAP_BORDER) * ui_scale,
+    );
+    Viewport {
+        physical_position: (position.max(Vec2::ZERO) * scale_factor).as_uvec2(),
+        physical_size: (inner_size * scale_factor).as_uvec2().max(UVec2::ONE),
+        ..default()
+    }
+}
+
+/// Follows the window size, and turns the minimap off while paused since it's drawn over the UI
+/// and would cover the pause menu.
+fn update_minimap_viewport(
+    window: Single<&Window>,
+    ui_scale: Res<UiScale>,
+    pause: Res<State<Pause>>,
+    mut camera: Single<&mut Camera, With<MinimapCamera>>,
+) {
+    let viewport = minimap_viewport(&window, ui_scale.0);
+    let moved = camera.viewport.as_ref().is_none_or(|current| {
+        current.physical_position != viewport.physical_position
+            || current.physical_size != viewport.physical_size
+    });
+    if moved {
+        camera.viewport = Some(viewport);
+    }
+    let is_active = !pause.get().0;
+    if camera.is_active != is_active {
+        camera.is_active = is_active;
+    }
+}
+
+/// Scale that fits a level of `level_size` into the minimap at `ui_scale`
+fn minimap_scale(level_size: Vec2, ui_scale: f32) -> f32 {
+    let inner_size = (MINIMAP_SIZE - 2.0 * MINIMAP_BORDER) * ui_scale;
+    (level_size * MINIMAP_MARGIN / inner_size).max_element()
+}
+
+fn fit_minimap_to_level(
+    on_level: Res<OnLevel>,
+    ui_scale: Res<UiScale>,
+    camera: Single<(Ref<MinimapCamera>, &mut Transform, &mut Projection)>,
+) {
+    let (minimap_camera, mut transform, mut projection) = camera.into_inner();
+    // A freshly spawned camera needs framing too, not just level changes and resizes
+    if !on_level.is_changed() && !ui_scale.is_changed() && !minimap_camera.is_added() {
+        return;
+    }
+    let Some(bounds) = level_bounds(&HexGrid::new(&on_level.0)) else {
+        return;
+    };
+    transform.translation.x = bounds.center().x;
+    transform.translation.y = bounds.center().y;
+    if let Projection::Orthographic(orthographic) = &mut *projection {
+        orthographic.scale = minimap_scale(bounds.size(), ui_scale.0);
+    }
+}
+
+fn pan_to_minimap_click(
+    click: On<Pointer<Click>>,
+    minimap: Single<(&Camera, &GlobalTransform), With<MinimapCamera>>,
+    mut main_camera: Single<&mut Transform, With<MainCamera>>,
+) {
+    if click.button != PointerButton::Primary {
+        return;
+    }
+    let (camera, camera_transform) = *minimap;
+    let Ok(target) = camera.viewport_to_world_2d(camera_transform, click.pointer_location.position)
+    else {
+        return;
+    };
+    main_camera.translation.x = target.x;
+    main_camera.translation.y = target.y;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::{CurrentLevel, get_level};
+
+    #[test]
+    fn test_minimap_scale_fits_level() {
+        // The whole level, margin included, fits inside the minimap along its tighter axis
+        let hex_grid = HexGrid::new(&get_level(CurrentLevel::Level1));
+        let size = level_bounds(&hex_grid).unwrap().size();
+        let scale = minimap_scale(size, 1.0);
+        let inner_size = MINIMAP_SIZE - 2.0 * MINIMAP_BORDER;
+        let shown = inner_size * scale;
+        assert!(shown.x >= size.x && shown.y >= size.y);
+        assert!((shown / (size * MINIMAP_MARGIN)).min_element() - 1.0 < 1e-4);
+        // A scaled down minimap shows the same area on fewer pixels
+        assert!((minimap_scale(size, 0.5) - scale * 2.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,113 @@
+//! The most stars earned on each level, kept between launches the same way as the settings:
+//! loaded on startup and written whenever a win beats a level's best.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    gameplay::{LevelComplete, swept_tile_counts},
+    level::{CurrentLevel, OnLevel},
+    replay::{ReplayPlayback, ReplayRecording},
+    saved_settings::{read_saved, write_saved},
+    win_screen::{MAX_STARS, win_stars},
+};
+
+/// Saved as `progress.cfg`, see `read_saved`
+const PROGRESS_NAME: &str = "progress";
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<LevelProgress>()
+        .add_systems(Startup, load_progress)
+        .add_observer(record_stars);
+}
+
+#[derive(Resource, Default, Clone, Debug, PartialEq)]
+pub struct LevelProgress {
+    pub best_stars: HashMap<CurrentLevel, u8>,
+}
+
+impl LevelProgress {
+    /// Records `stars` for `level` and returns whether they beat the level's best
+    pub fn record(&mut self, level: CurrentLevel, stars: u8) -> bool {
+        let best = self.best_stars.entry(level).or_default();
+        if stars <= *best {
+            return false;
+        }
+        *best = stars;
+        true
+    }
+
+    /// One `level<index>=<stars>` line per level that's been won, in level order
+    pub fn to_file_string(&self) -> String {
+        CurrentLevel::iterator()
+            .filter_map(|level| {
+                let stars = self.best_stars.get(level)?;
+                Some(format!("level{}={}", level.index(), stars))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Reads back `to_file_string`, any line that can't be read makes the whole file count as
+    /// corrupt
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut progress = LevelProgress::default();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (key, value) = line.split_once('=')?;
+            let level = CurrentLevel::from_index(key.strip_prefix("level")?.parse().ok()?)?;
+            let stars = value.parse().ok().filter(|stars| *stars <= MAX_STARS)?;
+            progress.best_stars.insert(level, stars);
+        }
+        Some(progress)
+    }
+}
+
+fn load_progress(mut progress: ResMut<LevelProgress>) {
+    let Some(text) = read_saved(PROGRESS_NAME) else {
+        return;
+    };
+    match LevelProgress::parse(&text) {
+        Some(saved) => *progress = saved,
+        None => warn!("Couldn't read the saved progress, starting over"),
+    }
+}
+
+/// Replays don't count, the stars were already earned when they were recorded
+fn record_stars(
+    _event: On<LevelComplete>,
+    on_level: Res<OnLevel>,
+    replay_recording: Res<ReplayRecording>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    mut progress: ResMut<LevelProgress>,
+) {
+    if replay_playback.is_some() {
+        return;
+    }
+    let level = &on_level.0;
+    let (tiles_swept, _) = swept_tile_counts(&replay_recording.0, level);
+    if progress.record(level.current_level, win_stars(tiles_swept, level.par)) {
+        write_saved(PROGRESS_NAME, &progress.to_file_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_keeps_the_best_stars() {
+        let mut progress = LevelProgress::default();
+        assert!(progress.record(CurrentLevel::Level2, 1));
+        assert!(progress.record(CurrentLevel::Level2, 3));
+        // A worse win later doesn't take stars away
+        assert!(!progress.record(CurrentLevel::Level2, 2));
+        assert!(progress.record(CurrentLevel::Level5, 2));
+        assert_eq!(
+            LevelProgress::parse(&progress.to_file_string()),
+            Some(progress)
+        );
+        assert_eq!(LevelProgress::parse("level2=4"), None);
+        assert_eq!(LevelProgress::parse("level99=1"), None);
+    }
+}
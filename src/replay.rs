@@ -0,0 +1,612 @@
+//! Records the sweeps made during a level so they can be shared as a solution code and
+//! played back against the same level.
+
+use std::fmt::Display;
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use bevy::prelude::*;
+
+use crate::{
+    PausableSystems,
+    gameplay::{GameState, restart_game},
+    hex_grid::{HexCoordinate, HexGrid},
+    level::{CurrentLevel, Facing, Level, OnLevel, get_level},
+    rewind::rewinding,
+    screens::Screen,
+    stone::{Stone, StoneSkins, apply_stone_collision, apply_tile_velocity_effects},
+    tile::{CurrentDragTileType, ScratchOffMaterial, Stroke, TileAssets, TileDragging, TileType},
+};
+
+/// Bumped whenever the binary layout of a replay code changes.
+const REPLAY_CODE_VERSION: u8 = 2;
+
+/// Codes from before aimed throws were recorded, still decoded with no throws.
+const REPLAY_CODE_VERSION_WITHOUT_THROWS: u8 = 1;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ReplayClock>()
+        .init_resource::<ReplayRecording>()
+        .add_systems(OnEnter(GameState::Countdown), start_recording)
+        .add_systems(
+            FixedUpdate,
+            (
+                apply_replay_sweeps.before(apply_stone_collision),
+                tick_replay_clock
+                    .after(apply_tile_velocity_effects)
+                    .run_if(in_state(GameState::Playing).and(not(rewinding))),
+            )
+                .run_if(in_state(Screen::Gameplay))
+                .in_set(PausableSystems),
+        )
+        .add_observer(on_play_replay);
+}
+
+/// A single sweep stroke applied to one tile.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SweepAction {
+    /// Number of physics steps that had run when the sweep happened.
+    /// Sweeps made during the countdown are recorded at tick 0.
+    pub tick: u32,
+    pub coordinate: HexCoordinate,
+    pub stroke: Stroke,
+    pub distance: f32,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Replay {
+    pub level: CurrentLevel,
+    /// Aimed throw per stone config, empty on levels with a fixed throw
+    pub throws: Vec<Option<Vec2>>,
+    pub actions: Vec<SweepAction>,
+}
+
+/// Where a recording stood at some point, so everything recorded since can be rolled back
+#[derive(Clone, PartialEq, Debug)]
+pub struct RecordingMark {
+    len: usize,
+    /// Kept whole since later strokes can be merged into it
+    last: Option<SweepAction>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ReplayCodeError {
+    InvalidBase64,
+    UnsupportedVersion(u8),
+    UnknownLevel(u8),
+    UnknownTileType(u8),
+    Truncated,
+    /// Ticks that add up past what a `u32` can hold
+    TickOverflow,
+}
+
+impl Display for ReplayCodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayCodeError::InvalidBase64 => write!(f, "replay code is not valid base64"),
+            ReplayCodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported replay code version {}", version)
+            }
+            ReplayCodeError::UnknownLevel(level) => write!(f, "unknown level id {}", level),
+            ReplayCodeError::UnknownTileType(tile_type) => {
+                write!(f, "unknown tile type id {}", tile_type)
+            }
+            ReplayCodeError::Truncated => write!(f, "replay code is truncated"),
+            ReplayCodeError::TickOverflow => write!(f, "replay code's ticks overflow"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayCodeError {}
+
+impl Replay {
+    pub fn new(level: CurrentLevel) -> Self {
+        Self {
+            level,
+            throws: Vec::new(),
+            actions: Vec::new(),
+        }
+    }
+
+    /// Sets each stone's aimed throw on `level` to the one recorded in this replay.
+    pub fn apply_throws(&self, level: &mut Level) {
+        for (stone_config, throw) in level.stone_configs.iter_mut().zip(&self.throws) {
+            stone_config.aimed_velocity = *throw;
+        }
+    }
+
+    /// Encodes the replay as a url-safe base64 string.
+    ///
+    /// Layout: `version | level | throw count | throws... | action count | actions...` where each
+    /// throw is a `0` byte for the configured throw or a `1` byte followed by `x | y`, and each
+    /// action is `tick delta | q | r | stroke | distance`. Integers are LEB128 varints (zigzag for
+    /// coordinates) and floats are stored as raw `f32` bits so playback is exact.
+    pub fn to_code(&self) -> String {
+        let mut bytes = vec![REPLAY_CODE_VERSION, self.level.index() as u8];
+        write_varint(&mut bytes, self.throws.len() as u32);
+        for throw in &self.throws {
+            match throw {
+                Some(velocity) => {
+                    bytes.push(1);
+                    bytes.extend_from_slice(&velocity.x.to_le_bytes());
+                    bytes.extend_from_slice(&velocity.y.to_le_bytes());
+                }
+                None => bytes.push(0),
+            }
+        }
+        write_varint(&mut bytes, self.actions.len() as u32);
+
+        let mut previous_tick = 0;
+        for action in &self.actions {
+            write_varint(&mut bytes, action.tick - previous_tick);
+            previous_tick = action.tick;
+            write_varint(&mut bytes, zigzag_encode(action.coordinate.q));
+            write_varint(&mut bytes, zigzag_encode(action.coordinate.r));
+            bytes.push(stroke_to_byte(&action.stroke));
+            bytes.extend_from_slice(&action.distance.to_le_bytes());
+        }
+
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    pub fn from_code(code: &str) -> Result<Replay, ReplayCodeError> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(code.trim())
+            .map_err(|_| ReplayCodeError::InvalidBase64)?;
+        let mut reader = ByteReader { bytes: &bytes };
+
+        let version = reader.byte()?;
+        if version != REPLAY_CODE_VERSION && version != REPLAY_CODE_VERSION_WITHOUT_THROWS {
+            return Err(ReplayCodeError::UnsupportedVersion(version));
+        }
+        let level_id = reader.byte()?;
+        let level = CurrentLevel::from_index(level_id as usize)
+            .ok_or(ReplayCodeError::UnknownLevel(level_id))?;
+
+        let mut throws = Vec::new();
+        if version != REPLAY_CODE_VERSION_WITHOUT_THROWS {
+            for _ in 0..reader.varint()? {
+                let throw = match reader.byte()? {
+                    0 => None,
+                    _ => Some(Vec2::new(
+                        f32::from_le_bytes(reader.array()?),
+                        f32::from_le_bytes(reader.array()?),
+                    )),
+                };
+                throws.push(throw);
+            }
+        }
+
+        let count = reader.varint()?;
+        let mut actions = Vec::new();
+        let mut tick = 0u32;
+        for _ in 0..count {
+            tick = tick
+                .checked_add(reader.varint()?)
+                .ok_or(ReplayCodeError::TickOverflow)?;
+            let q = zigzag_decode(reader.varint()?);
+            let r = zigzag_decode(reader.varint()?);
+            let stroke = stroke_from_byte(reader.byte()?)?;
+            let distance = f32::from_le_bytes(reader.array()?);
+            actions.push(SweepAction {
+                tick,
+                coordinate: HexCoordinate { q, r },
+                stroke,
+                distance,
+            });
+        }
+
+        Ok(Replay {
+            level,
+            throws,
+            actions,
+        })
+    }
+
+    /// Appends a stroke, merging it into the previous action when it hit the same tile with the
+    /// same broom during the same physics step. This keeps codes short without changing playback.
+    pub fn record(
+        &mut self,
+        tick: u32,
+        coordinate: &HexCoordinate,
+        stroke: &Stroke,
+        distance: f32,
+    ) {
+        if let Some(last) = self.actions.last_mut()
+            && last.tick == tick
+            && &last.coordinate == coordinate
+            && &last.stroke == stroke
+        {
+            last.distance += distance;
+            return;
+        }
+        self.actions.push(SweepAction {
+            tick,
+            coordinate: coordinate.clone(),
+            stroke: stroke.clone(),
+            distance,
+        });
+    }
+
+    pub fn mark(&self) -> RecordingMark {
+        RecordingMark {
+            len: self.actions.len(),
+            last: self.actions.last().cloned(),
+        }
+    }
+
+    /// Drops every action recorded since `mark` was taken, including any merged into its last one.
+    pub fn roll_back(&mut self, mark: &RecordingMark) {
+        self.actions.truncate(mark.len);
+        if let (Some(last), Some(marked_last)) = (self.actions.last_mut(), &mark.last) {
+            *last = marked_last.clone();
+        }
+    }
+}
+
+/// Erasing is stored outside the tile type range so codes without it decode unchanged.
+const ERASE_BYTE: u8 = 255;
+
+fn stroke_to_byte(stroke: &Stroke) -> u8 {
+    match stroke {
+        Stroke::Sweep(tile_type) => tile_type_to_byte(tile_type),
+        Stroke::Erase => ERASE_BYTE,
+    }
+}
+
+fn stroke_from_byte(byte: u8) -> Result<Stroke, ReplayCodeError> {
+    match byte {
+        ERASE_BYTE => Ok(Stroke::Erase),
+        _ => tile_type_from_byte(byte).map(Stroke::Sweep),
+    }
+}
+
+fn tile_type_to_byte(tile_type: &TileType) -> u8 {
+    match tile_type {
+        TileType::Wall => 0,
+        TileType::MaintainSpeed => 1,
+        TileType::SlowDown => 2,
+        TileType::TurnCounterclockwise => 3,
+        TileType::TurnClockwise => 4,
+        TileType::Goal => 5,
+        TileType::SpeedUp(facing) => 6 + facing.index() as u8,
+        // Gates can't be swept onto a tile, so they're never in a stroke to be read back
+        TileType::SpeedGate { .. } => 12,
+    }
+}
+
+fn tile_type_from_byte(byte: u8) -> Result<TileType, ReplayCodeError> {
+    match byte {
+        0 => Ok(TileType::Wall),
+        1 => Ok(TileType::MaintainSpeed),
+        2 => Ok(TileType::SlowDown),
+        3 => Ok(TileType::TurnCounterclockwise),
+        4 => Ok(TileType::TurnClockwise),
+        5 => Ok(TileType::Goal),
+        _ => Facing::from_index((byte - 6) as usize)
+            .map(TileType::SpeedUp)
+            .ok_or(ReplayCodeError::UnknownTileType(byte)),
+    }
+}
+
+fn zigzag_encode(value: i32) -> u32 {
+    ((value << 1) ^ (value >> 31)) as u32
+}
+
+fn zigzag_decode(value: u32) -> i32 {
+    ((value >> 1) as i32) ^ -((value & 1) as i32)
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl ByteReader<'_> {
+    fn byte(&mut self) -> Result<u8, ReplayCodeError> {
+        let (first, rest) = self.bytes.split_first().ok_or(ReplayCodeError::Truncated)?;
+        self.bytes = rest;
+        Ok(*first)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ReplayCodeError> {
+        let (head, rest) = self
+            .bytes
+            .split_first_chunk::<N>()
+            .ok_or(ReplayCodeError::Truncated)?;
+        self.bytes = rest;
+        Ok(*head)
+    }
+
+    fn varint(&mut self) -> Result<u32, ReplayCodeError> {
+        let mut value = 0u32;
+        for shift in (0..32).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ReplayCodeError::Truncated)
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Number of physics steps run since the level started.
+#[derive(Resource, Default)]
+pub struct ReplayClock(pub u32);
+
+/// The sweeps made so far on the current attempt.
+#[derive(Resource)]
+pub struct ReplayRecording(pub Replay);
+
+impl Default for ReplayRecording {
+    fn default() -> Self {
+        Self(Replay::new(CurrentLevel::default()))
+    }
+}
+
+impl ReplayRecording {
+    pub fn record(
+        &mut self,
+        tick: u32,
+        coordinate: &HexCoordinate,
+        stroke: &Stroke,
+        distance: f32,
+    ) {
+        self.0.record(tick, coordinate, stroke, distance);
+    }
+}
+
+/// Present while a replay is driving the sweeps. Player sweeping is ignored until it's removed.
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    pub next_action: usize,
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+/// Restarts the replay's level and plays its sweeps back.
+#[derive(Event)]
+pub struct PlayReplay(pub Replay);
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+fn start_recording(
+    mut commands: Commands,
+    on_level: Res<OnLevel>,
+    mut replay_clock: ResMut<ReplayClock>,
+    mut replay_recording: ResMut<ReplayRecording>,
+    replay_playback: Option<ResMut<ReplayPlayback>>,
+) {
+    replay_clock.0 = 0;
+    replay_recording.0 = Replay::new(on_level.0.current_level);
+    if !on_level.0.fixed_throw {
+        replay_recording.0.throws = on_level
+            .0
+            .stone_configs
+            .iter()
+            .map(|stone_config| stone_config.aimed_velocity)
+            .collect();
+    }
+
+    if let Some(mut replay_playback) = replay_playback {
+        if replay_playback.replay.level == on_level.0.current_level {
+            replay_playback.next_action = 0;
+        } else {
+            commands.remove_resource::<ReplayPlayback>();
+        }
+    }
+}
+
+fn tick_replay_clock(mut replay_clock: ResMut<ReplayClock>) {
+    replay_clock.0 += 1;
+}
+
+/// Applies every recorded sweep whose tick has been reached, before the physics step runs.
+fn apply_replay_sweeps(
+    replay_clock: Res<ReplayClock>,
+    replay_playback: Option<ResMut<ReplayPlayback>>,
+    mut tiles: Query<(&HexCoordinate, &mut TileDragging)>,
+) {
+    let Some(mut replay_playback) = replay_playback else {
+        return;
+    };
+
+    while let Some(action) = replay_playback
+        .replay
+        .actions
+        .get(replay_playback.next_action)
+        .filter(|action| action.tick <= replay_clock.0)
+    {
+        if let Some((_, mut tile)) = tiles
+            .iter_mut()
+            .find(|(coordinate, _)| **coordinate == action.coordinate)
+        {
+            action.stroke.apply(&mut tile, action.distance);
+        }
+        replay_playback.next_action += 1;
+    }
+}
+
+fn on_play_replay(
+    play_replay: On<PlayReplay>,
+    mut commands: Commands,
+    grid: Query<Entity, With<HexGrid>>,
+    stone_query: Query<Entity, With<Stone>>,
+    meshes: ResMut<Assets<Mesh>>,
+    materials: ResMut<Assets<ColorMaterial>>,
+    scratch_materials: ResMut<Assets<ScratchOffMaterial>>,
+    tile_assets: ResMut<TileAssets>,
+    stone_skins: StoneSkins,
+    current_drag_tile_type: ResMut<CurrentDragTileType>,
+    mut on_level: ResMut<OnLevel>,
+) {
+    let replay = play_replay.0.clone();
+    on_level.0 = get_level(replay.level);
+    replay.apply_throws(&mut on_level.0);
+    restart_game(
+        &mut commands,
+        grid,
+        stone_query,
+        meshes,
+        materials,
+        scratch_materials,
+        tile_assets,
+        stone_skins,
+        current_drag_tile_type,
+        &on_level.0,
+    );
+    commands.insert_resource(ReplayPlayback {
+        replay,
+        next_action: 0,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_replay() -> Replay {
+        Replay {
+            level: CurrentLevel::Level4,
+            throws: vec![None, Some(Vec2::new(-120.5, 33.0))],
+            actions: vec![
+                SweepAction {
+                    tick: 0,
+                    coordinate: HexCoordinate { q: 2, r: 1 },
+                    stroke: Stroke::Sweep(TileType::MaintainSpeed),
+                    distance: 12.5,
+                },
+                SweepAction {
+                    tick: 130,
+                    coordinate: HexCoordinate { q: -3, r: 400 },
+                    stroke: Stroke::Sweep(TileType::TurnClockwise),
+                    distance: 0.1,
+                },
+                SweepAction {
+                    tick: 130,
+                    coordinate: HexCoordinate { q: 8, r: -1 },
+                    stroke: Stroke::Sweep(TileType::SpeedUp(Facing::DownLeft)),
+                    distance: 250.0,
+                },
+                SweepAction {
+                    tick: 131,
+                    coordinate: HexCoordinate { q: 8, r: -1 },
+                    stroke: Stroke::Erase,
+                    distance: 40.0,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_replay_code_round_trip() {
+        let replay = sample_replay();
+        let decoded = Replay::from_code(&replay.to_code()).unwrap();
+        assert_eq!(decoded, replay);
+    }
+
+    #[test]
+    fn test_replay_code_rejects_unknown_version() {
+        let mut bytes = URL_SAFE_NO_PAD.decode(sample_replay().to_code()).unwrap();
+        bytes[0] = REPLAY_CODE_VERSION + 1;
+        assert_eq!(
+            Replay::from_code(&URL_SAFE_NO_PAD.encode(bytes)),
+            Err(ReplayCodeError::UnsupportedVersion(REPLAY_CODE_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn test_replay_code_decodes_codes_without_throws() {
+        // Version 1 codes are the current layout minus the throw count
+        let mut replay = sample_replay();
+        replay.throws.clear();
+        let mut bytes = URL_SAFE_NO_PAD.decode(replay.to_code()).unwrap();
+        bytes[0] = REPLAY_CODE_VERSION_WITHOUT_THROWS;
+        bytes.remove(2);
+        assert_eq!(
+            Replay::from_code(&URL_SAFE_NO_PAD.encode(bytes)),
+            Ok(replay)
+        );
+    }
+
+    #[test]
+    fn test_replay_code_rejects_truncated_input() {
+        let mut bytes = URL_SAFE_NO_PAD.decode(sample_replay().to_code()).unwrap();
+        bytes.truncate(bytes.len() - 1);
+        assert_eq!(
+            Replay::from_code(&URL_SAFE_NO_PAD.encode(bytes)),
+            Err(ReplayCodeError::Truncated)
+        );
+    }
+
+    #[test]
+    fn test_replay_code_rejects_overflowing_ticks() {
+        // Two actions each a whole u32 of ticks after the last can't both fit
+        let mut replay = Replay::new(CurrentLevel::Level1);
+        replay.record(u32::MAX, &HexCoordinate { q: 0, r: 0 }, &Stroke::Erase, 1.0);
+        let mut bytes = URL_SAFE_NO_PAD.decode(replay.to_code()).unwrap();
+        // Version, level, no throws, then the action count and the one action
+        assert_eq!(bytes[3], 1);
+        bytes[3] = 2;
+        let action = bytes[4..].to_vec();
+        bytes.extend(action);
+        assert_eq!(
+            Replay::from_code(&URL_SAFE_NO_PAD.encode(bytes)),
+            Err(ReplayCodeError::TickOverflow)
+        );
+    }
+
+    #[test]
+    fn test_replay_code_rejects_garbage() {
+        assert_eq!(
+            Replay::from_code("not a replay!"),
+            Err(ReplayCodeError::InvalidBase64)
+        );
+    }
+
+    #[test]
+    fn test_record_merges_same_tile_same_tick() {
+        let mut replay = Replay::new(CurrentLevel::Level1);
+        let coordinate = HexCoordinate { q: 1, r: 1 };
+        replay.record(3, &coordinate, &Stroke::Sweep(TileType::MaintainSpeed), 1.0);
+        replay.record(3, &coordinate, &Stroke::Sweep(TileType::MaintainSpeed), 2.0);
+        replay.record(3, &coordinate, &Stroke::Sweep(TileType::TurnClockwise), 4.0);
+        replay.record(4, &coordinate, &Stroke::Sweep(TileType::TurnClockwise), 8.0);
+
+        assert_eq!(replay.actions.len(), 3);
+        assert_eq!(replay.actions[0].distance, 3.0);
+    }
+
+    #[test]
+    fn test_roll_back_undoes_merged_actions() {
+        let mut replay = Replay::new(CurrentLevel::Level1);
+        let coordinate = HexCoordinate { q: 1, r: 1 };
+        let stroke = Stroke::Sweep(TileType::MaintainSpeed);
+        replay.record(0, &coordinate, &stroke, 1.0);
+        let before = replay.clone();
+        let mark = replay.mark();
+
+        // The first of these merges into the action that was already there
+        replay.record(0, &coordinate, &stroke, 2.0);
+        replay.record(0, &HexCoordinate { q: 2, r: 1 }, &stroke, 4.0);
+        replay.roll_back(&mark);
+
+        assert_eq!(replay, before);
+    }
+}
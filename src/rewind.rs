@@ -0,0 +1,168 @@
+//! Forgiving mode: holding Backspace runs the stones backward through their recent positions, and
+//! letting go resumes the physics from there.
+//!
+//! Only the stones are rewound. Sweeps stay as they are, so rewinding is a way to try a different
+//! sweep over the same stretch of ice. The replay clock stops while rewinding, which keeps
+//! recorded ticks in order, but a replay of a rewound attempt won't play out the same way.
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    PausableSystems,
+    gameplay::GameState,
+    level::OnLevel,
+    replay::ReplayPlayback,
+    screens::Screen,
+    stone::{ReachedGoal, Stone, StoneIsStopped, Velocity, apply_tile_velocity_effects},
+};
+
+pub const REWIND_KEY: KeyCode = KeyCode::Backspace;
+
+/// Physics steps kept per stone, two seconds at the default 64Hz fixed timestep
+const REWIND_MAX_SNAPSHOTS: usize = 128;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        (
+            record_stone_history
+                .after(apply_tile_velocity_effects)
+                .run_if(not(rewinding)),
+            rewind_stones.run_if(rewinding),
+        )
+            // Rewinding out of the fail screen brings the level back to life
+            .run_if(
+                in_state(Screen::Gameplay)
+                    .and(in_state(GameState::Playing).or(in_state(GameState::Lost))),
+            )
+            .in_set(PausableSystems),
+    )
+    .add_observer(resume_on_rewind);
+}
+
+/// Fired when a stopped stone is rewound back into motion
+#[derive(Event)]
+pub struct StoneRewound;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StoneSnapshot {
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+/// The stone's state after each of its most recent physics steps, oldest first
+#[derive(Component, Default, Debug)]
+pub struct StoneHistory(VecDeque<StoneSnapshot>);
+
+impl StoneHistory {
+    /// Adds the latest snapshot, dropping the oldest once the history is full.
+    pub fn record(&mut self, snapshot: StoneSnapshot) {
+        if self.0.len() == REWIND_MAX_SNAPSHOTS {
+            self.0.pop_front();
+        }
+        self.0.push_back(snapshot);
+    }
+
+    /// Takes the latest snapshot off the history.
+    pub fn rewind(&mut self) -> Option<StoneSnapshot> {
+        self.0.pop_back()
+    }
+}
+
+/// Run condition for while the player is holding the rewind key on a level that allows it.
+/// Replays can't be rewound, and neither can a level that's already been won.
+pub fn rewinding(
+    input: Res<ButtonInput<KeyCode>>,
+    on_level: Res<OnLevel>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    reached_goal: Query<(), With<ReachedGoal>>,
+) -> bool {
+    on_level.0.rewind
+        && input.pressed(REWIND_KEY)
+        && replay_playback.is_none()
+        && reached_goal.is_empty()
+}
+
+/// Stopped stones aren't recorded, so rewinding goes straight back to where they were moving
+fn record_stone_history(
+    mut stones: Query<
+        (&mut StoneHistory, &Transform, &Velocity),
+        (With<Stone>, Without<StoneIsStopped>),
+    >,
+) {
+    for (mut history, transform, velocity) in &mut stones {
+        history.record(StoneSnapshot {
+            position: transform.translation.truncate(),
+            velocity: velocity.0,
+        });
+    }
+}
+
+fn resume_on_rewind(
+    _event: On<StoneRewound>,
+    game_state: Res<State<GameState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    if *game_state.get() == GameState::Lost {
+        next_game_state.set(GameState::Playing);
+    }
+}
+
+fn rewind_stones(
+    mut commands: Commands,
+    mut stones: Query<(
+        Entity,
+        &mut StoneHistory,
+        &mut Transform,
+        &mut Velocity,
+        Has<StoneIsStopped>,
+    )>,
+) {
+    for (entity, mut history, mut transform, mut velocity, stopped) in &mut stones {
+        let Some(snapshot) = history.rewind() else {
+            continue;
+        };
+        transform.translation.x = snapshot.position.x;
+        transform.translation.y = snapshot.position.y;
+        velocity.0 = snapshot.velocity;
+        if stopped {
+            commands.entity(entity).remove::<StoneIsStopped>();
+            commands.trigger(StoneRewound);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(x: f32) -> StoneSnapshot {
+        StoneSnapshot {
+            position: Vec2::new(x, 0.0),
+            velocity: Vec2::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_history_rewinds_latest_first() {
+        let mut history = StoneHistory::default();
+        history.record(snapshot(1.0));
+        history.record(snapshot(2.0));
+        assert_eq!(history.rewind(), Some(snapshot(2.0)));
+        assert_eq!(history.rewind(), Some(snapshot(1.0)));
+        assert_eq!(history.rewind(), None);
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        // Recording past the limit forgets the oldest snapshots
+        let mut history = StoneHistory::default();
+        for i in 0..REWIND_MAX_SNAPSHOTS + 10 {
+            history.record(snapshot(i as f32));
+        }
+        assert_eq!(history.0.len(), REWIND_MAX_SNAPSHOTS);
+        assert_eq!(history.0.front(), Some(&snapshot(10.0)));
+    }
+}
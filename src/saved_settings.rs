@@ -0,0 +1,432 @@
+//! Keeps the settings menu's choices and the CRT effect's parameters between launches. They're
+//! loaded on startup, before the title screen, and written a moment after they stop changing: to
+//! `settings.cfg` on native, to localStorage on the web.
+
+use bevy::prelude::*;
+use bevy_seedling::{
+    pool::SamplerPool,
+    prelude::{MainBus, MusicPool, PerceptualVolume, SoundEffectsBus, VolumeNode},
+};
+
+use crate::{
+    auto_sweep::AutoSweep,
+    camera::MainCamera,
+    crt_postprocess::{CrtAnimation, CrtSettings},
+    fire_trail::EnableTrails,
+    gameplay::{ReduceMotion, ShowLandingMarker},
+    localization::Language,
+    screen_shake::ScreenShakeIntensity,
+    shadows::ShowShadows,
+    stone::StoneSkin,
+    tile::{MirrorSweep, TilePalette, TileTheme},
+};
+
+/// Saved as `settings.cfg`, see `read_saved`
+const SETTINGS_NAME: &str = "settings";
+
+/// How long settings have to stay unchanged before they're written, so dragging through a bunch of
+/// volume steps only writes once
+const SAVE_DELAY_SECS: f32 = 1.0;
+
+const CONVERTER: PerceptualVolume = PerceptualVolume::new();
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Startup, load_settings).add_systems(
+        Update,
+        (
+            apply_saved_audio_and_display.run_if(not(settings_applied)),
+            save_changed_settings.run_if(settings_applied),
+        ),
+    );
+}
+
+/// Everything that's kept between launches
+#[derive(Clone, Debug, PartialEq)]
+pub struct SavedSettings {
+    /// Perceptual volumes, as shown in the settings menu
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub auto_sweep: bool,
+    pub landing_marker: bool,
+    pub mirror_sweep: bool,
+    pub enable_trails: bool,
+    pub shadows: bool,
+    pub reduce_motion: bool,
+    pub screen_shake: f32,
+    pub palette: TilePalette,
+    pub theme: TileTheme,
+    pub stone_skin: StoneSkin,
+    pub language: Language,
+    /// Saved without its `time`, which only drives the effect's animation
+    pub crt: CrtSettings,
+}
+
+impl Default for SavedSettings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            auto_sweep: AutoSweep::default().0,
+            landing_marker: ShowLandingMarker::default().0,
+            mirror_sweep: MirrorSweep::default().0,
+            enable_trails: EnableTrails::default().0,
+            shadows: ShowShadows::default().0,
+            reduce_motion: ReduceMotion::default().0,
+            screen_shake: ScreenShakeIntensity::default().0,
+            palette: TilePalette::default(),
+            theme: TileTheme::default(),
+            stone_skin: StoneSkin::default(),
+            language: Language::default(),
+            crt: CrtSettings::default(),
+        }
+    }
+}
+
+impl SavedSettings {
+    /// One `key=value` line per setting
+    pub fn to_file_string(&self) -> String {
+        let crt = &self.crt;
+        [
+            format!("master_volume={}", self.master_volume),
+            format!("music_volume={}", self.music_volume),
+            format!("sfx_volume={}", self.sfx_volume),
+            format!("auto_sweep={}", self.auto_sweep),
+            format!("landing_marker={}", self.landing_marker),
+            format!("mirror_sweep={}", self.mirror_sweep),
+            format!("enable_trails={}", self.enable_trails),
+            format!("shadows={}", self.shadows),
+            format!("reduce_motion={}", self.reduce_motion),
+            format!("screen_shake={}", self.screen_shake),
+            format!("palette={:?}", self.palette),
+            format!("theme={:?}", self.theme),
+            format!("stone_skin={:?}", self.stone_skin),
+            format!("language={:?}", self.language),
+            format!("crt_scanline_intensity={}", crt.scanline_intensity),
+            format!("crt_scanline_count={}", crt.scanline_count),
+            format!("crt_curvature={}", crt.curvature),
+            format!("crt_vignette_intensity={}", crt.vignette_intensity),
+            format!("crt_chromatic_aberration={}", crt.chromatic_aberration),
+            format!("crt_brightness={}", crt.brightness),
+            format!("crt_noise_intensity={}", crt.noise_intensity),
+        ]
+        .join("\n")
+    }
+
+    /// Reads back `to_file_string`. Settings missing from the file keep their defaults, and keys
+    /// it doesn't know, from older or newer builds, are skipped. Any line that isn't a key and
+    /// value, or a value that can't be read, makes the whole file count as corrupt.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut settings = SavedSettings::default();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (key, value) = line.split_once('=')?;
+            let float = || value.parse::<f32>().ok().filter(|value| value.is_finite());
+            let crt = &mut settings.crt;
+            match key {
+                "master_volume" => settings.master_volume = float()?,
+                "music_volume" => settings.music_volume = float()?,
+                "sfx_volume" => settings.sfx_volume = float()?,
+                "auto_sweep" => settings.auto_sweep = value.parse().ok()?,
+                "landing_marker" => settings.landing_marker = value.parse().ok()?,
+                "mirror_sweep" => settings.mirror_sweep = value.parse().ok()?,
+                "enable_trails" => settings.enable_trails = value.parse().ok()?,
+                "shadows" => settings.shadows = value.parse().ok()?,
+                "reduce_motion" => settings.reduce_motion = value.parse().ok()?,
+                "screen_shake" => settings.screen_shake = float()?,
+                "palette" => {
+                    settings.palette = match value {
+                        "Normal" => TilePalette::Normal,
+                        "Deuteranopia" => TilePalette::Deuteranopia,
+                        "HighContrast" => TilePalette::HighContrast,
+                        _ => return None,
+                    }
+                }
+                "theme" => {
+                    settings.theme =
+                        *TileTheme::iterator().find(|theme| format!("{theme:?}") == value)?
+                }
+                "stone_skin" => {
+                    settings.stone_skin =
+                        *StoneSkin::iterator().find(|skin| format!("{skin:?}") == value)?
+                }
+                "language" => {
+                    settings.language = match value {
+                        "English" => Language::English,
+                        "Spanish" => Language::Spanish,
+                        _ => return None,
+                    }
+                }
+                "crt_scanline_intensity" => crt.scanline_intensity = float()?,
+                "crt_scanline_count" => crt.scanline_count = float()?,
+                "crt_curvature" => crt.curvature = float()?,
+                "crt_vignette_intensity" => crt.vignette_intensity = float()?,
+                "crt_chromatic_aberration" => crt.chromatic_aberration = float()?,
+                "crt_brightness" => crt.brightness = float()?,
+                "crt_noise_intensity" => crt.noise_intensity = float()?,
+                _ => {}
+            }
+        }
+        Some(settings)
+    }
+}
+
+/// The settings as last loaded or written, and the change waiting out `SAVE_DELAY_SECS`
+#[derive(Resource)]
+struct SettingsSync {
+    saved: SavedSettings,
+    /// Set once the volumes and CRT parameters have been applied, before which there's nothing
+    /// to compare against
+    applied: bool,
+    pending: Option<(SavedSettings, Timer)>,
+}
+
+fn settings_applied(sync: Res<SettingsSync>) -> bool {
+    sync.applied
+}
+
+fn load_settings(
+    mut commands: Commands,
+    mut auto_sweep: ResMut<AutoSweep>,
+    mut show_landing_marker: ResMut<ShowLandingMarker>,
+    mut mirror_sweep: ResMut<MirrorSweep>,
+    mut enable_trails: ResMut<EnableTrails>,
+    mut show_shadows: ResMut<ShowShadows>,
+    mut reduce_motion: ResMut<ReduceMotion>,
+    mut screen_shake: ResMut<ScreenShakeIntensity>,
+    mut palette: ResMut<TilePalette>,
+    mut theme: ResMut<TileTheme>,
+    mut stone_skin: ResMut<StoneSkin>,
+    mut language: ResMut<Language>,
+) {
+    let saved = match read_saved(SETTINGS_NAME) {
+        Some(text) => SavedSettings::parse(&text).unwrap_or_else(|| {
+            warn!("Couldn't read the saved settings, using the defaults");
+            SavedSettings::default()
+        }),
+        None => SavedSettings::default(),
+    };
+    auto_sweep.0 = saved.auto_sweep;
+    show_landing_marker.0 = saved.landing_marker;
+    mirror_sweep.0 = saved.mirror_sweep;
+    enable_trails.0 = saved.enable_trails;
+    show_shadows.0 = saved.shadows;
+    reduce_motion.0 = saved.reduce_motion;
+    screen_shake.0 = saved.screen_shake;
+    *palette = saved.palette;
+    *theme = saved.theme;
+    *stone_skin = saved.stone_skin;
+    *language = saved.language;
+    commands.insert_resource(SettingsSync {
+        saved,
+        applied: false,
+        pending: None,
+    });
+}
+
+/// Volumes can only be set once the audio graph exists, which takes a few frames
+fn apply_saved_audio_and_display(
+    mut sync: ResMut<SettingsSync>,
+    mut master: Query<&mut VolumeNode, With<MainBus>>,
+    mut music: Query<&mut VolumeNode, (With<SamplerPool<MusicPool>>, Without<MainBus>)>,
+    mut sfx: Query<
+        &mut VolumeNode,
+        (
+            With<SoundEffectsBus>,
+            Without<MainBus>,
+            Without<SamplerPool<MusicPool>>,
+        ),
+    >,
+    mut crt: Query<&mut CrtSettings, With<MainCamera>>,
+) {
+    let (Ok(mut master), Ok(mut music), Ok(mut sfx), Ok(mut crt)) = (
+        master.single_mut(),
+        music.single_mut(),
+        sfx.single_mut(),
+        crt.single_mut(),
+    ) else {
+        return;
+    };
+    let saved = &sync.saved;
+    master.volume = CONVERTER.perceptual_to_volume(saved.master_volume);
+    music.volume = CONVERTER.perceptual_to_volume(saved.music_volume);
+    sfx.volume = CONVERTER.perceptual_to_volume(saved.sfx_volume);
+    *crt = CrtSettings {
+        time: crt.time,
+        ..saved.crt
+    };
+    sync.applied = true;
+}
+
+fn save_changed_settings(
+    time: Res<Time>,
+    mut sync: ResMut<SettingsSync>,
+    master: Single<&VolumeNode, With<MainBus>>,
+    music: Single<&VolumeNode, With<SamplerPool<MusicPool>>>,
+    sfx: Single<&VolumeNode, With<SoundEffectsBus>>,
+    crt: Single<(&CrtSettings, Option<&CrtAnimation>), With<MainCamera>>,
+    auto_sweep: Res<AutoSweep>,
+    show_landing_marker: Res<ShowLandingMarker>,
+    mirror_sweep: Res<MirrorSweep>,
+    // Grouped to stay within the system parameter limit
+    (enable_trails, show_shadows): (Res<EnableTrails>, Res<ShowShadows>),
+    reduce_motion: Res<ReduceMotion>,
+    screen_shake: Res<ScreenShakeIntensity>,
+    palette: Res<TilePalette>,
+    theme: Res<TileTheme>,
+    stone_skin: Res<StoneSkin>,
+    language: Res<Language>,
+) {
+    let current = SavedSettings {
+        master_volume: CONVERTER.volume_to_perceptual(master.volume),
+        music_volume: CONVERTER.volume_to_perceptual(music.volume),
+        sfx_volume: CONVERTER.volume_to_perceptual(sfx.volume),
+        auto_sweep: auto_sweep.0,
+        landing_marker: show_landing_marker.0,
+        mirror_sweep: mirror_sweep.0,
+        enable_trails: enable_trails.0,
+        shadows: show_shadows.0,
+        reduce_motion: reduce_motion.0,
+        screen_shake: screen_shake.0,
+        palette: *palette,
+        theme: *theme,
+        stone_skin: *stone_skin,
+        language: *language,
+        // What the player chose, not the power-on animation's
+        crt: CrtSettings {
+            time: 0.0,
+            ..*CrtAnimation::steady_settings(crt.0, crt.1)
+        },
+    };
+    if current == sync.saved {
+        sync.pending = None;
+        return;
+    }
+    // Each new change starts the wait over
+    if sync
+        .pending
+        .as_ref()
+        .is_none_or(|(pending, _)| *pending != current)
+    {
+        sync.pending = Some((
+            current,
+            Timer::from_seconds(SAVE_DELAY_SECS, TimerMode::Once),
+        ));
+    }
+    let Some((_, timer)) = &mut sync.pending else {
+        return;
+    };
+    if timer.tick(time.delta()).is_finished()
+        && let Some((settings, _)) = sync.pending.take()
+    {
+        write_saved(SETTINGS_NAME, &settings.to_file_string());
+        sync.saved = settings;
+    }
+}
+
+/// Reads what was last saved under `name`: `<name>.cfg` next to the game on native, a
+/// localStorage item on the web
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_saved(name: &str) -> Option<String> {
+    std::fs::read_to_string(format!("{name}.cfg")).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_saved(name: &str, text: &str) {
+    let path = format!("{name}.cfg");
+    if let Err(error) = std::fs::write(&path, text) {
+        warn!("Couldn't save {path}: {error}");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn storage_key(name: &str) -> String {
+    format!("curling_experiments_{name}")
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn read_saved(name: &str) -> Option<String> {
+    local_storage()?.get_item(&storage_key(name)).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn write_saved(name: &str, text: &str) {
+    let saved = local_storage().map(|storage| storage.set_item(&storage_key(name), text));
+    if !matches!(saved, Some(Ok(()))) {
+        warn!("Couldn't save {name} to localStorage");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved_settings_round_trip() {
+        let settings = SavedSettings {
+            master_volume: 0.7,
+            music_volume: 0.0,
+            sfx_volume: 1.3,
+            auto_sweep: true,
+            landing_marker: false,
+            mirror_sweep: true,
+            enable_trails: false,
+            shadows: false,
+            reduce_motion: true,
+            screen_shake: 0.5,
+            palette: TilePalette::HighContrast,
+            theme: TileTheme::Dusk,
+            stone_skin: StoneSkin::TeamYellow,
+            language: Language::Spanish,
+            crt: CrtSettings {
+                curvature: 0.0,
+                brightness: 1.1,
+                ..default()
+            },
+        };
+        assert_eq!(
+            SavedSettings::parse(&settings.to_file_string()),
+            Some(settings)
+        );
+    }
+
+    #[test]
+    fn test_corrupt_settings_are_rejected() {
+        assert_eq!(SavedSettings::parse("master_volume=loud"), None);
+        assert_eq!(SavedSettings::parse("palette=Sepia"), None);
+        assert_eq!(SavedSettings::parse("stone_skin=Marble"), None);
+        assert_eq!(SavedSettings::parse("theme=Noon"), None);
+        assert_eq!(SavedSettings::parse("not a setting"), None);
+        // Settings that aren't in the file yet keep their defaults
+        assert_eq!(
+            SavedSettings::parse("music_volume=0.5"),
+            Some(SavedSettings {
+                music_volume: 0.5,
+                ..default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_unknown_settings_are_skipped() {
+        // A setting renamed, removed or added by another build doesn't cost the player the rest
+        assert_eq!(
+            SavedSettings::parse("music_volume=0.5\nfuture_setting=on\nreduce_motion=true"),
+            Some(SavedSettings {
+                music_volume: 0.5,
+                reduce_motion: true,
+                ..default()
+            })
+        );
+        // But known settings with values that can't be read still count as corrupt
+        assert_eq!(
+            SavedSettings::parse("future_setting=on\nmusic_volume=loud"),
+            None
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Shakes the camera when stones hit the walls or each other, harder the faster the hit. Hits add
+//! trauma that decays away, and the shake grows with the square of it so small knocks stay subtle.
+//! Only the speed along the hit's normal counts, so a stone sliding along a wall doesn't shake it.
+//!
+//! The shake is only on the camera while it renders: it's taken off again at the start of every
+//! frame, so panning, zooming and turning the cursor into world positions all see the steady camera.
+
+use bevy::{prelude::*, transform::TransformSystems};
+
+use crate::{
+    camera::MainCamera,
+    gameplay::ReduceMotion,
+    screens::Screen,
+    stone::{StoneHitWall, StonesCollided},
+};
+
+/// Furthest the camera moves at full trauma and intensity, in screen pixels
+const MAX_SHAKE_OFFSET: f32 = 12.0;
+/// Hits at this speed or faster add full trauma
+const FULL_SHAKE_SPEED: f32 = 300.0;
+/// Hits slower than this along their normal add no trauma
+const MIN_SHAKE_SPEED: f32 = 15.0;
+/// Trauma lost per second
+const TRAUMA_DECAY: f32 = 1.5;
+/// How fast the shake wobbles
+const SHAKE_FREQUENCY: f32 = 25.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ScreenShake>()
+        .init_resource::<ScreenShakeIntensity>()
+        .add_systems(First, remove_screen_shake)
+        .add_systems(
+            PostUpdate,
+            apply_screen_shake
+                .before(TransformSystems::Propagate)
+                .run_if(in_state(Screen::Gameplay)),
+        )
+        .add_systems(OnExit(Screen::Gameplay), calm_screen_shake)
+        .add_observer(shake_on_wall_hit)
+        .add_observer(shake_on_stone_collision);
+}
+
+/// Settings scale for how hard the screen shakes, 0 turns it off
+#[derive(Resource)]
+pub struct ScreenShakeIntensity(pub f32);
+
+impl Default for ScreenShakeIntensity {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct ScreenShake {
+    /// 0 to 1, how hard the screen's shaking
+    pub trauma: f32,
+    /// Drives the noise, only runs while shaking
+    elapsed: f32,
+    /// Offset put on the camera this frame, taken off again at the start of the next
+    applied: Vec2,
+}
+
+impl ScreenShake {
+    fn add_impact(&mut self, speed: f32) {
+        self.trauma = (self.trauma + impact_trauma(speed)).min(1.0);
+    }
+}
+
+/// Trauma a hit at `normal_speed` adds
+pub fn impact_trauma(normal_speed: f32) -> f32 {
+    if normal_speed < MIN_SHAKE_SPEED {
+        return 0.0;
+    }
+    (normal_speed / FULL_SHAKE_SPEED).clamp(0.0, 1.0)
+}
+
+/// Smooth noise between -1 and 1, from sines at unrelated frequencies so it doesn't visibly repeat
+fn shake_noise(time: f32, seed: f32) -> f32 {
+    let t = time * SHAKE_FREQUENCY + seed;
+    (t.sin() * 0.5 + (t * 2.17 + 1.3).sin() * 0.3 + (t * 4.59 + 2.9).sin() * 0.2).clamp(-1.0, 1.0)
+}
+
+/// Camera offset in screen pixels for `trauma` at `time`
+pub fn shake_offset(trauma: f32, intensity: f32, time: f32) -> Vec2 {
+    let strength = MAX_SHAKE_OFFSET * intensity * trauma * trauma;
+    Vec2::new(shake_noise(time, 0.0), shake_noise(time, 17.3)) * strength
+}
+
+fn shake_on_wall_hit(
+    event: On<StoneHitWall>,
+    reduce_motion: Res<ReduceMotion>,
+    mut screen_shake: ResMut<ScreenShake>,
+) {
+    if !reduce_motion.0 {
+        screen_shake.add_impact(event.normal_speed);
+    }
+}
+
+fn shake_on_stone_collision(
+    event: On<StonesCollided>,
+    reduce_motion: Res<ReduceMotion>,
+    mut screen_shake: ResMut<ScreenShake>,
+) {
+    if !reduce_motion.0 {
+        screen_shake.add_impact(event.normal_speed);
+    }
+}
+
+/// The camera's a root entity, so its global transform can be put back along with its transform
+fn remove_screen_shake(
+    mut screen_shake: ResMut<ScreenShake>,
+    camera: Single<(&mut Transform, &mut GlobalTransform), With<MainCamera>>,
+) {
+    if screen_shake.applied == Vec2::ZERO {
+        return;
+    }
+    let (mut transform, mut global_transform) = camera.into_inner();
+    transform.translation -= screen_shake.applied.extend(0.0);
+    *global_transform = GlobalTransform::from(*transform);
+    screen_shake.applied = Vec2::ZERO;
+}
+
+pub fn apply_screen_shake(
+    time: Res<Time>,
+    reduce_motion: Res<ReduceMotion>,
+    intensity: Res<ScreenShakeIntensity>,
+    mut screen_shake: ResMut<ScreenShake>,
+    camera: Single<(&mut Transform, &Projection), With<MainCamera>>,
+) {
+    if screen_shake.trauma <= 0.0 {
+        return;
+    }
+    screen_shake.trauma = (screen_shake.trauma - TRAUMA_DECAY * time.delta_secs()).max(0.0);
+    screen_shake.elapsed += time.delta_secs();
+    if reduce_motion.0 {
+        return;
+    }
+    let (mut transform, projection) = camera.into_inner();
+    let scale = match projection {
+        Projection::Orthographic(orthographic) => orthographic.scale,
+        _ => 1.0,
+    };
+    let offset = shake_offset(screen_shake.trauma, intensity.0, screen_shake.elapsed) * scale;
+    transform.translation += offset.extend(0.0);
+    screen_shake.applied = offset;
+}
+
+fn calm_screen_shake(mut screen_shake: ResMut<ScreenShake>) {
+    screen_shake.trauma = 0.0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shake_grows_with_trauma_and_stays_bounded() {
+        assert_eq!(impact_trauma(0.0), 0.0);
+        assert_eq!(impact_trauma(FULL_SHAKE_SPEED / 2.0), 0.5);
+        assert_eq!(impact_trauma(FULL_SHAKE_SPEED * 3.0), 1.0);
+        // Grazing along a wall barely moves into it, so it doesn't shake at all
+        assert_eq!(impact_trauma(MIN_SHAKE_SPEED * 0.5), 0.0);
+        // No trauma or no intensity is no shake at all
+        assert_eq!(shake_offset(0.0, 1.0, 0.4), Vec2::ZERO);
+        assert_eq!(shake_offset(1.0, 0.0, 0.4), Vec2::ZERO);
+        for step in 0..1000 {
+            let time = step as f32 * 0.01;
+            let full = shake_offset(1.0, 1.0, time);
+            assert!(full.x.abs() <= MAX_SHAKE_OFFSET && full.y.abs() <= MAX_SHAKE_OFFSET);
+            // Half the trauma is a quarter of the shake
+            assert!((shake_offset(0.5, 1.0, time) * 4.0).distance(full) < 1e-4);
+        }
+    }
+}
@@ -3,17 +3,48 @@
 
 use bevy::prelude::*;
 
-use crate::{asset_tracking::ResourceHandles, screens::Screen};
+use crate::{asset_tracking::ResourceHandles, localization::Localized, screens::Screen};
+
+const BAR_WIDTH: f32 = 320.0;
+const BAR_HEIGHT: f32 = 14.0;
+/// How quickly the bar catches up with the loading, per second
+const BAR_EASE: f32 = 8.0;
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Screen::Loading), spawn_loading_screen);
 
     app.add_systems(
         Update,
-        enter_gameplay_screen.run_if(in_state(Screen::Loading).and(all_assets_loaded)),
+        (
+            update_loading_bar.run_if(in_state(Screen::Loading)),
+            enter_gameplay_screen.run_if(in_state(Screen::Loading).and(all_assets_loaded)),
+        ),
     );
 }
 
+/// The part of the bar that fills, `shown` being how full it's drawn
+#[derive(Component, Default)]
+struct LoadingBarFill {
+    shown: f32,
+}
+
+/// How full the bar should be with `loaded` of `total` resources loaded
+pub fn loading_fraction((loaded, total): (usize, usize)) -> f32 {
+    if total == 0 {
+        1.0
+    } else {
+        loaded as f32 / total as f32
+    }
+}
+
+/// `shown` eased `dt` seconds towards `target`, never going back down
+pub fn ease_loading_bar(shown: f32, target: f32, dt: f32) -> f32 {
+    if target <= shown {
+        return shown;
+    }
+    shown + (target - shown) * (1.0 - (-BAR_EASE * dt).exp())
+}
+
 fn spawn_loading_screen(mut commands: Commands) {
     commands.spawn((
         (
@@ -31,17 +62,51 @@ fn spawn_loading_screen(mut commands: Commands) {
             Visibility::default(),
             Pickable::IGNORE,
         ),
-        children![(
-            Text::new("Loading..."),
-            TextFont {
-                font_size: 30.0,
-                ..default()
-            },
-            TextColor(Color::WHITE),
-        ),],
+        children![
+            (
+                Localized::new("loading"),
+                TextFont {
+                    font_size: 30.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ),
+            (
+                Name::new("Loading Bar"),
+                Node {
+                    width: Val::Px(BAR_WIDTH),
+                    height: Val::Px(BAR_HEIGHT),
+                    border: UiRect::all(Val::Px(2.0)),
+                    border_radius: BorderRadius::MAX,
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                BorderColor::all(Color::WHITE),
+                children![(
+                    LoadingBarFill::default(),
+                    Node {
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::WHITE),
+                )],
+            ),
+        ],
     ));
 }
 
+fn update_loading_bar(
+    time: Res<Time>,
+    resource_handles: Res<ResourceHandles>,
+    mut fill: Single<(&mut LoadingBarFill, &mut Node)>,
+) {
+    let target = loading_fraction(resource_handles.progress());
+    let (fill, node) = &mut *fill;
+    fill.shown = ease_loading_bar(fill.shown, target, time.delta_secs());
+    node.width = Val::Percent(fill.shown * 100.0);
+}
+
 fn enter_gameplay_screen(mut next_screen: ResMut<NextState<Screen>>) {
     next_screen.set(Screen::Gameplay);
 }
@@ -49,3 +114,27 @@ fn enter_gameplay_screen(mut next_screen: ResMut<NextState<Screen>>) {
 fn all_assets_loaded(resource_handles: Res<ResourceHandles>) -> bool {
     resource_handles.is_all_done()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loading_bar_fills_smoothly_and_never_backs_up() {
+        assert_eq!(loading_fraction((0, 4)), 0.0);
+        assert_eq!(loading_fraction((3, 4)), 0.75);
+        // Nothing to load is as good as done
+        assert_eq!(loading_fraction((0, 0)), 1.0);
+
+        // Catches up over a few frames rather than jumping
+        let mut shown = 0.0;
+        let first = ease_loading_bar(shown, 0.5, 1.0 / 60.0);
+        assert!(first > 0.0 && first < 0.5, "{first}");
+        for _ in 0..120 {
+            shown = ease_loading_bar(shown, 0.5, 1.0 / 60.0);
+        }
+        assert!((shown - 0.5).abs() < 1e-3);
+        // The bar holds still rather than emptying if the target drops
+        assert_eq!(ease_loading_bar(shown, 0.25, 1.0 / 60.0), shown);
+    }
+}
@@ -1,3 +1,6 @@
+//! The title screen. Its only UI is the main menu from `menus/main.rs`, whose Settings and Credits
+//! menus both go back to it.
+
 use bevy::prelude::*;
 
 use crate::{menus::Menu, screens::Screen};
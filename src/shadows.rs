@@ -0,0 +1,250 @@
+//! Soft shadows that make stones and walls look raised off the ice: a dark ellipse under each
+//! stone, stretching a little as it speeds up, and a copy of each wall's hex cast down and to the
+//! left onto its neighbors. Walls' own fills are lifted over their shadows so only the part
+//! falling on other tiles shows.
+//!
+//! A stone's shadow fades out before it reaches the goal's hole, so the two don't darken the
+//! same spot twice. Every shadow is a draw call of its own, so there's a setting to turn them off.
+
+use bevy::prelude::*;
+
+use crate::{
+    goal_vortex::OUTER_RADIUS,
+    layers,
+    level::OnLevel,
+    screens::Screen,
+    stone::{Stone, Velocity},
+    tile::{IsGoal, SweptTileType, TILE_FILL_Z, TileAssets, TileFill, TileType},
+};
+
+/// How far shadows fall from what casts them, down and to the left
+const SHADOW_OFFSET: Vec2 = Vec2::new(-4.0, -5.0);
+const SHADOW_COLOR: Color = Color::srgba(0.0, 0.0, 0.05, 0.3);
+/// The stone's shadow is squashed into an ellipse this much wider than it is tall
+const STONE_SHADOW_SIZE: Vec2 = Vec2::new(1.05, 0.85);
+/// How much bigger the shadow of a stone going `FAST_SHADOW_SPEED` or faster gets
+const FAST_SHADOW_GROWTH: f32 = 0.12;
+const FAST_SHADOW_SPEED: f32 = 600.0;
+/// Just under the stone, over the trails
+const STONE_SHADOW_Z: f32 = layers::STONE - 0.05;
+/// Over the fill of the wall's neighbors, under the wall's own raised fill
+const WALL_SHADOW_Z: f32 = TILE_FILL_Z + 0.05;
+const RAISED_FILL_Z: f32 = TILE_FILL_Z + 0.1;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ShowShadows>()
+        .init_resource::<ShadowAssets>()
+        .add_systems(
+            Update,
+            (
+                add_stone_shadows,
+                add_wall_shadows,
+                update_stone_shadows,
+                show_wall_shadows,
+            )
+                .chain()
+                .run_if(in_state(Screen::Gameplay)),
+        );
+}
+
+/// Settings toggle for the shadows under stones and walls
+#[derive(Resource)]
+pub struct ShowShadows(pub bool);
+
+impl Default for ShowShadows {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+#[derive(Resource)]
+struct ShadowAssets {
+    /// Unit sized, scaled to each stone
+    circle_mesh: Handle<Mesh>,
+    /// Shared by the walls, stones have their own so they can fade out on their own
+    wall_material: Handle<ColorMaterial>,
+}
+
+impl FromWorld for ShadowAssets {
+    fn from_world(world: &mut World) -> Self {
+        let circle_mesh = world.resource_mut::<Assets<Mesh>>().add(Circle::new(1.0));
+        let wall_material = world
+            .resource_mut::<Assets<ColorMaterial>>()
+            .add(SHADOW_COLOR);
+        Self {
+            circle_mesh,
+            wall_material,
+        }
+    }
+}
+
+#[derive(Component)]
+struct StoneShadow;
+
+#[derive(Component)]
+struct WallShadow;
+
+/// How much bigger a stone's shadow is at `speed`
+pub fn stone_shadow_scale(speed: f32) -> f32 {
+    1.0 + FAST_SHADOW_GROWTH * (speed / FAST_SHADOW_SPEED).clamp(0.0, 1.0)
+}
+
+/// How dark a shadow of `radius` is `distance` from the goal's center, fading out as it nears the
+/// hole so it's gone before it would touch it
+pub fn stone_shadow_strength(distance: f32, radius: f32, hole_radius: f32) -> f32 {
+    ((distance - hole_radius - radius) / radius).clamp(0.0, 1.0)
+}
+
+fn shadow_visibility(show_shadows: &ShowShadows) -> Visibility {
+    if show_shadows.0 {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
+
+fn add_stone_shadows(
+    mut commands: Commands,
+    shadow_assets: Res<ShadowAssets>,
+    show_shadows: Res<ShowShadows>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    stones: Query<Entity, Added<Stone>>,
+) {
+    for stone in &stones {
+        commands.entity(stone).with_child((
+            Name::new("Stone Shadow"),
+            StoneShadow,
+            Mesh2d(shadow_assets.circle_mesh.clone()),
+            MeshMaterial2d(materials.add(SHADOW_COLOR)),
+            Transform::from_translation(SHADOW_OFFSET.extend(layers::child_z(
+                STONE_SHADOW_Z,
+                layers::STONE,
+                1.0,
+            ))),
+            shadow_visibility(&show_shadows),
+            Pickable::IGNORE,
+        ));
+    }
+}
+
+/// Gives tiles swept to walls a shadow and lifts their fill over it, and undoes both for tiles
+/// swept to anything else
+fn add_wall_shadows(
+    mut commands: Commands,
+    tile_assets: Res<TileAssets>,
+    shadow_assets: Res<ShadowAssets>,
+    show_shadows: Res<ShowShadows>,
+    tiles: Query<(Entity, &Transform, Ref<SweptTileType>, &Children)>,
+    shadows: Query<(), With<WallShadow>>,
+    mut fills: Query<&mut Transform, (With<TileFill>, Without<SweptTileType>)>,
+) {
+    for (tile, tile_transform, swept_tile_type, children) in &tiles {
+        if !swept_tile_type.is_changed() {
+            continue;
+        }
+        let is_wall = swept_tile_type.0 == TileType::Wall;
+        let z = |z: f32| layers::child_z(z, layers::BOARD, tile_transform.scale.z);
+        let mut fills = fills.iter_many_mut(children);
+        while let Some(mut fill) = fills.fetch_next() {
+            fill.translation.z = z(if is_wall { RAISED_FILL_Z } else { TILE_FILL_Z });
+        }
+        let existing = children.iter().find(|child| shadows.contains(*child));
+        match (is_wall, existing) {
+            (true, None) => {
+                // The offset's in world space, and the tile's turned and scaled
+                let offset = tile_transform.rotation.inverse() * SHADOW_OFFSET.extend(0.0)
+                    / tile_transform.scale;
+                commands.entity(tile).with_child((
+                    Name::new("Wall Shadow"),
+                    WallShadow,
+                    Mesh2d(tile_assets.hex_mesh.clone()),
+                    MeshMaterial2d(shadow_assets.wall_material.clone()),
+                    Transform::from_translation(offset.with_z(z(WALL_SHADOW_Z))),
+                    shadow_visibility(&show_shadows),
+                    Pickable::IGNORE,
+                ));
+            }
+            (false, Some(shadow)) => commands.entity(shadow).despawn(),
+            _ => {}
+        }
+    }
+}
+
+/// Shadows are children of their stones, so they move with them and only need sizing and fading
+fn update_stone_shadows(
+    show_shadows: Res<ShowShadows>,
+    on_level: Res<OnLevel>,
+    goal: Query<&Transform, (With<IsGoal>, Without<StoneShadow>)>,
+    stones: Query<(&Stone, &Transform, &Velocity), Without<StoneShadow>>,
+    mut shadows: Query<
+        (
+            &ChildOf,
+            &MeshMaterial2d<ColorMaterial>,
+            &mut Transform,
+            &mut Visibility,
+        ),
+        With<StoneShadow>,
+    >,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let hole_radius = on_level.0.hex_radius * OUTER_RADIUS;
+    let goal = goal.iter().next().map(|goal| goal.translation.truncate());
+    for (child_of, material, mut transform, mut visibility) in &mut shadows {
+        let Ok((stone, stone_transform, velocity)) = stones.get(child_of.parent()) else {
+            continue;
+        };
+        visibility.set_if_neq(shadow_visibility(&show_shadows));
+        if !show_shadows.0 {
+            continue;
+        }
+        let radius = stone.radius * stone_shadow_scale(velocity.0.length());
+        transform.scale = (STONE_SHADOW_SIZE * radius).extend(1.0);
+        let strength = goal.map_or(1.0, |goal| {
+            let shadow = stone_transform.translation.truncate() + SHADOW_OFFSET;
+            stone_shadow_strength(shadow.distance(goal), radius, hole_radius)
+        });
+        let color = SHADOW_COLOR.with_alpha(SHADOW_COLOR.alpha() * strength);
+        // Looked at first, since fetching it mutably counts as changing it
+        if materials.get(&material.0).is_some_and(|m| m.color != color)
+            && let Some(material) = materials.get_mut(&material.0)
+        {
+            material.color = color;
+        }
+    }
+}
+
+fn show_wall_shadows(
+    show_shadows: Res<ShowShadows>,
+    mut shadows: Query<&mut Visibility, With<WallShadow>>,
+) {
+    if !show_shadows.is_changed() {
+        return;
+    }
+    for mut visibility in &mut shadows {
+        visibility.set_if_neq(shadow_visibility(&show_shadows));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stone_shadow_grows_with_speed_and_fades_near_the_hole() {
+        assert_eq!(stone_shadow_scale(0.0), 1.0);
+        assert!(stone_shadow_scale(300.0) > 1.0);
+        // Only ever slightly bigger, however fast
+        assert_eq!(stone_shadow_scale(5000.0), 1.0 + FAST_SHADOW_GROWTH);
+
+        let (radius, hole_radius) = (12.0, 22.0);
+        assert_eq!(stone_shadow_strength(200.0, radius, hole_radius), 1.0);
+        // Gone by the time its edge would reach the hole, and over it
+        assert_eq!(
+            stone_shadow_strength(hole_radius + radius, radius, hole_radius),
+            0.0
+        );
+        assert_eq!(stone_shadow_strength(0.0, radius, hole_radius), 0.0);
+        let fading = stone_shadow_strength(hole_radius + radius * 1.5, radius, hole_radius);
+        assert!(fading > 0.0 && fading < 1.0, "{fading}");
+    }
+}
@@ -0,0 +1,593 @@
+//! Headless stone physics. Mirrors the FixedUpdate chain in `gameplay` without touching the ECS,
+//! so a level can be simulated outside of gameplay: trajectory previews, the title screen demo, etc.
+
+use bevy::prelude::*;
+
+use crate::{
+    hex_grid::{HexCoordinate, HexGrid, hex_to_world},
+    level::Level,
+    replay::Replay,
+    stone::{Velocity, has_stopped, resolve_collision},
+    tile::{Stroke, TileDragging, TileEffect, compute_tile_effects},
+};
+
+/// Physics steps per second. Higher rates resolve fast stones hitting walls and each other more
+/// accurately, at the cost of running the physics, and the trajectory preview stepping the same way,
+/// more often. Tile effects scale with the step length so other rates play out nearly the same,
+/// but replays count time in steps and only play back right at the rate they were recorded at.
+pub const PHYSICS_HZ: f64 = 64.0;
+
+/// Length of one physics step at `PHYSICS_HZ`
+pub const PHYSICS_DT: f32 = (1.0 / PHYSICS_HZ) as f32;
+
+/// Most of a tile's radius a stone moves between tile effects, see `tile_effects_over_step`
+const MAX_SUBSTEP_TRAVEL: f32 = 0.5;
+/// Cap on substeps in one physics step, so an absurdly fast stone can't stall the frame
+const MAX_SUBSTEPS: u32 = 8;
+
+/// The level tunables the physics step reads.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhysicsParams {
+    pub drag_coefficient: f32,
+    pub slow_down_factor: f32,
+    pub rotation_factor: f32,
+    pub speed_up_factor: f32,
+    pub speed_up_arrow_radius: f32,
+    pub restitution: f32,
+    pub wall_friction: f32,
+    pub snap_distance: f32,
+    pub snap_velocity: f32,
+    pub stop_velocity: f32,
+    pub overlap_samples: u32,
+}
+
+impl PhysicsParams {
+    pub fn from_level(level: &Level) -> Self {
+        Self {
+            drag_coefficient: level.drag_coefficient,
+            slow_down_factor: level.slow_down_factor,
+            rotation_factor: level.rotation_factor,
+            speed_up_factor: level.speed_up_factor,
+            speed_up_arrow_radius: level.speed_up_arrow_radius,
+            restitution: level.restitution,
+            wall_friction: level.wall_friction,
+            snap_distance: level.snap_distance,
+            snap_velocity: level.snap_velocity,
+            stop_velocity: level.stop_velocity,
+            overlap_samples: level.overlap_samples,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SimStone {
+    pub position: Vec2,
+    pub velocity: Velocity,
+    pub radius: f32,
+    pub reached_goal: bool,
+}
+
+/// Runs one physics step over `stones`, in the same order as the FixedUpdate chain:
+/// stone collisions, then movement (snapping into `goal` if given), then tile effects.
+pub fn step_stones(
+    stones: &mut [SimStone],
+    tiles: &[(Vec2, &TileDragging, f32)],
+    hex_grid: &HexGrid,
+    params: &PhysicsParams,
+    goal: Option<Vec2>,
+    dt: f32,
+) {
+    // Step 1: Apply stone collisions (matches apply_stone_collision)
+    for j in 0..stones.len() {
+        for k in (j + 1)..stones.len() {
+            if let Some((new_vel1, new_vel2)) = resolve_collision(
+                stones[j].position,
+                &stones[j].velocity,
+                stones[j].radius,
+                stones[k].position,
+                &stones[k].velocity,
+                stones[k].radius,
+                params.restitution,
+            ) {
+                stones[j].velocity = new_vel1;
+                stones[k].velocity = new_vel2;
+            }
+        }
+    }
+
+    // Step 2: Move positions (matches update_stone_position)
+    for stone in stones.iter_mut() {
+        stone.position += stone.velocity.0 * dt;
+        if let Some(goal) = goal
+            && !stone.reached_goal
+            && stone.position.distance(goal) < params.snap_distance
+            && stone.velocity.0.length() < params.snap_velocity
+        {
+            stone.position = goal;
+            stone.velocity = Velocity(Vec2::ZERO);
+            stone.reached_goal = true;
+        }
+    }
+
+    // Step 3: Update velocities based on new positions (matches apply_tile_velocity_effects)
+    for stone in stones.iter_mut() {
+        let (position, effect) = tile_effects_over_step(
+            stone.position,
+            &stone.velocity,
+            stone.radius,
+            tiles,
+            hex_grid,
+            params,
+            dt,
+        );
+        stone.position = position;
+        stone.velocity = effect.velocity;
+    }
+}
+
+/// How many pieces a move of `speed * dt` is split into so no piece goes further than
+/// `MAX_SUBSTEP_TRAVEL` of a tile
+pub fn substeps(speed: f32, dt: f32, hex_radius: f32) -> u32 {
+    let pieces = (speed * dt / (hex_radius * MAX_SUBSTEP_TRAVEL)).ceil();
+    (pieces as u32).clamp(1, MAX_SUBSTEPS)
+}
+
+/// Tile effects for a stone that's just moved with `velocity` for `dt`, ending up at `end`.
+///
+/// A fast stone can move further than a tile in one step and skip right over it, so it's walked
+/// back and moved through the step again in `substeps`, with the tiles acting after each one.
+/// Returns where the stone ends up, and the effects over the whole step.
+pub fn tile_effects_over_step(
+    end: Vec2,
+    velocity: &Velocity,
+    radius: f32,
+    tiles: &[(Vec2, &TileDragging, f32)],
+    hex_grid: &HexGrid,
+    params: &PhysicsParams,
+    dt: f32,
+) -> (Vec2, TileEffect) {
+    let tile_effects = |position: Vec2, velocity: &Velocity, dt: f32| {
+        compute_tile_effects(position, velocity, radius, tiles, hex_grid, params, dt)
+    };
+    let substeps = substeps(velocity.0.length(), dt, hex_grid.hex_radius);
+    if substeps == 1 {
+        return (end, tile_effects(end, velocity, dt));
+    }
+
+    let sub_dt = dt / substeps as f32;
+    let mut position = end - velocity.0 * dt;
+    let mut effect = TileEffect {
+        velocity: velocity.clone(),
+        did_hit_wall: false,
+        wall_normal: Vec2::ZERO,
+    };
+    for _ in 0..substeps {
+        position += effect.velocity.0 * sub_dt;
+        let step = tile_effects(position, &effect.velocity, sub_dt);
+        effect = TileEffect {
+            velocity: step.velocity,
+            did_hit_wall: effect.did_hit_wall || step.did_hit_wall,
+            wall_normal: if step.did_hit_wall {
+                step.wall_normal
+            } else {
+                effect.wall_normal
+            },
+        };
+    }
+    (position, effect)
+}
+
+pub struct SimTile {
+    pub coordinate: HexCoordinate,
+    pub position: Vec2,
+    /// Radius scale, from the level's tile scale overrides
+    pub scale: f32,
+    pub dragging: TileDragging,
+}
+
+/// A whole level's worth of tiles and stones, stepped without the ECS.
+pub struct HeadlessSim {
+    pub params: PhysicsParams,
+    pub grid: HexGrid,
+    pub tiles: Vec<SimTile>,
+    pub stones: Vec<SimStone>,
+    pub goal: Vec2,
+    /// Number of physics steps run, comparable to `ReplayClock`
+    pub tick: u32,
+}
+
+impl HeadlessSim {
+    pub fn new(level: &Level) -> Self {
+        let grid = HexGrid::new(level);
+        let mut tiles: Vec<_> = level
+            .grid
+            .iter()
+            .map(|(coordinate, tile_type)| SimTile {
+                coordinate: coordinate.clone(),
+                position: hex_to_world(coordinate, &grid),
+                scale: level.tile_scale(coordinate),
+                dragging: TileDragging::new(tile_type, level.min_sweep_distance),
+            })
+            .collect();
+        // Same order spawn_hex_grid spawns them in, so effects are summed in a stable order
+        tiles.sort_by_key(|tile| (tile.coordinate.q, tile.coordinate.r));
+
+        let stones = level
+            .stone_configs
+            .iter()
+            .map(|stone_config| SimStone {
+                position: stone_config.start_position(level, &grid),
+                velocity: Velocity(stone_config.initial_velocity()),
+                radius: level.stone_radius,
+                reached_goal: false,
+            })
+            .collect();
+
+        Self {
+            params: PhysicsParams::from_level(level),
+            goal: hex_to_world(&level.goal_coordinate, &grid),
+            grid,
+            tiles,
+            stones,
+            tick: 0,
+        }
+    }
+
+    pub fn apply_stroke(&mut self, coordinate: &HexCoordinate, stroke: &Stroke, distance: f32) {
+        if let Some(tile) = self
+            .tiles
+            .iter_mut()
+            .find(|tile| &tile.coordinate == coordinate)
+        {
+            stroke.apply(&mut tile.dragging, distance);
+        }
+    }
+
+    pub fn step(&mut self, dt: f32) {
+        let tile_data: Vec<_> = self
+            .tiles
+            .iter()
+            .map(|tile| (tile.position, &tile.dragging, tile.scale))
+            .collect();
+        step_stones(
+            &mut self.stones,
+            &tile_data,
+            &self.grid,
+            &self.params,
+            Some(self.goal),
+            dt,
+        );
+        self.tick += 1;
+    }
+
+    pub fn reached_goal(&self) -> bool {
+        self.stones.iter().any(|stone| stone.reached_goal)
+    }
+
+    /// True once every stone has either reached the goal or come to a stop.
+    pub fn is_settled(&self) -> bool {
+        self.stones.iter().all(|stone| {
+            stone.reached_goal || has_stopped(&stone.velocity, self.params.stop_velocity)
+        })
+    }
+
+    /// Applies the strokes in `replay` that are due, then steps. Strokes land before the physics
+    /// step, the same way replay playback does in gameplay. `next_action` tracks progress.
+    pub fn step_replay(&mut self, replay: &Replay, next_action: &mut usize, dt: f32) {
+        while let Some(action) = replay
+            .actions
+            .get(*next_action)
+            .filter(|action| action.tick <= self.tick)
+        {
+            self.apply_stroke(&action.coordinate, &action.stroke, action.distance);
+            *next_action += 1;
+        }
+        self.step(dt);
+    }
+
+    /// Plays `replay` until the sim settles or `max_ticks` have run.
+    /// Returns whether a stone reached the goal.
+    #[cfg(test)]
+    pub fn run_replay(&mut self, replay: &Replay, dt: f32, max_ticks: u32) -> bool {
+        let mut next_action = 0;
+        while self.tick < max_ticks && !self.is_settled() {
+            self.step_replay(replay, &mut next_action, dt);
+        }
+        self.reached_goal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use crate::{
+        diagnostics_overlay::PhysicsTimings,
+        gameplay::{lands_in_goal, simulate_trajectories},
+        layers,
+        level::{CurrentLevel, OnLevel, get_level},
+        stone::{
+            Stone, StoneSkin, apply_stone_collision, apply_tile_velocity_effects,
+            update_stone_position,
+        },
+        tile::{IsGoal, ShowWallFlash, TileType, sweep},
+    };
+
+    /// Steps `sim`'s stones through the real FixedUpdate chain in a bare world, returning each
+    /// stone's position and velocity at the start and after every step
+    fn live_path(level: &Level, sim: &HeadlessSim, steps: usize) -> Vec<Vec<(Vec2, Vec2)>> {
+        let mut world = World::new();
+        world.insert_resource(OnLevel(level.clone()));
+        world.init_resource::<Time<Fixed>>();
+        world.init_resource::<PhysicsTimings>();
+        world.init_resource::<ShowWallFlash>();
+        world.spawn(sim.grid.clone());
+        world.spawn((
+            IsGoal,
+            Transform::from_translation(sim.goal.extend(layers::BOARD)),
+        ));
+        // In the order HeadlessSim keeps them, so effects are summed in the same order
+        for tile in &sim.tiles {
+            world.spawn((
+                Transform::from_translation(tile.position.extend(layers::BOARD))
+                    .with_scale(Vec3::splat(tile.scale)),
+                tile.dragging.clone(),
+            ));
+        }
+        for stone in &sim.stones {
+            world.spawn((
+                Stone {
+                    radius: stone.radius,
+                    skin: StoneSkin::Classic,
+                    trail_accum: 0.0,
+                    ember_seed: 1,
+                },
+                stone.velocity.clone(),
+                Transform::from_translation(stone.position.extend(layers::STONE)),
+            ));
+        }
+        let mut schedule = Schedule::default();
+        schedule.add_systems(
+            (
+                apply_stone_collision,
+                update_stone_position,
+                apply_tile_velocity_effects,
+            )
+                .chain(),
+        );
+
+        let mut stones = world.query::<(&Velocity, &Transform)>();
+        let mut path = Vec::with_capacity(steps + 1);
+        for step in 0..=steps {
+            if step > 0 {
+                world
+                    .resource_mut::<Time<Fixed>>()
+                    .advance_by(Duration::from_secs_f32(PHYSICS_DT));
+                schedule.run(&mut world);
+            }
+            path.push(
+                stones
+                    .iter(&world)
+                    .map(|(velocity, transform)| (transform.translation.truncate(), velocity.0))
+                    .collect(),
+            );
+        }
+        path
+    }
+
+    /// Runs `sim` live and through the preview's stepping side by side, failing at the first step
+    /// they part ways
+    fn assert_live_matches_preview(level: &Level, sim: &HeadlessSim, label: &str) {
+        const TOLERANCE: f32 = 1e-3;
+        const STEPS: usize = 2000;
+        let live = live_path(level, sim, STEPS);
+
+        // step_stones is what the preview steps with, so the two should agree step for step
+        let tile_data: Vec<_> = sim
+            .tiles
+            .iter()
+            .map(|tile| (tile.position, &tile.dragging, tile.scale))
+            .collect();
+        let mut stones = sim.stones.clone();
+        for (step, live_stones) in live.iter().enumerate() {
+            if step > 0 {
+                step_stones(
+                    &mut stones,
+                    &tile_data,
+                    &sim.grid,
+                    &sim.params,
+                    Some(sim.goal),
+                    PHYSICS_DT,
+                );
+            }
+            for (index, (stone, (position, velocity))) in stones.iter().zip(live_stones).enumerate()
+            {
+                assert!(
+                    stone.position.distance(*position) < TOLERANCE
+                        && stone.velocity.0.distance(*velocity) < TOLERANCE,
+                    "{label} stone {index} diverged at step {step}: live {position} \
+                     {velocity}, predicted {} {}",
+                    stone.position,
+                    stone.velocity.0,
+                );
+            }
+        }
+
+        // And the preview itself ends where the live stones do once they've slowed to a stop,
+        // unless the live stone snapped into the goal, which the preview doesn't do
+        if stones.iter().any(|stone| stone.reached_goal) {
+            return;
+        }
+        let trajectories = simulate_trajectories(
+            sim.stones.clone(),
+            &tile_data,
+            &sim.grid,
+            &sim.params,
+            PHYSICS_DT,
+        );
+        let Some(stopped) = live.iter().find(|live_stones| {
+            live_stones
+                .iter()
+                .all(|(_, velocity)| has_stopped(&Velocity(*velocity), level.stop_velocity))
+        }) else {
+            return;
+        };
+        for (trajectory, (position, _)) in trajectories.iter().zip(stopped) {
+            assert!(
+                trajectory.rest_position.distance(*position) < TOLERANCE,
+                "{label} preview rests at {}, live at {position}",
+                trajectory.rest_position,
+            );
+        }
+    }
+
+    #[test]
+    fn test_live_physics_matches_the_preview() {
+        for current_level in CurrentLevel::iterator() {
+            let mut level = get_level(*current_level);
+            // Kept apart from every other tunable, so passing the wrong one shows up
+            level.speed_up_factor += 50.0;
+            let mut sim = HeadlessSim::new(&level);
+            assert_live_matches_preview(&level, &sim, &format!("Unswept {current_level:?}"));
+            // With nothing slowing them, stones go on to the arrows, the walls and each other
+            for tile in &mut sim.tiles {
+                if tile.dragging.base_tile_type == TileType::SlowDown {
+                    sweep(
+                        &mut tile.dragging,
+                        &TileType::MaintainSpeed,
+                        level.min_sweep_distance,
+                    );
+                }
+            }
+            assert_live_matches_preview(&level, &sim, &format!("Swept {current_level:?}"));
+        }
+    }
+
+    #[test]
+    fn test_unswept_level_stops_short_of_goal() {
+        // Level1's SlowDown corridor stops the stone well before the goal without sweeping
+        let mut sim = HeadlessSim::new(&get_level(CurrentLevel::Level1));
+        let replay = Replay::new(CurrentLevel::Level1);
+        assert!(!sim.run_replay(&replay, 1.0 / 64.0, 2000));
+        assert!(sim.is_settled());
+    }
+
+    #[test]
+    fn test_step_stones_moves_before_applying_effects() {
+        // With no tiles underneath the stone only moves, so one step covers velocity * dt
+        let level = get_level(CurrentLevel::Level1);
+        let grid = HexGrid::new(&level);
+        let mut stones = [SimStone {
+            position: Vec2::ZERO,
+            velocity: Velocity(Vec2::new(64.0, 0.0)),
+            radius: 15.0,
+            reached_goal: false,
+        }];
+        step_stones(
+            &mut stones,
+            &[],
+            &grid,
+            &PhysicsParams::from_level(&level),
+            None,
+            1.0 / 64.0,
+        );
+        assert_eq!(stones[0].position, Vec2::new(1.0, 0.0));
+        assert_eq!(stones[0].velocity.0, Vec2::new(64.0, 0.0));
+    }
+
+    #[test]
+    fn test_fast_stone_curls_on_a_turn_tile_it_crosses() {
+        let level = get_level(CurrentLevel::Level1);
+        let grid = HexGrid::new(&level);
+        let params = PhysicsParams::from_level(&level);
+        let turn = TileDragging::new(&TileType::TurnClockwise, level.min_sweep_distance);
+        let tiles = [(Vec2::ZERO, &turn, 1.0)];
+        // From well clear of one side of the tile to well clear of the other in a single step
+        let clear = grid.hex_radius + level.stone_radius * 2.0;
+        let velocity = Velocity(Vec2::new(2.0 * clear / PHYSICS_DT, 0.0));
+        let mut stones = [SimStone {
+            position: Vec2::new(-clear, 0.0),
+            velocity: velocity.clone(),
+            radius: level.stone_radius,
+            reached_goal: false,
+        }];
+        assert!(substeps(velocity.0.length(), PHYSICS_DT, grid.hex_radius) > 1);
+        step_stones(&mut stones, &tiles, &grid, &params, None, PHYSICS_DT);
+        // Just the end of the step is off the tile, so without substeps it'd go straight on
+        let unstepped = compute_tile_effects(
+            Vec2::new(clear, 0.0),
+            &velocity,
+            level.stone_radius,
+            &tiles,
+            &grid,
+            &params,
+            PHYSICS_DT,
+        );
+        assert_eq!(unstepped.velocity.0.y, 0.0);
+        // Clockwise turns a stone heading right downwards
+        assert!(stones[0].velocity.0.y < 0.0, "{:?}", stones[0].velocity);
+        // Slow stones aren't split up at all
+        assert_eq!(substeps(100.0, PHYSICS_DT, grid.hex_radius), 1);
+    }
+
+    #[test]
+    fn test_rest_position_converges_across_rates() {
+        let level = get_level(CurrentLevel::Level1);
+        let sim = HeadlessSim::new(&level);
+        let tile_data: Vec<_> = sim
+            .tiles
+            .iter()
+            .map(|tile| (tile.position, &tile.dragging, tile.scale))
+            .collect();
+        let rest_position = |hz: f32| {
+            simulate_trajectories(
+                sim.stones.clone(),
+                &tile_data,
+                &sim.grid,
+                &sim.params,
+                1.0 / hz,
+            )[0]
+            .rest_position
+        };
+        let (at_64, at_128, at_256) = (
+            rest_position(64.0),
+            rest_position(128.0),
+            rest_position(256.0),
+        );
+        // Doubling the rate moves the stone's resting spot less each time, towards where it'd stop
+        // with infinitely small steps
+        assert!(at_64.distance(at_128) < 1.0, "{at_64} {at_128}");
+        assert!(at_128.distance(at_256) < at_64.distance(at_128));
+    }
+
+    #[test]
+    fn test_trajectory_rests_short_of_goal_without_sweeping() {
+        // The predicted resting spot is the end of the line, and unswept Level1 falls short
+        let level = get_level(CurrentLevel::Level1);
+        let sim = HeadlessSim::new(&level);
+        let tile_data: Vec<_> = sim
+            .tiles
+            .iter()
+            .map(|tile| (tile.position, &tile.dragging, tile.scale))
+            .collect();
+        let trajectories = simulate_trajectories(
+            sim.stones.clone(),
+            &tile_data,
+            &sim.grid,
+            &sim.params,
+            1.0 / 64.0,
+        );
+        assert_eq!(trajectories.len(), 1);
+        let trajectory = &trajectories[0];
+        assert_eq!(trajectory.points.last(), Some(&trajectory.rest_position));
+        assert_ne!(trajectory.rest_position, sim.stones[0].position);
+        assert!(!lands_in_goal(
+            trajectory.rest_position,
+            sim.goal,
+            level.snap_distance
+        ));
+        // Resting right on the goal counts
+        assert!(lands_in_goal(sim.goal, sim.goal, level.snap_distance));
+    }
+}
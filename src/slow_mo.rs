@@ -0,0 +1,191 @@
+//! Slows the game down for the last stone's final creep towards the goal, with the screen's edges
+//! darkening while it lasts. It scales virtual time, so physics steps, the countdown, trails and
+//! every other timer slow down together and play out exactly as they would at full speed.
+//!
+//! Reduce motion turns it off, and pausing, restarting or leaving the level puts time back to
+//! normal straight away.
+
+use bevy::prelude::*;
+
+use crate::{
+    Pause,
+    gameplay::{GameState, LevelStart, ReduceMotion, goal_distance_squared},
+    level::OnLevel,
+    screens::Screen,
+    stone::{ReachedGoal, Stone, Velocity, has_stopped},
+    tile::IsGoal,
+};
+
+/// How close to the goal the slow motion starts, in hexes, and how slow the stone has to be
+const SLOW_MO_HEXES: f32 = 1.5;
+const SLOW_MO_SPEED: f32 = 150.0;
+/// How fast the game runs at its slowest
+const SLOW_MO_SCALE: f32 = 0.4;
+/// How quickly the speed eases towards its target, per real second
+const SLOW_MO_EASE: f32 = 4.0;
+/// Darkness of the screen's corners at the slowest
+const VIGNETTE_ALPHA: f32 = 0.45;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SlowMo>()
+        .add_systems(OnEnter(Screen::Gameplay), spawn_slow_mo_vignette)
+        .add_systems(OnExit(Screen::Gameplay), end_slow_mo)
+        .add_systems(
+            Update,
+            (update_slow_mo, update_slow_mo_vignette)
+                .chain()
+                .run_if(in_state(Screen::Gameplay)),
+        )
+        .add_observer(end_slow_mo_on_level_start);
+}
+
+/// How fast virtual time runs, 1 normally
+#[derive(Resource, Debug)]
+pub struct SlowMo {
+    pub scale: f32,
+}
+
+impl Default for SlowMo {
+    fn default() -> Self {
+        Self { scale: 1.0 }
+    }
+}
+
+#[derive(Component)]
+struct SlowMoVignette;
+
+/// How fast the game should run with the last moving stone `distance` from the goal at `speed`
+pub fn slow_mo_target(distance: f32, speed: f32, hex_radius: f32) -> f32 {
+    // Neighboring hexes' centers are sqrt(3) radii apart
+    let range = SLOW_MO_HEXES * hex_radius * 3.0_f32.sqrt();
+    if distance < range && speed < SLOW_MO_SPEED {
+        SLOW_MO_SCALE
+    } else {
+        1.0
+    }
+}
+
+/// `scale` eased `real_dt` seconds towards `target`, landing on it once it's close
+pub fn ease_slow_mo(scale: f32, target: f32, real_dt: f32) -> f32 {
+    let eased = scale + (target - scale) * (1.0 - (-SLOW_MO_EASE * real_dt).exp());
+    if (eased - target).abs() < 0.005 {
+        target
+    } else {
+        eased
+    }
+}
+
+fn vignette(strength: f32) -> BackgroundGradient {
+    BackgroundGradient::from(RadialGradient::new(
+        UiPosition::CENTER,
+        RadialGradientShape::FarthestCorner,
+        vec![
+            ColorStop::new(Color::NONE, Val::Percent(55.0)),
+            ColorStop::new(
+                Color::BLACK.with_alpha(VIGNETTE_ALPHA * strength),
+                Val::Percent(100.0),
+            ),
+        ],
+    ))
+}
+
+fn spawn_slow_mo_vignette(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Slow Motion Vignette"),
+        SlowMoVignette,
+        DespawnOnExit(Screen::Gameplay),
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        vignette(0.0),
+        // Under the rest of the UI
+        GlobalZIndex(-1),
+        Pickable::IGNORE,
+    ));
+}
+
+/// Eases by real time, since virtual time is what's being slowed
+fn update_slow_mo(
+    real_time: Res<Time<Real>>,
+    reduce_motion: Res<ReduceMotion>,
+    pause: Res<State<Pause>>,
+    game_state: Res<State<GameState>>,
+    on_level: Res<OnLevel>,
+    stones: Query<(&Transform, &Velocity), (With<Stone>, Without<ReachedGoal>)>,
+    goal: Single<&Transform, (With<IsGoal>, Without<Stone>)>,
+    mut slow_mo: ResMut<SlowMo>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    let level = &on_level.0;
+    let scale = if reduce_motion.0 || pause.get().0 || *game_state.get() != GameState::Playing {
+        1.0
+    } else {
+        let mut moving = stones
+            .iter()
+            .filter(|(_, velocity)| !has_stopped(velocity, level.stop_velocity));
+        let target = match (moving.next(), moving.next()) {
+            (Some((transform, velocity)), None) => slow_mo_target(
+                goal_distance_squared(transform, &goal).sqrt(),
+                velocity.0.length(),
+                level.hex_radius,
+            ),
+            _ => 1.0,
+        };
+        ease_slow_mo(slow_mo.scale, target, real_time.delta_secs())
+    };
+    if scale != slow_mo.scale {
+        slow_mo.scale = scale;
+        virtual_time.set_relative_speed(scale);
+    }
+}
+
+fn update_slow_mo_vignette(
+    slow_mo: Res<SlowMo>,
+    mut vignette_gradient: Single<&mut BackgroundGradient, With<SlowMoVignette>>,
+) {
+    if slow_mo.is_changed() {
+        **vignette_gradient = vignette((1.0 - slow_mo.scale) / (1.0 - SLOW_MO_SCALE));
+    }
+}
+
+fn end_slow_mo(mut slow_mo: ResMut<SlowMo>, mut virtual_time: ResMut<Time<Virtual>>) {
+    slow_mo.scale = 1.0;
+    virtual_time.set_relative_speed(1.0);
+}
+
+fn end_slow_mo_on_level_start(
+    _event: On<LevelStart>,
+    slow_mo: ResMut<SlowMo>,
+    virtual_time: ResMut<Time<Virtual>>,
+) {
+    end_slow_mo(slow_mo, virtual_time);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slow_mo_only_for_a_slow_stone_near_the_goal() {
+        let hex_radius = 40.0;
+        assert_eq!(slow_mo_target(60.0, 80.0, hex_radius), SLOW_MO_SCALE);
+        // Too far away, or still going too fast to be a close call
+        assert_eq!(slow_mo_target(400.0, 80.0, hex_radius), 1.0);
+        assert_eq!(slow_mo_target(60.0, 300.0, hex_radius), 1.0);
+
+        // Easing in takes a moment, and lands exactly on the target rather than creeping forever
+        let mut scale = 1.0;
+        let mut frames = 0;
+        while scale != SLOW_MO_SCALE {
+            let eased = ease_slow_mo(scale, SLOW_MO_SCALE, 1.0 / 60.0);
+            assert!(eased < scale && eased >= SLOW_MO_SCALE);
+            scale = eased;
+            frames += 1;
+        }
+        assert!((10..120).contains(&frames), "{frames}");
+        assert_eq!(ease_slow_mo(1.0, 1.0, 1.0 / 60.0), 1.0);
+    }
+}
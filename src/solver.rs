@@ -0,0 +1,157 @@
+//! Searches for a set of `SlowDown` tiles to sweep into `MaintainSpeed` that gets a stone into the
+//! goal, by running candidate sweeps through the headless sim.
+
+use std::collections::HashSet;
+
+use crate::{
+    hex_grid::HexCoordinate,
+    level::Level,
+    sim::{HeadlessSim, PHYSICS_DT, PhysicsParams},
+    tile::{Stroke, TileType, tile_touched},
+};
+
+/// Fixed timestep the solver simulates with, matches the FixedUpdate rate
+const SOLVER_DT: f32 = PHYSICS_DT;
+
+/// Stones that haven't settled after this many steps are treated as stuck
+const SOLVER_MAX_TICKS: u32 = 2000;
+
+/// How a candidate sweep played out. Lower is better.
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+enum Outcome {
+    Win,
+    /// Closest any stone got to the goal
+    Miss(f32),
+}
+
+/// How a candidate sweep played out, and the tiles the stones passed over on the way.
+struct Evaluation {
+    outcome: Outcome,
+    touched: HashSet<HexCoordinate>,
+}
+
+/// Runs the level with `swept` tiles fully swept into `MaintainSpeed` before the throw.
+fn evaluate(level: &Level, params: &PhysicsParams, swept: &HashSet<HexCoordinate>) -> Evaluation {
+    let mut sim = HeadlessSim::new(level);
+    sim.params = params.clone();
+    let stroke = Stroke::Sweep(TileType::MaintainSpeed);
+    for coordinate in swept {
+        sim.apply_stroke(coordinate, &stroke, level.min_sweep_distance);
+    }
+
+    let mut closest = f32::MAX;
+    let mut positions = Vec::new();
+    while sim.tick < SOLVER_MAX_TICKS && !sim.is_settled() {
+        sim.step(SOLVER_DT);
+        for stone in &sim.stones {
+            closest = closest.min(stone.position.distance(sim.goal));
+            positions.push(stone.position);
+        }
+    }
+    let touched = sim
+        .tiles
+        .iter()
+        .filter(|tile| {
+            tile_touched(
+                &positions,
+                level.stone_radius,
+                tile.position,
+                &sim.grid,
+                tile.scale,
+                params.overlap_samples,
+            )
+        })
+        .map(|tile| tile.coordinate.clone())
+        .collect();
+    let outcome = if sim.reached_goal() {
+        Outcome::Win
+    } else {
+        Outcome::Miss(closest)
+    };
+    Evaluation { outcome, touched }
+}
+
+/// Finds a set of `SlowDown` tiles that, swept into `MaintainSpeed` before the throw, carries a
+/// stone into the goal.
+///
+/// Hill climbs from sweeping nothing: each round toggles whichever single tile brings the stones
+/// closest to the goal, and gives up once no toggle gets any closer. Not every solvable level is
+/// found this way, but the search stays at a few hundred simulations even on the biggest levels.
+/// Tiles the stones never pass over can't change anything, so they're skipped each round.
+pub fn solve(level: &Level, params: &PhysicsParams) -> Option<HashSet<HexCoordinate>> {
+    let mut candidates: Vec<_> = level
+        .grid
+        .iter()
+        .filter(|(_, tile_type)| **tile_type == TileType::SlowDown)
+        .map(|(coordinate, _)| coordinate.clone())
+        .collect();
+    // Stable order so ties always resolve to the same solution
+    candidates.sort_by_key(|coordinate| (coordinate.q, coordinate.r));
+
+    let mut swept = HashSet::new();
+    let mut best = evaluate(level, params, &swept);
+    while best.outcome != Outcome::Win {
+        let mut best_toggle = None;
+        let touched = std::mem::take(&mut best.touched);
+        for coordinate in candidates
+            .iter()
+            .filter(|coordinate| touched.contains(coordinate))
+        {
+            let mut next = swept.clone();
+            if !next.remove(coordinate) {
+                next.insert(coordinate.clone());
+            }
+            let evaluation = evaluate(level, params, &next);
+            if evaluation.outcome < best.outcome {
+                best = evaluation;
+                best_toggle = Some(next);
+                if best.outcome == Outcome::Win {
+                    break;
+                }
+            }
+        }
+        swept = best_toggle?;
+    }
+    Some(swept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::{CurrentLevel, get_level};
+
+    #[test]
+    fn test_solve_level_1() {
+        let level = get_level(CurrentLevel::Level1);
+        let params = PhysicsParams::from_level(&level);
+        let solution = solve(&level, &params).unwrap();
+
+        // Sweeping the solution has to actually win
+        assert_eq!(evaluate(&level, &params, &solution).outcome, Outcome::Win);
+        // Level1 can't be won without sweeping
+        assert!(!solution.is_empty());
+    }
+
+    #[test]
+    fn test_solve_level_4_through_its_speed_gate() {
+        let level = get_level(CurrentLevel::Level4);
+        let params = PhysicsParams::from_level(&level);
+        assert!(
+            level
+                .grid
+                .values()
+                .any(|tile_type| matches!(tile_type, TileType::SpeedGate { .. }))
+        );
+        // Sweeping the slow tile before the boost carries the stone fast enough through the gate
+        let solution = solve(&level, &params).unwrap();
+        assert_eq!(solution, HashSet::from([HexCoordinate { q: 2, r: 1 }]));
+        assert_eq!(evaluate(&level, &params, &solution).outcome, Outcome::Win);
+    }
+
+    #[test]
+    fn test_outcome_ordering() {
+        // Winning beats any miss, and closer misses beat further ones
+        assert!(Outcome::Win < Outcome::Miss(0.0));
+        assert!(Outcome::Miss(1.0) < Outcome::Miss(2.0));
+    }
+}
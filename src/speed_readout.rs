@@ -0,0 +1,130 @@
+//! A small speed readout above each stone, for tuning and for getting a feel for the drag model.
+//! It's toggled from the debug UI and fades from green to red as the stone nears the level's
+//! `stop_velocity`.
+
+use bevy::{camera::visibility::RenderLayers, prelude::*};
+
+use crate::{
+    layers,
+    level::OnLevel,
+    screens::Screen,
+    stone::{Stone, Velocity},
+};
+
+/// At or above this speed the readout is fully green
+const FAST_SPEED: f32 = 100.0;
+/// How much the speed has to change by before the text is rewritten, so it isn't laid out again
+/// every frame
+const READOUT_STEP: f32 = 1.0;
+/// Gap between the top of the stone and the readout
+const READOUT_GAP: f32 = 10.0;
+/// Over the stones, under the effects
+const READOUT_Z: f32 = layers::STONE + 1.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ShowStoneSpeed>().add_systems(
+        Update,
+        (add_speed_readouts, update_speed_readouts)
+            .chain()
+            .run_if(in_state(Screen::Gameplay)),
+    );
+}
+
+/// Whether stones show their speed, the debug UI's "Show Speed" checkbox
+#[derive(Resource, Default)]
+pub struct ShowStoneSpeed(pub bool);
+
+/// The speed the readout's text was last written for
+#[derive(Component)]
+struct SpeedReadout {
+    shown: Option<f32>,
+}
+
+/// Red at the level's `stop_velocity`, green from `FAST_SPEED` up
+pub fn speed_color(speed: f32, stop_velocity: f32) -> Color {
+    let fraction = ((speed - stop_velocity) / (FAST_SPEED - stop_velocity)).clamp(0.0, 1.0);
+    Color::srgb(1.0, 0.2, 0.2).mix(&Color::srgb(0.2, 1.0, 0.2), fraction)
+}
+
+/// Whether the speed has moved far enough from the one on display to be worth rewriting
+pub fn readout_is_stale(shown: Option<f32>, speed: f32) -> bool {
+    shown.is_none_or(|shown| (speed - shown).abs() > READOUT_STEP)
+}
+
+/// Only on the main camera's layer, the minimap's stone markers are too small to label
+fn add_speed_readouts(mut commands: Commands, stones: Query<(Entity, &Stone), Added<Stone>>) {
+    for (entity, stone) in &stones {
+        commands.entity(entity).with_child((
+            SpeedReadout { shown: None },
+            Text2d::new(""),
+            TextFont {
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(speed_color(0.0, 0.0)),
+            Transform::from_xyz(
+                0.0,
+                stone.radius + READOUT_GAP,
+                layers::child_z(READOUT_Z, layers::STONE, 1.0),
+            ),
+            RenderLayers::layer(0),
+            Visibility::Hidden,
+        ));
+    }
+}
+
+fn update_speed_readouts(
+    show_stone_speed: Res<ShowStoneSpeed>,
+    stones: Query<&Velocity, With<Stone>>,
+    mut readouts: Query<(
+        &ChildOf,
+        &mut SpeedReadout,
+        &mut Text2d,
+        &mut TextColor,
+        &mut Visibility,
+    )>,
+    on_level: Res<OnLevel>,
+) {
+    for (child_of, mut readout, mut text, mut color, mut visibility) in &mut readouts {
+        if !show_stone_speed.0 {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        }
+        visibility.set_if_neq(Visibility::Inherited);
+        let Ok(velocity) = stones.get(child_of.parent()) else {
+            continue;
+        };
+        let speed = velocity.0.length();
+        if readout_is_stale(readout.shown, speed) {
+            readout.shown = Some(speed);
+            text.0 = format!("{speed:.0}");
+            color.0 = speed_color(speed, on_level.0.stop_velocity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_speed_readout_only_updates_past_a_step() {
+        assert!(readout_is_stale(None, 0.0));
+        assert!(!readout_is_stale(Some(50.0), 50.8));
+        assert!(readout_is_stale(Some(50.0), 48.5));
+        // Red once stopped, green when fast, whatever the speed beyond that
+        let stop_velocity = 2.0;
+        assert_eq!(
+            speed_color(stop_velocity, stop_velocity),
+            speed_color(0.0, stop_velocity)
+        );
+        assert_eq!(
+            speed_color(FAST_SPEED, stop_velocity),
+            speed_color(FAST_SPEED * 3.0, stop_velocity)
+        );
+        assert_ne!(
+            speed_color(stop_velocity, stop_velocity),
+            speed_color(FAST_SPEED, stop_velocity)
+        );
+    }
+}
@@ -1,53 +1,364 @@
-use bevy::prelude::*;
+use std::slice::Iter;
 
+use bevy::{
+    asset::RenderAssetUsages,
+    ecs::{spawn::SpawnIter, system::SystemParam},
+    platform::time::Instant,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::diagnostics_overlay::PhysicsTimings;
+use crate::fire_trail::{ember_seed, rand01};
 use crate::gameplay::{LevelComplete, StoneStopped};
-use crate::hex_grid::{HexCoordinate, HexGrid, hex_to_world};
+use crate::hex_grid::{HexCoordinate, HexGrid, world_to_hex_tolerant};
+use crate::layers;
 use crate::level::OnLevel;
-use crate::tile::{IsGoal, TileDragging, compute_tile_effects};
+use crate::sim::{PhysicsParams, tile_effects_over_step};
+use crate::tile::{IsGoal, ShowWallFlash, TileDragging, TileType, WallFlash, nearly_clips_tile};
+
+/// How far the handle reaches behind the stone's center, in stone radii. It hangs a little past
+/// the edge, like a handle's gooseneck.
+const HANDLE_TAIL: f32 = 1.2;
+/// How far the handle reaches ahead of the stone's center, in stone radii
+const HANDLE_NOSE: f32 = 0.35;
+/// In stone radii
+const HANDLE_WIDTH: f32 = 0.28;
+/// Height of the handle above the stone's disc
+const HANDLE_Z: f32 = 0.1;
+/// Side of the generated granite texture, in pixels
+const GRANITE_SIZE: u32 = 64;
 
 #[derive(Component, Clone, Debug)]
 pub struct Stone {
     pub radius: f32,
+    pub skin: StoneSkin,
     pub trail_accum: f32,
+    /// Steps along with every random number the stone's fire trail takes
+    pub ember_seed: u32,
 }
 
-#[derive(Component, Clone)]
+#[derive(Component, Clone, Debug)]
 pub struct Velocity(pub Vec2);
 
+/// Which of the level's `stone_configs` a stone was spawned from
+#[derive(Component, Clone, Copy, Debug)]
+pub struct StoneIndex(pub usize);
+
 #[derive(Component, Debug)]
 pub struct ReachedGoal;
 
 #[derive(Component)]
 pub struct StoneIsStopped;
 
+/// A stone that's left every tile, warned about once until it's back on one
+#[derive(Component)]
+pub struct EscapedGrid;
+
+/// The stone's disc, a child so it can be squashed on impacts without changing the stone's radius
+#[derive(Component)]
+pub struct StoneBody;
+
+/// Handle on top of a stone's body, kept lying along the way the stone last moved
+#[derive(Component)]
+pub struct StoneHandle {
+    pub direction: Vec2,
+}
+
+/// How stones look, picked in the settings. A level's `StoneConfig` can give a stone its own skin
+/// so several stones can be told apart.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StoneSkin {
+    /// A plain disc in the level's color
+    #[default]
+    Classic,
+    Granite,
+    TeamRed,
+    TeamYellow,
+}
+
+impl StoneSkin {
+    pub fn iterator() -> Iter<'static, StoneSkin> {
+        static SKINS: [StoneSkin; 4] = [
+            StoneSkin::Classic,
+            StoneSkin::Granite,
+            StoneSkin::TeamRed,
+            StoneSkin::TeamYellow,
+        ];
+        SKINS.iter()
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            StoneSkin::Classic => StoneSkin::Granite,
+            StoneSkin::Granite => StoneSkin::TeamRed,
+            StoneSkin::TeamRed => StoneSkin::TeamYellow,
+            StoneSkin::TeamYellow => StoneSkin::Classic,
+        }
+    }
+
+    /// Localization key of the skin's name
+    pub fn label(self) -> &'static str {
+        match self {
+            StoneSkin::Classic => "skin-classic",
+            StoneSkin::Granite => "skin-granite",
+            StoneSkin::TeamRed => "skin-team-red",
+            StoneSkin::TeamYellow => "skin-team-yellow",
+        }
+    }
+
+    /// The stone's color at a glance, for icons. `level_color` is its `StoneConfig`'s.
+    pub fn color(self, level_color: Color) -> Color {
+        match self {
+            StoneSkin::Classic => level_color,
+            StoneSkin::Granite => Color::srgb(0.55, 0.55, 0.58),
+            StoneSkin::TeamRed => Color::srgb(0.8, 0.12, 0.12),
+            StoneSkin::TeamYellow => Color::srgb(0.95, 0.8, 0.1),
+        }
+    }
+
+    pub fn has_handle(self) -> bool {
+        matches!(self, StoneSkin::TeamRed | StoneSkin::TeamYellow)
+    }
+
+    /// How far behind its center a stone's trail starts, `t` being how fast it's going from 0 to
+    /// 1. A handle hangs out past the edge, so the trail starts as far behind that.
+    pub fn trail_offset(self, radius: f32, t: f32) -> f32 {
+        let back = if self.has_handle() { HANDLE_TAIL } else { 1.0 };
+        radius * (back - 0.1 + 0.9 * t)
+    }
+}
+
+/// A stone's handle lying along `direction`, as a child of a body turned by `body_rotation` so it
+/// squashes and shrinks along with the stone
+pub fn handle_transform(direction: Vec2, body_rotation: Quat, radius: f32) -> Transform {
+    let rotation = body_rotation.inverse() * Quat::from_rotation_z(direction.to_angle());
+    let center = radius * (HANDLE_NOSE - HANDLE_TAIL) / 2.0;
+    Transform {
+        translation: rotation * Vec3::new(center, 0.0, HANDLE_Z),
+        rotation,
+        scale: Vec3::new(
+            radius * (HANDLE_NOSE + HANDLE_TAIL),
+            radius * HANDLE_WIDTH,
+            1.0,
+        ),
+    }
+}
+
+/// Speckled grey granite, `size` pixels square in RGBA
+fn granite_pixels(size: u32) -> Vec<u8> {
+    let mut seed = 0x2545_f491;
+    let mut pixels = Vec::with_capacity((size * size * 4) as usize);
+    for _ in 0..size * size {
+        let fleck = rand01(&mut seed);
+        let grey = if fleck < 0.12 {
+            0.2 + rand01(&mut seed) * 0.15
+        } else if fleck > 0.92 {
+            0.85 + rand01(&mut seed) * 0.15
+        } else {
+            0.5 + (rand01(&mut seed) - 0.5) * 0.15
+        };
+        let value = (grey * 255.0) as u8;
+        pixels.extend([value, value, value.saturating_add(6), 255]);
+    }
+    pixels
+}
+
+/// What the skins are drawn with: the granite texture, one handle mesh scaled to each stone, and
+/// materials shared by every stone part that looks the same
+#[derive(Resource)]
+pub struct StoneSkinAssets {
+    granite: Handle<Image>,
+    handle_mesh: Handle<Mesh>,
+    /// Keyed by color and whether it shows the granite texture
+    materials: Vec<((Color, bool), Handle<ColorMaterial>)>,
+    /// Stone bodies, keyed by radius
+    body_meshes: Vec<(f32, Handle<Mesh>)>,
+}
+
+impl FromWorld for StoneSkinAssets {
+    fn from_world(world: &mut World) -> Self {
+        let granite = world.resource_mut::<Assets<Image>>().add(Image::new(
+            Extent3d {
+                width: GRANITE_SIZE,
+                height: GRANITE_SIZE,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            granite_pixels(GRANITE_SIZE),
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::RENDER_WORLD,
+        ));
+        let handle_mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Rectangle::new(1.0, 1.0));
+        Self::new(granite, handle_mesh)
+    }
+}
+
+impl StoneSkinAssets {
+    pub fn new(granite: Handle<Image>, handle_mesh: Handle<Mesh>) -> Self {
+        Self {
+            granite,
+            handle_mesh,
+            materials: Vec::new(),
+            body_meshes: Vec::new(),
+        }
+    }
+
+    /// The body mesh for stones of `radius`, made the first time it's asked for
+    pub fn body_mesh(&mut self, meshes: &mut Assets<Mesh>, radius: f32) -> Handle<Mesh> {
+        if let Some((_, mesh)) = self.body_meshes.iter().find(|(key, _)| *key == radius) {
+            return mesh.clone();
+        }
+        let mesh = meshes.add(Circle::new(radius));
+        self.body_meshes.push((radius, mesh.clone()));
+        mesh
+    }
+
+    fn material(
+        &mut self,
+        materials: &mut Assets<ColorMaterial>,
+        color: Color,
+        granite: bool,
+    ) -> Handle<ColorMaterial> {
+        if let Some((_, material)) = self
+            .materials
+            .iter()
+            .find(|(key, _)| *key == (color, granite))
+        {
+            return material.clone();
+        }
+        let material = materials.add(ColorMaterial {
+            color,
+            texture: granite.then(|| self.granite.clone()),
+            ..default()
+        });
+        self.materials.push(((color, granite), material.clone()));
+        material
+    }
+
+    /// Materials for the body and, if the skin has one, the handle of a stone in `skin` whose
+    /// `StoneConfig` color is `level_color`
+    pub fn stone_materials(
+        &mut self,
+        materials: &mut Assets<ColorMaterial>,
+        skin: StoneSkin,
+        level_color: Color,
+    ) -> (Handle<ColorMaterial>, Option<Handle<ColorMaterial>>) {
+        let body = match skin {
+            StoneSkin::Granite => self.material(materials, Color::WHITE, true),
+            _ => self.material(materials, skin.color(level_color), false),
+        };
+        // Handles are picked out in a lighter shade of the team's color
+        let handle = skin.has_handle().then(|| {
+            let highlight = skin.color(level_color).mix(&Color::WHITE, 0.6);
+            self.material(materials, highlight, false)
+        });
+        (body, handle)
+    }
+}
+
+/// The skin picked in the settings, and what stones are drawn with
+#[derive(SystemParam)]
+pub struct StoneSkins<'w> {
+    pub chosen: Res<'w, StoneSkin>,
+    pub assets: ResMut<'w, StoneSkinAssets>,
+}
+
 #[derive(Event)]
-pub struct StoneHitWall;
+pub struct StoneHitWall {
+    pub stone: Entity,
+    /// How fast it was going into the wall, along `normal`
+    pub normal_speed: f32,
+    /// Pointing away from the wall
+    pub normal: Vec2,
+}
+
+/// Two stones knocked into each other
+#[derive(Event)]
+pub struct StonesCollided {
+    pub stones: [Entity; 2],
+    /// How fast they were closing along `normal`, leaving out any glancing motion
+    pub normal_speed: f32,
+    /// Pointing from the first stone to the second
+    pub normal: Vec2,
+}
+
+/// Whether a stone going at `velocity` counts as stopped, against the level's `stop_velocity`.
+/// Shared by the game, the trajectory preview and the fire trail so they agree on when motion ends.
+pub fn has_stopped(velocity: &Velocity, stop_velocity: f32) -> bool {
+    velocity.0.length() <= stop_velocity
+}
 
-/// Returns a stone bundle at the given hex coordinate with the specified velocity
+/// Returns a stone bundle at `position` in the tile at `hex_coord` with the specified velocity,
+/// drawn in `skin` with `color` being its `StoneConfig`'s
 pub fn stone(
     meshes: &mut Assets<Mesh>,
     materials: &mut Assets<ColorMaterial>,
-    grid: &HexGrid,
+    skin_assets: &mut StoneSkinAssets,
+    skin: StoneSkin,
+    color: Color,
     hex_coord: &HexCoordinate,
+    position: Vec2,
     velocity: Vec2,
     radius: &f32,
 ) -> impl Bundle {
-    let black_material = materials.add(Color::BLACK);
-    let stone_mesh = meshes.add(Circle::new(*radius));
-    let stone_world_pos = hex_to_world(hex_coord, grid);
+    let stone_mesh = skin_assets.body_mesh(meshes, *radius);
+    let (body_material, handle_material) = skin_assets.stone_materials(materials, skin, color);
+    let direction = if velocity == Vec2::ZERO {
+        Vec2::X
+    } else {
+        velocity
+    };
+    let handle = handle_material.map(|material| {
+        (
+            StoneHandle { direction },
+            Mesh2d(skin_assets.handle_mesh.clone()),
+            MeshMaterial2d(material),
+            handle_transform(direction, Quat::IDENTITY, *radius),
+        )
+    });
 
     (
         Stone {
             radius: *radius,
+            skin,
             trail_accum: 0.0,
+            ember_seed: ember_seed(hex_coord),
         },
         Velocity(velocity),
-        Mesh2d(stone_mesh),
-        MeshMaterial2d(black_material),
-        Transform::from_xyz(stone_world_pos.x, stone_world_pos.y, 3.0),
+        Transform::from_translation(position.extend(layers::STONE)),
+        Visibility::default(),
+        children![(
+            StoneBody,
+            Mesh2d(stone_mesh),
+            MeshMaterial2d(body_material),
+            Children::spawn(SpawnIter(handle.into_iter())),
+        )],
     )
 }
 
+/// Keeps handles lying along the way their stone's going, and on it as the body squashes
+pub fn turn_stone_handles(
+    stones: Query<(&Stone, &Velocity, &Children)>,
+    bodies: Query<(&Transform, &Children), With<StoneBody>>,
+    mut handles: Query<(&mut StoneHandle, &mut Transform), Without<StoneBody>>,
+) {
+    for (stone, velocity, children) in &stones {
+        for (body_transform, body_children) in bodies.iter_many(children) {
+            let mut handles = handles.iter_many_mut(body_children);
+            while let Some((mut handle, mut transform)) = handles.fetch_next() {
+                if velocity.0 != Vec2::ZERO {
+                    handle.direction = velocity.0;
+                }
+                *transform =
+                    handle_transform(handle.direction, body_transform.rotation, stone.radius);
+            }
+        }
+    }
+}
+
 pub fn update_stone_position(
     mut commands: Commands,
     mut stone: Query<
@@ -90,7 +401,7 @@ pub fn update_stone_position(
                 commands.entity(stone_entity).insert(ReachedGoal);
                 commands.trigger(LevelComplete);
             }
-        } else if speed <= 2. && stopped.is_none() {
+        } else if has_stopped(&velocity, on_level.0.stop_velocity) && stopped.is_none() {
             commands.entity(stone_entity).insert(StoneIsStopped);
             commands.trigger(StoneStopped);
         }
@@ -105,6 +416,7 @@ pub fn resolve_collision(
     pos2: Vec2,
     vel2: &Velocity,
     radius2: f32,
+    restitution: f32,
 ) -> Option<(Velocity, Velocity)> {
     let distance_squared = pos1.distance_squared(pos2);
     let min_distance = radius1 + radius2;
@@ -119,6 +431,7 @@ pub fn resolve_collision(
 
     // If stones are at the exact same position, use a default direction
     let collision_normal = if collision_normal == Vec2::ZERO {
+        debug!("Stones overlapping exactly at {pos1}, pushing them apart along x");
         Vec2::X
     } else {
         collision_normal
@@ -135,9 +448,6 @@ pub fn resolve_collision(
         return None;
     }
 
-    // Coefficient of restitution (1.0 = perfectly elastic, 0.0 = perfectly inelastic)
-    let restitution = 0.85;
-
     // For equal masses: impulse = (1 + e) * v_rel_normal / 2
     let impulse_scalar = (1.0 + restitution) * velocity_along_normal / 2.0;
     let impulse = impulse_scalar * collision_normal;
@@ -148,23 +458,36 @@ pub fn resolve_collision(
     Some((new_vel1, new_vel2))
 }
 
-pub fn apply_stone_collision(mut stone_query: Query<(&Stone, &mut Velocity, &Transform)>) {
+pub fn apply_stone_collision(
+    mut commands: Commands,
+    mut stone_query: Query<(Entity, &Stone, &mut Velocity, &Transform)>,
+    on_level: Res<OnLevel>,
+) {
     let mut combinations = stone_query.iter_combinations_mut();
     while let Some(
         [
-            (stone1, mut velocity1, transform1),
-            (stone2, mut velocity2, transform2),
+            (entity1, stone1, mut velocity1, transform1),
+            (entity2, stone2, mut velocity2, transform2),
         ],
     ) = combinations.fetch_next()
     {
+        let pos1 = transform1.translation.truncate();
+        let pos2 = transform2.translation.truncate();
         if let Some((new_vel1, new_vel2)) = resolve_collision(
-            transform1.translation.truncate(),
+            pos1,
             &velocity1,
             stone1.radius,
-            transform2.translation.truncate(),
+            pos2,
             &velocity2,
             stone2.radius,
+            on_level.0.restitution,
         ) {
+            let normal = (pos2 - pos1).normalize_or(Vec2::X);
+            commands.trigger(StonesCollided {
+                stones: [entity1, entity2],
+                normal_speed: (velocity1.0 - velocity2.0).dot(normal).max(0.0),
+                normal,
+            });
             *velocity1 = new_vel1;
             *velocity2 = new_vel2;
         }
@@ -174,34 +497,175 @@ pub fn apply_stone_collision(mut stone_query: Query<(&Stone, &mut Velocity, &Tra
 /// System that modifies stone velocity based on tile types it overlaps with.
 pub fn apply_tile_velocity_effects(
     mut commands: Commands,
-    stone_query: Query<(&Stone, &mut Velocity, &Transform)>,
-    tiles: Query<(&Transform, &TileDragging), Without<Stone>>,
+    stone_query: Query<(Entity, &Stone, &mut Velocity, &mut Transform)>,
+    tiles: Query<(Entity, &Transform, &TileDragging), Without<Stone>>,
     grid: Single<&HexGrid>,
     on_level: Res<OnLevel>,
+    show_wall_flash: Res<ShowWallFlash>,
+    time: Res<Time<Fixed>>,
+    mut physics_timings: ResMut<PhysicsTimings>,
 ) {
-    for (stone, mut velocity, transform) in stone_query {
+    let started = Instant::now();
+    let params = PhysicsParams::from_level(&on_level.0);
+    for (stone_entity, stone, mut velocity, mut transform) in stone_query {
         let tile_data: Vec<_> = tiles
             .iter()
-            .map(|(transform, tile_dragging)| {
+            .map(|(_, transform, tile_dragging)| {
                 let position = transform.translation.truncate();
-                (position, tile_dragging)
+                (position, tile_dragging, transform.scale.x)
             })
             .collect();
-        let tile_effects = compute_tile_effects(
+        // Fast stones are stepped through the move again, which can leave them somewhere else
+        let (position, tile_effects) = tile_effects_over_step(
             transform.translation.truncate(),
             &velocity,
+            stone.radius,
             &tile_data,
             *grid,
-            on_level.0.drag_coefficient,
-            stone.radius,
-            on_level.0.slow_down_factor,
-            on_level.0.rotation_factor,
-            on_level.0.min_sweep_distance,
-            on_level.0.speed_up_arrow_radius,
+            &params,
+            time.delta_secs(),
         );
-        *velocity = tile_effects.velocity;
+        transform.translation = position.extend(transform.translation.z);
         if tile_effects.did_hit_wall {
-            commands.trigger(StoneHitWall);
+            commands.trigger(StoneHitWall {
+                stone: stone_entity,
+                normal_speed: (-velocity.0.dot(tile_effects.wall_normal)).max(0.0),
+                normal: tile_effects.wall_normal,
+            });
         }
+        *velocity = tile_effects.velocity;
+        if show_wall_flash.0 {
+            let stone_pos = transform.translation.truncate();
+            for (entity, tile_transform, tile_dragging) in &tiles {
+                if tile_dragging.base_tile_type == TileType::Wall
+                    && nearly_clips_tile(
+                        stone_pos,
+                        stone.radius,
+                        tile_transform.translation.truncate(),
+                        *grid,
+                        tile_transform.scale.x,
+                        on_level.0.overlap_samples,
+                    )
+                {
+                    commands.entity(entity).insert(WallFlash::default());
+                }
+            }
+        }
+    }
+    physics_timings.tile_effects = started.elapsed();
+}
+
+/// Warns when a stone leaves the level's tiles, it's likely got through a gap in the walls
+pub fn warn_escaped_stones(
+    mut commands: Commands,
+    stones: Query<(Entity, &Transform, Has<EscapedGrid>), With<Stone>>,
+    grid: Single<&HexGrid>,
+) {
+    for (entity, transform, escaped) in &stones {
+        let position = transform.translation.truncate();
+        let on_grid = world_to_hex_tolerant(position, &grid, 0.0).is_some();
+        if !on_grid && !escaped {
+            warn!("Stone {entity} escaped the level's tiles at {position}");
+            commands.entity(entity).insert(EscapedGrid);
+        } else if on_grid && escaped {
+            debug!("Stone {entity} is back on the level's tiles at {position}");
+            commands.entity(entity).remove::<EscapedGrid>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hex_grid::hex_to_world,
+        level::{CurrentLevel, get_level},
+    };
+
+    #[test]
+    fn test_escaped_stone_is_flagged_once() {
+        let level = get_level(CurrentLevel::Level1);
+        let grid = HexGrid::new(&level);
+        let on_tile = hex_to_world(level.grid.keys().next().unwrap(), &grid);
+        let mut world = World::new();
+        world.spawn(grid);
+        let stone = world
+            .spawn((
+                Stone {
+                    radius: level.stone_radius,
+                    skin: StoneSkin::Classic,
+                    trail_accum: 0.0,
+                    ember_seed: 1,
+                },
+                Transform::from_translation(on_tile.extend(layers::STONE)),
+            ))
+            .id();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(warn_escaped_stones);
+
+        schedule.run(&mut world);
+        assert!(!world.entity(stone).contains::<EscapedGrid>());
+        // Far off the level it's warned about, and stays flagged rather than warned every step
+        world
+            .entity_mut(stone)
+            .get_mut::<Transform>()
+            .unwrap()
+            .translation = Vec3::new(1e5, 1e5, layers::STONE);
+        schedule.run(&mut world);
+        assert!(world.entity(stone).contains::<EscapedGrid>());
+        schedule.run(&mut world);
+        assert!(world.entity(stone).contains::<EscapedGrid>());
+        // Back on the level clears it, so escaping again warns again
+        world
+            .entity_mut(stone)
+            .get_mut::<Transform>()
+            .unwrap()
+            .translation = on_tile.extend(layers::STONE);
+        schedule.run(&mut world);
+        assert!(!world.entity(stone).contains::<EscapedGrid>());
+    }
+
+    #[test]
+    fn test_handle_lies_along_the_throw() {
+        let radius = 20.0;
+        let direction = Vec2::new(1.0, 1.0);
+        // However the body's turned for a squash, the handle's tail points back along the throw
+        for body_angle in [0.0, 1.0, -2.5] {
+            let body_rotation = Quat::from_rotation_z(body_angle);
+            let handle = handle_transform(direction, body_rotation, radius);
+            let tail = body_rotation
+                * (handle.translation
+                    + handle.rotation * Vec3::new(-handle.scale.x / 2.0, 0.0, 0.0));
+            let expected = -direction.normalize() * radius * HANDLE_TAIL;
+            assert!(tail.truncate().distance(expected) < 1e-3, "{tail}");
+        }
+    }
+
+    #[test]
+    fn test_trail_clears_the_handle() {
+        let radius = 20.0;
+        // Only the team skins have handles, and their trail starts as far past the handle as
+        // other skins' trails start past the stone's edge
+        assert!(StoneSkin::TeamYellow.has_handle() && !StoneSkin::Granite.has_handle());
+        for skin in StoneSkin::iterator() {
+            let back = if skin.has_handle() { HANDLE_TAIL } else { 1.0 };
+            assert!((skin.trail_offset(radius, 0.5) - radius * (back + 0.35)).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_skins_cycle_through_all_four() {
+        for skin in StoneSkin::iterator() {
+            assert_eq!(skin.next().next().next().next(), *skin);
+        }
+    }
+
+    #[test]
+    fn test_granite_is_speckled_and_opaque() {
+        // Darker and lighter flecks, and opaque throughout
+        let granite = granite_pixels(GRANITE_SIZE);
+        let greys: Vec<u8> = granite.chunks(4).map(|pixel| pixel[0]).collect();
+        assert!(greys.iter().any(|grey| *grey < 100) && greys.iter().any(|grey| *grey > 200));
+        assert!(granite.chunks(4).all(|pixel| pixel[3] == 255));
     }
 }
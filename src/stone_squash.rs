@@ -0,0 +1,159 @@
+//! Squashes stones flat against whatever they hit and stretches them out sideways, easing back to
+//! round. Only the stone's disc is scaled, so the stone's physics don't change shape with it.
+//!
+//! A stone touching a wall hits it every physics step, so only hits moving into it fast enough
+//! squash it. Sliding along the wall leaves the squash from the first hit to ease out.
+
+use bevy::prelude::*;
+
+use crate::{
+    PausableSystems,
+    gameplay::ReduceMotion,
+    screens::Screen,
+    stone::{StoneBody, StoneHitWall, StonesCollided},
+};
+
+/// How long a stone takes to come back round
+const IMPACT_SECONDS: f32 = 0.15;
+/// How much of the stone's width along the hit is squashed away at first
+const MAX_SQUASH: f32 = 0.3;
+/// Hits slower than this along their normal don't squash the stone
+const MIN_SQUASH_SPEED: f32 = 15.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        animate_impacts
+            .run_if(in_state(Screen::Gameplay))
+            .in_set(PausableSystems),
+    )
+    .add_observer(squash_on_wall_hit)
+    .add_observer(squash_on_stone_collision);
+}
+
+/// A stone being squashed by a hit, replaced if it's hit again before it's round
+#[derive(Component, Debug)]
+pub struct ImpactAnim {
+    /// Along the hit, what the stone's squashed along
+    pub normal: Vec2,
+    /// `Time::elapsed_secs` when it was hit
+    pub started: f32,
+}
+
+/// The disc's scale along the hit and across it, `progress` from 0 at the hit to 1 once it's round
+/// again. The stretch makes up for the squash so the stone keeps its area.
+pub fn impact_scale(progress: f32) -> Vec2 {
+    let remaining = 1.0 - progress.clamp(0.0, 1.0);
+    let squash = 1.0 - MAX_SQUASH * remaining * remaining;
+    Vec2::new(squash, 1.0 / squash)
+}
+
+/// Whether a hit at `normal_speed` is hard enough to squash the stone
+pub fn squashes(normal_speed: f32) -> bool {
+    normal_speed >= MIN_SQUASH_SPEED
+}
+
+fn start_impact(
+    commands: &mut Commands,
+    stone: Entity,
+    normal: Vec2,
+    normal_speed: f32,
+    time: &Time,
+    reduce_motion: &ReduceMotion,
+) {
+    if reduce_motion.0 || !squashes(normal_speed) {
+        return;
+    }
+    // The stone may have been despawned by a restart since the hit
+    if let Ok(mut stone) = commands.get_entity(stone) {
+        stone.insert(ImpactAnim {
+            normal,
+            started: time.elapsed_secs(),
+        });
+    }
+}
+
+fn squash_on_wall_hit(
+    event: On<StoneHitWall>,
+    mut commands: Commands,
+    time: Res<Time>,
+    reduce_motion: Res<ReduceMotion>,
+) {
+    start_impact(
+        &mut commands,
+        event.stone,
+        event.normal,
+        event.normal_speed,
+        &time,
+        &reduce_motion,
+    );
+}
+
+fn squash_on_stone_collision(
+    event: On<StonesCollided>,
+    mut commands: Commands,
+    time: Res<Time>,
+    reduce_motion: Res<ReduceMotion>,
+) {
+    for stone in event.stones {
+        start_impact(
+            &mut commands,
+            stone,
+            event.normal,
+            event.normal_speed,
+            &time,
+            &reduce_motion,
+        );
+    }
+}
+
+fn animate_impacts(
+    mut commands: Commands,
+    time: Res<Time>,
+    stones: Query<(Entity, &ImpactAnim, &Children)>,
+    mut bodies: Query<&mut Transform, With<StoneBody>>,
+) {
+    for (entity, impact, children) in &stones {
+        let progress = (time.elapsed_secs() - impact.started) / IMPACT_SECONDS;
+        let mut bodies = bodies.iter_many_mut(children);
+        while let Some(mut transform) = bodies.fetch_next() {
+            if progress >= 1.0 {
+                *transform = Transform::IDENTITY;
+            } else {
+                transform.rotation = Quat::from_rotation_z(impact.normal.to_angle());
+                transform.scale = impact_scale(progress).extend(1.0);
+            }
+        }
+        if progress >= 1.0 {
+            commands.entity(entity).remove::<ImpactAnim>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_impact_squashes_then_settles_round() {
+        let hit = impact_scale(0.0);
+        assert!(hit.x < 1.0 && hit.y > 1.0);
+        assert_eq!(impact_scale(1.0), Vec2::ONE);
+        let mut previous = hit.x;
+        for step in 1..=10 {
+            let scale = impact_scale(step as f32 / 10.0);
+            // Eases back out without overshooting, keeping the stone's area throughout
+            assert!(scale.x >= previous && scale.x <= 1.0);
+            assert!((scale.x * scale.y - 1.0).abs() < 1e-5);
+            previous = scale.x;
+        }
+    }
+
+    #[test]
+    fn test_only_real_hits_squash() {
+        assert!(squashes(120.0));
+        // Sliding along a wall it's touching barely moves into it
+        assert!(!squashes(0.0));
+        assert!(!squashes(MIN_SQUASH_SPEED * 0.5));
+    }
+}
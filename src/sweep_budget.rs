@@ -0,0 +1,230 @@
+//! Tracks how much sweeping the player has done this attempt and, on levels with a
+//! `sweep_budget`, stops sweeps from accruing once it's used up. A HUD in the top right shows the
+//! running total and a bar of what's left.
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::{
+    PausableSystems,
+    gameplay::{GameState, LevelComplete, RestartCampaign},
+    level::{CurrentLevel, Level, OnLevel},
+    localization::Localized,
+    screens::Screen,
+};
+
+const BUDGET_BAR_WIDTH: f32 = 200.0;
+const BUDGET_BAR_COLOR: Color = Color::srgb(0.3, 0.8, 1.0);
+const BUDGET_BAR_EMPTY_COLOR: Color = Color::srgb(1.0, 0.25, 0.3);
+/// Flashes per second of the bar once the budget is used up
+const BUDGET_FLASH_RATE: f32 = 3.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SweepBudget>()
+        .init_resource::<RunStats>()
+        .add_systems(
+            OnEnter(Screen::Gameplay),
+            (spawn_sweep_hud, reset_run_stats),
+        )
+        .add_systems(OnEnter(GameState::Countdown), reset_sweep_budget)
+        .add_systems(
+            Update,
+            update_sweep_hud
+                .run_if(in_state(Screen::Gameplay).and(resource_exists::<OnLevel>))
+                .in_set(PausableSystems),
+        )
+        .add_observer(record_under_budget)
+        .add_observer(reset_run_stats_on_restart);
+}
+
+/// Sweep distance used so far this attempt
+#[derive(Resource, Default, Debug)]
+pub struct SweepBudget {
+    pub used: f32,
+}
+
+impl SweepBudget {
+    /// Takes `distance` out of `level`'s budget and returns how much of it may actually be swept,
+    /// which is all of it on levels without a budget.
+    pub fn spend(&mut self, level: &Level, distance: f32) -> f32 {
+        let allowed = match self.remaining(level) {
+            Some(remaining) => distance.min(remaining),
+            None => distance,
+        };
+        self.used += allowed;
+        allowed
+    }
+
+    pub fn remaining(&self, level: &Level) -> Option<f32> {
+        level
+            .sweep_budget
+            .map(|budget| (budget - self.used).max(0.0))
+    }
+}
+
+/// Results collected over a whole run, from leaving the title screen to the end
+#[derive(Resource, Default, Debug)]
+pub struct RunStats {
+    /// Levels with a budget that were won without using all of it
+    pub under_budget: HashSet<CurrentLevel>,
+}
+
+#[derive(Component)]
+struct SweepTotalText;
+
+/// Background of the budget bar, hidden on levels without a budget
+#[derive(Component)]
+struct BudgetBar;
+
+#[derive(Component)]
+struct BudgetBarFill;
+
+fn reset_sweep_budget(mut sweep_budget: ResMut<SweepBudget>) {
+    *sweep_budget = SweepBudget::default();
+}
+
+fn reset_run_stats(mut run_stats: ResMut<RunStats>) {
+    *run_stats = RunStats::default();
+}
+
+/// Starting over from the first level is a new run
+fn reset_run_stats_on_restart(_event: On<RestartCampaign>, mut run_stats: ResMut<RunStats>) {
+    *run_stats = RunStats::default();
+}
+
+fn record_under_budget(
+    _event: On<LevelComplete>,
+    on_level: Res<OnLevel>,
+    sweep_budget: Res<SweepBudget>,
+    mut run_stats: ResMut<RunStats>,
+) {
+    let level = &on_level.0;
+    if sweep_budget.remaining(level).is_some_and(|left| left > 0.0) {
+        run_stats.under_budget.insert(level.current_level);
+    }
+}
+
+fn spawn_sweep_hud(mut commands: Commands) {
+    commands.spawn((
+        DespawnOnExit(Screen::Gameplay),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(16.0),
+            top: Val::Px(16.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::End,
+            row_gap: Val::Px(4.0),
+            ..default()
+        },
+        Pickable::IGNORE,
+        children![
+            (
+                SweepTotalText,
+                Localized::new("swept"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+                Pickable::IGNORE,
+            ),
+            (
+                BudgetBar,
+                Node {
+                    width: Val::Px(BUDGET_BAR_WIDTH),
+                    height: Val::Px(10.0),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+                Visibility::Hidden,
+                Pickable::IGNORE,
+                children![(
+                    BudgetBarFill,
+                    Node {
+                        width: Val::Percent(100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(BUDGET_BAR_COLOR),
+                    Pickable::IGNORE,
+                )],
+            ),
+        ],
+    ));
+}
+
+fn update_sweep_hud(
+    time: Res<Time>,
+    on_level: Res<OnLevel>,
+    sweep_budget: Res<SweepBudget>,
+    mut total_text: Single<&mut Localized, With<SweepTotalText>>,
+    mut bar: Single<&mut Visibility, With<BudgetBar>>,
+    fill: Single<(&mut Node, &mut BackgroundColor), With<BudgetBarFill>>,
+) {
+    let level = &on_level.0;
+    // Only a used up budget's flashing needs redrawing when nothing's been swept
+    let exhausted = sweep_budget.remaining(level) == Some(0.0);
+    if !sweep_budget.is_changed() && !on_level.is_changed() && !total_text.is_added() && !exhausted
+    {
+        return;
+    }
+    total_text.set_if_neq(
+        Localized::new("swept").with_arg("amount", format!("{:.0}", sweep_budget.used)),
+    );
+
+    let (Some(budget), Some(remaining)) = (level.sweep_budget, sweep_budget.remaining(level))
+    else {
+        bar.set_if_neq(Visibility::Hidden);
+        return;
+    };
+    bar.set_if_neq(Visibility::Inherited);
+    let (mut fill_node, mut fill_color) = fill.into_inner();
+    let fraction = if budget > 0.0 {
+        remaining / budget
+    } else {
+        0.0
+    };
+    fill_node.width = Val::Percent(100.0 * fraction);
+
+    // The fill is gone once the budget runs out, so flash the whole bar instead
+    fill_color.0 = BUDGET_BAR_COLOR;
+    if remaining <= 0.0 {
+        let flash_on = (time.elapsed_secs() * BUDGET_FLASH_RATE).fract() < 0.5;
+        fill_node.width = Val::Percent(100.0);
+        fill_color.0 = if flash_on {
+            BUDGET_BAR_EMPTY_COLOR
+        } else {
+            BUDGET_BAR_EMPTY_COLOR.with_alpha(0.2)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::get_level;
+
+    #[test]
+    fn test_spend_stops_at_budget() {
+        let mut level = get_level(CurrentLevel::Level1);
+        level.sweep_budget = Some(100.0);
+        let mut sweep_budget = SweepBudget::default();
+
+        assert_eq!(sweep_budget.spend(&level, 60.0), 60.0);
+        // Only what's left of the budget can be swept
+        assert_eq!(sweep_budget.spend(&level, 60.0), 40.0);
+        assert_eq!(sweep_budget.spend(&level, 60.0), 0.0);
+        assert_eq!(sweep_budget.used, 100.0);
+        assert_eq!(sweep_budget.remaining(&level), Some(0.0));
+    }
+
+    #[test]
+    fn test_spend_without_budget_is_unlimited() {
+        let level = get_level(CurrentLevel::Level1);
+        let mut sweep_budget = SweepBudget::default();
+        assert_eq!(sweep_budget.spend(&level, 1e6), 1e6);
+        assert_eq!(sweep_budget.remaining(&level), None);
+    }
+}
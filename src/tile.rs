@@ -1,14 +1,27 @@
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::slice::Iter;
 
+use bevy::camera::visibility::RenderLayers;
 use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::picking::backend::{HitData, PointerHits, ray::RayMap};
 use bevy::prelude::*;
 use bevy::render::render_resource::AsBindGroup;
 use bevy::shader::ShaderRef;
 use bevy::sprite_render::Material2d;
 
-use crate::hex_grid::HexGrid;
+use crate::camera::{MainCamera, level_bounds};
+use crate::fire_trail::SweepShavings;
+use crate::gameplay::GameState;
+use crate::hex_grid::{HexCoordinate, HexGrid, world_to_hex_tolerant};
 use crate::intersection;
-use crate::level::{Facing, OnLevel};
+use crate::layers;
+use crate::level::{Facing, Level, OnLevel};
+use crate::minimap::MINIMAP_LAYER;
+use crate::replay::{ReplayClock, ReplayPlayback, ReplayRecording};
+use crate::sim::PhysicsParams;
+use crate::sweep_budget::SweepBudget;
+use crate::undo::StrokeUndo;
 
 // ============================================================================
 // Custom Scratch-Off Material
@@ -23,6 +36,9 @@ pub struct ScratchOffMaterial {
     pub reveal_color: LinearRgba,
     #[uniform(0)]
     pub progress: f32,
+    /// Offsets the noise so neighbouring tiles don't scratch in the same pattern
+    #[uniform(0)]
+    pub seed: f32,
 }
 
 impl Material2d for ScratchOffMaterial {
@@ -40,8 +56,8 @@ impl Material2d for ScratchOffMaterial {
 pub fn tile(
     tile_type: &TileType,
     world_pos: Vec2,
-    q: i32,
-    r: i32,
+    coordinate: HexCoordinate,
+    scale: f32,
     min_sweep_distance: f32,
     tile_assets: &TileAssets,
     scratch_materials: &mut Assets<ScratchOffMaterial>,
@@ -54,6 +70,7 @@ pub fn tile(
         top_color,
         reveal_color,
         progress: 0.0,
+        seed: scratch_seed(&coordinate),
     });
 
     let (arrow_visibility, arrow_rotation) = match &tile_type {
@@ -64,53 +81,116 @@ pub fn tile(
         _ => (Visibility::Hidden, Quat::IDENTITY),
     };
 
+    // Gates show bars, with the speed a stone needs to get through them
+    let (gate_visibility, gate_label) = match &tile_type {
+        TileType::SpeedGate { threshold } => (Visibility::Visible, format!("{threshold:.0}")),
+        _ => (Visibility::Hidden, String::new()),
+    };
+    let upright = Quat::from_rotation_z(-std::f32::consts::FRAC_PI_6);
+
+    let coordinate_label = format!("{},{}", coordinate.q, coordinate.r);
+    // The tile's scale would scale its parts' heights too
+    let z = |z: f32| layers::child_z(z, layers::BOARD, scale);
+
     (
-        TileDragging {
-            last_position: None,
-            distance_dragged: HashMap::from_iter([(tile_type.clone(), min_sweep_distance)]),
-            most_recent_tile_type: None,
-            drag_velocity: Vec2::ZERO,
-        },
+        coordinate,
+        TileDragging::new(tile_type, min_sweep_distance),
+        SweptTileType(tile_type.clone()),
         Visibility::Visible,
-        Transform::from_xyz(world_pos.x, world_pos.y, 0.0)
-            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_6)),
+        // The scale is also what the physics reads the tile's radius from
+        Transform::from_xyz(world_pos.x, world_pos.y, layers::BOARD)
+            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_6))
+            .with_scale(Vec3::splat(scale)),
         children![
             (
                 Mesh2d(tile_assets.hex_border_mesh.clone()),
                 MeshMaterial2d(tile_assets.border_material.clone()),
+                Transform::from_xyz(0., 0., z(TILE_BORDER_Z)),
+                RenderLayers::from_layers(&[0, MINIMAP_LAYER]),
             ),
             (
                 TileFill,
                 Mesh2d(tile_assets.hex_mesh.clone()),
                 MeshMaterial2d(scratch_material),
-                Transform::from_xyz(0., 0., 1.0),
+                Transform::from_xyz(0., 0., z(TILE_FILL_Z)),
+                RenderLayers::from_layers(&[0, MINIMAP_LAYER]),
                 Pickable {
                     should_block_lower: true,
                     is_hoverable: true,
                 }
             ),
+            (
+                NotAllowedOverlay,
+                Mesh2d(tile_assets.hex_mesh.clone()),
+                MeshMaterial2d(tile_assets.not_allowed_material.clone()),
+                Transform::from_xyz(0., 0., z(NOT_ALLOWED_Z)),
+                Visibility::Hidden,
+                Pickable::IGNORE,
+            ),
+            (
+                WallFlashOverlay,
+                Mesh2d(tile_assets.hex_mesh.clone()),
+                MeshMaterial2d(tile_assets.wall_flash_material.clone()),
+                Transform::from_xyz(0., 0., z(WALL_FLASH_Z)),
+                Visibility::Hidden,
+                Pickable::IGNORE,
+            ),
             (
                 TileCoordinateText,
                 Visibility::Hidden,
-                Text2d::new(format!("{},{}", q, r)),
+                Text2d::new(coordinate_label),
                 TextFont {
                     font_size: 10.0,
                     ..default()
                 },
                 TextColor(Color::BLACK),
-                Transform::from_xyz(0., 0., 2.0)
+                Transform::from_xyz(0., 0., z(COORDINATE_TEXT_Z))
                     .with_rotation(Quat::from_rotation_z(-std::f32::consts::FRAC_PI_6)),
             ),
+            (
+                TileGlyph,
+                Mesh2d(tile_assets.slow_glyph_mesh.clone()),
+                MeshMaterial2d(tile_assets.glyph_material.clone()),
+                Transform::from_xyz(0., 0., z(GLYPH_Z)),
+                Visibility::Hidden,
+                Pickable::IGNORE,
+            ),
             (
                 Mesh2d(tile_assets.arrow_mesh.clone()),
                 MeshMaterial2d(tile_assets.arrow_material.clone()),
-                Transform::from_xyz(0., 0., 3.0).with_rotation(arrow_rotation),
+                Transform::from_xyz(0., 0., z(ARROW_Z)).with_rotation(arrow_rotation),
                 arrow_visibility,
+                RenderLayers::from_layers(&[0, MINIMAP_LAYER]),
+            ),
+            (
+                Mesh2d(tile_assets.gate_mesh.clone()),
+                MeshMaterial2d(tile_assets.gate_material.clone()),
+                Transform::from_xyz(0., 0., z(ARROW_Z)).with_rotation(upright),
+                gate_visibility,
+                Pickable::IGNORE,
+                RenderLayers::from_layers(&[0, MINIMAP_LAYER]),
+            ),
+            (
+                Text2d::new(gate_label),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Transform::from_xyz(0., 0., z(GATE_LABEL_Z)).with_rotation(upright),
+                gate_visibility,
+                Pickable::IGNORE,
             ),
         ],
     )
 }
 
+/// A stable per-tile offset into the scratch noise, kept small enough for the shader's float math
+fn scratch_seed(coordinate: &HexCoordinate) -> f32 {
+    let hash = coordinate.q.wrapping_mul(73_856_093) ^ coordinate.r.wrapping_mul(19_349_663);
+    (hash.unsigned_abs() % 1000) as f32
+}
+
 // ============================================================================
 // Constants
 // ============================================================================
@@ -124,11 +204,50 @@ pub const COLORS: [Color; 6] = [
     Color::srgb(1., 60.0 / 255.0, 90.0 / 255.0),   // rgb(255,53,79)
 ];
 
+/// Tile colors picked from the Okabe-Ito palette, which stays distinct with deuteranopia
+const DEUTERANOPIA_COLORS: [Color; 6] = [
+    Color::srgb(238.0 / 255.0, 249.0 / 255.0, 1.), // rgb(238, 249, 255)
+    Color::srgb(86.0 / 255.0, 180.0 / 255.0, 233.0 / 255.0), // rgb(86, 180, 233)
+    Color::srgb(230.0 / 255.0, 159.0 / 255.0, 0.), // rgb(230, 159, 0)
+    Color::srgb(0., 114.0 / 255.0, 178.0 / 255.0), // rgb(0, 114, 178)
+    Color::srgb(204.0 / 255.0, 121.0 / 255.0, 167.0 / 255.0), // rgb(204, 121, 167)
+    Color::srgb(213.0 / 255.0, 94.0 / 255.0, 0.),  // rgb(213, 94, 0)
+];
+
+const HIGH_CONTRAST_COLORS: [Color; 6] = [
+    Color::WHITE,
+    Color::srgb(0.5, 0.5, 0.5),
+    Color::srgb(1.0, 0.84, 0.0),
+    Color::srgb(0.1, 0.1, 0.1),
+    Color::srgb(0.0, 0.75, 1.0),
+    Color::srgb(1.0, 0.1, 0.1),
+];
+
+/// The physics step length the tile effects' strengths are tuned for, Bevy's default 64Hz
+const TUNED_DT: f32 = 1.0 / 64.0;
+
+/// How close to the min sweep distance a type has to get for the tile to count as that type
+const TILE_TYPE_TOLERANCE: f32 = 2.0;
+
+/// Width of the hovered tile's outline, and its height over the tiles, under the stones
+const HOVER_OUTLINE_WIDTH: f32 = 3.0;
+const HOVER_OUTLINE_Z: f32 = layers::TILE_OVERLAY + 0.1;
+
+/// Where a tile's parts are drawn, stacked up from the board
+pub const TILE_BORDER_Z: f32 = layers::BOARD;
+pub const TILE_FILL_Z: f32 = layers::BOARD + 0.1;
+const NOT_ALLOWED_Z: f32 = layers::BOARD + 0.4;
+const WALL_FLASH_Z: f32 = layers::BOARD + 0.45;
+const COORDINATE_TEXT_Z: f32 = layers::BOARD + 0.5;
+const GLYPH_Z: f32 = layers::BOARD + 0.6;
+const ARROW_Z: f32 = layers::BOARD + 0.7;
+const GATE_LABEL_Z: f32 = layers::BOARD + 0.8;
+
 // ============================================================================
 // Components
 // ============================================================================
 
-#[derive(PartialEq, Debug, Clone, Eq, Hash)]
+#[derive(PartialEq, Debug, Clone)]
 pub enum TileType {
     Wall,
     MaintainSpeed,
@@ -137,28 +256,322 @@ pub enum TileType {
     TurnClockwise,
     Goal,
     SpeedUp(Facing),
+    /// A wall to stones slower than `threshold`, and straight ice to the rest
+    SpeedGate {
+        threshold: f32,
+    },
+}
+
+// Thresholds are never NaN, so equality is reflexive
+impl Eq for TileType {}
+
+impl Hash for TileType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            TileType::SpeedUp(facing) => facing.hash(state),
+            // Adding zero turns -0.0 into 0.0, which compare equal so have to hash the same
+            TileType::SpeedGate { threshold } => (threshold + 0.0).to_bits().hash(state),
+            _ => {}
+        }
+    }
 }
 
 #[derive(Component)]
 pub struct TileFill;
 
+/// Marks the tile's type with a shape, shown by palettes that don't rely on color alone
+#[derive(Component)]
+pub struct TileGlyph;
+
+/// Which colors the tiles and trajectory line are drawn in, picked in the settings
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TilePalette {
+    #[default]
+    Normal,
+    Deuteranopia,
+    HighContrast,
+}
+
+impl TilePalette {
+    pub fn next(self) -> Self {
+        match self {
+            TilePalette::Normal => TilePalette::Deuteranopia,
+            TilePalette::Deuteranopia => TilePalette::HighContrast,
+            TilePalette::HighContrast => TilePalette::Normal,
+        }
+    }
+
+    /// Localization key of the palette's name
+    pub fn label(self) -> &'static str {
+        match self {
+            TilePalette::Normal => "palette-normal",
+            TilePalette::Deuteranopia => "palette-deuteranopia",
+            TilePalette::HighContrast => "palette-high-contrast",
+        }
+    }
+
+    fn colors(self) -> &'static [Color; 6] {
+        match self {
+            TilePalette::Normal => &COLORS,
+            TilePalette::Deuteranopia => &DEUTERANOPIA_COLORS,
+            TilePalette::HighContrast => &HIGH_CONTRAST_COLORS,
+        }
+    }
+
+    pub fn tile_color(self, tile_type: &TileType) -> Color {
+        self.colors()[color_index(tile_type)]
+    }
+
+    /// Whether tiles also get a glyph for their type, so they can be told apart without color
+    pub fn shows_glyphs(self) -> bool {
+        self != TilePalette::Normal
+    }
+}
+
+/// Which of a palette's or theme's six colors `tile_type` is drawn in
+fn color_index(tile_type: &TileType) -> usize {
+    match tile_type {
+        TileType::Wall => 3,
+        TileType::MaintainSpeed => 0,
+        TileType::SlowDown => 1,
+        TileType::TurnCounterclockwise => 2,
+        TileType::TurnClockwise => 4,
+        TileType::Goal => 5,
+        TileType::SpeedUp(_facing) => 0,
+        TileType::SpeedGate { .. } => 3,
+    }
+}
+
+/// The look of the board, picked in the settings
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TileTheme {
+    #[default]
+    Day,
+    Night,
+    Dusk,
+}
+
+impl TileTheme {
+    pub fn iterator() -> Iter<'static, TileTheme> {
+        static THEMES: [TileTheme; 3] = [TileTheme::Day, TileTheme::Night, TileTheme::Dusk];
+        THEMES.iter()
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            TileTheme::Day => TileTheme::Night,
+            TileTheme::Night => TileTheme::Dusk,
+            TileTheme::Dusk => TileTheme::Day,
+        }
+    }
+
+    /// Localization key of the theme's name
+    pub fn label(self) -> &'static str {
+        match self {
+            TileTheme::Day => "theme-day",
+            TileTheme::Night => "theme-night",
+            TileTheme::Dusk => "theme-dusk",
+        }
+    }
+
+    pub fn colors(self) -> Theme {
+        match self {
+            TileTheme::Day => Theme {
+                tiles: COLORS,
+                border: Color::BLACK,
+                glyph: Color::BLACK.with_alpha(0.6),
+                wall_flash: Color::WHITE.with_alpha(0.5),
+                reach_goal: Color::srgb(0.2, 0.9, 0.3),
+                fall_short: Color::srgb(1.0, 0.25, 0.3),
+                hover_outline: Color::srgb(1.0, 0.95, 0.3),
+                gate_alpha: 0.8,
+                not_allowed_alpha: 0.35,
+                goal_hole: [
+                    Color::srgba(0.1, 0.0, 0.2, 0.6),
+                    Color::srgba(0.6, 0.3, 1.0, 0.8),
+                    Color::BLACK,
+                ],
+            },
+            TileTheme::Night => Theme {
+                tiles: [
+                    Color::srgb(0.24, 0.28, 0.36),
+                    Color::srgb(0.05, 0.42, 0.55),
+                    Color::srgb(0.12, 0.5, 0.38),
+                    Color::srgb(0.02, 0.06, 0.25),
+                    Color::srgb(0.5, 0.22, 0.5),
+                    Color::srgb(0.85, 0.2, 0.3),
+                ],
+                border: Color::srgb(0.45, 0.5, 0.62),
+                glyph: Color::WHITE.with_alpha(0.5),
+                wall_flash: Color::srgba(0.6, 0.8, 1.0, 0.5),
+                reach_goal: Color::srgb(0.3, 1.0, 0.5),
+                fall_short: Color::srgb(1.0, 0.4, 0.45),
+                hover_outline: Color::srgb(0.5, 0.95, 1.0),
+                gate_alpha: 0.9,
+                not_allowed_alpha: 0.45,
+                goal_hole: [
+                    Color::srgba(0.3, 0.6, 1.0, 0.35),
+                    Color::srgba(0.4, 0.9, 1.0, 0.85),
+                    Color::srgb(0.0, 0.02, 0.08),
+                ],
+            },
+            TileTheme::Dusk => Theme {
+                tiles: [
+                    Color::srgb(0.98, 0.9, 0.84),
+                    Color::srgb(0.95, 0.6, 0.35),
+                    Color::srgb(0.55, 0.75, 0.45),
+                    Color::srgb(0.35, 0.15, 0.35),
+                    Color::srgb(0.85, 0.4, 0.55),
+                    Color::srgb(0.9, 0.2, 0.15),
+                ],
+                border: Color::srgb(0.2, 0.08, 0.12),
+                glyph: Color::srgb(0.2, 0.08, 0.12).with_alpha(0.6),
+                wall_flash: Color::srgba(1.0, 0.85, 0.6, 0.5),
+                reach_goal: Color::srgb(0.45, 0.85, 0.25),
+                fall_short: Color::srgb(0.95, 0.3, 0.2),
+                hover_outline: Color::srgb(1.0, 0.6, 0.9),
+                gate_alpha: 0.75,
+                not_allowed_alpha: 0.3,
+                goal_hole: [
+                    Color::srgba(0.3, 0.05, 0.1, 0.6),
+                    Color::srgba(1.0, 0.55, 0.3, 0.8),
+                    Color::srgb(0.08, 0.0, 0.02),
+                ],
+            },
+        }
+    }
+}
+
+/// Every color the board's drawn in besides the accessibility palettes' own, see
+/// `TileAssets::apply_theme`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// Tile colors for the normal palette, in the same order as `COLORS`
+    pub tiles: [Color; 6],
+    pub border: Color,
+    pub glyph: Color,
+    pub wall_flash: Color,
+    pub reach_goal: Color,
+    pub fall_short: Color,
+    /// Outline around the hovered tile
+    pub hover_outline: Color,
+    pub gate_alpha: f32,
+    pub not_allowed_alpha: f32,
+    /// The goal vortex's outer disc, ring and core
+    pub goal_hole: [Color; 3],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        TileTheme::default().colors()
+    }
+}
+
+impl Theme {
+    /// The accessibility palettes keep their own tile colors whatever the theme
+    pub fn tile_color(&self, palette: TilePalette, tile_type: &TileType) -> Color {
+        match palette {
+            TilePalette::Normal => self.tiles[color_index(tile_type)],
+            _ => palette.tile_color(tile_type),
+        }
+    }
+
+    /// Color of the trajectory line and the speed-up arrows
+    pub fn accent_color(&self, palette: TilePalette) -> Color {
+        self.tile_color(palette, &TileType::Goal)
+    }
+}
+
 #[derive(Component)]
 pub struct TileCoordinateText;
 
+/// The one outline moved onto whichever tile is hovered, a child of the grid
+#[derive(Component)]
+pub struct HoverOutline;
+
+/// Red tint shown over a hovered tile that the current broom can't sweep
+#[derive(Component)]
+pub struct NotAllowedOverlay;
+
+/// Highlight flashed over a wall the stone only just missed, see `WallFlash`
+#[derive(Component)]
+pub struct WallFlashOverlay;
+
+/// How long a wall stays lit after a near miss, counting down
 #[derive(Component, Debug)]
+pub struct WallFlash(pub Timer);
+
+impl Default for WallFlash {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.15, TimerMode::Once))
+    }
+}
+
+/// Debug toggle for flashing walls the stone nearly clips
+#[derive(Resource, Default)]
+pub struct ShowWallFlash(pub bool);
+
+/// Settings toggle for sweeping a tile's mirror image across the level along with it
+#[derive(Resource, Default)]
+pub struct MirrorSweep(pub bool);
+
+#[derive(Component, Debug, Clone)]
 pub struct TileDragging {
     // Tracks the distance dragged for each tile type
     // The initial tile type is stored as having dragged the min sweep distance
     // All values in this map should sum to min sweep distance
     // Check add_drag for some details
     pub distance_dragged: HashMap<TileType, f32>,
+    /// The type the tile started as, erasing sweeps it back towards this
+    pub base_tile_type: TileType,
     pub last_position: Option<Vec2>,
     pub most_recent_tile_type: Option<TileType>,
-    /// Smoothed drag velocity in screen-space pixels/second, computed from recent drag events.
+    /// Smoothed drag velocity in world units/second, computed from recent drag events.
     /// Reset to Vec2::ZERO on DragEnd.
     pub drag_velocity: Vec2,
 }
 
+impl TileDragging {
+    /// An unswept tile, with all of the min sweep distance held by its own type
+    pub fn new(tile_type: &TileType, min_sweep_distance: f32) -> Self {
+        Self {
+            distance_dragged: HashMap::from_iter([(tile_type.clone(), min_sweep_distance)]),
+            base_tile_type: tile_type.clone(),
+            last_position: None,
+            most_recent_tile_type: None,
+            drag_velocity: Vec2::ZERO,
+        }
+    }
+
+    /// The type holding all of the min sweep distance, give or take `TILE_TYPE_TOLERANCE`, or `None`
+    /// while the tile is partway between types
+    pub fn settled_type(&self, min_sweep_distance: f32) -> Option<TileType> {
+        self.distance_dragged
+            .iter()
+            .find(|(_, distance)| **distance + TILE_TYPE_TOLERANCE >= min_sweep_distance)
+            .map(|(tile_type, _)| tile_type.clone())
+    }
+
+    /// Sweep distance left before `broom` has fully converted the tile
+    pub fn distance_to_complete(&self, broom: &TileType, min_sweep_distance: f32) -> f32 {
+        let swept = self.distance_dragged.get(broom).copied().unwrap_or(0.0);
+        (min_sweep_distance - swept).max(0.0)
+    }
+}
+
+/// The type a tile counts as. It only changes once a sweep or erase has fully converted the tile,
+/// partway through it stays the old type.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct SweptTileType(pub TileType);
+
+/// Fired when a tile's `SweptTileType` changes
+#[derive(EntityEvent, Debug)]
+pub struct TileChanged {
+    pub entity: Entity,
+    pub new: TileType,
+}
+
 #[derive(Component)]
 pub struct MouseHover;
 
@@ -168,21 +581,71 @@ pub struct CanBeDragged;
 #[derive(Component)]
 pub struct IsGoal;
 
+/// Broom types the player can sweep with, in the order the number keys and cycling use
+pub const BROOM_TYPES: [TileType; 3] = [
+    TileType::MaintainSpeed,
+    TileType::TurnCounterclockwise,
+    TileType::TurnClockwise,
+];
+
+/// Steps `step` brooms through `BROOM_TYPES` from `current`, wrapping around at either end.
+/// A non-broom `current` is treated as the first broom.
+pub fn cycle_broom_type(current: &TileType, step: i32) -> TileType {
+    let len = BROOM_TYPES.len() as i32;
+    let index = BROOM_TYPES
+        .iter()
+        .position(|tile_type| tile_type == current)
+        .unwrap_or(0) as i32;
+    BROOM_TYPES[(index + step).rem_euclid(len) as usize].clone()
+}
+
 pub fn tile_can_be_dragged(tile_type: &TileType) -> bool {
     !matches!(
         tile_type,
-        TileType::Wall | TileType::Goal | TileType::SpeedUp(_)
+        TileType::Wall | TileType::Goal | TileType::SpeedUp(_) | TileType::SpeedGate { .. }
     )
 }
 
+/// Whether sweeping `tile` with `broom` would change it. Tiles that can't be dragged never change,
+/// and neither do tiles already fully swept with that broom.
+pub fn broom_can_affect(tile: &TileDragging, broom: &TileType, min_sweep_distance: f32) -> bool {
+    tile_can_be_dragged(&tile.base_tile_type)
+        && tile.distance_to_complete(broom, min_sweep_distance) > 0.0
+}
+
 // ============================================================================
 // Resources
 // ============================================================================
 
 /// Tracks the current tile type being used for dragging/painting
-#[derive(Resource)]
+#[derive(Resource, PartialEq)]
 pub struct CurrentDragTileType(pub TileType);
 
+/// Holding either of these while dragging sweeps with `Level::precision_sweep_scale`
+pub const PRECISION_KEYS: [KeyCode; 2] = [KeyCode::ShiftLeft, KeyCode::ShiftRight];
+
+/// Multiplier the drag in progress is sweeping with, for showing next to the cursor
+#[derive(Resource, Default)]
+pub struct SweepSpeedReadout(pub Option<f32>);
+
+/// What a single drag stroke does to a tile
+#[derive(PartialEq, Debug, Clone)]
+pub enum Stroke {
+    /// Sweep with the given broom
+    Sweep(TileType),
+    /// Rough the tile back up towards its original type
+    Erase,
+}
+
+impl Stroke {
+    pub fn apply(&self, tile: &mut TileDragging, distance: f32) {
+        match self {
+            Stroke::Sweep(tile_type) => sweep(tile, tile_type, distance),
+            Stroke::Erase => erase(tile, distance),
+        }
+    }
+}
+
 #[derive(Resource)]
 pub struct TileAssets {
     pub hex_mesh: Handle<Mesh>,
@@ -191,6 +654,26 @@ pub struct TileAssets {
     pub border_material: Handle<ColorMaterial>,
     pub line_material: Handle<ColorMaterial>,
     pub arrow_material: Handle<ColorMaterial>,
+    pub not_allowed_material: Handle<ColorMaterial>,
+    pub wall_flash_material: Handle<ColorMaterial>,
+    pub landing_marker_mesh: Handle<Mesh>,
+    pub landing_marker_bar_mesh: Handle<Mesh>,
+    pub reach_goal_material: Handle<ColorMaterial>,
+    pub fall_short_material: Handle<ColorMaterial>,
+    pub slow_glyph_mesh: Handle<Mesh>,
+    pub goal_glyph_mesh: Handle<Mesh>,
+    /// Points right, turned around for counterclockwise tiles
+    pub turn_glyph_mesh: Handle<Mesh>,
+    pub glyph_material: Handle<ColorMaterial>,
+    /// Bars across a speed gate, upright with a gap in the middle for the threshold
+    pub gate_mesh: Handle<Mesh>,
+    pub gate_material: Handle<ColorMaterial>,
+    /// A ring just inside a tile's edge
+    pub hover_outline_mesh: Handle<Mesh>,
+    pub hover_outline_material: Handle<ColorMaterial>,
+    /// The sizes the meshes were built for
+    hex_radius: f32,
+    speed_up_arrow_radius: f32,
 }
 
 impl TileAssets {
@@ -213,16 +696,150 @@ impl TileAssets {
         );
         arrow_mesh.insert_indices(Indices::U32(vec![0, 1, 2]));
 
-        TileAssets {
+        let glyph_size = hex_grid.hex_radius;
+        // Three dots in a triangle
+        let dot = |angle: f32| {
+            let offset = Vec2::from_angle(angle.to_radians()) * glyph_size * 0.2;
+            Mesh::from(Circle::new(glyph_size * 0.07)).translated_by(offset.extend(0.0))
+        };
+        let mut slow_glyph_mesh = dot(90.0);
+        for angle in [210.0, 330.0] {
+            slow_glyph_mesh
+                .merge(&dot(angle))
+                .expect("circle meshes share attributes");
+        }
+
+        let bar = |x: f32, y: f32, height: f32| {
+            Mesh::from(Rectangle::new(glyph_size * 0.08, height))
+                .translated_by(Vec3::new(x, y, 0.0))
+        };
+        let mut gate_mesh = bar(-glyph_size * 0.35, 0.0, glyph_size * 0.9);
+        for (x, y, height) in [
+            (glyph_size * 0.35, 0.0, glyph_size * 0.9),
+            (0.0, glyph_size * 0.3, glyph_size * 0.3),
+            (0.0, -glyph_size * 0.3, glyph_size * 0.3),
+        ] {
+            gate_mesh
+                .merge(&bar(x, y, height))
+                .expect("rectangle meshes share attributes");
+        }
+
+        let mut hover_outline_mesh = Mesh::new(PrimitiveTopology::TriangleList, default());
+        let outer = RegularPolygon::new(hex_grid.hex_radius, 6).vertices(0.0);
+        let inner = RegularPolygon::new(hex_grid.hex_radius - HOVER_OUTLINE_WIDTH, 6).vertices(0.0);
+        hover_outline_mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            outer
+                .into_iter()
+                .chain(inner)
+                .map(|v| [v.x, v.y, 0.0])
+                .collect::<Vec<_>>(),
+        );
+        // Two triangles per side, between outer corners 0-5 and inner corners 6-11
+        hover_outline_mesh.insert_indices(Indices::U32(
+            (0..6)
+                .flat_map(|i| {
+                    let next = (i + 1) % 6;
+                    [i, next, i + 6, i + 6, next, next + 6]
+                })
+                .collect(),
+        ));
+
+        let tile_assets = TileAssets {
             hex_mesh: meshes.add(RegularPolygon::new(
                 hex_grid.hex_radius - border_thickness,
                 6,
             )),
             hex_border_mesh: meshes.add(RegularPolygon::new(hex_grid.hex_radius, 6)),
             arrow_mesh: meshes.add(arrow_mesh),
-            border_material: materials.add(Color::BLACK),
-            line_material: materials.add(COLORS[5]),
-            arrow_material: materials.add(COLORS[5]),
+            border_material: materials.add(ColorMaterial::default()),
+            line_material: materials.add(ColorMaterial::default()),
+            arrow_material: materials.add(ColorMaterial::default()),
+            not_allowed_material: materials.add(ColorMaterial::default()),
+            wall_flash_material: materials.add(ColorMaterial::default()),
+            landing_marker_mesh: meshes.add(Annulus::new(
+                hex_grid.hex_radius * 0.25,
+                hex_grid.hex_radius * 0.3,
+            )),
+            landing_marker_bar_mesh: meshes.add(Rectangle::new(hex_grid.hex_radius * 0.8, 3.0)),
+            reach_goal_material: materials.add(ColorMaterial::default()),
+            fall_short_material: materials.add(ColorMaterial::default()),
+            slow_glyph_mesh: meshes.add(slow_glyph_mesh),
+            goal_glyph_mesh: meshes.add(Annulus::new(glyph_size * 0.28, glyph_size * 0.36)),
+            turn_glyph_mesh: meshes.add(Triangle2d::new(
+                Vec2::new(glyph_size * 0.3, 0.0),
+                Vec2::new(-glyph_size * 0.15, glyph_size * 0.25),
+                Vec2::new(-glyph_size * 0.15, -glyph_size * 0.25),
+            )),
+            glyph_material: materials.add(ColorMaterial::default()),
+            gate_mesh: meshes.add(gate_mesh),
+            gate_material: materials.add(ColorMaterial::default()),
+            hover_outline_mesh: meshes.add(hover_outline_mesh),
+            hover_outline_material: materials.add(ColorMaterial::default()),
+            hex_radius: hex_grid.hex_radius,
+            speed_up_arrow_radius,
+        };
+        tile_assets.apply_theme(materials, &Theme::default(), TilePalette::Normal);
+        tile_assets
+    }
+
+    /// Recolors the shared materials in place, so everything already drawn with them picks up
+    /// the new colors without being respawned
+    pub fn apply_theme(
+        &self,
+        materials: &mut Assets<ColorMaterial>,
+        theme: &Theme,
+        palette: TilePalette,
+    ) {
+        let accent = theme.accent_color(palette);
+        let gate = theme.tile_color(palette, &TileType::MaintainSpeed);
+        for (handle, color) in [
+            (&self.border_material, theme.border),
+            (&self.line_material, accent),
+            (&self.arrow_material, accent),
+            (
+                &self.not_allowed_material,
+                accent.with_alpha(theme.not_allowed_alpha),
+            ),
+            (&self.wall_flash_material, theme.wall_flash),
+            (&self.reach_goal_material, theme.reach_goal),
+            (&self.fall_short_material, theme.fall_short),
+            (&self.glyph_material, theme.glyph),
+            (&self.gate_material, gate.with_alpha(theme.gate_alpha)),
+            (&self.hover_outline_material, theme.hover_outline),
+        ] {
+            if let Some(material) = materials.get_mut(handle) {
+                material.color = color;
+            }
+        }
+    }
+
+    /// Rebuilds the assets only if the level's sizes differ from the ones they were built for, so
+    /// restarting a level reuses the existing handles instead of adding new ones every time.
+    pub fn rebuild_if_changed(
+        &mut self,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<ColorMaterial>,
+        hex_grid: &HexGrid,
+        speed_up_arrow_radius: f32,
+    ) {
+        if self.hex_radius != hex_grid.hex_radius
+            || self.speed_up_arrow_radius != speed_up_arrow_radius
+        {
+            *self = TileAssets::new(meshes, materials, hex_grid, speed_up_arrow_radius);
+        }
+    }
+
+    /// Glyph mesh and rotation marking `tile_type`, if it has one
+    pub fn tile_glyph(&self, tile_type: &TileType) -> Option<(Handle<Mesh>, f32)> {
+        match tile_type {
+            TileType::SlowDown => Some((self.slow_glyph_mesh.clone(), 0.0)),
+            TileType::Goal => Some((self.goal_glyph_mesh.clone(), 0.0)),
+            TileType::TurnClockwise => Some((self.turn_glyph_mesh.clone(), 0.0)),
+            TileType::TurnCounterclockwise => {
+                Some((self.turn_glyph_mesh.clone(), std::f32::consts::PI))
+            }
+            _ => None,
         }
     }
 }
@@ -249,28 +866,90 @@ pub fn toggle_tile_coordinates(
     }
 }
 
-/// Returns the base color for a given tile type
-fn get_tile_color(tile_type: &TileType) -> Color {
-    match tile_type {
-        TileType::Wall => COLORS[3],
-        TileType::MaintainSpeed => COLORS[0],
-        TileType::SlowDown => COLORS[1],
-        TileType::TurnCounterclockwise => COLORS[2],
-        TileType::TurnClockwise => COLORS[4],
-        TileType::Goal => COLORS[5],
-        TileType::SpeedUp(_facing) => COLORS[0],
+/// Returns the base color for a given tile type in the normal palette
+pub fn get_tile_color(tile_type: &TileType) -> Color {
+    TilePalette::Normal.tile_color(tile_type)
+}
+
+/// Recolors the shared materials whenever the theme or palette changes, and after the tile assets
+/// are rebuilt since they start out in the day theme. The handles stay the same, so everything
+/// already using them picks up the new colors.
+pub fn apply_tile_theme(
+    theme: Res<TileTheme>,
+    palette: Res<TilePalette>,
+    tile_assets: Res<TileAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    if !theme.is_changed() && !palette.is_changed() && !tile_assets.is_changed() {
+        return;
+    }
+    tile_assets.apply_theme(&mut materials, &theme.colors(), *palette);
+}
+
+/// Keeps each tile's glyph in line with its type, only on palettes that show them
+pub fn update_tile_glyphs(
+    palette: Res<TilePalette>,
+    tile_assets: Res<TileAssets>,
+    tiles: Query<(Ref<SweptTileType>, &Children)>,
+    mut glyphs: Query<(&mut Mesh2d, &mut Transform, &mut Visibility), With<TileGlyph>>,
+) {
+    for (swept_tile_type, children) in &tiles {
+        if !palette.is_changed() && !swept_tile_type.is_changed() {
+            continue;
+        }
+        let glyph = tile_assets
+            .tile_glyph(&swept_tile_type.0)
+            .filter(|_| palette.shows_glyphs());
+        for child in children.iter() {
+            let Ok((mut mesh, mut transform, mut visibility)) = glyphs.get_mut(child) else {
+                continue;
+            };
+            let Some((glyph_mesh, rotation)) = &glyph else {
+                visibility.set_if_neq(Visibility::Hidden);
+                continue;
+            };
+            mesh.0 = glyph_mesh.clone();
+            // Undoes the tile's own rotation so glyphs stay upright
+            transform.rotation = Quat::from_rotation_z(rotation - std::f32::consts::FRAC_PI_6);
+            visibility.set_if_neq(Visibility::Inherited);
+        }
+    }
+}
+
+/// Only looks at tiles whose sweeps changed this frame
+pub fn detect_tile_changes(
+    mut commands: Commands,
+    on_level: Res<OnLevel>,
+    mut tiles: Query<(Entity, &TileDragging, &mut SweptTileType), Changed<TileDragging>>,
+) {
+    for (entity, tile_dragging, mut swept_tile_type) in &mut tiles {
+        let Some(new) = tile_dragging.settled_type(on_level.0.min_sweep_distance) else {
+            continue;
+        };
+        if new != swept_tile_type.0 {
+            swept_tile_type.0 = new.clone();
+            commands.trigger(TileChanged { entity, new });
+        }
     }
 }
 
+/// Only touches the materials of tiles whose sweeping changed, since every material written to is
+/// uploaded again
 pub fn update_tile_material(
-    tile_query: Query<(Entity, &TileDragging)>,
+    tile_query: Query<(Entity, Ref<TileDragging>)>,
     children_query: Query<&Children>,
-    _tile_assets: Res<TileAssets>,
     on_level: Res<OnLevel>,
     mut scratch_materials: ResMut<Assets<ScratchOffMaterial>>,
     fill_query: Query<&MeshMaterial2d<ScratchOffMaterial>, With<TileFill>>,
+    palette: Res<TilePalette>,
+    theme: Res<TileTheme>,
 ) {
+    let everything_changed = on_level.is_changed() || palette.is_changed() || theme.is_changed();
+    let colors = theme.colors();
     for (entity, tile_dragging) in tile_query {
+        if !everything_changed && !tile_dragging.is_changed() {
+            continue;
+        }
         let Ok(children) = children_query.get(entity) else {
             continue;
         };
@@ -304,7 +983,7 @@ pub fn update_tile_material(
             .most_recent_tile_type
             .clone()
             .unwrap_or(TileType::MaintainSpeed);
-        let reveal_color = get_tile_color(&reveal_tile_type).to_linear();
+        let reveal_color = colors.tile_color(*palette, &reveal_tile_type).to_linear();
         let mut sorted_distance_dragged = tile_dragging.distance_dragged.iter().collect::<Vec<_>>();
         sorted_distance_dragged.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap());
         let top_tile_type = sorted_distance_dragged
@@ -312,7 +991,7 @@ pub fn update_tile_material(
             .map(|s| s.0)
             .find(|s| **s != reveal_tile_type)
             .unwrap_or(&reveal_tile_type);
-        let top_color = get_tile_color(top_tile_type).to_linear();
+        let top_color = colors.tile_color(*palette, top_tile_type).to_linear();
 
         for child in children.iter() {
             // Update scratch-off material properties
@@ -327,6 +1006,142 @@ pub fn update_tile_material(
     }
 }
 
+/// Spawned with the grid, and so with the meshes built for the level's `hex_radius`
+pub fn hover_outline(tile_assets: &TileAssets) -> impl Bundle {
+    (
+        Name::new("Hover Outline"),
+        HoverOutline,
+        Mesh2d(tile_assets.hover_outline_mesh.clone()),
+        MeshMaterial2d(tile_assets.hover_outline_material.clone()),
+        Transform::default(),
+        Visibility::Hidden,
+        Pickable::IGNORE,
+    )
+}
+
+/// Moves the outline onto the hovered tile, turned and scaled along with it. Walls and the goal
+/// are never swept, so they aren't outlined.
+pub fn update_hover_outline(
+    tiles: Query<(&Transform, &TileDragging), With<MouseHover>>,
+    outline: Single<(&mut Transform, &mut Visibility), (With<HoverOutline>, Without<TileDragging>)>,
+) {
+    let (mut transform, mut visibility) = outline.into_inner();
+    let hovered = tiles.iter().find(|(_, tile_dragging)| {
+        !matches!(
+            tile_dragging.base_tile_type,
+            TileType::Wall | TileType::Goal
+        )
+    });
+    let Some((tile_transform, _)) = hovered else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+    visibility.set_if_neq(Visibility::Inherited);
+    *transform = Transform {
+        translation: tile_transform
+            .translation
+            .truncate()
+            .extend(layers::child_z(HOVER_OUTLINE_Z, layers::BOARD, 1.0)),
+        ..*tile_transform
+    };
+}
+
+/// Tints the hovered tile red when sweeping it right now would do nothing
+pub fn update_not_allowed_overlay(
+    tile_query: Query<(&TileDragging, &Children, Has<MouseHover>)>,
+    mut overlay_query: Query<&mut Visibility, With<NotAllowedOverlay>>,
+    current_drag_tile_type: Res<CurrentDragTileType>,
+    on_level: Res<OnLevel>,
+    game_state: Res<State<GameState>>,
+) {
+    let level = &on_level.0;
+    let can_sweep = sweeping_allowed(game_state.get(), level);
+    for (tile_dragging, children, hovered) in &tile_query {
+        let not_allowed = hovered
+            && !(can_sweep
+                && broom_can_affect(
+                    tile_dragging,
+                    &current_drag_tile_type.0,
+                    level.min_sweep_distance,
+                ));
+        let mut overlays = overlay_query.iter_many_mut(children);
+        while let Some(mut visibility) = overlays.fetch_next() {
+            visibility.set_if_neq(if not_allowed {
+                Visibility::Visible
+            } else {
+                Visibility::Hidden
+            });
+        }
+    }
+}
+
+/// Lights the overlay of walls with a running `WallFlash`, and drops the flash once it's run out
+pub fn update_wall_flash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut tile_query: Query<(Entity, &Children, Option<&mut WallFlash>), With<TileDragging>>,
+    mut overlay_query: Query<&mut Visibility, With<WallFlashOverlay>>,
+) {
+    for (entity, children, flash) in &mut tile_query {
+        let lit = match flash {
+            Some(mut flash) => {
+                flash.0.tick(time.delta());
+                if flash.0.is_finished() {
+                    commands.entity(entity).remove::<WallFlash>();
+                }
+                !flash.0.is_finished()
+            }
+            None => false,
+        };
+        let mut overlays = overlay_query.iter_many_mut(children);
+        while let Some(mut visibility) = overlays.fetch_next() {
+            visibility.set_if_neq(if lit {
+                Visibility::Visible
+            } else {
+                Visibility::Hidden
+            });
+        }
+    }
+}
+
+/// A picking backend for pointers just outside every tile: within the level's `pick_tolerance` it
+/// hits the closest tile, so hovering and sweeping near an edge doesn't drop out. The hits sit
+/// behind everything else, so the tile meshes' own hits and anything drawn over them still win.
+pub fn pick_tiles_near_pointer(
+    ray_map: Res<RayMap>,
+    on_level: Res<OnLevel>,
+    camera: Single<(Entity, &Camera), With<MainCamera>>,
+    hex_grid: Single<&HexGrid>,
+    tiles: Query<(Entity, &HexCoordinate), With<TileDragging>>,
+    mut pointer_hits: MessageWriter<PointerHits>,
+) {
+    let (camera_entity, camera) = *camera;
+    for (ray_id, ray) in ray_map.iter() {
+        if ray_id.camera != camera_entity {
+            continue;
+        }
+        let position = ray.origin.truncate();
+        let Some(coordinate) =
+            world_to_hex_tolerant(position, &hex_grid, on_level.0.pick_tolerance)
+        else {
+            // Holes in a level are expected, but a pointer over one is worth seeing when debugging
+            if level_bounds(&hex_grid).is_some_and(|bounds| bounds.contains(position)) {
+                debug!("No tile under the pointer at {position}, inside the level");
+            }
+            continue;
+        };
+        let Some((tile, _)) = tiles.iter().find(|(_, tile)| **tile == coordinate) else {
+            continue;
+        };
+        let hit = HitData::new(camera_entity, f32::MAX, Some(position.extend(0.0)), None);
+        pointer_hits.write(PointerHits::new(
+            ray_id.pointer,
+            vec![(tile, hit)],
+            camera.order as f32,
+        ));
+    }
+}
+
 //=============================================================================
 // Observers
 //=============================================================================
@@ -341,54 +1156,332 @@ pub fn on_pointer_out(out: On<Pointer<Out>>, mut commands: Commands) {
 
 pub fn on_tile_drag_enter(
     drag_enter: On<Pointer<DragEnter>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
     mut tile_dragging_q: Query<Option<&mut TileDragging>>,
 ) {
+    if drag_enter.pointer_id.is_touch() {
+        return;
+    }
     if let Ok(Some(mut tile_dragging)) = tile_dragging_q.get_mut(drag_enter.entity) {
-        tile_dragging.last_position = Some(drag_enter.pointer_location.position);
+        let (camera, camera_transform) = *camera;
+        tile_dragging.last_position = camera
+            .viewport_to_world_2d(camera_transform, drag_enter.pointer_location.position)
+            .ok();
+    }
+}
+
+/// Whether the player may sweep or erase tiles in `game_state`. Pre-sweeping during the countdown is
+/// always allowed, while the stone is moving it's up to the level.
+pub fn sweeping_allowed(game_state: &GameState, level: &Level) -> bool {
+    match game_state {
+        GameState::Initial | GameState::Aiming | GameState::Won | GameState::Lost => false,
+        GameState::Countdown => true,
+        GameState::Playing => level.live_sweeping,
+    }
+}
+
+/// Run condition for sweeping input that observers can't take, like switching brooms
+pub fn can_sweep(game_state: Res<State<GameState>>, on_level: Res<OnLevel>) -> bool {
+    sweeping_allowed(game_state.get(), &on_level.0)
+}
+
+/// How much a sweep at `speed` world units/second counts for. Ramps linearly between the level's
+/// slow and fast multipliers, and holds at either end beyond their speeds.
+pub fn sweep_speed_multiplier(level: &Level, speed: f32) -> f32 {
+    if speed <= level.sweep_slow_speed {
+        return level.sweep_slow_multiplier;
+    }
+    if speed >= level.sweep_fast_speed {
+        return level.sweep_fast_multiplier;
+    }
+    let t = (speed - level.sweep_slow_speed) / (level.sweep_fast_speed - level.sweep_slow_speed);
+    level.sweep_slow_multiplier + (level.sweep_fast_multiplier - level.sweep_slow_multiplier) * t
+}
+
+/// How much a drag counts for on top of the speed curve, less while precision sweeping
+pub fn precision_scale(level: &Level, precision: bool) -> f32 {
+    if precision {
+        level.precision_sweep_scale
+    } else {
+        1.0
+    }
+}
+
+/// Moves a drag on `tile` to `position` and returns the stroke it made, if any. Right mouse button
+/// erases instead of sweeping with `broom`. Sweeps are scaled by how fast the drag is moving, and
+/// both sweeps and erases are scaled down while `precision` is held. Sweeps are paid for out of
+/// `sweep_budget`, and stop accruing once the level's budget runs out.
+pub fn drag_tile(
+    tile: &mut TileDragging,
+    position: Vec2,
+    button: PointerButton,
+    broom: &TileType,
+    level: &Level,
+    game_state: &GameState,
+    dt: f32,
+    precision: bool,
+    sweep_budget: &mut SweepBudget,
+) -> Option<(Stroke, f32)> {
+    let last_position = tile.last_position.replace(position)?;
+    let delta_pos = position - last_position;
+
+    // Compute instantaneous velocity and smooth with exponential moving average
+    if dt > 0.0 {
+        let instantaneous_velocity = delta_pos / dt;
+        // Smoothing factor: 0.0 = no smoothing (instant), 1.0 = no change
+        let smoothing = 0.3;
+        tile.drag_velocity =
+            tile.drag_velocity * smoothing + instantaneous_velocity * (1.0 - smoothing);
+    }
+
+    if !sweeping_allowed(game_state, level) {
+        return None;
+    }
+    let scale = precision_scale(level, precision);
+    let (stroke, distance) = match button {
+        PointerButton::Secondary => (Stroke::Erase, delta_pos.length() * level.erase_rate * scale),
+        _ => (
+            Stroke::Sweep(broom.clone()),
+            delta_pos.length() * sweep_speed_multiplier(level, tile.drag_velocity.length()) * scale,
+        ),
+    };
+    let erase_locked = level.real_time && *game_state == GameState::Playing;
+    if stroke == Stroke::Erase && erase_locked {
+        return None;
+    }
+    // Only what the tile can still take is paid for, the rest of a stroke over a swept tile is free
+    let distance = match &stroke {
+        Stroke::Sweep(broom) => sweep_budget.spend(
+            level,
+            distance.min(tile.distance_to_complete(broom, level.min_sweep_distance)),
+        ),
+        Stroke::Erase => distance,
+    };
+    if distance <= 0.0 {
+        return None;
     }
+    stroke.apply(tile, distance);
+    Some((stroke, distance))
 }
 
 pub fn on_tile_dragging(
     drag: On<Pointer<Drag>>,
-    mut tile: Single<&mut TileDragging, (With<MouseHover>, With<CanBeDragged>)>,
+    tile: Single<
+        (Entity, &mut TileDragging, &HexCoordinate),
+        (With<MouseHover>, With<CanBeDragged>),
+    >,
+    mut other_tiles: Query<
+        (Entity, &mut TileDragging, &HexCoordinate),
+        (Without<MouseHover>, With<CanBeDragged>),
+    >,
+    mirror_sweep: Res<MirrorSweep>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
     current_drag_tile_type: Res<CurrentDragTileType>,
+    on_level: Res<OnLevel>,
+    game_state: Res<State<GameState>>,
+    time: Res<Time>,
+    input: Res<ButtonInput<KeyCode>>,
+    replay_clock: Res<ReplayClock>,
+    mut replay_recording: ResMut<ReplayRecording>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    mut sweep_speed_readout: ResMut<SweepSpeedReadout>,
+    mut stroke_undo: ResMut<StrokeUndo>,
+    mut sweep_budget: ResMut<SweepBudget>,
+    mut sweep_shavings: SweepShavings,
+) {
+    // Player input is ignored while a replay is driving the sweeps.
+    // Observers don't take run conditions, so the game state is checked in drag_tile. Touches
+    // sweep through `touch_sweep` instead
+    if replay_playback.is_some() || drag.pointer_id.is_touch() {
+        return;
+    }
+    let (entity, mut tile, coordinate) = tile.into_inner();
+    let before = tile.clone();
+    // Sweep in world space so zooming the camera doesn't change how far a drag sweeps
+    let (camera, camera_transform) = *camera;
+    let Ok(position) =
+        camera.viewport_to_world_2d(camera_transform, drag.pointer_location.position)
+    else {
+        return;
+    };
+    let precision = input.any_pressed(PRECISION_KEYS);
+    if let Some((stroke, distance)) = drag_tile(
+        &mut tile,
+        position,
+        drag.button,
+        &current_drag_tile_type.0,
+        &on_level.0,
+        game_state.get(),
+        time.delta_secs(),
+        precision,
+        &mut sweep_budget,
+    ) {
+        stroke_undo.touch(entity, &before);
+        if let Stroke::Sweep(_) = stroke {
+            sweep_speed_readout.0 = Some(
+                sweep_speed_multiplier(&on_level.0, tile.drag_velocity.length())
+                    * precision_scale(&on_level.0, precision),
+            );
+            if let Some(from) = before.last_position {
+                sweep_shavings.spawn(
+                    from,
+                    position,
+                    distance,
+                    &current_drag_tile_type.0,
+                    tile.drag_velocity.length(),
+                );
+            }
+        }
+        replay_recording.record(replay_clock.0, coordinate, &stroke, distance);
+        mirror_stroke(
+            &mirror_sweep,
+            &on_level.0,
+            coordinate,
+            &stroke,
+            distance,
+            other_tiles.iter_mut(),
+            replay_clock.0,
+            &mut replay_recording,
+            &mut sweep_budget,
+            &mut stroke_undo,
+        );
+    }
+}
+
+/// Gives the mirror image of the tile at `coordinate` the same stroke, if mirror sweeping is on
+/// and the budget covers it too. `tiles` is searched for the mirror tile.
+pub fn mirror_stroke<'a>(
+    mirror_sweep: &MirrorSweep,
+    level: &Level,
+    coordinate: &HexCoordinate,
+    stroke: &Stroke,
+    distance: f32,
+    mut tiles: impl Iterator<Item = (Entity, Mut<'a, TileDragging>, &'a HexCoordinate)>,
+    tick: u32,
+    replay_recording: &mut ReplayRecording,
+    sweep_budget: &mut SweepBudget,
+    stroke_undo: &mut StrokeUndo,
+) {
+    let Some(mirrored) = mirror_sweep.0.then(|| level.mirrored(coordinate)).flatten() else {
+        return;
+    };
+    let Some((mirror_entity, mut mirror_tile, _)) = tiles.find(|(_, _, other)| **other == mirrored)
+    else {
+        return;
+    };
+    let distance = match stroke {
+        Stroke::Sweep(broom) => sweep_budget.spend(
+            level,
+            distance.min(mirror_tile.distance_to_complete(broom, level.min_sweep_distance)),
+        ),
+        Stroke::Erase => distance,
+    };
+    if distance > 0.0 {
+        stroke_undo.touch(mirror_entity, &mirror_tile);
+        stroke.apply(&mut mirror_tile, distance);
+        replay_recording.record(tick, &mirrored, stroke, distance);
+    }
+}
+
+/// Double-clicking a sweepable tile finishes sweeping it with the current broom in one go.
+pub fn on_tile_click(
+    click: On<Pointer<Click>>,
+    mut last_click: Local<Option<(Entity, f64)>>,
+    mut tile_dragging_q: Query<(&mut TileDragging, &HexCoordinate), With<CanBeDragged>>,
+    current_drag_tile_type: Res<CurrentDragTileType>,
+    on_level: Res<OnLevel>,
+    game_state: Res<State<GameState>>,
     time: Res<Time>,
+    replay_clock: Res<ReplayClock>,
+    mut replay_recording: ResMut<ReplayRecording>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    mut sweep_budget: ResMut<SweepBudget>,
+    mut stroke_undo: ResMut<StrokeUndo>,
 ) {
-    if let Some(last_position) = tile.last_position {
-        let delta_pos = drag.pointer_location.position - last_position;
-        add_drag(
-            &mut tile.distance_dragged,
+    let level = &on_level.0;
+    let blocked = replay_playback.is_some() || !sweeping_allowed(game_state.get(), level);
+    // Long presses are sweeps that happened to end on the tile they started on, not clicks
+    let window = level.double_click_window as f64;
+    if !level.double_click_sweep
+        || blocked
+        || click.button != PointerButton::Primary
+        || click.duration.as_secs_f64() > window
+    {
+        return;
+    }
+
+    let now = time.elapsed_secs_f64();
+    let is_double_click = last_click
+        .is_some_and(|(entity, clicked_at)| entity == click.entity && now - clicked_at <= window);
+    if !is_double_click {
+        *last_click = Some((click.entity, now));
+        return;
+    }
+    *last_click = None;
+
+    if let Ok((mut tile, coordinate)) = tile_dragging_q.get_mut(click.entity) {
+        finish_sweeping(
+            click.entity,
+            &mut tile,
+            coordinate,
             &current_drag_tile_type.0,
-            delta_pos.length(),
+            level,
+            replay_clock.0,
+            &mut replay_recording,
+            &mut sweep_budget,
+            &mut stroke_undo,
         );
-        tile.most_recent_tile_type = Some(current_drag_tile_type.0.clone());
-        tile.last_position = Some(drag.pointer_location.position);
+    }
+}
 
-        // Compute instantaneous velocity and smooth with exponential moving average
-        let dt = time.delta_secs();
-        if dt > 0.0 {
-            let instantaneous_velocity = delta_pos / dt;
-            // Smoothing factor: 0.0 = no smoothing (instant), 1.0 = no change
-            let smoothing = 0.3;
-            tile.drag_velocity =
-                tile.drag_velocity * smoothing + instantaneous_velocity * (1.0 - smoothing);
-        }
+/// Sweeps `tile` the rest of the way with `broom` as a stroke of its own, so a single undo takes it
+/// back
+pub fn finish_sweeping(
+    entity: Entity,
+    tile: &mut TileDragging,
+    coordinate: &HexCoordinate,
+    broom: &TileType,
+    level: &Level,
+    tick: u32,
+    replay_recording: &mut ReplayRecording,
+    sweep_budget: &mut SweepBudget,
+    stroke_undo: &mut StrokeUndo,
+) {
+    stroke_undo.begin(replay_recording.0.mark(), sweep_budget.used);
+    let stroke = Stroke::Sweep(broom.clone());
+    let distance = sweep_budget.spend(
+        level,
+        tile.distance_to_complete(broom, level.min_sweep_distance),
+    );
+    if distance > 0.0 {
+        stroke_undo.touch(entity, tile);
+        stroke.apply(tile, distance);
+        replay_recording.record(tick, coordinate, &stroke, distance);
     }
+    stroke_undo.end(replay_recording.0.mark());
 }
 
 pub fn on_tile_drag_leave(
-    _drag_leave: On<Pointer<DragLeave>>,
+    drag_leave: On<Pointer<DragLeave>>,
     mut tile_dragging_q: Query<&mut TileDragging>,
 ) {
+    if drag_leave.pointer_id.is_touch() {
+        return;
+    }
     for mut tile_dragging in &mut tile_dragging_q {
         tile_dragging.drag_velocity = Vec2::ZERO;
     }
 }
 
 pub fn on_tile_drag_end(
-    _drag_end: On<Pointer<DragEnd>>,
+    drag_end: On<Pointer<DragEnd>>,
     mut tile_dragging_q: Query<&mut TileDragging>,
+    mut sweep_speed_readout: ResMut<SweepSpeedReadout>,
 ) {
+    if drag_end.pointer_id.is_touch() {
+        return;
+    }
+    sweep_speed_readout.0 = None;
     for mut tile_dragging in &mut tile_dragging_q {
         tile_dragging.drag_velocity = Vec2::ZERO;
         tile_dragging.last_position = None;
@@ -478,9 +1571,108 @@ fn point_in_triangle(p: Vec2, v0: Vec2, v1: Vec2, v2: Vec2) -> bool {
     !(has_neg && has_pos)
 }
 
+/// The fraction taken off over `steps` steps of taking `fraction` off each step
+fn compound(fraction: f32, steps: f32) -> f32 {
+    1.0 - (1.0 - fraction.min(1.0)).powf(steps)
+}
+
+/// Reflects `velocity` off the edge of a wall tile `offset` away from the stone, then rubs off
+/// `friction` of the motion along the edge. Returns the edge's normal.
+fn bounce_off_wall(velocity: &mut Vec2, offset: Vec2, weight: f32, friction: f32) -> Vec2 {
+    // Use proper hexagon edge normal instead of radial direction
+    let wall_normal = hex_edge_normal(offset);
+    let dot = velocity.dot(wall_normal);
+    // Only reflect if moving toward the wall
+    if dot < 0.0 {
+        // Store original speed to preserve magnitude after reflection
+        let original_speed = velocity.length();
+        // Apply partial reflection based on weight
+        *velocity -= 2.0 * dot * wall_normal * weight;
+        // Re-normalize to original speed to prevent floating-point drift
+        let new_speed = velocity.length();
+        if new_speed > 1e-10 {
+            *velocity *= original_speed / new_speed;
+        }
+    }
+    // Friction only slows the part of the motion along the wall, the more of the stone rubs
+    // against it the more it slows
+    let tangential = *velocity - velocity.dot(wall_normal) * wall_normal;
+    *velocity -= tangential * friction;
+    wall_normal
+}
+
+/// Whether `tile_type` stops a stone coming in at `velocity` like a wall
+fn blocks(tile_type: &TileType, velocity: &crate::stone::Velocity) -> bool {
+    match tile_type {
+        TileType::Wall => true,
+        TileType::SpeedGate { threshold } => velocity.0.length() < *threshold,
+        _ => false,
+    }
+}
+
 pub struct TileEffect {
     pub velocity: crate::stone::Velocity,
     pub did_hit_wall: bool,
+    /// Pointing away from the last wall hit, zero if none was
+    pub wall_normal: Vec2,
+}
+
+/// Tiles covering less of the stone than this don't affect it
+const MIN_EFFECT_RATIO: f32 = 0.01;
+/// Walls have to cover this much of the stone between them to bounce it, less only grazes them
+const WALL_PRIORITY_RATIO: f32 = 0.05;
+/// How close the stone has to pass a wall without touching it for the wall to flash
+const NEAR_MISS_MARGIN: f32 = 6.0;
+
+/// Radius stones are clipped against for a tile at `scale`, a little inside its border
+fn tile_radius(hex_grid: &HexGrid, scale: f32) -> f32 {
+    hex_grid.hex_radius * scale - 2.
+}
+
+/// Whether the stone passes within `NEAR_MISS_MARGIN` of the tile without overlapping it enough
+/// for any effect to apply
+pub fn nearly_clips_tile(
+    stone_pos: Vec2,
+    stone_radius: f32,
+    tile_position: Vec2,
+    hex_grid: &HexGrid,
+    scale: f32,
+    samples: u32,
+) -> bool {
+    let radius = tile_radius(hex_grid, scale);
+    if stone_pos.distance(tile_position) > radius + stone_radius + NEAR_MISS_MARGIN {
+        return false;
+    }
+    let overlap = |stone_radius| {
+        intersection::ratio_circle_area_inside_hexagon(
+            stone_pos,
+            stone_radius,
+            tile_position,
+            radius,
+            samples,
+        )
+    };
+    overlap(stone_radius) < MIN_EFFECT_RATIO && overlap(stone_radius + NEAR_MISS_MARGIN) > 0.0
+}
+
+/// Whether a stone at any of `positions` covers enough of the tile for the tile to affect it
+pub fn tile_touched(
+    positions: &[Vec2],
+    stone_radius: f32,
+    tile_position: Vec2,
+    hex_grid: &HexGrid,
+    scale: f32,
+    samples: u32,
+) -> bool {
+    intersection::ratios_for_circles(
+        positions,
+        stone_radius,
+        tile_position,
+        tile_radius(hex_grid, scale),
+        samples,
+    )
+    .into_iter()
+    .any(|ratio| ratio >= MIN_EFFECT_RATIO)
 }
 
 /// Computes the new velocity after applying all tile effects at the given position.
@@ -488,75 +1680,179 @@ pub struct TileEffect {
 ///
 /// When a tile is being dragged, the effects are blended between the current tile type
 /// and the target tile type based on the drag progress.
+///
+/// A stone over several tiles gets each one's drag, turn and pull in proportion to how much of the
+/// stone it covers, out of all the tiles the stone covers. Walls, and gates the stone's too slow
+/// for, take priority once they cover `WALL_PRIORITY_RATIO` of it between them: the stone bounces
+/// off them and tiles under the rest of it don't turn it that step. Walls covering less are only
+/// grazed and have no effect, leaving the tiles under the rest of the stone to share all of it.
 pub fn compute_tile_effects(
     stone_pos: Vec2,
     velocity: &crate::stone::Velocity,
-    tiles: &[(Vec2, &TileDragging)],
+    stone_radius: f32,
+    tiles: &[(Vec2, &TileDragging, f32)],
     hex_grid: &HexGrid,
-    drag_coefficient: f32,
+    params: &PhysicsParams,
+    dt: f32,
+) -> TileEffect {
+    tile_effects(
+        stone_pos,
+        velocity,
+        stone_radius,
+        tiles,
+        hex_grid,
+        params,
+        dt,
+        true,
+    )
+}
+
+/// `compute_tile_effects`, with the fast path for a stone inside a single tile optional so tests
+/// can compare it against clipping every tile.
+fn tile_effects(
+    stone_pos: Vec2,
+    velocity: &crate::stone::Velocity,
     stone_radius: f32,
-    slow_down_factor: f32,
-    rotation_factor: f32,
-    speed_up_factor: f32,
-    speed_up_arrow_radius: f32,
+    tiles: &[(Vec2, &TileDragging, f32)],
+    hex_grid: &HexGrid,
+    params: &PhysicsParams,
+    dt: f32,
+    fast_path: bool,
 ) -> TileEffect {
+    let &PhysicsParams {
+        drag_coefficient,
+        slow_down_factor,
+        rotation_factor,
+        speed_up_factor,
+        speed_up_arrow_radius,
+        wall_friction,
+        overlap_samples,
+        ..
+    } = params;
     let mut new_velocity = velocity.0;
+    // The effects' strengths are tuned per step of `TUNED_DT`, longer or shorter steps compound
+    // them more or less
+    let steps = dt / TUNED_DT;
 
     let mut rotation_angle: f32 = 0.0;
     let mut total_drag: f32 = 0.0;
     let mut did_hit_wall = false;
+    let mut wall_normal = Vec2::ZERO;
 
-    for (tile_position, dragging) in tiles {
-        let ratio = intersection::ratio_circle_area_inside_hexagon(
-            stone_pos,
-            stone_radius,
-            *tile_position,
-            hex_grid.hex_radius - 2.,
-            60,
-        );
-        if ratio < 0.01 {
-            continue;
-        }
+    let tile_radius = |scale: f32| tile_radius(hex_grid, scale);
+    // A stone entirely inside one tile can't be touching any other, so there's nothing to clip.
+    // Tiles scaled up past their spacing overlap their neighbors though.
+    let no_overlaps = tiles.iter().all(|(_, _, scale)| *scale <= 1.0);
+    let inside_tile = tiles
+        .iter()
+        .find(|(tile_position, _, scale)| {
+            intersection::circle_inside_hexagon(
+                stone_pos,
+                stone_radius,
+                *tile_position,
+                tile_radius(*scale),
+            )
+        })
+        .filter(|_| fast_path && no_overlaps);
+    let touched_tiles = match inside_tile {
+        Some(tile) => std::slice::from_ref(tile),
+        None => tiles,
+    };
 
+    let overlaps: Vec<_> = touched_tiles
+        .iter()
+        .map(|(tile_position, dragging, scale)| {
+            let ratio = if inside_tile.is_some() {
+                intersection::ratio_circle_fully_inside(overlap_samples)
+            } else {
+                intersection::ratio_circle_area_inside_hexagon(
+                    stone_pos,
+                    stone_radius,
+                    *tile_position,
+                    tile_radius(*scale),
+                    overlap_samples,
+                )
+            };
+            (*tile_position, *dragging, *scale, ratio)
+        })
+        .filter(|(_, _, _, ratio)| *ratio >= MIN_EFFECT_RATIO)
+        .collect();
+    let total_overlap: f32 = overlaps.iter().map(|(_, _, _, ratio)| ratio).sum();
+    let wall_overlap: f32 = overlaps
+        .iter()
+        .map(|(_, dragging, _, ratio)| {
+            let total_distance: f32 = dragging.distance_dragged.values().sum();
+            let wall_distance: f32 = dragging
+                .distance_dragged
+                .iter()
+                .filter(|(tile_type, _)| blocks(tile_type, velocity))
+                .map(|(_, distance)| distance)
+                .sum();
+            ratio * wall_distance / total_distance
+        })
+        .sum();
+    let walls_win = wall_overlap >= WALL_PRIORITY_RATIO;
+    // Grazed walls have no effect, so they don't take any of the stone from the other tiles either.
+    // Walls are skipped below unless they win, so nothing's shared out of an all wall overlap.
+    let shared_overlap = if walls_win {
+        total_overlap
+    } else {
+        total_overlap - wall_overlap
+    };
+
+    for (tile_position, dragging, scale, ratio) in overlaps {
+        // Each tile's share of the stone, so a stone between tiles feels them in proportion
+        let share = ratio / shared_overlap;
         let total_distance: f32 = dragging.distance_dragged.values().sum();
         for (tile_type, distance) in &dragging.distance_dragged {
             let weight = distance / total_distance;
             if weight < 0.001 {
                 continue;
             }
-            let weighted_ratio = ratio * weight;
+            let weighted_ratio = share * weight;
 
             match tile_type {
                 TileType::Wall => {
-                    did_hit_wall = true;
-                    // Use proper hexagon edge normal instead of radial direction
-                    let wall_normal = hex_edge_normal(stone_pos - tile_position);
-                    let dot = new_velocity.dot(wall_normal);
-                    // Only reflect if moving toward the wall
-                    if dot < 0.0 {
-                        // Store original speed to preserve magnitude after reflection
-                        let original_speed = new_velocity.length();
-                        // Apply partial reflection based on weight
-                        new_velocity -= 2.0 * dot * wall_normal * weight;
-                        // Re-normalize to original speed to prevent floating-point drift
-                        let new_speed = new_velocity.length();
-                        if new_speed > 1e-10 {
-                            new_velocity *= original_speed / new_speed;
-                        }
+                    if walls_win {
+                        did_hit_wall = true;
+                        wall_normal = bounce_off_wall(
+                            &mut new_velocity,
+                            stone_pos - tile_position,
+                            weight,
+                            compound(wall_friction * weighted_ratio, steps),
+                        );
                     }
                 }
-                TileType::MaintainSpeed => {
+                // Gates go by the speed the stone came in with, so other tiles slowing it this
+                // step don't shut the gate on it
+                TileType::SpeedGate { .. } if blocks(tile_type, velocity) => {
+                    if walls_win {
+                        did_hit_wall = true;
+                        wall_normal = bounce_off_wall(
+                            &mut new_velocity,
+                            stone_pos - tile_position,
+                            weight,
+                            compound(wall_friction * weighted_ratio, steps),
+                        );
+                    }
+                }
+                TileType::MaintainSpeed | TileType::SpeedGate { .. } => {
                     total_drag += drag_coefficient * weighted_ratio;
                 }
                 TileType::SlowDown => {
                     total_drag += drag_coefficient * weighted_ratio * slow_down_factor;
                 }
+                // Turning a stone that's bouncing off a wall could turn it back into the wall
                 TileType::TurnCounterclockwise => {
-                    rotation_angle += rotation_factor * weighted_ratio;
+                    if !walls_win {
+                        rotation_angle += rotation_factor * weighted_ratio;
+                    }
                     total_drag += drag_coefficient * weighted_ratio;
                 }
                 TileType::TurnClockwise => {
-                    rotation_angle -= rotation_factor * weighted_ratio;
+                    if !walls_win {
+                        rotation_angle -= rotation_factor * weighted_ratio;
+                    }
                     total_drag += drag_coefficient * weighted_ratio;
                 }
                 TileType::Goal => {
@@ -566,16 +1862,16 @@ pub fn compute_tile_effects(
                     if distance > 1e-10 {
                         let direction = to_center / distance;
                         // Pull strength proportional to how much of stone is inside
-                        let pull_strength = 0.5 * weighted_ratio;
+                        let pull_strength = 0.5 * weighted_ratio * steps;
                         new_velocity += direction * pull_strength;
                     }
                     total_drag += drag_coefficient * slow_down_factor * weighted_ratio;
                 }
                 TileType::SpeedUp(facing) => {
                     let [v0, v1, v2] = arrow_triangle_world_vertices(
-                        *tile_position,
+                        tile_position,
                         facing,
-                        speed_up_arrow_radius,
+                        speed_up_arrow_radius * scale,
                     );
                     if point_in_triangle(stone_pos, v0, v1, v2) {
                         // Inside the arrow triangle — launch in the facing direction
@@ -587,7 +1883,7 @@ pub fn compute_tile_effects(
                         let distance = to_center.length();
                         if distance > 1e-10 {
                             let direction = to_center / distance;
-                            let pull_strength = 0.5 * weighted_ratio;
+                            let pull_strength = 0.5 * weighted_ratio * steps;
                             new_velocity += direction * pull_strength;
                         }
                     }
@@ -597,6 +1893,7 @@ pub fn compute_tile_effects(
     }
 
     // Apply accumulated rotation to velocity vector
+    rotation_angle *= steps;
     if rotation_angle.abs() > 1e-10 {
         let (sin_angle, cos_angle) = rotation_angle.sin_cos();
         new_velocity = Vec2::new(
@@ -608,16 +1905,31 @@ pub fn compute_tile_effects(
     // Apply accumulated drag - reduces velocity magnitude while preserving direction
     if total_drag > 0.0 {
         // Clamp drag factor to prevent velocity reversal
-        let drag_factor = (1.0 - total_drag).max(0.0);
+        let drag_factor = (1.0 - total_drag).max(0.0).powf(steps);
         new_velocity *= drag_factor;
     }
 
     TileEffect {
         velocity: crate::stone::Velocity(new_velocity),
         did_hit_wall,
+        wall_normal,
     }
 }
 
+/// Sweeps `distance` worth of `tile_type` into the tile, updating which broom touched it last.
+/// Shared by pointer dragging and replay playback so both accrue sweeps identically.
+pub fn sweep(tile: &mut TileDragging, tile_type: &TileType, distance: f32) {
+    add_drag(&mut tile.distance_dragged, tile_type, distance);
+    tile.most_recent_tile_type = Some(tile_type.clone());
+}
+
+/// Roughs `distance` worth of sweeping back out of the tile, shifting it towards its base type.
+/// The most recent broom is left alone so the scratch-off progress shrinks instead of swapping colors.
+pub fn erase(tile: &mut TileDragging, distance: f32) {
+    let base_tile_type = tile.base_tile_type.clone();
+    add_drag(&mut tile.distance_dragged, &base_tile_type, distance);
+}
+
 // drag_distances should always sum to completely_swept_drag_distance
 // when adding a drag we need to subtract the drag_distance from all other tiles in drag_distances in the proportion of their current value to the sum of all values
 fn add_drag(
@@ -656,6 +1968,29 @@ fn add_drag(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        level::{CurrentLevel, get_level},
+        sim::PHYSICS_DT,
+        stone::{StoneSkin, StoneSkinAssets},
+    };
+
+    /// What the tiles do to a stone of `level`'s size at `stone_pos` over one physics step
+    fn effects_on(
+        level: &Level,
+        stone_pos: Vec2,
+        velocity: Vec2,
+        tiles: &[(Vec2, &TileDragging, f32)],
+    ) -> TileEffect {
+        compute_tile_effects(
+            stone_pos,
+            &crate::stone::Velocity(velocity),
+            level.stone_radius,
+            tiles,
+            &HexGrid::new(level),
+            &PhysicsParams::from_level(level),
+            PHYSICS_DT,
+        )
+    }
 
     #[test]
     fn test_add_drag_proportional_reduction() {
@@ -715,4 +2050,741 @@ mod tests {
         assert_eq!(distances.get(&TileType::SlowDown).copied().unwrap(), 100.0);
         assert_eq!(distances.values().sum::<f32>(), 100.0);
     }
+
+    #[test]
+    fn test_erase_returns_towards_base_type() {
+        let mut tile = TileDragging::new(&TileType::SlowDown, 100.0);
+        sweep(&mut tile, &TileType::MaintainSpeed, 100.0);
+
+        // Erasing 30.0 moves that much back from MaintainSpeed to the SlowDown base
+        erase(&mut tile, 30.0);
+
+        assert_eq!(tile.distance_dragged[&TileType::SlowDown], 30.0);
+        assert_eq!(tile.distance_dragged[&TileType::MaintainSpeed], 70.0);
+        // The broom stays the most recent so the scratch-off shows reduced progress
+        assert_eq!(tile.most_recent_tile_type, Some(TileType::MaintainSpeed));
+    }
+
+    #[test]
+    fn test_cycle_broom_type_wraps() {
+        // Forward from the last broom wraps to the first
+        assert_eq!(
+            cycle_broom_type(&TileType::TurnClockwise, 1),
+            TileType::MaintainSpeed
+        );
+        // Backward from the first broom wraps to the last
+        assert_eq!(
+            cycle_broom_type(&TileType::MaintainSpeed, -1),
+            TileType::TurnClockwise
+        );
+        assert_eq!(
+            cycle_broom_type(&TileType::MaintainSpeed, 1),
+            TileType::TurnCounterclockwise
+        );
+    }
+
+    #[test]
+    fn test_cycle_broom_type_skips_non_brooms() {
+        // Non-broom types are never produced, and count as the first broom
+        assert_eq!(
+            cycle_broom_type(&TileType::SlowDown, 1),
+            TileType::TurnCounterclockwise
+        );
+        for step in -4..=4 {
+            assert!(BROOM_TYPES.contains(&cycle_broom_type(&TileType::Goal, step)));
+        }
+    }
+
+    /// A SlowDown tile partway through a drag, so the next drag event sweeps it
+    fn mid_drag_tile() -> TileDragging {
+        let mut tile = TileDragging::new(&TileType::SlowDown, 250.0);
+        tile.last_position = Some(Vec2::ZERO);
+        tile
+    }
+
+    #[test]
+    fn test_drag_during_countdown_sweeps() {
+        let mut level = crate::level::get_level(crate::level::CurrentLevel::Level1);
+        // Pre-sweeping is allowed even on levels that forbid sweeping a moving stone
+        level.live_sweeping = false;
+        let mut tile = mid_drag_tile();
+        let stroke = drag_tile(
+            &mut tile,
+            Vec2::new(30.0, 40.0),
+            PointerButton::Primary,
+            &TileType::MaintainSpeed,
+            &level,
+            &GameState::Countdown,
+            1.0 / 60.0,
+            false,
+            &mut SweepBudget::default(),
+        );
+
+        assert_eq!(stroke, Some((Stroke::Sweep(TileType::MaintainSpeed), 50.0)));
+        assert_eq!(tile.distance_dragged[&TileType::MaintainSpeed], 50.0);
+    }
+
+    #[test]
+    fn test_drag_over_swept_tile_leaves_budget() {
+        let mut level = crate::level::get_level(crate::level::CurrentLevel::Level1);
+        level.sweep_budget = Some(1000.0);
+        let mut tile = TileDragging::new(&TileType::SlowDown, level.min_sweep_distance);
+        sweep(
+            &mut tile,
+            &TileType::MaintainSpeed,
+            level.min_sweep_distance - 10.0,
+        );
+        tile.last_position = Some(Vec2::ZERO);
+        let mut sweep_budget = SweepBudget::default();
+        let mut drag = |tile: &mut TileDragging, to: Vec2| {
+            drag_tile(
+                tile,
+                to,
+                PointerButton::Primary,
+                &TileType::MaintainSpeed,
+                &level,
+                &GameState::Countdown,
+                1.0 / 60.0,
+                false,
+                &mut sweep_budget,
+            )
+        };
+
+        // Only the last bit the tile still needed is paid for
+        let stroke = drag(&mut tile, Vec2::new(30.0, 40.0));
+        assert_eq!(stroke, Some((Stroke::Sweep(TileType::MaintainSpeed), 10.0)));
+        // Once it's swept, dragging over it again costs nothing
+        assert_eq!(drag(&mut tile, Vec2::ZERO), None);
+        assert_eq!(sweep_budget.used, 10.0);
+    }
+
+    #[test]
+    fn test_drag_while_playing_respects_live_sweeping() {
+        let mut level = crate::level::get_level(crate::level::CurrentLevel::Level1);
+        level.live_sweeping = false;
+        let mut tile = mid_drag_tile();
+        let stroke = drag_tile(
+            &mut tile,
+            Vec2::new(30.0, 40.0),
+            PointerButton::Primary,
+            &TileType::MaintainSpeed,
+            &level,
+            &GameState::Playing,
+            1.0 / 60.0,
+            false,
+            &mut SweepBudget::default(),
+        );
+
+        assert_eq!(stroke, None);
+        assert!(!tile.distance_dragged.contains_key(&TileType::MaintainSpeed));
+        // The drag is still tracked, so resuming doesn't sweep the distance skipped over
+        assert_eq!(tile.last_position, Some(Vec2::new(30.0, 40.0)));
+
+        level.live_sweeping = true;
+        assert!(
+            drag_tile(
+                &mut tile,
+                Vec2::new(60.0, 80.0),
+                PointerButton::Primary,
+                &TileType::MaintainSpeed,
+                &level,
+                &GameState::Playing,
+                1.0 / 60.0,
+                false,
+                &mut SweepBudget::default(),
+            )
+            .is_some()
+        );
+        assert_eq!(tile.distance_dragged[&TileType::MaintainSpeed], 50.0);
+    }
+
+    #[test]
+    fn test_precision_drag_sweeps_less() {
+        let mut level = crate::level::get_level(crate::level::CurrentLevel::Level1);
+        level.sweep_fast_multiplier = 1.2;
+        let mut tile = mid_drag_tile();
+        // Fast enough to be on the top of the speed curve
+        tile.drag_velocity = Vec2::new(1000.0, 0.0);
+        let stroke = drag_tile(
+            &mut tile,
+            Vec2::new(30.0, 40.0),
+            PointerButton::Primary,
+            &TileType::MaintainSpeed,
+            &level,
+            &GameState::Countdown,
+            1.0 / 60.0,
+            true,
+            &mut SweepBudget::default(),
+        );
+
+        // Both multipliers apply: 50 * 1.2 * 0.25
+        let (_, distance) = stroke.unwrap();
+        assert!((distance - 15.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_sweep_speed_multiplier_curve() {
+        let mut level = crate::level::get_level(crate::level::CurrentLevel::Level1);
+        // The default curve is flat, so speed makes no difference
+        assert_eq!(sweep_speed_multiplier(&level, 0.0), 1.0);
+        assert_eq!(sweep_speed_multiplier(&level, 5000.0), 1.0);
+
+        level.sweep_slow_multiplier = 0.5;
+        level.sweep_fast_multiplier = 1.2;
+        // Clamped at either end of the 200..800 ramp
+        assert_eq!(sweep_speed_multiplier(&level, 50.0), 0.5);
+        assert_eq!(sweep_speed_multiplier(&level, 1000.0), 1.2);
+        // Halfway along the ramp is halfway between the multipliers
+        assert!((sweep_speed_multiplier(&level, 500.0) - 0.85).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_broom_can_affect() {
+        // Walls and goals can never be swept
+        let wall = TileDragging::new(&TileType::Wall, 250.0);
+        assert!(!broom_can_affect(&wall, &TileType::MaintainSpeed, 250.0));
+        let goal = TileDragging::new(&TileType::Goal, 250.0);
+        assert!(!broom_can_affect(&goal, &TileType::MaintainSpeed, 250.0));
+
+        // A fully swept tile is done for that broom, but another broom can still change it
+        let mut tile = TileDragging::new(&TileType::SlowDown, 250.0);
+        assert!(broom_can_affect(&tile, &TileType::MaintainSpeed, 250.0));
+        sweep(&mut tile, &TileType::MaintainSpeed, 250.0);
+        assert!(!broom_can_affect(&tile, &TileType::MaintainSpeed, 250.0));
+        assert!(broom_can_affect(&tile, &TileType::TurnClockwise, 250.0));
+    }
+
+    #[test]
+    fn test_no_sweeping_while_aiming() {
+        let level = crate::level::get_level(crate::level::CurrentLevel::Level1);
+        assert!(!sweeping_allowed(&GameState::Aiming, &level));
+        assert!(sweeping_allowed(&GameState::Countdown, &level));
+    }
+
+    #[test]
+    fn test_restarting_reuses_tile_assets() {
+        // Restarting the same level over and over must not keep adding materials and meshes, the
+        // stones' included
+        let level = get_level(CurrentLevel::Level1);
+        let grid = HexGrid::new(&level);
+        let mut meshes = Assets::<Mesh>::default();
+        let mut materials = Assets::<ColorMaterial>::default();
+        let mut tile_assets = TileAssets::new(
+            &mut meshes,
+            &mut materials,
+            &grid,
+            level.speed_up_arrow_radius,
+        );
+        let mut skin_assets = StoneSkinAssets::new(Handle::default(), Handle::default());
+        let mut restart = |tile_assets: &mut TileAssets,
+                           meshes: &mut Assets<Mesh>,
+                           materials: &mut Assets<ColorMaterial>,
+                           grid: &HexGrid| {
+            tile_assets.rebuild_if_changed(meshes, materials, grid, level.speed_up_arrow_radius);
+            for stone_config in &level.stone_configs {
+                skin_assets.body_mesh(meshes, level.stone_radius);
+                for skin in StoneSkin::iterator() {
+                    skin_assets.stone_materials(materials, *skin, stone_config.color);
+                }
+            }
+        };
+        restart(&mut tile_assets, &mut meshes, &mut materials, &grid);
+        let (mesh_count, material_count) = (meshes.len(), materials.len());
+        for _ in 0..20 {
+            restart(&mut tile_assets, &mut meshes, &mut materials, &grid);
+        }
+        assert_eq!(meshes.len(), mesh_count);
+        assert_eq!(materials.len(), material_count);
+
+        // A different hex size needs new meshes
+        let mut bigger = level.clone();
+        bigger.hex_radius *= 2.0;
+        let hex_mesh = tile_assets.hex_mesh.clone();
+        restart(
+            &mut tile_assets,
+            &mut meshes,
+            &mut materials,
+            &HexGrid::new(&bigger),
+        );
+        assert_ne!(tile_assets.hex_mesh, hex_mesh);
+    }
+
+    #[test]
+    fn test_theme_recolors_tile_materials_in_place() {
+        let level = get_level(CurrentLevel::Level1);
+        let mut meshes = Assets::<Mesh>::default();
+        let mut materials = Assets::<ColorMaterial>::default();
+        let tile_assets = TileAssets::new(
+            &mut meshes,
+            &mut materials,
+            &HexGrid::new(&level),
+            level.speed_up_arrow_radius,
+        );
+        let colors = |materials: &Assets<ColorMaterial>| {
+            let mut colors: Vec<_> = materials
+                .iter()
+                .map(|(id, material)| (id, material.color))
+                .collect();
+            colors.sort_by_key(|(id, _)| *id);
+            colors
+        };
+        let day = colors(&materials);
+        tile_assets.apply_theme(
+            &mut materials,
+            &TileTheme::Night.colors(),
+            TilePalette::Normal,
+        );
+        let night = colors(&materials);
+        // Same materials, nothing added, but every one of them recolored
+        assert_eq!(night.len(), day.len());
+        for ((day_id, day_color), (night_id, night_color)) in day.iter().zip(&night) {
+            assert_eq!(day_id, night_id);
+            assert_ne!(day_color, night_color);
+        }
+        // Every theme is reachable from the settings button, and none of them recolor the
+        // accessibility palettes
+        assert_eq!(TileTheme::Day.next().next().next(), TileTheme::Day);
+        for theme in TileTheme::iterator() {
+            assert_eq!(
+                theme
+                    .colors()
+                    .tile_color(TilePalette::HighContrast, &TileType::Goal),
+                TilePalette::HighContrast.tile_color(&TileType::Goal)
+            );
+        }
+    }
+
+    #[test]
+    fn test_hover_outline_follows_the_hovered_tile() {
+        let mut world = World::new();
+        let outline = world
+            .spawn((HoverOutline, Transform::default(), Visibility::Hidden))
+            .id();
+        let tile_transform = Transform::from_xyz(120.0, -40.0, 0.0)
+            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_6))
+            .with_scale(Vec3::splat(0.5));
+        let tile = world
+            .spawn((
+                tile_transform,
+                TileDragging::new(&TileType::MaintainSpeed, 100.0),
+            ))
+            .id();
+        let wall = world
+            .spawn((
+                Transform::default(),
+                TileDragging::new(&TileType::Wall, 100.0),
+            ))
+            .id();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(update_hover_outline);
+        let outline_state = |world: &World| {
+            let entity = world.entity(outline);
+            (
+                *entity.get::<Transform>().unwrap(),
+                *entity.get::<Visibility>().unwrap(),
+            )
+        };
+
+        schedule.run(&mut world);
+        assert_eq!(outline_state(&world).1, Visibility::Hidden);
+        // It sits over the hovered tile, shrunk with it for scaled down tiles
+        world.entity_mut(tile).insert(MouseHover);
+        schedule.run(&mut world);
+        let (transform, visibility) = outline_state(&world);
+        assert_eq!(visibility, Visibility::Inherited);
+        assert_eq!(
+            transform.translation,
+            Vec3::new(120.0, -40.0, HOVER_OUTLINE_Z)
+        );
+        assert_eq!(transform.rotation, tile_transform.rotation);
+        assert_eq!(transform.scale, tile_transform.scale);
+        // Walls aren't outlined
+        world.entity_mut(tile).remove::<MouseHover>();
+        world.entity_mut(wall).insert(MouseHover);
+        schedule.run(&mut world);
+        assert_eq!(outline_state(&world).1, Visibility::Hidden);
+    }
+
+    #[test]
+    fn test_tile_effects_fast_path_matches_clipping() {
+        // A stone centered on a tile of each type gets the same effect with and without the fast
+        // path, which skips clipping the stone against every tile
+        let level = get_level(CurrentLevel::Level1);
+        let grid = HexGrid::new(&level);
+        let tile_types = [
+            TileType::MaintainSpeed,
+            TileType::SlowDown,
+            TileType::TurnClockwise,
+            TileType::Goal,
+            TileType::SpeedUp(Facing::UpRight),
+        ];
+        for tile_type in tile_types {
+            let neighbor = TileDragging::new(&TileType::Wall, level.min_sweep_distance);
+            let tile = TileDragging::new(&tile_type, level.min_sweep_distance);
+            let center = Vec2::new(100.0, 50.0);
+            let tiles = [
+                (center, &tile, 1.0),
+                (
+                    center + Vec2::new(grid.horiz_spacing, grid.vert_spacing / 2.0),
+                    &neighbor,
+                    1.0,
+                ),
+            ];
+            // Slightly off center so the Goal's pull has a direction
+            let stone_pos = center + Vec2::new(3.0, -2.0);
+            let velocity = crate::stone::Velocity(Vec2::new(120.0, 40.0));
+            let effect = |fast_path| {
+                tile_effects(
+                    stone_pos,
+                    &velocity,
+                    level.stone_radius,
+                    &tiles,
+                    &grid,
+                    &PhysicsParams::from_level(&level),
+                    PHYSICS_DT,
+                    fast_path,
+                )
+            };
+            let (fast, clipped) = (effect(true), effect(false));
+            assert!(
+                fast.velocity.0.distance(clipped.velocity.0) < 1e-3,
+                "{tile_type:?}: {:?} vs {:?}",
+                fast.velocity.0,
+                clipped.velocity.0
+            );
+            assert_eq!(fast.did_hit_wall, clipped.did_hit_wall);
+        }
+    }
+
+    #[test]
+    fn test_wall_friction_slows_stone_sliding_along_wall() {
+        let level = get_level(CurrentLevel::Level1);
+        let grid = HexGrid::new(&level);
+        let wall = TileDragging::new(&TileType::Wall, level.min_sweep_distance);
+        let tiles = [(Vec2::ZERO, &wall, 1.0)];
+        // Centered on the middle of an edge, half the stone over the wall, moving along the edge
+        let normal = hex_edge_normal(Vec2::from_angle(std::f32::consts::FRAC_PI_6));
+        let stone_pos = normal * grid.hex_radius * 3f32.sqrt() / 2.0;
+        let slide = |wall_friction: f32| {
+            let level = Level {
+                wall_friction,
+                ..level.clone()
+            };
+            let mut velocity = normal.perp() * 200.0;
+            for _ in 0..60 {
+                velocity = effects_on(&level, stone_pos, velocity, &tiles).velocity.0;
+            }
+            velocity
+        };
+        // Without friction nothing slows a stone skimming the wall
+        assert!((slide(0.0).length() - 200.0).abs() < 1e-3);
+        let slowed = slide(level.wall_friction);
+        assert!(slowed.length() < 150.0, "{slowed:?}");
+        // Only slowed, not turned into or away from the wall
+        assert!(slowed.dot(normal).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_speed_gate_only_lets_fast_stones_through() {
+        let level = get_level(CurrentLevel::Level1);
+        let grid = HexGrid::new(&level);
+        let gate = TileDragging::new(
+            &TileType::SpeedGate { threshold: 200.0 },
+            level.min_sweep_distance,
+        );
+        let tiles = [(Vec2::ZERO, &gate, 1.0)];
+        // Centered on the middle of an edge, half the stone over the gate, heading into it
+        let normal = hex_edge_normal(Vec2::from_angle(std::f32::consts::FRAC_PI_6));
+        let stone_pos = normal * grid.hex_radius * 3f32.sqrt() / 2.0;
+        let hit_gate = |speed: f32| effects_on(&level, stone_pos, -normal * speed, &tiles);
+        // Just under the threshold it bounces straight back out
+        let bounced = hit_gate(199.0);
+        assert!(bounced.did_hit_wall);
+        assert!(
+            bounced.velocity.0.dot(normal) > 0.0,
+            "{:?}",
+            bounced.velocity
+        );
+        // Just over, it carries on in, only dragged like on straight ice
+        let passed = hit_gate(201.0);
+        assert!(!passed.did_hit_wall);
+        assert!(passed.velocity.0.dot(normal) < 0.0, "{:?}", passed.velocity);
+    }
+
+    #[test]
+    fn test_straddling_a_wall_and_a_turn_blends_by_overlap() {
+        let level = get_level(CurrentLevel::Level1);
+        let grid = HexGrid::new(&level);
+        let turn = TileDragging::new(&TileType::TurnCounterclockwise, level.min_sweep_distance);
+        let straight = TileDragging::new(&TileType::MaintainSpeed, level.min_sweep_distance);
+        let wall = TileDragging::new(&TileType::Wall, level.min_sweep_distance);
+        // A turn tile in the middle, with its neighbor across the edge facing `normal`
+        let normal = hex_edge_normal(Vec2::from_angle(std::f32::consts::FRAC_PI_6));
+        let apothem = grid.hex_radius * 3f32.sqrt() / 2.0;
+        let neighbor = normal * apothem * 2.0;
+        // Heading into the neighbor at an angle
+        let velocity = crate::stone::Velocity((normal + normal.perp() * 0.5) * 200.0);
+        let effect = |stone_pos: Vec2, tiles: &[(Vec2, &TileDragging, f32)]| {
+            effects_on(&level, stone_pos, velocity.0, tiles)
+        };
+        let turned = |effect: &TileEffect| velocity.0.angle_to(effect.velocity.0);
+
+        // Half over a straight tile, it's turned half as much as right inside the turn tile
+        let full_turn = turned(&effect(Vec2::ZERO, &[(Vec2::ZERO, &turn, 1.0)]));
+        let on_edge = normal * apothem;
+        let half_turn = turned(&effect(
+            on_edge,
+            &[(Vec2::ZERO, &turn, 1.0), (neighbor, &straight, 1.0)],
+        ));
+        assert!(full_turn > 0.0);
+        assert!(
+            (half_turn / full_turn - 0.5).abs() < 0.05,
+            "{half_turn} {full_turn}"
+        );
+
+        // Half over a wall, it bounces straight off it without the turn tile turning it back in
+        let against_wall = [(Vec2::ZERO, &turn, 1.0), (neighbor, &wall, 1.0)];
+        let bounced = effect(on_edge, &against_wall);
+        let bounced_off_straight = effect(
+            on_edge,
+            &[(Vec2::ZERO, &straight, 1.0), (neighbor, &wall, 1.0)],
+        );
+        assert!(bounced.did_hit_wall);
+        assert!(
+            bounced.velocity.0.dot(normal) < 0.0,
+            "{:?}",
+            bounced.velocity
+        );
+        assert!(
+            bounced.velocity.0.distance(bounced_off_straight.velocity.0) < 1e-3,
+            "{:?} vs {:?}",
+            bounced.velocity,
+            bounced_off_straight.velocity
+        );
+
+        // Only just grazing the wall, it's not bounced and the turn tile still turns it
+        let wall_ratio = |stone_pos: Vec2| {
+            intersection::ratio_circle_area_inside_hexagon(
+                stone_pos,
+                level.stone_radius,
+                neighbor,
+                tile_radius(&grid, 1.0),
+                level.overlap_samples,
+            )
+        };
+        let grazing = (0..200)
+            .map(|step| normal * (apothem - level.stone_radius + step as f32 * 0.1))
+            .find(|stone_pos| {
+                (MIN_EFFECT_RATIO..WALL_PRIORITY_RATIO).contains(&wall_ratio(*stone_pos))
+            })
+            .unwrap();
+        let grazed = effect(grazing, &against_wall);
+        assert!(!grazed.did_hit_wall);
+        assert!(grazed.velocity.0.dot(normal) > 0.0);
+        assert!(turned(&grazed) > full_turn * 0.9, "{}", turned(&grazed));
+    }
+
+    #[test]
+    fn test_grazing_a_wall_leaves_the_other_tiles_their_share() {
+        let level = get_level(CurrentLevel::Level1);
+        let grid = HexGrid::new(&level);
+        let slow = TileDragging::new(&TileType::SlowDown, level.min_sweep_distance);
+        let wall = TileDragging::new(&TileType::Wall, level.min_sweep_distance);
+        let normal = hex_edge_normal(Vec2::from_angle(std::f32::consts::FRAC_PI_6));
+        let apothem = grid.hex_radius * 3f32.sqrt() / 2.0;
+        let neighbor = normal * apothem * 2.0;
+        let velocity = crate::stone::Velocity(normal.perp() * 200.0);
+        let effect = |stone_pos: Vec2, tiles: &[(Vec2, &TileDragging, f32)]| {
+            effects_on(&level, stone_pos, velocity.0, tiles)
+        };
+        let wall_ratio = |stone_pos: Vec2| {
+            intersection::ratio_circle_area_inside_hexagon(
+                stone_pos,
+                level.stone_radius,
+                neighbor,
+                tile_radius(&grid, 1.0),
+                level.overlap_samples,
+            )
+        };
+        let grazing = (0..200)
+            .map(|step| normal * (apothem - level.stone_radius + step as f32 * 0.1))
+            .find(|stone_pos| {
+                (MIN_EFFECT_RATIO..WALL_PRIORITY_RATIO).contains(&wall_ratio(*stone_pos))
+            })
+            .unwrap();
+
+        // The slow tile slows the stone exactly as much as if the wall weren't there
+        let grazed = effect(grazing, &[(Vec2::ZERO, &slow, 1.0), (neighbor, &wall, 1.0)]);
+        let alone = effect(grazing, &[(Vec2::ZERO, &slow, 1.0)]);
+        assert!(!grazed.did_hit_wall);
+        assert!(
+            grazed.velocity.0.distance(alone.velocity.0) < 1e-4,
+            "{:?} vs {:?}",
+            grazed.velocity,
+            alone.velocity
+        );
+    }
+
+    #[test]
+    fn test_scaled_down_tile_only_affects_its_own_area() {
+        let level = get_level(CurrentLevel::Level1);
+        let grid = HexGrid::new(&level);
+        let slow = TileDragging::new(&TileType::SlowDown, level.min_sweep_distance);
+        // Within a full size tile, but past the edge of a half size one
+        let stone_pos = Vec2::new(grid.hex_radius * 0.7, 0.0);
+        let velocity = crate::stone::Velocity(Vec2::new(0.0, 100.0));
+        let speed_with_scale = |scale: f32| {
+            effects_on(&level, stone_pos, velocity.0, &[(Vec2::ZERO, &slow, scale)])
+                .velocity
+                .0
+                .length()
+        };
+        assert!(speed_with_scale(1.0) < 100.0);
+        assert_eq!(speed_with_scale(0.5), 100.0);
+    }
+
+    #[test]
+    fn test_near_miss_only_just_outside_the_wall() {
+        let level = get_level(CurrentLevel::Level1);
+        let grid = HexGrid::new(&level);
+        // Straight out from the tile's flat top edge, measured from the stone's edge
+        let apothem = tile_radius(&grid, 1.0) * 3.0_f32.sqrt() / 2.0;
+        let near_miss = |gap: f32| {
+            let stone_pos = Vec2::new(0.0, apothem + level.stone_radius + gap);
+            nearly_clips_tile(
+                stone_pos,
+                level.stone_radius,
+                Vec2::ZERO,
+                &grid,
+                1.0,
+                level.overlap_samples,
+            )
+        };
+        assert!(near_miss(NEAR_MISS_MARGIN / 2.0));
+        // Far enough away is no miss at all, and overlapping is a hit instead
+        assert!(!near_miss(NEAR_MISS_MARGIN * 2.0));
+        assert!(!near_miss(-level.stone_radius));
+    }
+
+    #[test]
+    fn test_palettes_keep_tile_types_distinct() {
+        let tile_types = [
+            TileType::Wall,
+            TileType::MaintainSpeed,
+            TileType::SlowDown,
+            TileType::TurnCounterclockwise,
+            TileType::TurnClockwise,
+            TileType::Goal,
+        ];
+        for palette in [
+            TilePalette::Normal,
+            TilePalette::Deuteranopia,
+            TilePalette::HighContrast,
+        ] {
+            for (i, a) in tile_types.iter().enumerate() {
+                for b in &tile_types[i + 1..] {
+                    assert_ne!(
+                        palette.tile_color(a),
+                        palette.tile_color(b),
+                        "{palette:?}: {a:?} and {b:?}"
+                    );
+                }
+            }
+        }
+        // Every palette is reachable from the settings button
+        assert_eq!(
+            TilePalette::Normal.next().next().next(),
+            TilePalette::Normal
+        );
+    }
+
+    #[test]
+    fn test_settled_type_only_after_full_conversion() {
+        let mut tile = TileDragging::new(&TileType::SlowDown, 100.0);
+        assert_eq!(tile.settled_type(100.0), Some(TileType::SlowDown));
+        // Halfway through a sweep the tile is neither type yet
+        sweep(&mut tile, &TileType::MaintainSpeed, 50.0);
+        assert_eq!(tile.settled_type(100.0), None);
+        // Within the tolerance of a full sweep counts as converted
+        sweep(&mut tile, &TileType::MaintainSpeed, 49.0);
+        assert_eq!(tile.settled_type(100.0), Some(TileType::MaintainSpeed));
+    }
+
+    #[test]
+    fn test_mirror_stroke_follows_the_setting_and_the_budget() {
+        let mut level = get_level(CurrentLevel::Level1);
+        // Columns 0 to 4, so (0, 1) mirrors onto (4, 1)
+        level.grid = (0..5)
+            .flat_map(|q| (0..3).map(move |r| (HexCoordinate { q, r }, TileType::SlowDown)))
+            .collect();
+        level.sweep_budget = Some(15.0);
+        let swept = HexCoordinate { q: 0, r: 1 };
+        let mirrored = HexCoordinate { q: 4, r: 1 };
+        let mut world = World::new();
+        let mirror_entity = world
+            .spawn((
+                TileDragging::new(&TileType::SlowDown, level.min_sweep_distance),
+                mirrored.clone(),
+            ))
+            .id();
+        let mut tiles = world.query::<(Entity, &mut TileDragging, &HexCoordinate)>();
+        let mut replay_recording = ReplayRecording::default();
+        let mut sweep_budget = SweepBudget::default();
+        let mut stroke_undo = StrokeUndo::default();
+        let stroke = Stroke::Sweep(TileType::MaintainSpeed);
+        let mut mirror = |world: &mut World, mirror_sweep: bool, sweep_budget: &mut SweepBudget| {
+            mirror_stroke(
+                &MirrorSweep(mirror_sweep),
+                &level,
+                &swept,
+                &stroke,
+                10.0,
+                tiles.iter_mut(world),
+                0,
+                &mut replay_recording,
+                sweep_budget,
+                &mut stroke_undo,
+            );
+        };
+        let dragged = |world: &World| {
+            world
+                .entity(mirror_entity)
+                .get::<TileDragging>()
+                .unwrap()
+                .distance_dragged
+                .clone()
+        };
+        let unswept = dragged(&world);
+
+        // With the setting off the mirror tile is left alone
+        mirror(&mut world, false, &mut sweep_budget);
+        assert_eq!(dragged(&world), unswept);
+        assert_eq!(sweep_budget.used, 0.0);
+
+        // With it on the mirror tile gets the stroke, paid for out of the budget
+        mirror(&mut world, true, &mut sweep_budget);
+        assert_ne!(dragged(&world), unswept);
+        assert_eq!(sweep_budget.used, 10.0);
+
+        // Only what's left of the budget is swept
+        mirror(&mut world, true, &mut sweep_budget);
+        assert_eq!(sweep_budget.used, 15.0);
+
+        // A mirror tile that's already swept takes nothing from the budget and isn't recorded
+        let mut mirror_tile = world.get_mut::<TileDragging>(mirror_entity).unwrap();
+        sweep(
+            &mut mirror_tile,
+            &TileType::MaintainSpeed,
+            level.min_sweep_distance,
+        );
+        let mut fresh_budget = SweepBudget::default();
+        mirror(&mut world, true, &mut fresh_budget);
+        assert_eq!(fresh_budget.used, 0.0);
+
+        // Recorded against the mirror tile, so replays sweep it too
+        let actions = &replay_recording.0.actions;
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].coordinate, mirrored);
+        assert_eq!(actions[0].distance, 15.0);
+    }
 }
@@ -0,0 +1,319 @@
+//! Keyboard-only sweeping. The arrow keys move a cursor between tiles and holding Enter or Space
+//! sweeps the tile under it with the current broom. Moving the mouse hands control back to it.
+
+use bevy::{input::mouse::AccumulatedMouseMotion, prelude::*};
+use bevy_egui::input::EguiWantsInput;
+
+use crate::{
+    PausableSystems,
+    gameplay::GameState,
+    hex_grid::{HexCoordinate, HexGrid, hex_to_world},
+    layers,
+    level::{Facing, OnLevel},
+    level_transition::transitioning,
+    replay::{ReplayClock, ReplayPlayback, ReplayRecording},
+    screens::Screen,
+    sweep_budget::SweepBudget,
+    tile::{CanBeDragged, CurrentDragTileType, MouseHover, Stroke, TileDragging, sweeping_allowed},
+};
+
+const SWEEP_KEYS: [KeyCode; 3] = [KeyCode::Enter, KeyCode::NumpadEnter, KeyCode::Space];
+
+const ARROW_KEYS: [KeyCode; 4] = [
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+];
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<TileCursor>()
+        .add_systems(OnEnter(Screen::Gameplay), spawn_tile_cursor_ring)
+        .add_systems(
+            Update,
+            (
+                hide_tile_cursor_on_mouse_move,
+                move_tile_cursor.run_if(not(transitioning)),
+                hover_tile_cursor,
+                sweep_with_tile_cursor.run_if(not(transitioning)),
+                update_tile_cursor_ring,
+            )
+                .chain()
+                .run_if(in_state(Screen::Gameplay).and(resource_exists::<OnLevel>))
+                .in_set(PausableSystems),
+        );
+}
+
+/// The tile selected with the keyboard. Only shown and swept once an arrow key has been pressed.
+#[derive(Resource, Default)]
+pub struct TileCursor {
+    coordinate: Option<HexCoordinate>,
+    visible: bool,
+}
+
+impl TileCursor {
+    /// Whether the keyboard is in use for sweeping
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+}
+
+/// Outline drawn above the tile the cursor is on
+#[derive(Component)]
+struct TileCursorRing;
+
+/// Moves one tile from `from` towards `arrow`, wrapping around at the edges of the grid's bounds
+/// and skipping over coordinates that have no tile.
+fn step_cursor(hex_grid: &HexGrid, from: &HexCoordinate, arrow: KeyCode) -> HexCoordinate {
+    let width = hex_grid.cols.1 - hex_grid.cols.0;
+    let height = hex_grid.rows.1 - hex_grid.rows.0;
+    let mut next = from.clone();
+    for _ in 0..width * height {
+        next = next.neighbor(&arrow_facing(arrow, &next));
+        next.q = hex_grid.cols.0 + (next.q - hex_grid.cols.0).rem_euclid(width);
+        next.r = hex_grid.rows.0 + (next.r - hex_grid.rows.0).rem_euclid(height);
+        if hex_grid.level.grid.contains_key(&next) {
+            return next;
+        }
+    }
+    from.clone()
+}
+
+/// Direction an arrow key moves in from `coordinate`. Left and right stay on the same row, which
+/// zig-zags between the up and down diagonals depending on the column.
+fn arrow_facing(arrow: KeyCode, coordinate: &HexCoordinate) -> Facing {
+    let odd_column = coordinate.q.rem_euclid(2) == 1;
+    match (arrow, odd_column) {
+        (KeyCode::ArrowLeft, false) => Facing::UpLeft,
+        (KeyCode::ArrowLeft, true) => Facing::DownLeft,
+        (KeyCode::ArrowRight, false) => Facing::UpRight,
+        (KeyCode::ArrowRight, true) => Facing::DownRight,
+        (KeyCode::ArrowDown, _) => Facing::Down,
+        _ => Facing::Up,
+    }
+}
+
+fn spawn_tile_cursor_ring(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut tile_cursor: ResMut<TileCursor>,
+) {
+    *tile_cursor = TileCursor::default();
+    commands.spawn((
+        DespawnOnExit(Screen::Gameplay),
+        TileCursorRing,
+        // Unit sized, scaled up to the level's hex radius
+        Mesh2d(meshes.add(Annulus::new(0.8, 0.95))),
+        MeshMaterial2d(materials.add(Color::WHITE)),
+        Transform::from_xyz(0., 0., layers::FX + 0.1),
+        Visibility::Hidden,
+    ));
+}
+
+fn remove_cursor_hover(
+    commands: &mut Commands,
+    tiles: &Query<(Entity, &HexCoordinate), With<TileDragging>>,
+    coordinate: &HexCoordinate,
+) {
+    for (entity, tile_coordinate) in tiles {
+        if tile_coordinate == coordinate {
+            commands.entity(entity).remove::<MouseHover>();
+        }
+    }
+}
+
+fn hide_tile_cursor_on_mouse_move(
+    mut commands: Commands,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    mut tile_cursor: ResMut<TileCursor>,
+    tiles: Query<(Entity, &HexCoordinate), With<TileDragging>>,
+) {
+    if !tile_cursor.visible || mouse_motion.delta == Vec2::ZERO {
+        return;
+    }
+    tile_cursor.visible = false;
+    if let Some(coordinate) = &tile_cursor.coordinate {
+        remove_cursor_hover(&mut commands, &tiles, coordinate);
+    }
+}
+
+fn move_tile_cursor(
+    mut commands: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    egui_wants_input: Option<Res<EguiWantsInput>>,
+    on_level: Res<OnLevel>,
+    hex_grid: Single<&HexGrid>,
+    mut tile_cursor: ResMut<TileCursor>,
+    tiles: Query<(Entity, &HexCoordinate), With<TileDragging>>,
+) {
+    if egui_wants_input.is_some_and(|egui| egui.wants_any_keyboard_input()) {
+        return;
+    }
+    let level = &on_level.0;
+    // Start from the first stone, or anywhere if the cursor fell off after a level change
+    let current = tile_cursor
+        .coordinate
+        .clone()
+        .filter(|coordinate| level.grid.contains_key(coordinate))
+        .or_else(|| {
+            level
+                .stone_configs
+                .first()
+                .map(|stone_config| stone_config.start_coordinate.clone())
+        })
+        .or_else(|| level.grid.keys().next().cloned());
+    let Some(current) = current else {
+        return;
+    };
+    let Some(arrow) = ARROW_KEYS.into_iter().find(|key| input.just_pressed(*key)) else {
+        return;
+    };
+
+    // The first press only reveals the cursor where it already is
+    let next = if tile_cursor.visible {
+        step_cursor(&hex_grid, &current, arrow)
+    } else {
+        current
+    };
+    if let Some(previous) = &tile_cursor.coordinate
+        && previous != &next
+    {
+        remove_cursor_hover(&mut commands, &tiles, previous);
+    }
+    tile_cursor.coordinate = Some(next);
+    tile_cursor.visible = true;
+}
+
+/// Marks the cursor's tile as hovered, the same way the mouse does. Runs every frame since
+/// restarting the level respawns the tiles.
+fn hover_tile_cursor(
+    mut commands: Commands,
+    tile_cursor: Res<TileCursor>,
+    tiles: Query<(Entity, &HexCoordinate, Has<MouseHover>), With<TileDragging>>,
+) {
+    if !tile_cursor.visible {
+        return;
+    }
+    for (entity, coordinate, hovered) in &tiles {
+        if !hovered && Some(coordinate) == tile_cursor.coordinate.as_ref() {
+            commands.entity(entity).insert(MouseHover);
+        }
+    }
+}
+
+fn sweep_with_tile_cursor(
+    input: Res<ButtonInput<KeyCode>>,
+    egui_wants_input: Option<Res<EguiWantsInput>>,
+    time: Res<Time>,
+    tile_cursor: Res<TileCursor>,
+    mut tiles: Query<(&HexCoordinate, &mut TileDragging), With<CanBeDragged>>,
+    current_drag_tile_type: Res<CurrentDragTileType>,
+    on_level: Res<OnLevel>,
+    game_state: Res<State<GameState>>,
+    replay_clock: Res<ReplayClock>,
+    mut replay_recording: ResMut<ReplayRecording>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    mut sweep_budget: ResMut<SweepBudget>,
+) {
+    let egui_has_focus = egui_wants_input.is_some_and(|egui| egui.wants_any_keyboard_input());
+    // Same rules as sweeping with the mouse
+    let blocked = replay_playback.is_some() || !sweeping_allowed(game_state.get(), &on_level.0);
+    if !tile_cursor.visible || egui_has_focus || blocked || !input.any_pressed(SWEEP_KEYS) {
+        return;
+    }
+    let Some((coordinate, mut tile)) = tiles
+        .iter_mut()
+        .find(|(coordinate, _)| Some(*coordinate) == tile_cursor.coordinate.as_ref())
+    else {
+        return;
+    };
+
+    let level = &on_level.0;
+    let broom = &current_drag_tile_type.0;
+    let distance = sweep_budget.spend(
+        level,
+        (level.keyboard_sweep_rate * time.delta_secs())
+            .min(tile.distance_to_complete(broom, level.min_sweep_distance)),
+    );
+    if distance <= 0.0 {
+        return;
+    }
+    let stroke = Stroke::Sweep(broom.clone());
+    stroke.apply(&mut tile, distance);
+    replay_recording.record(replay_clock.0, coordinate, &stroke, distance);
+}
+
+fn update_tile_cursor_ring(
+    tile_cursor: Res<TileCursor>,
+    hex_grid: Single<&HexGrid>,
+    ring: Single<(&mut Transform, &mut Visibility), With<TileCursorRing>>,
+) {
+    let (mut transform, mut visibility) = ring.into_inner();
+    let Some(coordinate) = tile_cursor
+        .coordinate
+        .as_ref()
+        .filter(|_| tile_cursor.visible)
+    else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Visible;
+    let position = hex_to_world(coordinate, &hex_grid);
+    transform.translation.x = position.x;
+    transform.translation.y = position.y;
+    transform.scale = Vec3::splat(hex_grid.tile_radius(coordinate));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        level::{CurrentLevel, get_level},
+        tile::TileType,
+    };
+
+    /// A level whose tiles fill q in 0..3 and r in 0..2, minus any `holes`
+    fn rectangle_grid(holes: &[HexCoordinate]) -> HexGrid {
+        let mut level = get_level(CurrentLevel::Level1);
+        level.grid = (0..3)
+            .flat_map(|q| (0..2).map(move |r| HexCoordinate { q, r }))
+            .filter(|coordinate| !holes.contains(coordinate))
+            .map(|coordinate| (coordinate, TileType::SlowDown))
+            .collect();
+        HexGrid::new(&level)
+    }
+
+    #[test]
+    fn test_step_cursor_wraps_at_edges() {
+        let hex_grid = rectangle_grid(&[]);
+        // Off the right edge comes back in on the left
+        assert_eq!(
+            step_cursor(
+                &hex_grid,
+                &HexCoordinate { q: 2, r: 1 },
+                KeyCode::ArrowRight
+            ),
+            HexCoordinate { q: 0, r: 1 }
+        );
+        // Off the bottom comes back in at the top
+        assert_eq!(
+            step_cursor(&hex_grid, &HexCoordinate { q: 1, r: 1 }, KeyCode::ArrowDown),
+            HexCoordinate { q: 1, r: 0 }
+        );
+    }
+
+    #[test]
+    fn test_step_cursor_skips_missing_tiles() {
+        let hex_grid = rectangle_grid(&[HexCoordinate { q: 1, r: 1 }]);
+        // Moving right stays on the same row, jumping over the hole
+        assert_eq!(
+            step_cursor(
+                &hex_grid,
+                &HexCoordinate { q: 0, r: 1 },
+                KeyCode::ArrowRight
+            ),
+            HexCoordinate { q: 2, r: 1 }
+        );
+    }
+}
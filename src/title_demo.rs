@@ -0,0 +1,259 @@
+//! Looping background demo for the title screen. A canned winning sweep of Level1 is played
+//! through the headless sim and drawn behind the main menu to show newcomers how the game plays.
+
+use bevy::prelude::*;
+
+use crate::{
+    gameplay::{create_tapered_line_mesh, simulate_trajectories},
+    hex_grid::HexCoordinate,
+    layers,
+    level::{CurrentLevel, Level, get_level},
+    replay::{Replay, SweepAction},
+    screens::Screen,
+    sim::HeadlessSim,
+    tile::{COLORS, Stroke, TILE_BORDER_Z, TILE_FILL_Z, TileType, get_tile_color},
+};
+
+const DEMO_LEVEL: CurrentLevel = CurrentLevel::Level1;
+
+/// Physics steps to linger on the finished shot before starting over
+const DEMO_HOLD_TICKS: u32 = 128;
+
+/// Safety net so a demo that never settles still loops
+const DEMO_MAX_TICKS: u32 = 2000;
+
+/// Each demo sweep is spread over this many physics steps so the tile visibly fills in
+const DEMO_STROKES_PER_SWEEP: u32 = 10;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::Title), spawn_title_demo)
+        .add_systems(OnExit(Screen::Title), remove_title_demo)
+        .add_systems(
+            FixedUpdate,
+            step_title_demo.run_if(in_state(Screen::Title).and(resource_exists::<TitleDemo>)),
+        )
+        .add_systems(
+            Update,
+            draw_title_demo.run_if(in_state(Screen::Title).and(resource_exists::<TitleDemo>)),
+        );
+}
+
+#[derive(Resource)]
+struct TitleDemo {
+    level: Level,
+    replay: Replay,
+    sim: HeadlessSim,
+    next_action: usize,
+    hold_ticks: u32,
+    line_material: Handle<ColorMaterial>,
+}
+
+impl TitleDemo {
+    fn restart(&mut self) {
+        self.sim = HeadlessSim::new(&self.level);
+        self.next_action = 0;
+        self.hold_ticks = 0;
+    }
+}
+
+#[derive(Component)]
+struct DemoTile(usize);
+
+#[derive(Component)]
+struct DemoStone(usize);
+
+#[derive(Component)]
+struct DemoMoveLine;
+
+/// Sweeps Level1's SlowDown corridor into MaintainSpeed, each tile a little before the stone
+/// reaches it, which is enough to carry the stone into the goal.
+fn demo_replay(level: &Level) -> Replay {
+    let sweeps = [
+        (0, HexCoordinate { q: 2, r: 1 }),
+        (25, HexCoordinate { q: 3, r: 2 }),
+        (65, HexCoordinate { q: 4, r: 2 }),
+        (115, HexCoordinate { q: 5, r: 3 }),
+        (175, HexCoordinate { q: 6, r: 3 }),
+    ];
+    let stroke_distance = level.min_sweep_distance / DEMO_STROKES_PER_SWEEP as f32;
+
+    let mut replay = Replay::new(level.current_level);
+    for (start_tick, coordinate) in sweeps {
+        for tick in start_tick..start_tick + DEMO_STROKES_PER_SWEEP {
+            replay.actions.push(SweepAction {
+                tick,
+                coordinate: coordinate.clone(),
+                stroke: Stroke::Sweep(TileType::MaintainSpeed),
+                distance: stroke_distance,
+            });
+        }
+    }
+    replay
+}
+
+fn spawn_title_demo(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let level = get_level(DEMO_LEVEL);
+    let sim = HeadlessSim::new(&level);
+
+    let border_thickness = 1.0;
+    let hex_mesh = meshes.add(RegularPolygon::new(
+        sim.grid.hex_radius - border_thickness,
+        6,
+    ));
+    let hex_border_mesh = meshes.add(RegularPolygon::new(sim.grid.hex_radius, 6));
+    let border_material = materials.add(Color::BLACK);
+
+    for (index, tile) in sim.tiles.iter().enumerate() {
+        commands.spawn((
+            DespawnOnExit(Screen::Title),
+            DemoTile(index),
+            Mesh2d(hex_mesh.clone()),
+            MeshMaterial2d(materials.add(get_tile_color(&tile.dragging.base_tile_type))),
+            Transform::from_xyz(tile.position.x, tile.position.y, TILE_FILL_Z)
+                .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_6)),
+            children![(
+                Mesh2d(hex_border_mesh.clone()),
+                MeshMaterial2d(border_material.clone()),
+                Transform::from_xyz(0., 0., layers::child_z(TILE_BORDER_Z, TILE_FILL_Z, 1.0)),
+            )],
+        ));
+    }
+
+    let stone_mesh = meshes.add(Circle::new(level.stone_radius));
+    for (index, (stone, stone_config)) in sim.stones.iter().zip(&level.stone_configs).enumerate() {
+        commands.spawn((
+            DespawnOnExit(Screen::Title),
+            DemoStone(index),
+            Mesh2d(stone_mesh.clone()),
+            MeshMaterial2d(materials.add(stone_config.color)),
+            Transform::from_xyz(stone.position.x, stone.position.y, layers::STONE),
+        ));
+    }
+
+    commands.insert_resource(TitleDemo {
+        replay: demo_replay(&level),
+        level,
+        sim,
+        next_action: 0,
+        hold_ticks: 0,
+        line_material: materials.add(COLORS[5]),
+    });
+}
+
+fn remove_title_demo(mut commands: Commands) {
+    commands.remove_resource::<TitleDemo>();
+}
+
+fn step_title_demo(mut title_demo: ResMut<TitleDemo>, time: Res<Time<Fixed>>) {
+    let title_demo = &mut *title_demo;
+
+    if title_demo.sim.is_settled() || title_demo.sim.tick >= DEMO_MAX_TICKS {
+        title_demo.hold_ticks += 1;
+        if title_demo.hold_ticks >= DEMO_HOLD_TICKS {
+            title_demo.restart();
+        }
+        return;
+    }
+
+    title_demo.sim.step_replay(
+        &title_demo.replay,
+        &mut title_demo.next_action,
+        time.delta_secs(),
+    );
+}
+
+fn draw_title_demo(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    title_demo: Res<TitleDemo>,
+    tiles: Query<(&DemoTile, &MeshMaterial2d<ColorMaterial>)>,
+    mut stones: Query<(&DemoStone, &mut Transform)>,
+    lines: Query<Entity, With<DemoMoveLine>>,
+    fixed_time: Res<Time<Fixed>>,
+) {
+    let sim = &title_demo.sim;
+
+    for (demo_stone, mut transform) in &mut stones {
+        if let Some(stone) = sim.stones.get(demo_stone.0) {
+            transform.translation.x = stone.position.x;
+            transform.translation.y = stone.position.y;
+        }
+    }
+
+    // Fade each tile from its base color towards the broom that swept it
+    for (demo_tile, material) in &tiles {
+        let Some(tile) = sim.tiles.get(demo_tile.0) else {
+            continue;
+        };
+        let Some(material) = materials.get_mut(&material.0) else {
+            continue;
+        };
+        let base_color = get_tile_color(&tile.dragging.base_tile_type).to_linear();
+        material.color = match &tile.dragging.most_recent_tile_type {
+            Some(tile_type) => {
+                let progress = tile
+                    .dragging
+                    .distance_dragged
+                    .get(tile_type)
+                    .map_or(0.0, |distance| {
+                        distance / title_demo.level.min_sweep_distance
+                    });
+                base_color
+                    .mix(
+                        &get_tile_color(tile_type).to_linear(),
+                        progress.clamp(0.0, 1.0),
+                    )
+                    .into()
+            }
+            None => base_color.into(),
+        };
+    }
+
+    for line in &lines {
+        commands.entity(line).despawn();
+    }
+    if sim.is_settled() {
+        return;
+    }
+    let tile_data: Vec<_> = sim
+        .tiles
+        .iter()
+        .map(|tile| (tile.position, &tile.dragging, tile.scale))
+        .collect();
+    let trajectories = simulate_trajectories(
+        sim.stones.clone(),
+        &tile_data,
+        &sim.grid,
+        &sim.params,
+        fixed_time.timestep().as_secs_f32(),
+    );
+    for trajectory in trajectories {
+        if let Some(mesh) = create_tapered_line_mesh(&trajectory.points, 6.0, 1.0) {
+            commands.spawn((
+                DespawnOnExit(Screen::Title),
+                DemoMoveLine,
+                Mesh2d(meshes.add(mesh)),
+                MeshMaterial2d(title_demo.line_material.clone()),
+                Transform::from_xyz(0., 0., layers::PREDICTION),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_demo_replay_wins() {
+        // The demo should always show a successful shot
+        let level = get_level(DEMO_LEVEL);
+        let mut sim = HeadlessSim::new(&level);
+        assert!(sim.run_replay(&demo_replay(&level), 1.0 / 64.0, DEMO_MAX_TICKS));
+    }
+}
@@ -0,0 +1,243 @@
+//! Sweeping with a finger. Touches are read straight from `TouchInput` and fed through the same
+//! `drag_tile` as mouse drags, which skip touch pointers so nothing is swept twice. Only the first
+//! finger down sweeps, any others are ignored until it lifts.
+
+use bevy::{input::touch::TouchPhase, prelude::*};
+
+use crate::{
+    PausableSystems,
+    camera::MainCamera,
+    fire_trail::SweepShavings,
+    gameplay::GameState,
+    hex_grid::{HexCoordinate, HexGrid, world_to_hex_tolerant},
+    level::OnLevel,
+    replay::{ReplayClock, ReplayPlayback, ReplayRecording},
+    screens::Screen,
+    sweep_budget::SweepBudget,
+    tile::{
+        CanBeDragged, CurrentDragTileType, MirrorSweep, Stroke, SweepSpeedReadout, TileDragging,
+        drag_tile, mirror_stroke, precision_scale, sweep_speed_multiplier,
+    },
+    undo::StrokeUndo,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<TouchSweep>()
+        .add_systems(OnEnter(Screen::Gameplay), reset_touch_sweep)
+        .add_systems(
+            Update,
+            sweep_with_touch
+                .run_if(in_state(Screen::Gameplay).and(resource_exists::<OnLevel>))
+                .in_set(PausableSystems),
+        );
+}
+
+/// The finger that's sweeping, and the tile it was last over
+#[derive(Resource, Default, Debug)]
+pub struct TouchSweep {
+    finger: Option<u64>,
+    tile: Option<Entity>,
+}
+
+impl TouchSweep {
+    /// Claims sweeping for `finger` if no other finger is down
+    pub fn start(&mut self, finger: u64) -> bool {
+        if self.finger.is_some() {
+            return false;
+        }
+        self.finger = Some(finger);
+        self.tile = None;
+        true
+    }
+
+    pub fn is_sweeping(&self, finger: u64) -> bool {
+        self.finger == Some(finger)
+    }
+
+    /// Lets go of sweeping if `finger` had it
+    pub fn end(&mut self, finger: u64) -> bool {
+        if !self.is_sweeping(finger) {
+            return false;
+        }
+        *self = TouchSweep::default();
+        true
+    }
+}
+
+fn reset_touch_sweep(mut touch_sweep: ResMut<TouchSweep>) {
+    *touch_sweep = TouchSweep::default();
+}
+
+/// A stroke runs from the finger touching down to it lifting, so a single undo takes it all back
+fn sweep_with_touch(
+    mut touch_inputs: MessageReader<TouchInput>,
+    mut touch_sweep: ResMut<TouchSweep>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    hex_grid: Single<&HexGrid>,
+    mut tiles: Query<(Entity, &HexCoordinate, &mut TileDragging), With<CanBeDragged>>,
+    current_drag_tile_type: Res<CurrentDragTileType>,
+    mirror_sweep: Res<MirrorSweep>,
+    on_level: Res<OnLevel>,
+    game_state: Res<State<GameState>>,
+    time: Res<Time>,
+    (replay_clock, mut replay_recording): (Res<ReplayClock>, ResMut<ReplayRecording>),
+    replay_playback: Option<Res<ReplayPlayback>>,
+    mut sweep_speed_readout: ResMut<SweepSpeedReadout>,
+    mut stroke_undo: ResMut<StrokeUndo>,
+    mut sweep_budget: ResMut<SweepBudget>,
+    mut sweep_shavings: SweepShavings,
+) {
+    let (camera, camera_transform) = *camera;
+    let level = &on_level.0;
+    // Several moves can arrive in one frame, only the first one's drag speed is measured
+    let mut dt = time.delta_secs();
+    for touch in touch_inputs.read() {
+        match touch.phase {
+            // Touching down only marks where the finger is, below, so a tap sweeps nothing
+            TouchPhase::Started => {
+                if touch_sweep.start(touch.id) {
+                    stroke_undo.begin(replay_recording.0.mark(), sweep_budget.used);
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Canceled => {
+                if touch_sweep.end(touch.id) {
+                    stroke_undo.end(replay_recording.0.mark());
+                    sweep_speed_readout.0 = None;
+                    for (_, _, mut tile) in &mut tiles {
+                        tile.drag_velocity = Vec2::ZERO;
+                        tile.last_position = None;
+                    }
+                }
+                continue;
+            }
+            TouchPhase::Moved => {}
+        }
+        // Player input is ignored while a replay is driving the sweeps
+        if !touch_sweep.is_sweeping(touch.id) || replay_playback.is_some() {
+            continue;
+        }
+        // Sweep in world space so zooming the camera doesn't change how far a drag sweeps
+        let Ok(position) = camera.viewport_to_world_2d(camera_transform, touch.position) else {
+            continue;
+        };
+        let entity = world_to_hex_tolerant(position, &hex_grid, level.pick_tolerance).and_then(
+            |coordinate| {
+                tiles
+                    .iter()
+                    .find(|(_, tile_coordinate, _)| **tile_coordinate == coordinate)
+                    .map(|(entity, _, _)| entity)
+            },
+        );
+        // Moving onto another tile starts measuring from there, the same as a mouse drag entering it
+        if entity != touch_sweep.tile {
+            if let Some(previous) = touch_sweep.tile
+                && let Ok((_, _, mut tile)) = tiles.get_mut(previous)
+            {
+                tile.drag_velocity = Vec2::ZERO;
+                tile.last_position = None;
+            }
+            touch_sweep.tile = entity;
+        }
+        let Some((entity, coordinate, mut tile)) =
+            entity.and_then(|entity| tiles.get_mut(entity).ok())
+        else {
+            continue;
+        };
+        let before = tile.clone();
+        let swept = drag_tile(
+            &mut tile,
+            position,
+            PointerButton::Primary,
+            &current_drag_tile_type.0,
+            level,
+            game_state.get(),
+            dt,
+            false,
+            &mut sweep_budget,
+        );
+        dt = 0.0;
+        let Some((stroke, distance)) = swept else {
+            continue;
+        };
+        stroke_undo.touch(entity, &before);
+        if let Stroke::Sweep(_) = stroke {
+            sweep_speed_readout.0 = Some(
+                sweep_speed_multiplier(level, tile.drag_velocity.length())
+                    * precision_scale(level, false),
+            );
+            if let Some(from) = before.last_position {
+                sweep_shavings.spawn(
+                    from,
+                    position,
+                    distance,
+                    &current_drag_tile_type.0,
+                    tile.drag_velocity.length(),
+                );
+            }
+        }
+        replay_recording.record(replay_clock.0, coordinate, &stroke, distance);
+        let coordinate = coordinate.clone();
+        mirror_stroke(
+            &mirror_sweep,
+            level,
+            &coordinate,
+            &stroke,
+            distance,
+            tiles
+                .iter_mut()
+                .map(|(entity, coordinate, tile)| (entity, tile, coordinate)),
+            replay_clock.0,
+            &mut replay_recording,
+            &mut sweep_budget,
+            &mut stroke_undo,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        level::{CurrentLevel, get_level},
+        tile::TileType,
+    };
+
+    #[test]
+    fn test_only_the_first_finger_sweeps() {
+        let mut touch_sweep = TouchSweep::default();
+        assert!(touch_sweep.start(1));
+        // A second finger down at the same time is ignored, and lifting it changes nothing
+        assert!(!touch_sweep.start(2));
+        assert!(!touch_sweep.is_sweeping(2));
+        assert!(!touch_sweep.end(2));
+        assert!(touch_sweep.is_sweeping(1));
+        // Once the first finger lifts, the next one down takes over
+        assert!(touch_sweep.end(1));
+        assert!(touch_sweep.start(2));
+    }
+
+    #[test]
+    fn test_tap_sweeps_nothing() {
+        let level = get_level(CurrentLevel::Level1);
+        let mut tile = TileDragging::new(&TileType::SlowDown, level.min_sweep_distance);
+        let mut sweep_budget = SweepBudget::default();
+        let broom = TileType::MaintainSpeed;
+        let mut drag = |position: Vec2| {
+            drag_tile(
+                &mut tile,
+                position,
+                PointerButton::Primary,
+                &broom,
+                &level,
+                &GameState::Countdown,
+                1.0 / 60.0,
+                false,
+                &mut sweep_budget,
+            )
+        };
+        // Touching down only marks where the finger is, and holding still doesn't move it
+        assert_eq!(drag(Vec2::ZERO), None);
+        assert_eq!(drag(Vec2::ZERO), None);
+        assert!(drag(Vec2::new(10.0, 0.0)).is_some());
+    }
+}
@@ -0,0 +1,384 @@
+//! Step-by-step prompts for the early levels. Each level can have a `TutorialScript` whose steps
+//! are shown one at a time until the player does what the step asks, with a spotlight that darkens
+//! everything but the tile or UI the step is about.
+
+use bevy::{ecs::spawn::SpawnIter, prelude::*};
+
+use crate::{
+    PausableSystems,
+    camera::MainCamera,
+    gameplay::GameState,
+    hex_grid::HexCoordinate,
+    level::{CurrentLevel, OnLevel},
+    localization::Localized,
+    screens::Screen,
+    tile::{BROOM_TYPES, CurrentDragTileType, TileChanged, TileType},
+    ui::BroomToolbar,
+};
+
+const SHADE_COLOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+/// Room left around the spotlit target, in logical pixels
+const SPOTLIGHT_PADDING: f32 = 12.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<TutorialProgress>()
+        .add_systems(OnEnter(Screen::Gameplay), spawn_tutorial_overlay)
+        .add_systems(OnEnter(GameState::Countdown), reset_tutorial)
+        .add_systems(OnEnter(GameState::Playing), on_stone_launched)
+        .add_systems(
+            Update,
+            (advance_on_broom, update_tutorial_overlay)
+                .chain()
+                .run_if(in_state(Screen::Gameplay))
+                .in_set(PausableSystems),
+        )
+        .add_observer(advance_on_tile_changed);
+}
+
+/// What a step points the player at
+#[derive(Debug, Clone, PartialEq)]
+pub enum TutorialHighlight {
+    Tile(HexCoordinate),
+    BroomToolbar,
+}
+
+/// What the player has to do to finish a step
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepCondition {
+    /// Sweeps the tile at the coordinate over to any broom
+    SweptTile(HexCoordinate),
+    /// Sweeps any tile over to any broom
+    SweptAnyTile,
+    /// Has the broom selected, including if it already was when the step came up
+    SwitchedBroom(TileType),
+    StoneLaunched,
+}
+
+/// Something the player did that might finish the current step
+#[derive(Debug, Clone, PartialEq)]
+pub enum TutorialAction {
+    SweptTile(HexCoordinate),
+    SelectedBroom(TileType),
+    StoneLaunched,
+}
+
+impl StepCondition {
+    pub fn is_met_by(&self, action: &TutorialAction) -> bool {
+        match (self, action) {
+            (StepCondition::SweptTile(target), TutorialAction::SweptTile(swept)) => target == swept,
+            (StepCondition::SweptAnyTile, TutorialAction::SweptTile(_)) => true,
+            (StepCondition::SwitchedBroom(target), TutorialAction::SelectedBroom(selected)) => {
+                target == selected
+            }
+            (StepCondition::StoneLaunched, TutorialAction::StoneLaunched) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TutorialStep {
+    /// Localization key of what the step says
+    pub text: &'static str,
+    pub highlight: Option<TutorialHighlight>,
+    pub until: StepCondition,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TutorialScript(pub Vec<TutorialStep>);
+
+pub fn tutorial_script(current_level: CurrentLevel) -> TutorialScript {
+    let steps = match current_level {
+        CurrentLevel::Level0 => vec![TutorialStep {
+            text: "tutorial-sweep-tile",
+            highlight: Some(TutorialHighlight::Tile(HexCoordinate { q: 0, r: 0 })),
+            until: StepCondition::SweptTile(HexCoordinate { q: 0, r: 0 }),
+        }],
+        CurrentLevel::Level1 => vec![
+            TutorialStep {
+                text: "tutorial-stone-untouchable",
+                highlight: None,
+                until: StepCondition::StoneLaunched,
+            },
+            TutorialStep {
+                text: "tutorial-sweep-speed",
+                highlight: None,
+                until: StepCondition::SweptAnyTile,
+            },
+        ],
+        CurrentLevel::Level2 => vec![TutorialStep {
+            text: "tutorial-number-keys",
+            highlight: Some(TutorialHighlight::BroomToolbar),
+            until: StepCondition::SwitchedBroom(BROOM_TYPES[1].clone()),
+        }],
+        CurrentLevel::Level3 => vec![
+            TutorialStep {
+                text: "tutorial-third-broom",
+                highlight: Some(TutorialHighlight::BroomToolbar),
+                until: StepCondition::SwitchedBroom(BROOM_TYPES[2].clone()),
+            },
+            TutorialStep {
+                text: "tutorial-restart",
+                highlight: None,
+                until: StepCondition::StoneLaunched,
+            },
+        ],
+        _ => vec![],
+    };
+    TutorialScript(steps)
+}
+
+/// The current level's script and how far into it the player is. Reset with every attempt.
+#[derive(Resource, Debug, Default)]
+pub struct TutorialProgress {
+    pub script: TutorialScript,
+    pub step: usize,
+}
+
+impl TutorialProgress {
+    pub fn new(script: TutorialScript) -> Self {
+        Self { script, step: 0 }
+    }
+
+    pub fn current_step(&self) -> Option<&TutorialStep> {
+        self.script.0.get(self.step)
+    }
+
+    /// Moves on to the next step if `action` finishes the current one
+    pub fn advance(&mut self, action: &TutorialAction) -> bool {
+        let finished = self
+            .current_step()
+            .is_some_and(|step| step.until.is_met_by(action));
+        if finished {
+            self.step += 1;
+        }
+        finished
+    }
+}
+
+#[derive(Component)]
+struct TutorialOverlay;
+
+#[derive(Component)]
+struct TutorialText;
+
+/// One of the four shades around the spotlight, in the order top, bottom, left, right
+#[derive(Component)]
+struct SpotlightShade(usize);
+
+fn reset_tutorial(on_level: Res<OnLevel>, mut progress: ResMut<TutorialProgress>) {
+    *progress = TutorialProgress::new(tutorial_script(on_level.0.current_level));
+}
+
+fn advance_on_tile_changed(
+    tile_changed: On<TileChanged>,
+    tiles: Query<&HexCoordinate>,
+    mut progress: ResMut<TutorialProgress>,
+) {
+    if !BROOM_TYPES.contains(&tile_changed.new) {
+        return;
+    }
+    if let Ok(coordinate) = tiles.get(tile_changed.entity) {
+        progress.advance(&TutorialAction::SweptTile(coordinate.clone()));
+    }
+}
+
+/// Checked every frame so a step asking for the broom that's already selected finishes right away
+fn advance_on_broom(
+    current_drag_tile_type: Res<CurrentDragTileType>,
+    mut progress: ResMut<TutorialProgress>,
+) {
+    let action = TutorialAction::SelectedBroom(current_drag_tile_type.0.clone());
+    let finishes = progress
+        .current_step()
+        .is_some_and(|step| step.until.is_met_by(&action));
+    if finishes {
+        progress.advance(&action);
+    }
+}
+
+fn on_stone_launched(mut progress: ResMut<TutorialProgress>) {
+    progress.advance(&TutorialAction::StoneLaunched);
+}
+
+fn spawn_tutorial_overlay(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Tutorial Overlay"),
+        DespawnOnExit(Screen::Gameplay),
+        TutorialOverlay,
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        Visibility::Hidden,
+        // The player still has to reach the target through the overlay
+        Pickable::IGNORE,
+        Children::spawn((
+            SpawnIter((0..4).map(|side| {
+                (
+                    SpotlightShade(side),
+                    Node {
+                        position_type: PositionType::Absolute,
+                        ..default()
+                    },
+                    BackgroundColor(SHADE_COLOR),
+                    Visibility::Hidden,
+                    Pickable::IGNORE,
+                )
+            })),
+            Spawn((
+                TutorialText,
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(80.0),
+                    padding: UiRect::all(Val::Px(20.0)),
+                    ..default()
+                },
+                Localized::new(""),
+                TextFont {
+                    font_size: 30.0,
+                    ..default()
+                },
+                TextLayout::new_with_justify(Justify::Center),
+                TextColor(Color::WHITE),
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+                Pickable::IGNORE,
+            )),
+        )),
+    ));
+}
+
+/// Screen rect of the step's target in UI units, if it's on screen right now
+fn highlight_rect(
+    highlight: &TutorialHighlight,
+    hex_radius: f32,
+    ui_scale: f32,
+    camera: (&Camera, &GlobalTransform),
+    tiles: &Query<(&HexCoordinate, &GlobalTransform)>,
+    toolbar: &Query<(&ComputedNode, &UiGlobalTransform), With<BroomToolbar>>,
+) -> Option<Rect> {
+    match highlight {
+        TutorialHighlight::Tile(target) => {
+            let (_, tile_transform) = tiles.iter().find(|(coordinate, _)| *coordinate == target)?;
+            let (camera, camera_transform) = camera;
+            let center = tile_transform.translation();
+            let corner = Vec3::new(hex_radius, hex_radius, 0.0);
+            let a = camera
+                .world_to_viewport(camera_transform, center - corner)
+                .ok()?;
+            let b = camera
+                .world_to_viewport(camera_transform, center + corner)
+                .ok()?;
+            Some(Rect::from_corners(a / ui_scale, b / ui_scale))
+        }
+        TutorialHighlight::BroomToolbar => {
+            let (node, transform) = toolbar.iter().next()?;
+            let scale = node.inverse_scale_factor();
+            Some(Rect::from_center_size(
+                transform.translation * scale,
+                node.size() * scale,
+            ))
+        }
+    }
+}
+
+/// Left, top, width and height of the shade on `side` of `hole` in a window of `window_size`
+fn shade_bounds(side: usize, hole: Rect, window_size: Vec2) -> (f32, f32, f32, f32) {
+    match side {
+        0 => (0.0, 0.0, window_size.x, hole.min.y),
+        1 => (0.0, hole.max.y, window_size.x, window_size.y - hole.max.y),
+        2 => (0.0, hole.min.y, hole.min.x, hole.height()),
+        _ => (
+            hole.max.x,
+            hole.min.y,
+            window_size.x - hole.max.x,
+            hole.height(),
+        ),
+    }
+}
+
+fn update_tutorial_overlay(
+    progress: Res<TutorialProgress>,
+    game_state: Res<State<GameState>>,
+    on_level: Res<OnLevel>,
+    window: Single<&Window>,
+    ui_scale: Res<UiScale>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    tiles: Query<(&HexCoordinate, &GlobalTransform)>,
+    toolbar: Query<(&ComputedNode, &UiGlobalTransform), With<BroomToolbar>>,
+    mut overlay: Single<&mut Visibility, (With<TutorialOverlay>, Without<SpotlightShade>)>,
+    mut text: Single<&mut Localized, With<TutorialText>>,
+    mut shades: Query<(&SpotlightShade, &mut Node, &mut Visibility), Without<TutorialOverlay>>,
+) {
+    // The win and fail screens have their own overlays
+    let step = progress
+        .current_step()
+        .filter(|_| !matches!(game_state.get(), GameState::Won | GameState::Lost));
+    let Some(step) = step else {
+        overlay.set_if_neq(Visibility::Hidden);
+        return;
+    };
+    overlay.set_if_neq(Visibility::Inherited);
+    if text.key != step.text {
+        **text = Localized::new(step.text);
+    }
+
+    let hole = step.highlight.as_ref().and_then(|highlight| {
+        highlight_rect(
+            highlight,
+            on_level.0.hex_radius,
+            ui_scale.0,
+            *camera,
+            &tiles,
+            &toolbar,
+        )
+    });
+    for (shade, mut node, mut visibility) in &mut shades {
+        let Some(hole) = hole else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        visibility.set_if_neq(Visibility::Inherited);
+        let (left, top, width, height) = shade_bounds(
+            shade.0,
+            hole.inflate(SPOTLIGHT_PADDING),
+            window.size() / ui_scale.0,
+        );
+        node.left = Val::Px(left);
+        node.top = Val::Px(top);
+        node.width = Val::Px(width.max(0.0));
+        node.height = Val::Px(height.max(0.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tutorial_advances_only_on_current_step() {
+        let mut progress = TutorialProgress::new(tutorial_script(CurrentLevel::Level3));
+        // Sweeping or launching doesn't skip the broom step
+        assert!(!progress.advance(&TutorialAction::StoneLaunched));
+        assert!(!progress.advance(&TutorialAction::SelectedBroom(BROOM_TYPES[1].clone())));
+        assert_eq!(progress.step, 0);
+
+        assert!(progress.advance(&TutorialAction::SelectedBroom(BROOM_TYPES[2].clone())));
+        assert!(progress.advance(&TutorialAction::StoneLaunched));
+        // Nothing left to advance past
+        assert!(progress.current_step().is_none());
+        assert!(!progress.advance(&TutorialAction::StoneLaunched));
+    }
+
+    #[test]
+    fn test_swept_tile_step_needs_its_tile() {
+        let target = HexCoordinate { q: 0, r: 0 };
+        let condition = StepCondition::SweptTile(target.clone());
+        assert!(!condition.is_met_by(&TutorialAction::SweptTile(HexCoordinate { q: 1, r: 0 })));
+        assert!(condition.is_met_by(&TutorialAction::SweptTile(target.clone())));
+        assert!(StepCondition::SweptAnyTile.is_met_by(&TutorialAction::SweptTile(target)));
+    }
+}
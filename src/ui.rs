@@ -1,12 +1,28 @@
-use bevy::prelude::*;
+use std::mem::discriminant;
+
+use bevy::{
+    ecs::spawn::SpawnIter, input::common_conditions::input_just_pressed, prelude::*,
+    window::PrimaryWindow,
+};
 
 use crate::{
     PausableSystems,
-    gameplay::{GameState, StoneStopped},
+    camera::FOLLOW_KEY,
+    gameplay::{GameState, RESTART_KEY},
+    input_buffer::InputBuffer,
     level::CurrentLevel,
-    level::OnLevel,
+    level::{Level, OnLevel},
+    level_transition::transitioning,
+    localization::Localized,
+    menus::btn,
+    rewind::REWIND_KEY,
     screens::Screen,
-    tile::{CurrentDragTileType, TileType},
+    tile::{
+        BROOM_TYPES, CurrentDragTileType, PRECISION_KEYS, SweepSpeedReadout, SweptTileType,
+        TileChanged, TileDragging, TilePalette, TileTheme, TileType, get_tile_color,
+    },
+    tile_cursor::TileCursor,
+    undo::UNDO_KEY,
 };
 
 #[derive(Component)]
@@ -18,8 +34,31 @@ struct CountdownUI;
 #[derive(Component)]
 struct BroomTypeText;
 
+/// A toolbar button that switches to its broom when clicked
+#[derive(Component)]
+struct BroomButton(TileType);
+
+const BROOM_BUTTON_SIZE: f32 = 48.0;
+const BROOM_BUTTON_ACTIVE_BORDER: Color = Color::WHITE;
+const BROOM_BUTTON_INACTIVE_BORDER: Color = Color::srgba(0.0, 0.0, 0.0, 0.7);
+
+/// Shown next to the broom while Shift is held for precision sweeping
 #[derive(Component)]
-struct StoneStoppedUI;
+struct PrecisionText;
+
+/// Briefly scales up the broom text after the broom changes so the switch is noticeable
+#[derive(Component)]
+struct BroomPulse(Timer);
+
+const BROOM_PULSE_SECONDS: f32 = 0.25;
+const BROOM_PULSE_SCALE: f32 = 0.3;
+
+/// Small label next to the cursor showing how much the current drag speed is counting for
+#[derive(Component)]
+struct SweepSpeedText;
+
+/// Distance in pixels between the cursor and the sweep speed label
+const SWEEP_SPEED_TEXT_OFFSET: Vec2 = Vec2::new(16.0, 16.0);
 
 #[derive(Component)]
 struct TipUI;
@@ -33,24 +72,85 @@ struct Countdown {
 #[derive(Component)]
 pub struct BroomUI;
 
-#[derive(Component)]
-pub struct Level0UI;
-
 #[derive(Component)]
 pub struct BottomLeftUI;
 
 #[derive(Component)]
 pub struct MainUI;
 
+/// The row of broom buttons along the bottom
+#[derive(Component)]
+pub struct BroomToolbar;
+
+/// The tile legend's list, collapsed down to its button until opened
+#[derive(Component)]
+struct TileLegendEntries;
+
+/// A legend row's color swatch, recolored along with the palette
+#[derive(Component)]
+struct LegendSwatch(TileType);
+
+/// Whether the tile legend is open, kept from level to level
+#[derive(Resource, Default)]
+struct TileLegendOpen(bool);
+
+/// How close Level0 is to being swept, along the bottom of the screen
+#[derive(Component)]
+struct Level0Progress;
+
+#[derive(Component)]
+struct Level0ProgressFill;
+
+/// Pops up over the progress bar once it's full
+#[derive(Component)]
+struct NiceFlourish;
+
+/// Shrinks the "Nice!" down from big as it appears
+#[derive(Component)]
+struct FlourishPop(Timer);
+
+const LEVEL_0_PROGRESS_WIDTH: f32 = 320.0;
+const LEVEL_0_PROGRESS_COLOR: Color = Color::srgb(0.35, 0.85, 1.0);
+const NICE_FLOURISH_SECONDS: f32 = 0.4;
+const NICE_FLOURISH_SCALE: f32 = 0.8;
+
+const LEGEND_KEY: KeyCode = KeyCode::KeyL;
+const LEGEND_SWATCH_SIZE: f32 = 18.0;
+
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(Startup, setup)
+    app.init_resource::<SweepSpeedReadout>()
+        .init_resource::<TileLegendOpen>()
+        .add_systems(Startup, setup)
+        .add_systems(OnEnter(Screen::Gameplay), spawn_sweep_speed_text)
         .add_systems(
             Update,
-            (update_broom_type_ui, update_countdown)
+            (
+                update_broom_type_ui,
+                animate_broom_pulse,
+                update_precision_text,
+                update_broom_toolbar,
+                update_countdown.run_if(not(transitioning)),
+                update_level_0_progress,
+                animate_nice_flourish,
+            )
                 .run_if(in_state(Screen::Gameplay))
                 .in_set(PausableSystems),
         )
-        .add_observer(on_stone_stopped);
+        .add_systems(
+            Update,
+            (
+                toggle_tile_legend_on_key.run_if(input_just_pressed(LEGEND_KEY)),
+                update_tile_legend,
+            )
+                .chain()
+                .run_if(in_state(Screen::Gameplay)),
+        )
+        .add_systems(
+            Update,
+            update_sweep_speed_text
+                .run_if(in_state(Screen::Gameplay).and(resource_exists::<OnLevel>)),
+        )
+        .add_observer(show_nice_flourish);
     app.add_systems(OnEnter(GameState::Countdown), on_level_start);
 }
 
@@ -88,7 +188,7 @@ fn countdown_ui(time_left: u32) -> impl Bundle {
     )
 }
 
-fn spawn_bottom_left_ui(mut commands: Commands, current_level: &CurrentLevel) {
+fn spawn_bottom_left_ui(mut commands: Commands, level: &Level) {
     commands
         .spawn((
             DespawnOnExit(Screen::Gameplay),
@@ -127,7 +227,7 @@ fn spawn_bottom_left_ui(mut commands: Commands, current_level: &CurrentLevel) {
                     BackgroundColor(Color::srgba(1.0, 1.0, 1.0, 0.5)),
                 ))
                 .with_children(|p3| {
-                    if let Some(tips) = tip_ui(current_level) {
+                    if let Some(tips) = tip_ui(&level.current_level) {
                         for tip in tips {
                             p3.spawn(tip);
                         }
@@ -147,7 +247,7 @@ fn spawn_bottom_left_ui(mut commands: Commands, current_level: &CurrentLevel) {
                 ))
                 .with_children(|p3| {
                     p3.spawn((
-                        Text::new("Controls"),
+                        Localized::new("controls-title"),
                         TextFont {
                             font_size: 25.0,
                             ..default()
@@ -155,103 +255,68 @@ fn spawn_bottom_left_ui(mut commands: Commands, current_level: &CurrentLevel) {
                         TextColor(Color::WHITE),
                         Pickable::IGNORE,
                     ));
-                    p3.spawn((
-                        Text::new("R: Restart Level"),
-                        TextFont {
-                            font_size: 20.0,
-                            ..default()
-                        },
-                        TextColor(Color::WHITE),
-                        Pickable::IGNORE,
-                    ));
-                    p3.spawn((
-                        Text::new("1-3: Switch Brooms"),
-                        TextFont {
-                            font_size: 20.0,
-                            ..default()
-                        },
-                        TextColor(Color::WHITE),
-                        Pickable::IGNORE,
-                    ));
+                    for control in controls(level) {
+                        p3.spawn((
+                            control,
+                            TextFont {
+                                font_size: 20.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                            Pickable::IGNORE,
+                        ));
+                    }
                 });
             });
         });
 }
 
-fn level_0_ui() -> impl Bundle {
-    (
-        Node {
-            width: Val::Percent(100.0),
-            justify_content: JustifyContent::Center,
-            padding: UiRect::all(Val::Px(50.0)),
-            flex_direction: FlexDirection::Column,
-            row_gap: Val::Px(20.0),
-            ..default()
-        },
-        Pickable::IGNORE,
-        Level0UI,
-        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
-        children![
-            (
-                Text::new("Let's practice that sweeping technique!"),
-                TextFont {
-                    font_size: 30.0,
-                    ..default()
-                },
-                TextColor(Color::WHITE),
-                Pickable::IGNORE,
-            ),
-            (
-                Text::new("Click and drag on the tile to make it smooth"),
-                TextFont {
-                    font_size: 30.0,
-                    ..default()
-                },
-                TextColor(Color::WHITE),
-                Pickable::IGNORE,
-            )
-        ],
-    )
-}
-
-fn level_1_tip_ui() -> Vec<Text> {
-    [
-        Text::new("You can't affect the stone directly"),
-        Text::new("Sweep tiles to control the speed"),
-    ]
-    .to_vec()
-}
-
-fn level_2_tip_ui() -> Vec<Text> {
-    [
-        Text::new("Number keys change broom types"),
-        Text::new("#2 sure might be handy"),
-    ]
-    .to_vec()
+/// The controls listed for `level`. Controls bound to a single key take its name from the
+/// binding itself, so the list can't drift from the keys the game listens for.
+fn controls(level: &Level) -> Vec<Localized> {
+    let bound = |key: &'static str, key_code: KeyCode| {
+        Localized::new(key).with_arg("key", key_name(key_code))
+    };
+    let mut controls = vec![
+        bound("controls-restart", RESTART_KEY),
+        Localized::new("controls-brooms"),
+        Localized::new("controls-erase"),
+        Localized::new("controls-precision"),
+        bound("controls-undo", UNDO_KEY),
+        bound("controls-legend", LEGEND_KEY),
+        Localized::new("controls-keyboard"),
+    ];
+    if level.rewind {
+        controls.push(bound("controls-rewind", REWIND_KEY));
+    }
+    controls.extend([
+        bound("controls-follow", FOLLOW_KEY),
+        Localized::new("controls-camera"),
+    ]);
+    controls
 }
 
-fn level_3_tip_ui() -> Vec<Text> {
-    [
-        Text::new("I wonder what the #3 does..."),
-        Text::new("Remember that you can hit R at any time to restart"),
-    ]
-    .to_vec()
+/// `key` as it's printed on the keyboard, at least for letters and the named keys
+fn key_name(key: KeyCode) -> String {
+    let name = format!("{key:?}");
+    name.strip_prefix("Key").unwrap_or(&name).to_string()
 }
 
-fn level_4_tip_ui() -> Vec<Text> {
-    [Text::new("Let's get BOOSTING!")].to_vec()
+fn level_4_tip_ui() -> Vec<Localized> {
+    [Localized::new("tip-level-4")].to_vec()
 }
 
-fn level_5_tip_ui() -> Vec<Text> {
-    [Text::new("Good luck with this one ;)")].to_vec()
+fn level_5_tip_ui() -> Vec<Localized> {
+    [Localized::new("tip-level-5")].to_vec()
 }
 
 fn tip_ui(current_level: &CurrentLevel) -> Option<Vec<impl Bundle>> {
     let lines = match current_level {
-        CurrentLevel::Level0 => vec![],
-        CurrentLevel::Level1 => level_1_tip_ui(),
-        CurrentLevel::Level2 => level_2_tip_ui(),
-        CurrentLevel::Level3 => level_3_tip_ui(),
+        // The earlier levels have tutorial scripts instead
+        CurrentLevel::Level0
+        | CurrentLevel::Level1
+        | CurrentLevel::Level2
+        | CurrentLevel::Level3 => vec![],
         CurrentLevel::Level4 => level_4_tip_ui(),
         CurrentLevel::Level5 => level_5_tip_ui(),
         CurrentLevel::Level6 => vec![],
@@ -283,73 +348,498 @@ fn broom_type_ui(tile_type: &TileType) -> impl Bundle {
         MainUI,
         BroomUI,
         Pickable::IGNORE,
-        children![(
-            BroomTypeText,
-            Text::new(get_broom_type_text(tile_type)),
-            TextFont {
-                font_size: 30.0,
-                ..default()
-            },
-            TextColor(Color::WHITE),
-            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
-            Pickable::IGNORE,
-        )],
+        children![
+            (
+                BroomTypeText,
+                get_broom_type_text(tile_type),
+                TextFont {
+                    font_size: 30.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+                Pickable::IGNORE,
+            ),
+            (
+                PrecisionText,
+                Localized::new("precision"),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 0.9, 0.4)),
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+                Node {
+                    margin: UiRect::left(Val::Px(10.0)),
+                    align_self: AlignSelf::Center,
+                    ..default()
+                },
+                Visibility::Hidden,
+                Pickable::IGNORE,
+            )
+        ],
     )
 }
 
-fn stone_stopped_ui() -> impl Bundle {
+/// One button per broom along the bottom of the screen, in number key order. The container only
+/// covers the buttons so the tiles around them can still be swept.
+fn broom_toolbar() -> impl Bundle {
     (
         Node {
-            width: Val::Percent(100.0),
-            height: Val::Percent(100.0),
-            justify_content: JustifyContent::Center,
-            align_items: AlignItems::Center,
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(16.0),
+            left: Val::Percent(50.0),
+            column_gap: Val::Px(8.0),
             ..default()
         },
-        Pickable::IGNORE,
-        StoneStoppedUI,
+        // Centered on the screen rather than starting at the middle
+        UiTransform::from_translation(Val2::percent(-50.0, 0.0)),
         MainUI,
-        children![(
-            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
-            Text::new("Too bad! Press R to retry."),
-            TextFont {
-                font_size: 50.0,
-                ..default()
-            },
-            TextColor(Color::srgba(1.0, 0.9, 0.2, 0.9)),
-            Pickable::IGNORE,
-        )],
+        BroomToolbar,
+        Children::spawn(SpawnWith(|parent: &mut ChildSpawner| {
+            for (i, broom) in BROOM_TYPES.iter().enumerate() {
+                parent
+                    .spawn((
+                        BroomButton(broom.clone()),
+                        Button,
+                        Node {
+                            width: Val::Px(BROOM_BUTTON_SIZE),
+                            height: Val::Px(BROOM_BUTTON_SIZE),
+                            border: UiRect::all(Val::Px(3.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            border_radius: BorderRadius::all(Val::Px(8.0)),
+                            ..default()
+                        },
+                        BackgroundColor(get_tile_color(broom)),
+                        BorderColor::all(BROOM_BUTTON_INACTIVE_BORDER),
+                        children![(
+                            Text::new((i + 1).to_string()),
+                            TextFont {
+                                font_size: 20.0,
+                                ..default()
+                            },
+                            TextColor(Color::BLACK),
+                            Pickable::IGNORE,
+                        )],
+                    ))
+                    .observe(select_broom);
+            }
+        })),
     )
 }
 
-fn get_broom_type_text(tile_type: &TileType) -> String {
-    format!(
-        "Broom: {}",
-        match tile_type {
-            TileType::MaintainSpeed => "Straight",
-            TileType::TurnCounterclockwise => "Counterclockwise",
-            TileType::TurnClockwise => "Clockwise",
-
-            //Shouldn't be able to drag these
-            TileType::SlowDown => "SlowDown",
-            TileType::Goal => "Goal",
-            TileType::Wall => "Wall",
-            TileType::SpeedUp(_) => "SpeedUp",
+/// The tile types worth explaining on a level: the ones on its grid and the ones the brooms sweep
+/// tiles into. Speed-up tiles are listed once, whichever way they face.
+pub fn legend_tile_types<'a>(grid: impl IntoIterator<Item = &'a TileType>) -> Vec<TileType> {
+    let mut tile_types: Vec<TileType> = Vec::new();
+    for tile_type in grid.into_iter().chain(&BROOM_TYPES) {
+        if !tile_types
+            .iter()
+            .any(|listed| discriminant(listed) == discriminant(tile_type))
+        {
+            tile_types.push(tile_type.clone());
         }
+    }
+    tile_types.sort_by_key(|tile_type| match tile_type {
+        TileType::MaintainSpeed => 0,
+        TileType::TurnCounterclockwise => 1,
+        TileType::TurnClockwise => 2,
+        TileType::SlowDown => 3,
+        TileType::SpeedUp(_) => 4,
+        TileType::Wall => 5,
+        TileType::SpeedGate { .. } => 6,
+        TileType::Goal => 7,
+    });
+    tile_types
+}
+
+fn legend_description(tile_type: &TileType) -> &'static str {
+    match tile_type {
+        TileType::MaintainSpeed => "legend-straight",
+        TileType::TurnCounterclockwise => "legend-counterclockwise",
+        TileType::TurnClockwise => "legend-clockwise",
+        TileType::SlowDown => "legend-slow-down",
+        TileType::SpeedUp(_) => "legend-speed-up",
+        TileType::Wall => "legend-wall",
+        TileType::SpeedGate { .. } => "legend-speed-gate",
+        TileType::Goal => "legend-goal",
+    }
+}
+
+/// A "?" button in the top left that opens a list of the level's tile types and what they do
+fn tile_legend(level: &Level, open: bool) -> impl Bundle {
+    let tile_types = legend_tile_types(level.grid.values());
+    (
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(16.0),
+            // Below the level timer
+            top: Val::Px(60.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::FlexStart,
+            row_gap: Val::Px(6.0),
+            ..default()
+        },
+        MainUI,
+        children![
+            btn("legend-button", toggle_tile_legend),
+            (
+                TileLegendEntries,
+                Node {
+                    display: if open { Display::Flex } else { Display::None },
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+                Children::spawn(SpawnIter(tile_types.into_iter().map(legend_row))),
+            ),
+        ],
+    )
+}
+
+fn legend_row(tile_type: TileType) -> impl Bundle {
+    let description = legend_description(&tile_type);
+    (
+        Node {
+            column_gap: Val::Px(8.0),
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        children![
+            (
+                LegendSwatch(tile_type),
+                Node {
+                    width: Val::Px(LEGEND_SWATCH_SIZE),
+                    height: Val::Px(LEGEND_SWATCH_SIZE),
+                    border: UiRect::all(Val::Px(1.0)),
+                    border_radius: BorderRadius::all(Val::Px(3.0)),
+                    ..default()
+                },
+                BorderColor::all(Color::WHITE),
+            ),
+            (
+                Localized::new(description),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ),
+        ],
     )
 }
 
+fn toggle_tile_legend(_: On<Pointer<Click>>, mut open: ResMut<TileLegendOpen>) {
+    open.0 = !open.0;
+}
+
+fn toggle_tile_legend_on_key(mut open: ResMut<TileLegendOpen>) {
+    open.0 = !open.0;
+}
+
+fn update_tile_legend(
+    open: Res<TileLegendOpen>,
+    palette: Res<TilePalette>,
+    theme: Res<TileTheme>,
+    mut entries: Query<&mut Node, With<TileLegendEntries>>,
+    mut swatches: Query<(Ref<LegendSwatch>, &mut BackgroundColor)>,
+) {
+    if open.is_changed() {
+        for mut node in &mut entries {
+            node.display = if open.0 { Display::Flex } else { Display::None };
+        }
+    }
+    for (swatch, mut background_color) in &mut swatches {
+        if palette.is_changed() || theme.is_changed() || swatch.is_added() {
+            background_color.0 = theme.colors().tile_color(*palette, &swatch.0);
+        }
+    }
+}
+
+fn select_broom(
+    click: On<Pointer<Click>>,
+    broom_buttons: Query<&BroomButton>,
+    mut current_drag_tile_type: ResMut<CurrentDragTileType>,
+) {
+    if let Ok(BroomButton(broom)) = broom_buttons.get(click.entity) {
+        current_drag_tile_type.set_if_neq(CurrentDragTileType(broom.clone()));
+    }
+}
+
+fn get_broom_type_text(tile_type: &TileType) -> Localized {
+    Localized::new(match tile_type {
+        TileType::MaintainSpeed => "broom-straight",
+        TileType::TurnCounterclockwise => "broom-counterclockwise",
+        TileType::TurnClockwise => "broom-clockwise",
+
+        //Shouldn't be able to drag these
+        TileType::SlowDown => "broom-slow-down",
+        TileType::Goal => "broom-goal",
+        TileType::Wall => "broom-wall",
+        TileType::SpeedUp(_) => "broom-speed-up",
+        TileType::SpeedGate { .. } => "broom-speed-gate",
+    })
+}
+
 fn update_broom_type_ui(
+    mut commands: Commands,
     current_drag_tile_type: Res<CurrentDragTileType>,
-    mut text_query: Query<&mut Text, With<BroomTypeText>>,
+    mut text_query: Query<(Entity, &mut Localized), With<BroomTypeText>>,
 ) {
     if current_drag_tile_type.is_changed() {
-        for mut text in &mut text_query {
-            **text = get_broom_type_text(&current_drag_tile_type.0)
+        for (entity, mut text) in &mut text_query {
+            *text = get_broom_type_text(&current_drag_tile_type.0);
+            if !current_drag_tile_type.is_added() {
+                commands
+                    .entity(entity)
+                    .insert(BroomPulse(Timer::from_seconds(
+                        BROOM_PULSE_SECONDS,
+                        TimerMode::Once,
+                    )));
+            }
+        }
+    }
+}
+
+/// Outlines the active broom's button, whether it was picked by clicking or from the keyboard
+fn update_broom_toolbar(
+    current_drag_tile_type: Res<CurrentDragTileType>,
+    palette: Res<TilePalette>,
+    theme: Res<TileTheme>,
+    mut broom_buttons: Query<(Ref<BroomButton>, &mut BorderColor, &mut BackgroundColor)>,
+) {
+    for (broom_button, mut border_color, mut background_color) in &mut broom_buttons {
+        if palette.is_changed() || theme.is_changed() || broom_button.is_added() {
+            background_color.0 = theme.colors().tile_color(*palette, &broom_button.0);
+        }
+        if !current_drag_tile_type.is_changed() && !broom_button.is_added() {
+            continue;
+        }
+        *border_color = BorderColor::all(if broom_button.0 == current_drag_tile_type.0 {
+            BROOM_BUTTON_ACTIVE_BORDER
+        } else {
+            BROOM_BUTTON_INACTIVE_BORDER
+        });
+    }
+}
+
+fn update_precision_text(
+    input: Res<ButtonInput<KeyCode>>,
+    mut precision_text: Query<&mut Visibility, With<PrecisionText>>,
+) {
+    let visibility = if input.any_pressed(PRECISION_KEYS) {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut precision_visibility in &mut precision_text {
+        precision_visibility.set_if_neq(visibility);
+    }
+}
+
+fn animate_broom_pulse(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pulse_query: Query<(Entity, &mut BroomPulse, &mut UiTransform)>,
+) {
+    for (entity, mut pulse, mut ui_transform) in &mut pulse_query {
+        pulse.0.tick(time.delta());
+        if pulse.0.is_finished() {
+            ui_transform.scale = Vec2::ONE;
+            commands.entity(entity).remove::<BroomPulse>();
+        } else {
+            ui_transform.scale =
+                Vec2::splat(1.0 + BROOM_PULSE_SCALE * pulse.0.fraction_remaining());
         }
     }
 }
 
+/// The least swept tile's progress towards `MaintainSpeed`, which is what finishes Level0. Tiles
+/// that already count as `MaintainSpeed` are done, even if they're a hair short of the distance.
+pub fn level_0_progress<'a>(
+    tiles: impl IntoIterator<Item = (&'a TileDragging, &'a SweptTileType)>,
+    min_sweep_distance: f32,
+) -> f32 {
+    tiles
+        .into_iter()
+        .map(|(tile_dragging, swept_tile_type)| {
+            if swept_tile_type.0 == TileType::MaintainSpeed || min_sweep_distance <= 0.0 {
+                return 1.0;
+            }
+            let dragged = tile_dragging
+                .distance_dragged
+                .get(&TileType::MaintainSpeed)
+                .copied()
+                .unwrap_or(0.0);
+            (dragged / min_sweep_distance).clamp(0.0, 1.0)
+        })
+        .fold(1.0, f32::min)
+}
+
+fn level_0_progress_bar() -> impl Bundle {
+    (
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(48.0),
+            left: Val::Percent(50.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(8.0),
+            ..default()
+        },
+        UiTransform::from_translation(Val2::percent(-50.0, 0.0)),
+        MainUI,
+        Level0Progress,
+        Pickable::IGNORE,
+        children![
+            (
+                NiceFlourish,
+                Localized::new("tutorial-nice"),
+                TextFont {
+                    font_size: 32.0,
+                    ..default()
+                },
+                TextColor(Color::srgba(1.0, 0.9, 0.2, 0.9)),
+                Visibility::Hidden,
+                Pickable::IGNORE,
+            ),
+            (
+                Node {
+                    width: Val::Px(LEVEL_0_PROGRESS_WIDTH),
+                    height: Val::Px(16.0),
+                    border: UiRect::all(Val::Px(2.0)),
+                    border_radius: BorderRadius::all(Val::Px(8.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+                BorderColor::all(Color::WHITE),
+                Pickable::IGNORE,
+                children![(
+                    Level0ProgressFill,
+                    Node {
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        border_radius: BorderRadius::all(Val::Px(6.0)),
+                        ..default()
+                    },
+                    BackgroundColor(LEVEL_0_PROGRESS_COLOR),
+                    Pickable::IGNORE,
+                )],
+            ),
+        ],
+    )
+}
+
+/// Only rescans the tiles on frames where some sweeping changed
+fn update_level_0_progress(
+    on_level: Res<OnLevel>,
+    tiles: Query<(&TileDragging, &SweptTileType)>,
+    swept: Query<(), Changed<TileDragging>>,
+    mut fill: Query<&mut Node, With<Level0ProgressFill>>,
+) {
+    let Ok(mut fill) = fill.single_mut() else {
+        return;
+    };
+    if swept.is_empty() && !on_level.is_changed() {
+        return;
+    }
+    let progress = level_0_progress(tiles, on_level.0.min_sweep_distance);
+    fill.width = Val::Percent(100.0 * progress);
+}
+
+/// Only a tile changing type can finish the sweeping or undo it, so the "Nice!" waits for that
+fn show_nice_flourish(
+    _tile_changed: On<TileChanged>,
+    mut commands: Commands,
+    tiles: Query<&SweptTileType>,
+    mut nice: Query<(Entity, &mut Visibility), With<NiceFlourish>>,
+) {
+    let Ok((entity, mut visibility)) = nice.single_mut() else {
+        return;
+    };
+    let done = tiles
+        .iter()
+        .all(|swept_tile_type| swept_tile_type.0 == TileType::MaintainSpeed);
+    if done && *visibility == Visibility::Hidden {
+        commands
+            .entity(entity)
+            .insert(FlourishPop(Timer::from_seconds(
+                NICE_FLOURISH_SECONDS,
+                TimerMode::Once,
+            )));
+    }
+    visibility.set_if_neq(if done {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    });
+}
+
+fn animate_nice_flourish(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut flourish: Query<(Entity, &mut FlourishPop, &mut UiTransform)>,
+) {
+    for (entity, mut pop, mut ui_transform) in &mut flourish {
+        pop.0.tick(time.delta());
+        if pop.0.is_finished() {
+            ui_transform.scale = Vec2::ONE;
+            commands.entity(entity).remove::<FlourishPop>();
+        } else {
+            ui_transform.scale =
+                Vec2::splat(1.0 + NICE_FLOURISH_SCALE * pop.0.fraction_remaining());
+        }
+    }
+}
+
+fn spawn_sweep_speed_text(mut commands: Commands, mut readout: ResMut<SweepSpeedReadout>) {
+    readout.0 = None;
+    commands.spawn((
+        DespawnOnExit(Screen::Gameplay),
+        SweepSpeedText,
+        Text::new(""),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        Visibility::Hidden,
+        Pickable::IGNORE,
+    ));
+}
+
+/// Follows the cursor while sweeping. Hidden when the drag is counting for exactly its length.
+fn update_sweep_speed_text(
+    readout: Res<SweepSpeedReadout>,
+    on_level: Res<OnLevel>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    ui_scale: Res<UiScale>,
+    text: Single<(&mut Text, &mut Node, &mut Visibility), With<SweepSpeedText>>,
+) {
+    let (mut text, mut node, mut visibility) = text.into_inner();
+    let level = &on_level.0;
+    let flat_curve = level.sweep_slow_multiplier == level.sweep_fast_multiplier;
+    let multiplier = readout
+        .0
+        .filter(|multiplier| !flat_curve || *multiplier != 1.0);
+    let (Some(multiplier), Some(cursor)) = (multiplier, window.cursor_position()) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Visible;
+    **text = format!("x{multiplier:.2}");
+    // The cursor is in window coordinates and the UI is scaled
+    node.left = Val::Px(cursor.x / ui_scale.0 + SWEEP_SPEED_TEXT_OFFSET.x);
+    node.top = Val::Px(cursor.y / ui_scale.0 + SWEEP_SPEED_TEXT_OFFSET.y);
+}
+
 /// System that updates the countdown and starts physics when it reaches zero
 fn update_countdown(
     mut commands: Commands,
@@ -358,6 +848,8 @@ fn update_countdown(
     mut text_query: Query<&mut Text, With<CountdownText>>,
     countdown_ui_query: Single<Entity, With<CountdownUI>>,
     mut next_game_state: ResMut<NextState<GameState>>,
+    mut input_buffer: ResMut<InputBuffer>,
+    tile_cursor: Res<TileCursor>,
 ) {
     // Only run countdown while physics is paused and countdown is active
     if countdown.count == 0 {
@@ -366,8 +858,14 @@ fn update_countdown(
 
     countdown.timer.tick(time.delta());
 
-    if countdown.timer.just_finished() {
-        countdown.count = countdown.count.saturating_sub(1);
+    // Space skips straight to the launch, unless it's sweeping with the keyboard
+    let skipped = !tile_cursor.is_visible() && input_buffer.consume(KeyCode::Space);
+    if skipped || countdown.timer.just_finished() {
+        countdown.count = if skipped {
+            0
+        } else {
+            countdown.count.saturating_sub(1)
+        };
 
         if countdown.count == 0 {
             commands.entity(*countdown_ui_query).despawn();
@@ -387,44 +885,114 @@ fn on_level_start(
     mut commands: Commands,
     mut countdown: ResMut<Countdown>,
     on_level: Res<OnLevel>,
-    level_0_ui_entity: Query<Entity, With<Level0UI>>,
     main_ui_entity: Query<Entity, With<MainUI>>,
     current_drag_tile_type: Res<CurrentDragTileType>,
+    tile_legend_open: Res<TileLegendOpen>,
 ) {
     for entity in main_ui_entity.iter() {
         commands.entity(entity).despawn();
     }
 
-    for entity in level_0_ui_entity.iter() {
-        commands.entity(entity).despawn();
-    }
-
     let level = &on_level.0;
     match level.current_level {
+        // Only the tutorial prompt and how far the sweeping has got
         CurrentLevel::Level0 => {
-            commands.spawn((DespawnOnExit(Screen::Gameplay), level_0_ui()));
+            commands.spawn((DespawnOnExit(Screen::Gameplay), level_0_progress_bar()));
         }
         _ => {
             commands.spawn((
                 DespawnOnExit(Screen::Gameplay),
                 broom_type_ui(&current_drag_tile_type.0),
             ));
+            commands.spawn((DespawnOnExit(Screen::Gameplay), broom_toolbar()));
+            commands.spawn((
+                DespawnOnExit(Screen::Gameplay),
+                tile_legend(level, tile_legend_open.0),
+            ));
             if let Some(c) = level.countdown {
                 countdown.count = c;
                 countdown.timer.reset();
                 commands.spawn((DespawnOnExit(Screen::Gameplay), countdown_ui(c)));
             }
-            spawn_bottom_left_ui(commands, &level.current_level);
+            spawn_bottom_left_ui(commands, level);
         }
     }
 }
 
-fn on_stone_stopped(
-    mut _ev: On<StoneStopped>,
-    mut commands: Commands,
-    stone_stopped_ui_entity: Query<Entity, With<StoneStoppedUI>>,
-) {
-    if stone_stopped_ui_entity.is_empty() {
-        commands.spawn(stone_stopped_ui());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        level::{Facing, get_level},
+        tile::sweep,
+    };
+
+    #[test]
+    fn test_level_0_progress_is_the_least_swept_tile() {
+        let mut half_swept = TileDragging::new(&TileType::SlowDown, 100.0);
+        sweep(&mut half_swept, &TileType::MaintainSpeed, 50.0);
+        let mut nearly_swept = TileDragging::new(&TileType::SlowDown, 100.0);
+        sweep(&mut nearly_swept, &TileType::MaintainSpeed, 99.0);
+        let slow_down = SweptTileType(TileType::SlowDown);
+        let maintain_speed = SweptTileType(TileType::MaintainSpeed);
+
+        assert_eq!(
+            level_0_progress(
+                [(&half_swept, &slow_down), (&nearly_swept, &slow_down)],
+                100.0
+            ),
+            0.5
+        );
+        // Within the tolerance of the distance already counts as swept
+        assert_eq!(
+            level_0_progress([(&nearly_swept, &maintain_speed)], 100.0),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_controls_name_the_bound_keys() {
+        assert_eq!(key_name(UNDO_KEY), "Z");
+        assert_eq!(key_name(REWIND_KEY), "Backspace");
+
+        let mut level = get_level(CurrentLevel::Level1);
+        level.rewind = false;
+        let listed = controls(&level);
+        let undo = listed
+            .iter()
+            .find(|control| control.key == "controls-undo")
+            .unwrap();
+        assert_eq!(undo.args, [("key", "Z".to_string())]);
+        // Rewinding is only listed on levels that allow it
+        assert!(
+            !listed
+                .iter()
+                .any(|control| control.key == "controls-rewind")
+        );
+        level.rewind = true;
+        assert_eq!(controls(&level).len(), listed.len() + 1);
+    }
+
+    #[test]
+    fn test_legend_lists_each_tile_kind_once() {
+        let grid = [
+            TileType::Goal,
+            TileType::SlowDown,
+            TileType::SpeedUp(Facing::Up),
+            TileType::SpeedUp(Facing::Down),
+            TileType::SlowDown,
+        ];
+        // The brooms' types come first even though they're not on the grid yet
+        assert_eq!(
+            legend_tile_types(&grid),
+            vec![
+                TileType::MaintainSpeed,
+                TileType::TurnCounterclockwise,
+                TileType::TurnClockwise,
+                TileType::SlowDown,
+                TileType::SpeedUp(Facing::Up),
+                TileType::Goal,
+            ]
+        );
     }
 }
@@ -0,0 +1,45 @@
+//! Keeps the UI laid out the same at any window size. Everything's placed for the 1024x768 window
+//! the game started out with, and `UiScale` shrinks or grows it to fit the window's shorter side
+//! relative to that, so smaller and wider windows don't push the corners' UI into each other.
+//! Anything placing UI in window coordinates has to divide by the scale.
+
+use bevy::{prelude::*, window::PrimaryWindow};
+
+/// The window size the UI is laid out for
+pub const DESIGN_SIZE: Vec2 = Vec2::new(1024.0, 768.0);
+
+/// Below this, text gets too small to read
+const MIN_UI_SCALE: f32 = 0.5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(PreUpdate, scale_ui_to_window);
+}
+
+/// The scale that fits the design size into `window_size` without cropping either side
+pub fn ui_scale_for(window_size: Vec2) -> f32 {
+    (window_size / DESIGN_SIZE).min_element().max(MIN_UI_SCALE)
+}
+
+fn scale_ui_to_window(window: Single<&Window, With<PrimaryWindow>>, mut ui_scale: ResMut<UiScale>) {
+    let scale = ui_scale_for(window.size());
+    if ui_scale.0 != scale {
+        ui_scale.0 = scale;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ui_scale_fits_the_shorter_side() {
+        assert_eq!(ui_scale_for(DESIGN_SIZE), 1.0);
+        // 800x600 is the same shape, just smaller
+        assert_eq!(ui_scale_for(Vec2::new(800.0, 600.0)), 800.0 / 1024.0);
+        // Ultrawide windows are limited by their height, leaving the extra width as room between
+        assert_eq!(ui_scale_for(Vec2::new(2560.0, 1080.0)), 1080.0 / 768.0);
+        // Tall windows by their width
+        assert_eq!(ui_scale_for(Vec2::new(512.0, 1200.0)), 0.5);
+        assert_eq!(ui_scale_for(Vec2::new(100.0, 100.0)), MIN_UI_SCALE);
+    }
+}
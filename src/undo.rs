@@ -0,0 +1,219 @@
+//! Z undoes the most recent drag stroke, putting every tile it swept or erased back exactly how it
+//! was. A double-click sweep counts as a stroke of its own. Only one stroke is remembered, so
+//! pressing Z a second time does nothing.
+
+use std::collections::HashMap;
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use bevy_egui::input::EguiWantsInput;
+
+use crate::{
+    PausableSystems,
+    gameplay::GameState,
+    level::OnLevel,
+    level_transition::transitioning,
+    replay::{RecordingMark, ReplayPlayback, ReplayRecording},
+    screens::Screen,
+    sweep_budget::SweepBudget,
+    tile::{TileDragging, TileType, sweeping_allowed},
+};
+
+pub const UNDO_KEY: KeyCode = KeyCode::KeyZ;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<StrokeUndo>().add_systems(
+        Update,
+        undo_last_stroke
+            .run_if(
+                in_state(Screen::Gameplay)
+                    .and(input_just_pressed(UNDO_KEY))
+                    .and(not(transitioning)),
+            )
+            .in_set(PausableSystems),
+    );
+}
+
+/// How a tile looked before a stroke first touched it
+struct TileBefore {
+    distance_dragged: HashMap<TileType, f32>,
+    most_recent_tile_type: Option<TileType>,
+}
+
+/// Everything a single stroke, from `DragStart` to `DragEnd`, changed
+struct StrokeRecord {
+    tiles: HashMap<Entity, TileBefore>,
+    /// The replay recording from before the stroke's sweeps were added to it
+    before: RecordingMark,
+    /// The replay recording right after the stroke, to tell whether anything was swept since
+    after: Option<RecordingMark>,
+    /// Sweep budget used before the stroke, refunded on undo
+    budget_used: f32,
+}
+
+#[derive(Resource, Default)]
+pub struct StrokeUndo {
+    /// The stroke being dragged right now
+    current: Option<StrokeRecord>,
+    /// The most recent finished stroke that changed anything
+    last: Option<StrokeRecord>,
+}
+
+impl StrokeUndo {
+    pub fn begin(&mut self, before: RecordingMark, budget_used: f32) {
+        self.current = Some(StrokeRecord {
+            tiles: HashMap::new(),
+            before,
+            after: None,
+            budget_used,
+        });
+    }
+
+    /// Remembers `tile` as it was before the stroke changed it. Call with the tile's state from
+    /// before each change, only the first one of the stroke is kept.
+    pub fn touch(&mut self, entity: Entity, tile: &TileDragging) {
+        if let Some(current) = &mut self.current {
+            current.tiles.entry(entity).or_insert_with(|| TileBefore {
+                distance_dragged: tile.distance_dragged.clone(),
+                most_recent_tile_type: tile.most_recent_tile_type.clone(),
+            });
+        }
+    }
+
+    /// Whether a mouse or touch stroke is being dragged right now
+    pub fn in_progress(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Strokes that didn't change anything are dropped, leaving the previous one to undo
+    pub fn end(&mut self, after: RecordingMark) {
+        if let Some(mut current) = self.current.take()
+            && !current.tiles.is_empty()
+        {
+            current.after = Some(after);
+            self.last = Some(current);
+        }
+    }
+}
+
+/// Touches make their strokes in `touch_sweep`
+pub fn on_stroke_start(
+    drag_start: On<Pointer<DragStart>>,
+    mut stroke_undo: ResMut<StrokeUndo>,
+    replay_recording: Res<ReplayRecording>,
+    sweep_budget: Res<SweepBudget>,
+) {
+    if drag_start.pointer_id.is_touch() {
+        return;
+    }
+    stroke_undo.begin(replay_recording.0.mark(), sweep_budget.used);
+}
+
+pub fn on_stroke_end(
+    drag_end: On<Pointer<DragEnd>>,
+    mut stroke_undo: ResMut<StrokeUndo>,
+    replay_recording: Res<ReplayRecording>,
+) {
+    if drag_end.pointer_id.is_touch() {
+        return;
+    }
+    stroke_undo.end(replay_recording.0.mark());
+}
+
+/// Undoing also drops the stroke from the replay recording. If anything has been swept since, the
+/// stroke isn't the latest change anymore and there's nothing to undo. Undoing counts as erasing, so
+/// it's locked whenever erasing is.
+fn undo_last_stroke(
+    egui_wants_input: Option<Res<EguiWantsInput>>,
+    mut stroke_undo: ResMut<StrokeUndo>,
+    mut tiles: Query<&mut TileDragging>,
+    mut replay_recording: ResMut<ReplayRecording>,
+    on_level: Res<OnLevel>,
+    game_state: Res<State<GameState>>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    mut sweep_budget: ResMut<SweepBudget>,
+) {
+    let level = &on_level.0;
+    let game_state = game_state.get();
+    let erase_locked = level.real_time && *game_state == GameState::Playing;
+    if egui_wants_input.is_some_and(|egui| egui.wants_any_keyboard_input())
+        || replay_playback.is_some()
+        || !sweeping_allowed(game_state, level)
+        || erase_locked
+    {
+        return;
+    }
+    let Some(stroke) = stroke_undo.last.take() else {
+        return;
+    };
+    if stroke.after.as_ref() != Some(&replay_recording.0.mark()) {
+        return;
+    }
+
+    // Tiles respawned by a restart since the stroke are skipped
+    for (entity, before) in stroke.tiles {
+        if let Ok(mut tile) = tiles.get_mut(entity) {
+            tile.distance_dragged = before.distance_dragged;
+            tile.most_recent_tile_type = before.most_recent_tile_type;
+        }
+    }
+    replay_recording.0.roll_back(&stroke.before);
+    sweep_budget.used = stroke.budget_used;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hex_grid::HexCoordinate,
+        level::{CurrentLevel, get_level},
+        tile::finish_sweeping,
+    };
+
+    #[test]
+    fn test_undo_takes_back_a_double_click_sweep() {
+        let mut level = get_level(CurrentLevel::Level1);
+        level.sweep_budget = Some(1000.0);
+        let unswept = TileDragging::new(&TileType::SlowDown, level.min_sweep_distance);
+        let mut tile = unswept.clone();
+        let mut world = World::new();
+        let entity = world.spawn_empty().id();
+        let mut replay_recording = ReplayRecording::default();
+        let mut sweep_budget = SweepBudget::default();
+        let mut stroke_undo = StrokeUndo::default();
+
+        // Double-clicking sweeps the tile the whole way in one go
+        finish_sweeping(
+            entity,
+            &mut tile,
+            &HexCoordinate { q: 0, r: 0 },
+            &TileType::MaintainSpeed,
+            &level,
+            3,
+            &mut replay_recording,
+            &mut sweep_budget,
+            &mut stroke_undo,
+        );
+        assert_eq!(
+            tile.settled_type(level.min_sweep_distance),
+            Some(TileType::MaintainSpeed)
+        );
+        assert_eq!(replay_recording.0.actions.len(), 1);
+        assert!(sweep_budget.used > 0.0);
+
+        world.entity_mut(entity).insert(tile);
+        world.insert_resource(OnLevel(level));
+        world.insert_resource(State::new(GameState::Countdown));
+        world.insert_resource(replay_recording);
+        world.insert_resource(sweep_budget);
+        world.insert_resource(stroke_undo);
+        let mut schedule = Schedule::default();
+        schedule.add_systems(undo_last_stroke);
+        schedule.run(&mut world);
+
+        // A single undo puts the tile, the recording and the budget back
+        let tile = world.entity(entity).get::<TileDragging>().unwrap();
+        assert_eq!(tile.distance_dragged, unswept.distance_dragged);
+        assert!(world.resource::<ReplayRecording>().0.actions.is_empty());
+        assert_eq!(world.resource::<SweepBudget>().used, 0.0);
+    }
+}
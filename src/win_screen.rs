@@ -0,0 +1,169 @@
+//! The overlay shown over the confetti once a level is won, with how the win went and buttons to
+//! retry the level or move on to the next one.
+
+use bevy::{ecs::spawn::SpawnIter, prelude::*};
+
+use crate::{
+    gameplay::{GameState, LevelComplete, swept_tile_counts},
+    level::OnLevel,
+    level_timer::{LevelTimer, format_level_time},
+    level_transition::{StartLevelTransition, TransitionTo},
+    localization::Localized,
+    menus::btn,
+    replay::ReplayRecording,
+    screens::Screen,
+    sweep_budget::SweepBudget,
+};
+
+pub const MAX_STARS: u8 = 3;
+const STAR_COLOR: Color = Color::srgb(1.0, 0.84, 0.0);
+const MISSING_STAR_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.2);
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_observer(spawn_win_overlay);
+}
+
+/// Stars a win earns out of `MAX_STARS`: all of them for sweeping at most `par` tiles, and one less
+/// for each further `par` tiles swept, or each tile on a par of zero. Every win gets at least one.
+pub fn win_stars(tiles_swept: usize, par: usize) -> u8 {
+    let over_par = tiles_swept.saturating_sub(par);
+    let stars_lost = over_par.div_ceil(par.max(1));
+    MAX_STARS - stars_lost.min(MAX_STARS as usize - 1) as u8
+}
+
+fn spawn_win_overlay(
+    _event: On<LevelComplete>,
+    mut commands: Commands,
+    on_level: Res<OnLevel>,
+    level_timer: Res<LevelTimer>,
+    sweep_budget: Res<SweepBudget>,
+    replay_recording: Res<ReplayRecording>,
+) {
+    let level = &on_level.0;
+    let (tiles_swept, _) = swept_tile_counts(&replay_recording.0, level);
+    let stars = win_stars(tiles_swept, level.par);
+    let next_label = if level.current_level.next().is_some() {
+        "win-next"
+    } else {
+        "win-finish"
+    };
+
+    commands.spawn((
+        Name::new("Win Overlay"),
+        DespawnOnExit(Screen::Gameplay),
+        DespawnOnExit(GameState::Won),
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        // Also keeps the pointer off the tiles underneath
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.4)),
+        children![(
+            Node {
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(20.0),
+                padding: UiRect::all(Val::Px(30.0)),
+                border: UiRect::all(Val::Px(2.0)),
+                border_radius: BorderRadius::all(Val::Px(25.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.1, 0.1, 0.1, 0.9)),
+            BorderColor::all(Color::srgb(0.9, 0.9, 0.9)),
+            children![
+                (
+                    Localized::new("win-title"),
+                    TextFont {
+                        font_size: 36.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ),
+                star_row(stars),
+                stat_text(
+                    Localized::new("win-time")
+                        .with_arg("time", format_level_time(level_timer.elapsed))
+                ),
+                stat_text(
+                    Localized::new("swept").with_arg("amount", format!("{:.0}", sweep_budget.used))
+                ),
+                (
+                    Node {
+                        column_gap: Val::Px(30.0),
+                        ..default()
+                    },
+                    children![btn("retry", retry), btn(next_label, next_level)],
+                ),
+            ],
+        )],
+    ));
+}
+
+fn star_row(stars: u8) -> impl Bundle {
+    (
+        Node {
+            column_gap: Val::Px(20.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        },
+        Children::spawn(SpawnIter((0..MAX_STARS).map(move |star| {
+            (
+                Node {
+                    width: Val::Px(28.0),
+                    height: Val::Px(28.0),
+                    ..default()
+                },
+                UiTransform::from_rotation(Rot2::degrees(45.0)),
+                BackgroundColor(if star < stars {
+                    STAR_COLOR
+                } else {
+                    MISSING_STAR_COLOR
+                }),
+            )
+        }))),
+    )
+}
+
+fn stat_text(text: Localized) -> impl Bundle {
+    (
+        text,
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+    )
+}
+
+fn retry(_: On<Pointer<Click>>, mut commands: Commands) {
+    commands.trigger(StartLevelTransition(TransitionTo::RestartLevel));
+}
+
+fn next_level(_: On<Pointer<Click>>, mut commands: Commands) {
+    commands.trigger(StartLevelTransition(TransitionTo::NextLevel));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_win_stars() {
+        // Three stars at or under par
+        assert_eq!(win_stars(0, 3), 3);
+        assert_eq!(win_stars(3, 3), 3);
+        // Up to another par's worth over loses one
+        assert_eq!(win_stars(4, 3), 2);
+        assert_eq!(win_stars(6, 3), 2);
+        // Any further still wins a star
+        assert_eq!(win_stars(7, 3), 1);
+        assert_eq!(win_stars(30, 3), 1);
+        // A par of zero loses a star per tile
+        assert_eq!(win_stars(0, 0), 3);
+        assert_eq!(win_stars(1, 0), 2);
+        assert_eq!(win_stars(2, 0), 1);
+    }
+}